@@ -0,0 +1,48 @@
+// IDA* on 4x4 boards deep enough to take a while, with each heuristic that
+// doesn't need the pattern database built first. Run with `cargo bench`.
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+use slide_puzzle::{
+    puzzle::{shuffle_grid, Grid},
+    solver::{self, Heuristic},
+};
+
+fn deep_boards() -> Vec<Grid> {
+    // Seeded so every run times the same boards
+    let mut rng = StdRng::seed_from_u64(689);
+    (0..4)
+        .map(|_| {
+            let mut grid = Grid::solved(4, 4);
+            shuffle_grid(&mut grid, 60, &mut rng);
+            grid
+        })
+        .collect()
+}
+
+fn ida_star(c: &mut Criterion) {
+    let boards = deep_boards();
+    let mut group = c.benchmark_group("ida_star_4x4");
+    group.sample_size(10);
+    for (name, heuristic) in [
+        ("manhattan", Heuristic::Manhattan),
+        ("linear_conflict", Heuristic::LinearConflict),
+    ] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &heuristic,
+            |b, &heuristic| {
+                b.iter(|| {
+                    for grid in &boards {
+                        black_box(solver::ida_star(black_box(grid), heuristic));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, ida_star);
+criterion_main!(benches);
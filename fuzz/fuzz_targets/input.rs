@@ -0,0 +1,53 @@
+// Whatever a player can do with the mouse and keyboard, a frame at a time.
+// Every two bytes are one action on one of the piece windows.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sfml::{system::Vector2, window::Key};
+use slide_puzzle::{fake_renderer::FakeRenderer, World};
+
+const KEYS: [Key; 14] = [
+    Key::Up,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::H,
+    Key::S,
+    Key::R,
+    Key::M,
+    Key::Z,
+    Key::Y,
+    Key::K,
+    Key::Enter,
+    Key::Escape,
+    Key::Space,
+];
+
+fuzz_target!(|data: &[u8]| {
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop.clone(), 3, 3, 100, 10, 20);
+    let pieces = world.pieces.len();
+
+    for action in data.chunks_exact(2) {
+        if !world.playing {
+            break;
+        }
+        let window = desktop.find_window(&(action[1] as usize % pieces + 1).to_string());
+        let value = action[1] as i32;
+        match action[0] % 4 {
+            0 => desktop.press_mouse(window, value % 100, value / 3),
+            1 => {
+                let position = desktop.window_position(window);
+                desktop.move_mouse(Vector2::new(position.x + value - 128, position.y));
+            }
+            2 => desktop.release_mouse(window),
+            _ => {
+                let key = KEYS[action[1] as usize % KEYS.len()];
+                desktop.press_key_with(window, key, action[0] & 0x80 != 0);
+            }
+        }
+        world.s_update();
+        world.s_render();
+    }
+});
@@ -0,0 +1,18 @@
+// Replay files, as shared between players. One that decodes must either be
+// refused for playback or play back move by move.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slide_puzzle::{demo::Demo, replay::Replay};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(replay) = Replay::decode(data) {
+        let _ = Demo::from_replay(&replay);
+    }
+    // Most damage to a whole file is caught by zstd, so the payload inside
+    // is fed in on its own too
+    if let Ok(replay) = Replay::parse_payload(data) {
+        let _ = Demo::from_replay(&replay);
+    }
+});
@@ -0,0 +1,14 @@
+// Save files as read back from disk, checksum and version line included,
+// here with the gamepad mapping as what's inside
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slide_puzzle::{gamepad::Mapping, saves};
+
+fuzz_target!(|data: &[u8]| {
+    let text = saves::verify(&String::from_utf8_lossy(data)).and_then(saves::split_version);
+    if let Ok((_, text)) = text {
+        let _ = Mapping::parse(&text);
+    }
+});
@@ -0,0 +1,26 @@
+// Scramble links and notation, as pasted or opened from a slidingpuzzle://
+// link. Any board that parses has to round-trip and be playable.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slide_puzzle::{fake_renderer::FakeRenderer, scramble, World};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(grid) = scramble::parse(&String::from_utf8_lossy(data)) else {
+        return;
+    };
+    assert_eq!(scramble::parse(&scramble::to_notation(&grid)), Ok(grid.clone()));
+
+    let mut world = World::new(
+        FakeRenderer::new(1920, 1080),
+        grid.width(),
+        grid.height(),
+        100,
+        10,
+        0,
+    );
+    world.set_grid(grid);
+    world.s_update();
+    world.s_render();
+});
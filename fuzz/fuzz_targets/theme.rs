@@ -0,0 +1,18 @@
+// Theme files, which players share and the game reloads while running. A
+// theme that parses is drawn with, so its colours and shapes get used.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slide_puzzle::{fake_renderer::FakeRenderer, theme::Theme, World};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(theme) = Theme::parse(&String::from_utf8_lossy(data)) else {
+        return;
+    };
+
+    let mut world = World::new(FakeRenderer::new(1920, 1080), 3, 3, 100, 10, 0);
+    world.theme = theme;
+    world.s_update();
+    world.s_render();
+});
@@ -0,0 +1,66 @@
+use crate::{puzzle::Grid, solver, MoveRecord};
+
+// A stretch of moves that wandered away from the solution and back
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detour {
+    // Move numbers, counting from 1
+    pub first: usize,
+    pub last: usize,
+    // How many more moves it took than going straight there
+    pub extra: u32,
+}
+
+impl Detour {
+    pub fn describe(&self) -> String {
+        let moves = if self.first == self.last {
+            format!("Move {}", self.first)
+        } else {
+            format!("Moves {}\u{2013}{}", self.first, self.last)
+        };
+        let plural = if self.extra == 1 { "" } else { "s" };
+
+        format!("{} added {} extra move{}", moves, self.extra, plural)
+    }
+}
+
+// Every detour in a solve, worst first. A detour starts with a move that
+// takes the board further from solved and lasts until it's back as close
+// as it was. Empty if a power-up ever left the board unsolvable, since
+// there's no optimal play to compare against then.
+pub fn detours(start_grid: &Grid, history: &[MoveRecord]) -> Vec<Detour> {
+    let Some(distances) = std::iter::once(start_grid)
+        .chain(history.iter().map(|record| &record.grid))
+        .map(solver::distance)
+        .collect::<Option<Vec<u8>>>()
+    else {
+        return Vec::new();
+    };
+
+    let mut detours = Vec::new();
+    let mut index = 0;
+    while index < history.len() {
+        // Move `index` goes from distances[index] to distances[index + 1]
+        if distances[index + 1] <= distances[index] {
+            index += 1;
+            continue;
+        }
+
+        let level = distances[index];
+        let mut last = index;
+        while last + 1 < history.len() && distances[last + 1] > level {
+            last += 1;
+        }
+
+        let length = (last - index + 1) as u32;
+        let progress = level.saturating_sub(distances[last + 1]) as u32;
+        detours.push(Detour {
+            first: index + 1,
+            last: last + 1,
+            extra: length.saturating_sub(progress),
+        });
+        index = last + 1;
+    }
+
+    detours.sort_by(|a, b| b.extra.cmp(&a.extra).then(a.first.cmp(&b.first)));
+    detours
+}
@@ -0,0 +1,144 @@
+use log::info;
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    font,
+    puzzle::Grid,
+    renderer::{Renderer, WindowHandle},
+    splits::PHASES,
+};
+
+const WINDOW_SIZE: u32 = 200;
+const LINE_HEIGHT: f32 = 28.0;
+
+// A move that bends the rules, used up from a limited stock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerUp {
+    // Swap two neighbouring pieces
+    Swap,
+    // Swap the gap with any piece
+    Teleport,
+}
+
+impl PowerUp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUp::Swap => "swap",
+            PowerUp::Teleport => "teleport",
+        }
+    }
+}
+
+// The power-ups the player has to spend, shown in a window of their own
+pub struct PowerUps<W: WindowHandle> {
+    pub swaps: u32,
+    pub teleports: u32,
+    // The first piece of a swap, waiting for its partner
+    pub selected: Option<usize>,
+    // How many rows have paid out so far this solve
+    rewarded: usize,
+    window: W,
+}
+
+impl<W: WindowHandle> PowerUps<W> {
+    // One of each to start with
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, position: Vector2<i32>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Power-ups");
+        window.set_position(position);
+
+        Self {
+            swaps: 1,
+            teleports: 1,
+            selected: None,
+            rewarded: 0,
+            window,
+        }
+    }
+
+    pub fn count(&self, power_up: PowerUp) -> u32 {
+        match power_up {
+            PowerUp::Swap => self.swaps,
+            PowerUp::Teleport => self.teleports,
+        }
+    }
+
+    // Spend one, if there's one to spend
+    pub fn take(&mut self, power_up: PowerUp) -> bool {
+        let count = match power_up {
+            PowerUp::Swap => &mut self.swaps,
+            PowerUp::Teleport => &mut self.teleports,
+        };
+        if *count == 0 {
+            return false;
+        }
+
+        *count -= 1;
+        true
+    }
+
+    // Every row finished for the first time this solve earns one of each.
+    // The last phase is the whole board, which needs no reward.
+    pub fn reward(&mut self, grid: &Grid) {
+        while let Some(&(name, cells)) = PHASES[..PHASES.len() - 1].get(self.rewarded) {
+            let done = grid
+                .cells()
+                .take(cells)
+                .enumerate()
+                .all(|(cell, piece)| piece as usize == cell);
+            if !done {
+                return;
+            }
+
+            self.rewarded += 1;
+            self.swaps += 1;
+            self.teleports += 1;
+            info!("{} done, earned a swap and a teleport", name);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.rewarded = 0;
+        self.selected = None;
+    }
+
+    pub fn update(&mut self) {
+        // Nothing to interact with, but events still need draining
+        while self.window.poll_event().is_some() {}
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        let lines = [
+            ("Swaps (shift)", self.swaps),
+            ("Teleports (ctrl)", self.teleports),
+        ];
+        for (index, (name, count)) in lines.iter().enumerate() {
+            let y = index as f32 * LINE_HEIGHT * 2.0 + 5.0;
+            self.draw_text(name, Vector2::new(5.0, y), Color::WHITE);
+
+            let color = if *count > 0 {
+                Color::rgb(0, 200, 0)
+            } else {
+                Color::rgb(200, 0, 0)
+            };
+            self.draw_text(
+                &count.to_string(),
+                Vector2::new(5.0, y + LINE_HEIGHT),
+                color,
+            );
+        }
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, position: Vector2<f32>, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(position);
+        self.window.draw(&text);
+    }
+}
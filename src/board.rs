@@ -0,0 +1,66 @@
+use sfml::system::Vector2;
+
+use crate::{
+    parity,
+    puzzle::{find_in_grid, Grid},
+};
+
+// A board of any size, rows top to bottom, with pieces numbered from 0 and
+// -1 for the gap. What tools outside the game need to judge a scramble
+// lives here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    pub grid: Grid,
+}
+
+impl Board {
+    pub fn new(grid: Grid) -> Self {
+        Self { grid }
+    }
+
+    // Whether sliding alone can ever reach the solved board. Exactly half of
+    // all boards can: on boards an odd number of pieces wide those with an
+    // even number of pieces the wrong way round (see parity::explain). On
+    // even widths a slide up or down changes that count by an odd number, so
+    // the gap's row counts too. Swapping any two pieces flips the answer.
+    pub fn is_solvable(&self) -> bool {
+        let inversions = parity::inversions(&self.grid);
+
+        if self.grid.width() % 2 == 1 {
+            inversions.is_multiple_of(2)
+        } else {
+            let rows_above_home = self.grid.height() - 1 - find_in_grid(&self.grid, -1).y as usize;
+            (inversions + rows_above_home).is_multiple_of(2)
+        }
+    }
+
+    // How far every piece is from home, counting rows and columns. A slide
+    // moves one piece one cell, so solving always takes at least this many
+    // moves, and it's 0 only for the solved board. It says nothing about
+    // whether the board can be solved at all; unsolvable boards get a number
+    // too.
+    pub fn distance_to_goal_lower_bound(&self) -> u32 {
+        let width = self.grid.width() as i32;
+
+        (0..self.grid.piece_count() as i8)
+            .map(|piece| {
+                let position = find_in_grid(&self.grid, piece);
+                let (x, y) = (piece as i32 % width, piece as i32 / width);
+                (position.x as i32 - x).unsigned_abs() + (position.y as i32 - y).unsigned_abs()
+            })
+            .sum()
+    }
+
+    // Every piece that's somewhere else on `other`, with the cell it's in
+    // here and the one it's in there, lowest numbered piece first. The gap
+    // isn't a piece. Both boards need to be the same shape.
+    pub fn diff(&self, other: &Board) -> Vec<(i8, Vector2<i8>, Vector2<i8>)> {
+        (0..self.grid.piece_count() as i8)
+            .filter_map(|piece| {
+                let from = find_in_grid(&self.grid, piece);
+                let to = find_in_grid(&other.grid, piece);
+                (from != to).then_some((piece, from, to))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,289 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font, io_worker,
+    profile::Profile,
+    renderer::{Renderer, WindowHandle},
+    saves::{self, Migration},
+};
+
+const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+const MAX_NAME_LENGTH: usize = 32;
+// Favourites are picked with the number keys
+const NUMBER_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+// Changes to the bookmarks file's format, oldest first
+const MIGRATIONS: &[Migration] = &[];
+
+// A seed worth playing again, under a name the player gave it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    pub seed: u64,
+}
+
+// The player's favourite seeds, kept one "seed = name" line each in the
+// order they were bookmarked
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bookmarks {
+    pub entries: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut bookmarks = Self::default();
+
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (seed, name) = line
+                .split_once('=')
+                .ok_or(format!("Expected \"seed = name\", got \"{}\"", line))?;
+            let seed = seed
+                .trim()
+                .parse()
+                .map_err(|_| format!("Bad seed \"{}\"", seed.trim()))?;
+            bookmarks.add(name, seed);
+        }
+
+        Ok(bookmarks)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|bookmark| format!("{} = {}\n", bookmark.seed, bookmark.name))
+            .collect()
+    }
+
+    // Bookmarking a seed twice just renames it
+    pub fn add(&mut self, name: &str, seed: u64) {
+        let name = match name.trim() {
+            "" => default_name(seed),
+            name => name.chars().take(MAX_NAME_LENGTH).collect(),
+        };
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|bookmark| bookmark.seed == seed)
+        {
+            Some(bookmark) => bookmark.name = name,
+            None => self.entries.push(Bookmark { name, seed }),
+        }
+    }
+
+    // The saved bookmarks, or none if there aren't any yet
+    pub fn load(path: &Path) -> Self {
+        match saves::read_versioned(path, MIGRATIONS) {
+            Ok(text) => Self::parse(&text).unwrap_or_else(|error| {
+                warn!("Ignoring bookmarks {}: {}", path.display(), error);
+                Self::default()
+            }),
+            Err(error) => {
+                debug!("No bookmarks at {}: {}", path.display(), error);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: PathBuf) {
+        let text = self.to_text();
+        io_worker::spawn(move || {
+            if let Err(error) = saves::write_versioned(&path, &text, MIGRATIONS) {
+                warn!("Couldn't save bookmarks to {}: {}", path.display(), error);
+            }
+        });
+    }
+}
+
+fn default_name(seed: u64) -> String {
+    format!("Seed {}", seed)
+}
+
+// Next to the personal best splits, so each profile has its own favourites
+pub fn default_path(profile: &Profile) -> Option<PathBuf> {
+    profile.data_dir().map(|dir| dir.join("bookmarks.txt"))
+}
+
+// Asks what to call the seed being bookmarked
+pub struct BookmarkPrompt<W: WindowHandle> {
+    window: W,
+    seed: u64,
+    name: String,
+    pub cancelled: bool,
+}
+
+impl<W: WindowHandle> BookmarkPrompt<W> {
+    pub fn open<R: Renderer<Window = W>>(
+        renderer: &mut R,
+        seed: u64,
+        position: Vector2<i32>,
+    ) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Bookmark seed");
+        window.set_position(position);
+        window.raise();
+        window.request_focus();
+        debug!("Asking for a name for seed {}", seed);
+
+        Self {
+            window,
+            seed,
+            name: String::new(),
+            cancelled: false,
+        }
+    }
+
+    // The name once Enter is pressed, which is the default one if nothing
+    // was typed
+    pub fn update(&mut self) -> Option<String> {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::KeyPressed {
+                    code: Key::Enter, ..
+                } => {
+                    let name = match self.name.trim() {
+                        "" => default_name(self.seed),
+                        name => name.to_string(),
+                    };
+                    info!("Bookmarked seed {} as \"{}\"", self.seed, name);
+                    return Some(name);
+                }
+                Event::KeyPressed {
+                    code: Key::Escape, ..
+                }
+                | Event::Closed => self.cancelled = true,
+                Event::KeyPressed {
+                    code: Key::Backspace,
+                    ..
+                } => {
+                    self.name.pop();
+                }
+                Event::TextEntered { unicode }
+                    if !unicode.is_control() && self.name.chars().count() < MAX_NAME_LENGTH =>
+                {
+                    self.name.push(unicode)
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        self.draw_text("Name this seed:", 0, Color::WHITE);
+        let name = match self.name.as_str() {
+            "" => default_name(self.seed),
+            name => format!("{}_", name),
+        };
+        let color = if self.name.is_empty() {
+            Color::rgb(128, 128, 128)
+        } else {
+            Color::rgb(0, 200, 0)
+        };
+        self.draw_text(&name, 1, color);
+        self.draw_text("Enter to save", 3, Color::WHITE);
+        self.draw_text("Escape to cancel", 4, Color::WHITE);
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
+
+// Lists the favourite seeds before the game starts, to play one again
+pub struct FavouritesPicker<W: WindowHandle> {
+    window: W,
+    bookmarks: Vec<Bookmark>,
+    pub cancelled: bool,
+}
+
+impl<W: WindowHandle> FavouritesPicker<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, mut bookmarks: Vec<Bookmark>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Favourites");
+        let desktop = renderer.desktop_size();
+        window.set_position(Vector2::new(
+            (desktop.x / 2 - WINDOW_SIZE / 2) as i32,
+            (desktop.y / 2 - WINDOW_SIZE / 2) as i32,
+        ));
+        // The newest ones are the likeliest to be wanted again
+        bookmarks.reverse();
+        bookmarks.truncate(NUMBER_KEYS.len());
+
+        Self {
+            window,
+            bookmarks,
+            cancelled: false,
+        }
+    }
+
+    // The chosen bookmark once a number is pressed. Escape or closing the
+    // window plays a new seed instead.
+    pub fn update(&mut self) -> Option<Bookmark> {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::KeyPressed {
+                    code: Key::Escape, ..
+                }
+                | Event::Closed => self.cancelled = true,
+                Event::KeyPressed { code, .. } => {
+                    let index = NUMBER_KEYS.iter().position(|&key| key == code);
+                    if let Some(bookmark) = index.and_then(|index| self.bookmarks.get(index)) {
+                        return Some(bookmark.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        self.draw_text("Play a favourite:", 0, Color::WHITE);
+        let lines: Vec<String> = self
+            .bookmarks
+            .iter()
+            .enumerate()
+            .map(|(index, bookmark)| format!("{}: {}", index + 1, bookmark.name))
+            .collect();
+        for (index, line) in lines.iter().enumerate() {
+            self.draw_text(line, index as u32 + 1, Color::WHITE);
+        }
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
@@ -0,0 +1,27 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use log::{info, warn};
+use sfml::system::Vector2;
+
+use crate::io_worker;
+
+// Encode RGBA pixels as a PNG on the I/O worker, so captures don't stall a frame
+pub fn save_png(path: PathBuf, size: Vector2<u32>, pixels: Vec<u8>) {
+    io_worker::spawn(move || match write_png(&path, size, &pixels) {
+        Ok(()) => info!("Saved capture to {}", path.display()),
+        Err(error) => warn!("Couldn't save capture to {}: {}", path.display(), error),
+    });
+}
+
+fn write_png(path: &PathBuf, size: Vector2<u32>, pixels: &[u8]) -> Result<(), String> {
+    let file = File::create(path).map_err(|error| error.to_string())?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), size.x, size.y);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(|error| error.to_string())
+}
@@ -0,0 +1,127 @@
+// A challenge is a scramble with rules attached. Its link is the scramble's
+// link with the rules as a query, e.g.
+//
+//   slidingpuzzle://123/456/7_8?moves=30&time=60&nohints
+//
+// for solving in at most 30 moves and a minute, without the hints or the
+// solver. A link without a query is a plain scramble.
+
+use std::time::Duration;
+
+use crate::{locale, puzzle::Grid, scramble};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rules {
+    pub move_limit: Option<u32>,
+    pub time_limit: Option<Duration>,
+    pub no_hints: bool,
+}
+
+impl Rules {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // The part of a link after the '?'
+    pub fn parse_query(query: &str) -> Result<Self, String> {
+        let mut rules = Self::default();
+
+        for rule in query.split('&').filter(|rule| !rule.is_empty()) {
+            match rule.split_once('=') {
+                Some(("moves", moves)) => {
+                    rules.move_limit = Some(
+                        moves
+                            .parse()
+                            .map_err(|_| format!("Bad move limit \"{}\"", moves))?,
+                    )
+                }
+                Some(("time", seconds)) => {
+                    rules.time_limit = Some(Duration::from_secs(
+                        seconds
+                            .parse()
+                            .map_err(|_| format!("Bad time limit \"{}\"", seconds))?,
+                    ))
+                }
+                None if rule == "nohints" => rules.no_hints = true,
+                // Playing by fewer rules than the sender meant would be unfair
+                _ => return Err(format!("Unknown challenge rule \"{}\"", rule)),
+            }
+        }
+
+        Ok(rules)
+    }
+
+    pub fn to_query(&self) -> String {
+        let mut rules = Vec::new();
+        if let Some(moves) = self.move_limit {
+            rules.push(format!("moves={}", moves));
+        }
+        if let Some(time) = self.time_limit {
+            rules.push(format!("time={}", time.as_secs()));
+        }
+        if self.no_hints {
+            rules.push("nohints".to_string());
+        }
+
+        rules.join("&")
+    }
+
+    // e.g. "30 moves, 1:00.0, no hints"
+    pub fn describe(&self) -> String {
+        let mut rules = Vec::new();
+        if let Some(moves) = self.move_limit {
+            rules.push(format!(
+                "{} move{}",
+                locale::current().format_count(moves as u64),
+                if moves == 1 { "" } else { "s" }
+            ));
+        }
+        if let Some(time) = self.time_limit {
+            rules.push(locale::current().format_duration(time));
+        }
+        if self.no_hints {
+            rules.push("no hints".to_string());
+        }
+
+        rules.join(", ")
+    }
+
+    // Why a board that isn't solved yet has already lost the challenge
+    pub fn broken(&self, moves: u32, elapsed: Duration) -> Option<&'static str> {
+        if self.move_limit.is_some_and(|limit| moves >= limit) {
+            Some("out of moves")
+        } else if self.time_limit.is_some_and(|limit| elapsed >= limit) {
+            Some("out of time")
+        } else {
+            None
+        }
+    }
+
+    // The line added to the shared summary, saying how the challenge went
+    pub fn result_line(&self, failure: Option<&str>) -> String {
+        match failure {
+            Some(reason) => format!("Challenge ({}): ❌ {}", self.describe(), reason),
+            None => format!("Challenge ({}): ✅", self.describe()),
+        }
+    }
+}
+
+// A scramble link, with or without rules
+pub fn parse(text: &str) -> Result<(Grid, Rules), String> {
+    let text = text.trim();
+    let (scramble, rules) = match text.split_once('?') {
+        Some((scramble, query)) => (scramble, Rules::parse_query(query)?),
+        None => (text, Rules::default()),
+    };
+
+    Ok((scramble::parse(scramble)?, rules))
+}
+
+pub fn to_link(grid: &Grid, rules: &Rules) -> String {
+    let link = scramble::to_link(grid);
+    if rules.is_empty() {
+        link
+    } else {
+        format!("{}?{}", link, rules.to_query())
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+use rand::Rng;
+
+use crate::{puzzle::Move, renderer::Renderer, solver, World};
+
+pub const DEFAULT_MOVES: u32 = 10;
+// Between chaos moves, when picked with --mode chaos
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+// Slides a random piece every so often for the player to work around
+pub struct Chaos {
+    pub interval: Duration,
+    // Chaos moves still to come
+    pub remaining: u32,
+    next_at: Option<Instant>,
+    last_move: Option<Move>,
+}
+
+impl Chaos {
+    pub fn new(interval: Duration, moves: u32) -> Self {
+        Self {
+            interval,
+            remaining: moves,
+            next_at: None,
+            last_move: None,
+        }
+    }
+
+    pub fn update<R: Renderer>(&mut self, world: &mut World<R>) {
+        // The clock only runs while the player is solving
+        if self.remaining == 0 || world.started_at.is_none() || world.solved {
+            self.next_at = None;
+            return;
+        }
+
+        let now = Instant::now();
+        let next_at = *self.next_at.get_or_insert(now + self.interval);
+        // Wait for the player to let go rather than pull a piece out of their hand
+        if now < next_at || world.grabbed_piece.is_some() || !world.auto_moves.is_empty() {
+            return;
+        }
+
+        // Don't just undo the last move, whether it was the player's or ours
+        let player_move = world.history.last().map(|record| record.slide);
+        let all: Vec<Move> = solver::moves(&world.grid)
+            .into_iter()
+            .map(|(step, _)| step)
+            .collect();
+        let mut options: Vec<Move> = all
+            .iter()
+            .copied()
+            .filter(|&step| Some(step) != player_move && Some(step) != self.last_move)
+            .collect();
+        if options.is_empty() {
+            options = all;
+        }
+
+        let step = options[world.rng.gen_range(0..options.len())];
+        world.auto_moves.push_back(step);
+        self.remaining -= 1;
+        self.last_move = Some(step);
+        self.next_at = Some(now + self.interval);
+
+        info!("Chaos slid piece {}", step);
+        debug!("{} chaos moves left", self.remaining);
+    }
+}
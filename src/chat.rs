@@ -0,0 +1,206 @@
+// Chat between the players of a network match, passed through the relay
+// server alongside the match itself (see ranked.rs):
+//
+//   POST /matches/<id>/chat             {"player", "text"}
+//   GET  /matches/<id>/chat?after=<n>   -> {"messages": [{"player", "text"}, ...]}
+//
+// The server echoes our own messages back, so everything shown comes from it
+// in the order it arrived there.
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use log::{debug, warn};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font,
+    ranked::{get, post, POLL_INTERVAL},
+    renderer::{Renderer, WindowHandle},
+    updates::wrap,
+};
+
+const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+// Roughly what fits across the window at 24px
+const LINE_CHARS: usize = 24;
+// What fits above the input line
+const HISTORY_LINES: usize = 8;
+const MAX_MESSAGE_LENGTH: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub player: String,
+    pub text: String,
+}
+
+pub fn parse_messages(json: &str) -> Result<Vec<ChatMessage>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|error| format!("Bad chat JSON: {}", error))?;
+
+    value
+        .get("messages")
+        .and_then(|messages| messages.as_array())
+        .ok_or("Chat has no messages")?
+        .iter()
+        .map(|message| {
+            let field = |name: &str| {
+                message
+                    .get(name)
+                    .and_then(|field| field.as_str())
+                    .map(str::to_string)
+                    .ok_or(format!("Chat message has no {}", name))
+            };
+            Ok(ChatMessage {
+                player: field("player")?,
+                text: field("text")?,
+            })
+        })
+        .collect()
+}
+
+// Sends what's typed and fetches what's new on a background thread, which
+// stops when the window it talks to goes away
+struct ChatClient {
+    outgoing: Sender<String>,
+    incoming: Receiver<Vec<ChatMessage>>,
+}
+
+impl ChatClient {
+    fn start(server: &str, match_id: &str, player: &str) -> Self {
+        let (outgoing, to_send) = mpsc::channel::<String>();
+        let (received, incoming) = mpsc::channel();
+        let url = format!("{}/matches/{}/chat", server, match_id);
+        let player = player.to_string();
+
+        thread::spawn(move || {
+            let mut seen = 0;
+            loop {
+                loop {
+                    match to_send.try_recv() {
+                        Ok(text) => {
+                            let body = serde_json::json!({ "player": player, "text": text });
+                            if let Err(error) = post(&url, body) {
+                                warn!("Couldn't send chat message: {}", error);
+                            }
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => return,
+                    }
+                }
+
+                match get(&format!("{}?after={}", url, seen)).and_then(|json| parse_messages(&json))
+                {
+                    Ok(messages) if messages.is_empty() => {}
+                    Ok(messages) => {
+                        seen += messages.len();
+                        if received.send(messages).is_err() {
+                            return;
+                        }
+                    }
+                    Err(error) => debug!("Couldn't fetch chat: {}", error),
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+        Self { outgoing, incoming }
+    }
+}
+
+// The message history with a line to type in under it
+pub struct ChatWindow<W: WindowHandle> {
+    window: W,
+    client: ChatClient,
+    history: Vec<String>,
+    input: String,
+}
+
+impl<W: WindowHandle> ChatWindow<W> {
+    pub fn open<R: Renderer<Window = W>>(
+        renderer: &mut R,
+        position: Vector2<i32>,
+        server: &str,
+        match_id: &str,
+        player: &str,
+    ) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Chat");
+        window.set_position(position);
+
+        Self {
+            window,
+            client: ChatClient::start(server, match_id, player),
+            history: Vec::new(),
+            input: String::new(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::KeyPressed {
+                    code: Key::Enter, ..
+                } => {
+                    let text = std::mem::take(&mut self.input);
+                    if !text.trim().is_empty() {
+                        let _ = self.client.outgoing.send(text.trim().to_string());
+                    }
+                }
+                Event::KeyPressed {
+                    code: Key::Backspace,
+                    ..
+                } => {
+                    self.input.pop();
+                }
+                Event::TextEntered { unicode }
+                    if !unicode.is_control() && self.input.chars().count() < MAX_MESSAGE_LENGTH =>
+                {
+                    self.input.push(unicode)
+                }
+                _ => {}
+            }
+        }
+
+        for message in self.client.incoming.try_iter().flatten() {
+            self.history.extend(wrap(
+                &format!("{}: {}", message.player, message.text),
+                LINE_CHARS,
+            ));
+        }
+        // Only the newest lines fit
+        let excess = self.history.len().saturating_sub(HISTORY_LINES);
+        self.history.drain(..excess);
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        for (index, line) in self.history.iter().enumerate() {
+            let mut text = Text::new(line, font(), 24);
+            text.set_fill_color(Color::WHITE);
+            text.set_position(Vector2::new(5.0, index as f32 * LINE_HEIGHT + 5.0));
+            self.window.draw(&text);
+        }
+
+        // The end of what's being typed, if it's too long to fit
+        let shown: String = {
+            let chars: Vec<char> = self.input.chars().collect();
+            chars[chars.len().saturating_sub(LINE_CHARS - 3)..]
+                .iter()
+                .collect()
+        };
+        let mut text = Text::new(&format!("> {}_", shown), font(), 24);
+        text.set_fill_color(Color::rgb(0, 200, 0));
+        text.set_position(Vector2::new(5.0, WINDOW_SIZE as f32 - LINE_HEIGHT - 5.0));
+        self.window.draw(&text);
+
+        self.window.display();
+    }
+}
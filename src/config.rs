@@ -0,0 +1,138 @@
+// Defaults for the command line, kept in config.toml in the config
+// directory (~/.config/slide-puzzle on Linux). Flags given on the command
+// line win over it. It's any TOML with whole numbers at the top and
+// "#rrggbb" strings in a [colors] table, e.g.
+//
+//   piece_size = 120
+//   shuffle = 50
+//
+//   [colors]
+//   in_place = "#0080ff"
+//
+// A copy with every option commented out is written the first time the game
+// runs, so there's something to find and edit.
+
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::Path};
+
+use log::{debug, info, warn};
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{
+    theme::{parse_color, Theme},
+    FRAMERATE, MIX_STEPS,
+};
+
+pub const FILE_NAME: &str = "config.toml";
+
+const DEFAULT_FILE: &str = "\
+# Defaults for slide-puzzle. Flags on the command line win over these.
+# Uncomment a line to change it.
+
+# How big each piece is and the gap between pieces, in pixels
+# piece_size = 100
+# padding = 10
+
+# Frames drawn per second
+# framerate = 60
+
+# Random slides each new board is shuffled with
+# shuffle = 7
+
+# The colours pieces start with. A theme file (--theme) takes over from these.
+[colors]
+# in_place = \"#00c800\"
+# out_of_place = \"#c80000\"
+# hidden = \"#3c3c3c\"
+# label = \"#ffffff\"
+# goal = \"#e6e6e6\"
+# cursor = \"#ffdc00\"
+";
+
+// Anything the file leaves out keeps its default
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub piece_size: u32,
+    pub padding: u32,
+    #[serde(deserialize_with = "at_least_one")]
+    pub framerate: u32,
+    #[serde(deserialize_with = "at_least_one")]
+    pub shuffle: u32,
+    #[serde(rename = "colors", deserialize_with = "colors")]
+    pub theme: Theme,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            piece_size: 100,
+            padding: 10,
+            framerate: FRAMERATE,
+            shuffle: MIX_STEPS,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|error| error.to_string().trim_end().to_string())
+    }
+
+    // The config at `path`, or the defaults if there isn't a usable one. A
+    // missing file is created with every option commented out.
+    pub fn load(path: &Path) -> Self {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                let written = path
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(path, DEFAULT_FILE));
+                match written {
+                    Ok(()) => info!("Wrote a default config to {}", path.display()),
+                    Err(error) => debug!("Couldn't write {}: {}", path.display(), error),
+                }
+                return Self::default();
+            }
+            Err(error) => {
+                warn!("Couldn't read {}: {}", path.display(), error);
+                return Self::default();
+            }
+        };
+
+        match Self::parse(&text) {
+            Ok(config) => {
+                debug!("Loaded config from {}", path.display());
+                config
+            }
+            Err(error) => {
+                warn!(
+                    "{} is invalid, using the defaults: {}",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+fn at_least_one<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match u32::deserialize(deserializer)? {
+        0 => Err(D::Error::custom("must be at least 1")),
+        number => Ok(number),
+    }
+}
+
+// The [colors] table, over the default theme
+fn colors<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Theme, D::Error> {
+    let mut theme = Theme::default();
+    for (name, value) in BTreeMap::<String, String>::deserialize(deserializer)? {
+        let color = parse_color(&value)
+            .ok_or_else(|| D::Error::custom(format!("{} isn't written \"#rrggbb\"", name)))?;
+        theme.set_color(&name, color).map_err(D::Error::custom)?;
+    }
+
+    Ok(theme)
+}
@@ -0,0 +1,379 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, warn};
+use rand::Rng;
+use sfml::{
+    system::Vector2,
+    window::{mouse, Event},
+};
+
+use crate::{
+    puzzle::{shuffle_grid, slide_piece, Grid},
+    renderer::Renderer,
+    replay::Replay,
+    solver, World,
+};
+
+// Frames spent dragging a piece across to the gap
+const DRAG_FRAMES: u32 = 20;
+// Pause on the solved board before the demo starts over
+const RESTART_DELAY: Duration = Duration::from_secs(3);
+
+// The screensaver plays slower so it's nice to watch
+const SCREENSAVER_SHUFFLE_STEPS: u32 = 30;
+const SCREENSAVER_DRAG_FRAMES: u32 = 45;
+const SCREENSAVER_MOVE_DELAY: Duration = Duration::from_millis(900);
+
+// Exhibition showcases are quicker, and take the shortest way back
+const SHOWCASE_SHUFFLE_STEPS: u32 = 40;
+const SHOWCASE_DRAG_FRAMES: u32 = 15;
+const SHOWCASE_MOVE_DELAY: Duration = Duration::from_millis(400);
+
+// Replays keep the player's timing, but a drag takes a few frames however
+// quick the real move was
+const REPLAY_DRAG_FRAMES: u32 = 6;
+const REPLAY_LEAD_IN: Duration = Duration::from_millis(500);
+
+// Each line is "<delay in ms> <piece>", the piece being slid into the gap
+const BUILTIN_SCRIPT: &str = "\
+1500 6
+600 1
+600 7
+600 5
+600 8
+600 6
+600 4
+600 2
+600 1
+600 4
+600 5
+600 8
+";
+
+pub struct DemoMove {
+    pub delay: Duration,
+    pub piece: usize,
+}
+
+enum DemoState {
+    Waiting {
+        until: Instant,
+    },
+    Dragging {
+        piece: usize,
+        start: Vector2<i32>,
+        offset: Vector2<i32>,
+        frame: u32,
+    },
+    Restarting {
+        until: Instant,
+    },
+}
+
+// Plays a scripted solve through the world's input pipeline, then loops
+pub struct Demo {
+    moves: Vec<DemoMove>,
+    next_move: usize,
+    start_grid: Grid,
+    state: DemoState,
+    drag_frames: u32,
+    // When set, every loop plays a fresh shuffle of this many steps
+    shuffle_steps: Option<u32>,
+    // Solve each shuffle like the solver would rather than undoing it
+    solve_shuffle: bool,
+    move_delay: Duration,
+}
+
+impl Demo {
+    pub fn builtin() -> Self {
+        Self::from_script(BUILTIN_SCRIPT).expect("Built-in demo script is invalid")
+    }
+
+    // Shuffle the board, then slide everything back by undoing the shuffle.
+    // The shuffle is dealt when the demo starts.
+    pub fn screensaver() -> Self {
+        Self {
+            moves: Vec::new(),
+            next_move: 0,
+            start_grid: Grid::solved(3, 3),
+            state: DemoState::Waiting {
+                until: Instant::now(),
+            },
+            drag_frames: SCREENSAVER_DRAG_FRAMES,
+            shuffle_steps: Some(SCREENSAVER_SHUFFLE_STEPS),
+            solve_shuffle: false,
+            move_delay: SCREENSAVER_MOVE_DELAY,
+        }
+    }
+
+    // Shuffle the board and have the solver solve it in as few moves as it
+    // can, for showing off at exhibitions
+    pub fn showcase() -> Self {
+        Self {
+            drag_frames: SHOWCASE_DRAG_FRAMES,
+            shuffle_steps: Some(SHOWCASE_SHUFFLE_STEPS),
+            solve_shuffle: true,
+            move_delay: SHOWCASE_MOVE_DELAY,
+            ..Self::screensaver()
+        }
+    }
+
+    fn reshuffle(&mut self, width: usize, height: usize, rng: &mut impl Rng) {
+        let Some(shuffle_steps) = self.shuffle_steps else {
+            return;
+        };
+
+        let mut grid = Grid::solved(width, height);
+        let moved_pieces = shuffle_grid(&mut grid, shuffle_steps, rng);
+
+        let pieces: Vec<i8> = match solver::solve(&grid) {
+            Some(solution) if self.solve_shuffle => {
+                solution.iter().map(|step| step.piece).collect()
+            }
+            _ => moved_pieces.into_iter().rev().collect(),
+        };
+
+        self.start_grid = grid;
+        self.moves = pieces
+            .iter()
+            .map(|&piece| DemoMove {
+                delay: self.move_delay,
+                piece: piece as usize,
+            })
+            .collect();
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let script = fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+
+        Self::from_script(&script)
+    }
+
+    pub fn from_script(script: &str) -> Result<Self, String> {
+        let mut moves = Vec::new();
+
+        for (line_number, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (delay, piece) = match fields.as_slice() {
+                [delay, piece] => (delay.parse::<u64>(), piece.parse::<usize>()),
+                _ => {
+                    return Err(format!(
+                        "Line {}: expected \"<delay ms> <piece>\"",
+                        line_number + 1
+                    ))
+                }
+            };
+
+            match (delay, piece) {
+                (Ok(delay), Ok(piece)) if (1..=8).contains(&piece) => moves.push(DemoMove {
+                    delay: Duration::from_millis(delay),
+                    piece: piece - 1,
+                }),
+                _ => {
+                    return Err(format!(
+                        "Line {}: invalid delay or piece (pieces are 1-8)",
+                        line_number + 1
+                    ))
+                }
+            }
+        }
+
+        // Work backwards from the solved board to find where the script
+        // starts. Scripts are written for the 3x3 board.
+        let mut start_grid = Grid::solved(3, 3);
+        for demo_move in moves.iter().rev() {
+            if !slide_piece(&mut start_grid, demo_move.piece as i8) {
+                return Err(format!(
+                    "Piece {} can never be next to the gap at that point in the script",
+                    demo_move.piece + 1
+                ));
+            }
+        }
+
+        Ok(Self {
+            moves,
+            next_move: 0,
+            start_grid,
+            state: DemoState::Waiting {
+                until: Instant::now(),
+            },
+            drag_frames: DRAG_FRAMES,
+            shuffle_steps: None,
+            solve_shuffle: false,
+            move_delay: Duration::ZERO,
+        })
+    }
+
+    // Plays a recorded solve back move by move, with the same pauses
+    pub fn from_replay(replay: &Replay) -> Result<Self, String> {
+        let mut grid = replay.start_grid.clone();
+        let mut moves = Vec::new();
+        let mut last_at = None;
+
+        for (index, replay_move) in replay.moves.iter().enumerate() {
+            if replay_move.power_up.is_some() {
+                return Err(format!(
+                    "Move {} uses a power-up, which can't be played back",
+                    index + 1
+                ));
+            }
+            if !slide_piece(&mut grid, replay_move.piece) {
+                return Err(format!(
+                    "Move {} slides piece {}, which isn't next to the gap",
+                    index + 1,
+                    replay_move.piece + 1
+                ));
+            }
+
+            moves.push(DemoMove {
+                delay: last_at.map_or(REPLAY_LEAD_IN, |last_at| {
+                    replay_move.at.saturating_sub(last_at)
+                }),
+                piece: replay_move.piece as usize,
+            });
+            last_at = Some(replay_move.at);
+        }
+
+        Ok(Self {
+            moves,
+            next_move: 0,
+            start_grid: replay.start_grid.clone(),
+            state: DemoState::Waiting {
+                until: Instant::now(),
+            },
+            drag_frames: REPLAY_DRAG_FRAMES,
+            shuffle_steps: None,
+            solve_shuffle: false,
+            move_delay: Duration::ZERO,
+        })
+    }
+
+    pub fn start<R: Renderer>(&mut self, world: &mut World<R>) {
+        info!("Starting demo");
+        self.reshuffle(world.width, world.height, &mut world.rng);
+        world.animate_to_grid(self.start_grid.clone());
+        self.next_move = 0;
+        self.state = DemoState::Waiting {
+            until: Instant::now() + self.next_delay(),
+        };
+    }
+
+    // Whether the last move has been played and the demo is about to start over
+    pub fn finished(&self) -> bool {
+        matches!(self.state, DemoState::Restarting { .. })
+    }
+
+    fn next_delay(&self) -> Duration {
+        self.moves
+            .get(self.next_move)
+            .map_or(Duration::ZERO, |demo_move| demo_move.delay)
+    }
+
+    pub fn update<R: Renderer>(&mut self, world: &mut World<R>) {
+        let now = Instant::now();
+
+        match self.state {
+            DemoState::Waiting { until } if now >= until => {
+                let Some(demo_move) = self.moves.get(self.next_move) else {
+                    if !world.solved {
+                        warn!("Demo script finished without solving the board");
+                    }
+                    self.state = DemoState::Restarting {
+                        until: now + RESTART_DELAY,
+                    };
+                    return;
+                };
+                let piece = demo_move.piece;
+                self.next_move += 1;
+
+                let available_move = world.get_available_move(piece);
+                if available_move.x == 0 && available_move.y == 0 {
+                    // Someone moved pieces under the demo's feet, start over
+                    warn!("Demo piece {} can't move, restarting", piece + 1);
+                    self.state = DemoState::Restarting { until: now };
+                    return;
+                }
+
+                // Press in the middle of the piece and drag it a whole cell over
+                let half_size = world.piece_size as i32 / 2;
+                let cell_size = (world.piece_size + world.padding) as i32;
+                let piece_px = world.get_px_from_grid(piece);
+                let start = Vector2::new(piece_px.x + half_size, piece_px.y + half_size);
+                let offset = Vector2::new(
+                    available_move.x as i32 * cell_size,
+                    available_move.y as i32 * cell_size,
+                );
+
+                debug!("Demo dragging piece {}", piece + 1);
+                world.mouse_override = Some(start);
+                world.injected_events.push_back((
+                    piece,
+                    Event::MouseButtonPressed {
+                        button: mouse::Button::Left,
+                        x: half_size,
+                        y: half_size,
+                    },
+                ));
+                self.state = DemoState::Dragging {
+                    piece,
+                    start,
+                    offset,
+                    frame: 0,
+                };
+            }
+            DemoState::Dragging {
+                piece,
+                start,
+                offset,
+                frame,
+            } => {
+                let frame = frame + 1;
+                let mouse_position = Vector2::new(
+                    start.x + offset.x * frame as i32 / self.drag_frames as i32,
+                    start.y + offset.y * frame as i32 / self.drag_frames as i32,
+                );
+                world.mouse_override = Some(mouse_position);
+
+                if frame < self.drag_frames {
+                    self.state = DemoState::Dragging {
+                        piece,
+                        start,
+                        offset,
+                        frame,
+                    };
+                    return;
+                }
+
+                let piece_px = world.get_px_from_grid(piece);
+                world.injected_events.push_back((
+                    piece,
+                    Event::MouseButtonReleased {
+                        button: mouse::Button::Left,
+                        x: mouse_position.x - piece_px.x,
+                        y: mouse_position.y - piece_px.y,
+                    },
+                ));
+                // The release ends the grab, so the real mouse can take over again
+                world.mouse_override = None;
+                self.state = DemoState::Waiting {
+                    until: now + self.next_delay(),
+                };
+            }
+            DemoState::Restarting { until } if now >= until => {
+                world.mouse_override = None;
+                self.start(world);
+            }
+            _ => {}
+        }
+    }
+}
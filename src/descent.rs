@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    font,
+    layout::Side,
+    puzzle::{shuffle_grid, Grid},
+    renderer::{Renderer, WindowHandle},
+    share::format_duration,
+    solver, World,
+};
+
+const FIRST_BUDGET: Duration = Duration::from_secs(60);
+// Each board gets this much of the time the last one had
+const SHRINK: f32 = 0.85;
+const MIN_BUDGET: Duration = Duration::from_secs(10);
+// Scrambles closer to solved than this are too easy to count
+const MIN_DISTANCE: u8 = 10;
+
+const WINDOW_SIZE: u32 = 200;
+const LINE_HEIGHT: f32 = 28.0;
+
+// One scramble after another, each with less time than the last, until the
+// clock runs out on one
+pub struct Descent<W: WindowHandle> {
+    pub budget: Duration,
+    pub cleared: u32,
+    pub deadline: Instant,
+    pub over: bool,
+    window: W,
+}
+
+impl<W: WindowHandle> Descent<W> {
+    // Opens the countdown left of the board and deals the first scramble
+    pub fn start<R: Renderer<Window = W>>(world: &mut World<R>) -> Self {
+        let mut window = world.renderer.create_window(WINDOW_SIZE, "Descent");
+        window.set_position(world.side_window_position(Side::Left, WINDOW_SIZE));
+
+        let mut descent = Self {
+            budget: FIRST_BUDGET,
+            cleared: 0,
+            deadline: Instant::now(),
+            over: false,
+            window,
+        };
+        descent.next_board(world);
+
+        descent
+    }
+
+    fn next_board<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        let grid = loop {
+            let mut grid = Grid::solved(3, 3);
+            shuffle_grid(&mut grid, 40, &mut world.rng);
+            if solver::distance(&grid).is_some_and(|distance| distance >= MIN_DISTANCE) {
+                break grid;
+            }
+        };
+
+        info!(
+            "Descent board {}: {} to solve it",
+            self.cleared + 1,
+            format_duration(self.budget)
+        );
+        // The clock runs from when the board is dealt, not the first move
+        self.deadline = Instant::now() + self.budget;
+        world.animate_to_grid(grid);
+    }
+
+    pub fn update<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        // Nothing to interact with, but events still need draining
+        while self.window.poll_event().is_some() {}
+
+        if self.over {
+            return;
+        }
+
+        if world.solved && world.moves > 0 {
+            self.cleared += 1;
+            println!(
+                "Board {} cleared with {} to spare",
+                self.cleared,
+                format_duration(self.remaining())
+            );
+
+            self.budget = self.budget.mul_f32(SHRINK).max(MIN_BUDGET);
+            self.next_board(world);
+        } else if self.remaining().is_zero() {
+            self.over = true;
+            println!("Out of time! {} boards cleared", self.cleared);
+            debug!("Descent ended on a {} budget", format_duration(self.budget));
+            world.playing = false;
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        let remaining = self.remaining();
+        // Red for the last ten seconds
+        let color = if remaining < Duration::from_secs(10) {
+            Color::rgb(200, 0, 0)
+        } else {
+            Color::WHITE
+        };
+        self.draw_text(&format!("Board {}", self.cleared + 1), 0, Color::WHITE);
+        self.draw_text(&format_duration(remaining), 1, color);
+        self.draw_text(&format!("Cleared: {}", self.cleared), 3, Color::WHITE);
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
@@ -0,0 +1,173 @@
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{puzzle::Grid, renderer::Renderer, solver, World};
+
+// Pause on a finished drill before the next one slides in
+const NEXT_DRILL_DELAY: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrillKind {
+    // Three pieces rotated among themselves, everything else in place
+    ThreeCycle,
+    // The top row is done, the bottom two rows are scrambled
+    LastTwoRows,
+}
+
+impl DrillKind {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "three-cycle" => Ok(DrillKind::ThreeCycle),
+            "last-two-rows" => Ok(DrillKind::LastTwoRows),
+            _ => Err(format!(
+                "Unknown drill \"{}\" (expected three-cycle or last-two-rows)",
+                name
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DrillKind::ThreeCycle => "three-cycle",
+            DrillKind::LastTwoRows => "last-two-rows",
+        }
+    }
+
+    // Optimal solution lengths that make a worthwhile drill, so trivial
+    // positions and ones that need a full solve are left out
+    fn optimal_range(&self) -> (u8, u8) {
+        match self {
+            DrillKind::ThreeCycle => (8, 24),
+            DrillKind::LastTwoRows => (8, 20),
+        }
+    }
+
+    // Drills are on the 3x3 board, which the solver knows every position of
+    fn candidate(&self, rng: &mut impl Rng) -> Grid {
+        let solved = Grid::solved(3, 3);
+        let mut grid = solved.clone();
+
+        match self {
+            DrillKind::ThreeCycle => {
+                let mut cells: Vec<usize> = (0..8).collect();
+                cells.shuffle(rng);
+                let (a, b, c) = (cells[0], cells[1], cells[2]);
+
+                grid.set_cell(a, c as i8);
+                grid.set_cell(b, a as i8);
+                grid.set_cell(c, b as i8);
+            }
+            DrillKind::LastTwoRows => {
+                // Only ever slide pieces within the bottom two rows
+                for _ in 0..40 {
+                    let mut options: Vec<Grid> = solver::moves(&grid)
+                        .into_iter()
+                        .map(|(_, next)| next)
+                        .filter(|next| next[0] == solved[0])
+                        .collect();
+                    grid = options.swap_remove(rng.gen_range(0..options.len()));
+                }
+            }
+        }
+
+        grid
+    }
+
+    // Keep generating until the solver agrees the position fits the drill
+    pub fn generate(&self, rng: &mut impl Rng) -> (Grid, u8) {
+        let (min, max) = self.optimal_range();
+
+        loop {
+            let grid = self.candidate(rng);
+            if let Some(optimal) = solver::distance(&grid) {
+                if (min..=max).contains(&optimal) {
+                    return (grid, optimal);
+                }
+            }
+        }
+    }
+}
+
+// Hands out drill positions one after another and keeps score
+pub struct Drill {
+    pub kind: DrillKind,
+    pub optimal: u8,
+    pub attempts: u32,
+    pub successes: u32,
+    next_at: Option<Instant>,
+}
+
+impl Drill {
+    pub fn new(kind: DrillKind) -> Self {
+        Self {
+            kind,
+            optimal: 0,
+            attempts: 0,
+            successes: 0,
+            next_at: None,
+        }
+    }
+
+    // A drill counts as passed within one and a half times the optimal moves
+    pub fn par(&self) -> u32 {
+        self.optimal as u32 * 3 / 2
+    }
+
+    pub fn start<R: Renderer>(&mut self, world: &mut World<R>) {
+        let (grid, optimal) = self.kind.generate(&mut world.rng);
+        self.optimal = optimal;
+        self.next_at = None;
+
+        info!(
+            "Drill {} ({}): optimal {} moves, par {}",
+            self.attempts + 1,
+            self.kind.name(),
+            optimal,
+            self.par()
+        );
+        world.animate_to_grid(grid);
+    }
+
+    pub fn update<R: Renderer>(&mut self, world: &mut World<R>) {
+        match self.next_at {
+            None if world.solved && world.moves > 0 => {
+                self.attempts += 1;
+                let passed = world.moves <= self.par();
+                if passed {
+                    self.successes += 1;
+                } else if let Some(solution) = solver::solve(&world.start_grid) {
+                    let pieces: Vec<String> =
+                        solution.iter().map(|step| step.to_string()).collect();
+                    println!("An optimal solution was: {}", pieces.join(" "));
+                }
+
+                println!(
+                    "Drill {}: {} moves (par {}), {}. {}/{} at par ({:.0}%)",
+                    self.attempts,
+                    world.moves,
+                    self.par(),
+                    if passed { "passed" } else { "missed" },
+                    self.successes,
+                    self.attempts,
+                    self.success_rate() * 100.0
+                );
+                self.next_at = Some(Instant::now() + NEXT_DRILL_DELAY);
+            }
+            Some(next_at) if Instant::now() >= next_at => {
+                debug!("Moving on to the next drill");
+                self.start(world);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn success_rate(&self) -> f32 {
+        if self.attempts == 0 {
+            0.0
+        } else {
+            self.successes as f32 / self.attempts as f32
+        }
+    }
+}
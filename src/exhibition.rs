@@ -0,0 +1,177 @@
+use std::time::{Duration, Instant};
+
+use log::info;
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    demo::Demo,
+    font, locale,
+    renderer::{Renderer, WindowHandle},
+    World,
+};
+
+const WINDOW_SIZE: u32 = 400;
+const HEADLINE_SIZE: u32 = 64;
+const SUBTITLE_SIZE: u32 = 28;
+// How long the solved showcase board stays up before visitors get a go
+const SHOWCASE_PAUSE: Duration = Duration::from_secs(2);
+const CHEER_TIME: Duration = Duration::from_secs(5);
+// A visitor's board goes back to the showcase after this long untouched
+pub const INACTIVITY_RESET: Duration = Duration::from_secs(30);
+
+enum Stage {
+    // The solver solves a fresh shuffle while people watch
+    Showcase {
+        demo: Demo,
+        finished_at: Option<Instant>,
+    },
+    // A board is dealt and waits for someone to play it
+    YourTurn,
+    // Someone solved it
+    Cheering {
+        moves: u32,
+        until: Instant,
+    },
+}
+
+// A looping mode for demo booths. The solver shows off, then invites
+// whoever is watching to have a go, and starts over once they finish or
+// walk away. Big text above the board says what's going on.
+pub struct Exhibition<W: WindowHandle> {
+    window: W,
+    stage: Stage,
+    stage_started: Instant,
+}
+
+impl<W: WindowHandle> Exhibition<W> {
+    pub fn start<R: Renderer<Window = W>>(world: &mut World<R>) -> Self {
+        let top_left = world.grid_pos_to_px(0, 0);
+        let board_size = world.board_size().x as i32;
+        let mut window = world.renderer.create_window(WINDOW_SIZE, "Exhibition");
+        window.set_position(Vector2::new(
+            top_left.x + (board_size - WINDOW_SIZE as i32) / 2,
+            (top_left.y - WINDOW_SIZE as i32 - world.padding as i32).max(0),
+        ));
+
+        let mut exhibition = Self {
+            window,
+            stage: Stage::YourTurn,
+            stage_started: Instant::now(),
+        };
+        exhibition.showcase(world);
+
+        exhibition
+    }
+
+    fn showcase<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        info!("Exhibition showcase");
+        let mut demo = Demo::showcase();
+        demo.start(world);
+        self.enter(Stage::Showcase {
+            demo,
+            finished_at: None,
+        });
+    }
+
+    fn your_turn<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        info!("Exhibition waiting for a visitor");
+        // The showcase may have been cut off in the middle of a drag
+        world.mouse_override = None;
+        world.deal_board();
+        self.enter(Stage::YourTurn);
+    }
+
+    fn enter(&mut self, stage: Stage) {
+        self.stage = stage;
+        self.stage_started = Instant::now();
+    }
+
+    pub fn update<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        // Nothing to click, but the events still need draining
+        while self.window.poll_event().is_some() {}
+
+        let now = Instant::now();
+        let touched = world
+            .touched_at
+            .is_some_and(|touched_at| touched_at > self.stage_started);
+
+        match &mut self.stage {
+            // Touching anything during the showcase is asking to play
+            Stage::Showcase { .. } if touched => self.your_turn(world),
+            Stage::Showcase { demo, finished_at } => {
+                if !demo.finished() {
+                    demo.update(world);
+                } else if now - *finished_at.get_or_insert(now) >= SHOWCASE_PAUSE {
+                    self.your_turn(world);
+                }
+            }
+            Stage::YourTurn if world.solved && world.moves > 0 => {
+                info!("Exhibition board solved in {} moves", world.moves);
+                let moves = world.moves;
+                self.enter(Stage::Cheering {
+                    moves,
+                    until: now + CHEER_TIME,
+                });
+            }
+            Stage::YourTurn => {
+                let last_activity = world.touched_at.map_or(world.last_move_at, |touched_at| {
+                    touched_at.max(world.last_move_at)
+                });
+                if now - last_activity >= INACTIVITY_RESET {
+                    info!("Nobody's played for a while, back to the showcase");
+                    self.showcase(world);
+                }
+            }
+            Stage::Cheering { until, .. } => {
+                if now >= *until || !world.solved {
+                    self.showcase(world);
+                }
+            }
+        }
+    }
+
+    // The headline and the line under it
+    pub fn lines(&self) -> [String; 2] {
+        match &self.stage {
+            Stage::Showcase { .. } => ["Watch this!".into(), "Touch a piece to play".into()],
+            Stage::YourTurn => ["Your turn!".into(), "Slide the pieces back in order".into()],
+            Stage::Cheering { moves, .. } => [
+                "Solved!".into(),
+                format!(
+                    "In {} move{}",
+                    locale::current().format_count(*moves as u64),
+                    if *moves == 1 { "" } else { "s" }
+                ),
+            ],
+        }
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        // The headline slowly pulses to catch the eye of passers-by
+        let pulse = (self.stage_started.elapsed().as_secs_f32() * 3.0).sin() * 0.5 + 0.5;
+        let [headline, subtitle] = self.lines();
+        self.draw_text(
+            &headline,
+            HEADLINE_SIZE,
+            Color::rgb(255, 200 + (pulse * 55.0) as u8, (pulse * 100.0) as u8),
+            140.0,
+        );
+        self.draw_text(&subtitle, SUBTITLE_SIZE, Color::WHITE, 240.0);
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, size: u32, color: Color, y: f32) {
+        let mut text = Text::new(string, font(), size);
+        text.set_fill_color(color);
+        let bounds = text.local_bounds();
+        text.set_origin(Vector2::new(bounds.width / 2.0, 0.0));
+        text.set_position(Vector2::new(WINDOW_SIZE as f32 / 2.0, y));
+        self.window.draw(&text);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use sfml::{
+    graphics::{Color, Drawable, IntRect},
+    system::Vector2,
+    window::{mouse, Event, Key, Scancode},
+};
+
+use crate::renderer::{Renderer, WindowHandle};
+
+struct FakeWindowState {
+    title: String,
+    position: Vector2<i32>,
+    size: u32,
+    events: VecDeque<Event>,
+    clear_color: Color,
+}
+
+struct FakeDesktop {
+    size: Vector2<u32>,
+    mouse_position: Vector2<i32>,
+    focused: Option<usize>,
+    // Window indices from bottom to top
+    stacking: Vec<usize>,
+    windows: Vec<FakeWindowState>,
+    clipboard: String,
+    // Number of things drawn for each capture
+    captures: Vec<usize>,
+    // Screens other than one covering the whole desktop, see set_monitors
+    monitors: Option<Vec<IntRect>>,
+}
+
+impl FakeDesktop {
+    fn raise(&mut self, window: usize) {
+        self.stacking.retain(|&other| other != window);
+        self.stacking.push(window);
+    }
+}
+
+// Headless renderer for tests. Clones share the same desktop, so a test can
+// keep a handle to inject input while the world owns the other one.
+#[derive(Clone)]
+pub struct FakeRenderer {
+    desktop: Rc<RefCell<FakeDesktop>>,
+}
+
+pub struct FakeWindow {
+    desktop: Rc<RefCell<FakeDesktop>>,
+    index: usize,
+}
+
+impl FakeRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            desktop: Rc::new(RefCell::new(FakeDesktop {
+                size: Vector2::new(width, height),
+                mouse_position: Vector2::new(0, 0),
+                focused: None,
+                stacking: Vec::new(),
+                windows: Vec::new(),
+                clipboard: String::new(),
+                captures: Vec::new(),
+                monitors: None,
+            })),
+        }
+    }
+
+    // The newest window with this title, as closed windows are kept
+    pub fn find_window(&self, title: &str) -> usize {
+        self.desktop
+            .borrow()
+            .windows
+            .iter()
+            .rposition(|window| window.title == title)
+            .unwrap_or_else(|| panic!("No window titled {}", title))
+    }
+
+    pub fn window_position(&self, window: usize) -> Vector2<i32> {
+        self.desktop.borrow().windows[window].position
+    }
+
+    pub fn window_size(&self, window: usize) -> u32 {
+        self.desktop.borrow().windows[window].size
+    }
+
+    pub fn top_window(&self) -> Option<usize> {
+        self.desktop.borrow().stacking.last().copied()
+    }
+
+    pub fn clipboard(&self) -> String {
+        self.desktop.borrow().clipboard.clone()
+    }
+
+    pub fn captures(&self) -> Vec<usize> {
+        self.desktop.borrow().captures.clone()
+    }
+
+    // Put text on the clipboard as if another program had copied it
+    pub fn copy(&self, text: &str) {
+        self.desktop.borrow_mut().clipboard = text.to_string();
+    }
+
+    // Plug in or unplug screens, the main one first
+    pub fn set_monitors(&self, monitors: Vec<IntRect>) {
+        self.desktop.borrow_mut().monitors = Some(monitors);
+    }
+
+    pub fn clear_color(&self, window: usize) -> Color {
+        self.desktop.borrow().windows[window].clear_color
+    }
+
+    pub fn push_event(&self, window: usize, event: Event) {
+        self.desktop.borrow_mut().windows[window]
+            .events
+            .push_back(event);
+    }
+
+    pub fn move_mouse(&self, position: Vector2<i32>) {
+        self.desktop.borrow_mut().mouse_position = position;
+    }
+
+    // Press the left button at a point inside the window, in window coordinates
+    pub fn press_mouse(&self, window: usize, x: i32, y: i32) {
+        let position = self.window_position(window);
+        self.move_mouse(Vector2::new(position.x + x, position.y + y));
+        self.push_event(
+            window,
+            Event::MouseButtonPressed {
+                button: mouse::Button::Left,
+                x,
+                y,
+            },
+        );
+    }
+
+    // Release the left button over the window, wherever the mouse currently is
+    pub fn release_mouse(&self, window: usize) {
+        let mouse_position = self.desktop.borrow().mouse_position;
+        let position = self.window_position(window);
+        self.push_event(
+            window,
+            Event::MouseButtonReleased {
+                button: mouse::Button::Left,
+                x: mouse_position.x - position.x,
+                y: mouse_position.y - position.y,
+            },
+        );
+    }
+
+    pub fn press_key(&self, window: usize, code: Key) {
+        self.press_key_with(window, code, false);
+    }
+
+    pub fn press_key_with(&self, window: usize, code: Key, ctrl: bool) {
+        self.push_event(
+            window,
+            Event::KeyPressed {
+                code,
+                scan: Scancode::Unknown,
+                alt: false,
+                ctrl,
+                shift: false,
+                system: false,
+            },
+        );
+    }
+}
+
+impl Renderer for FakeRenderer {
+    type Window = FakeWindow;
+
+    fn create_window(&mut self, size: u32, title: &str) -> FakeWindow {
+        let mut desktop = self.desktop.borrow_mut();
+        desktop.windows.push(FakeWindowState {
+            title: title.to_string(),
+            position: Vector2::new(0, 0),
+            size,
+            events: VecDeque::new(),
+            clear_color: Color::BLACK,
+        });
+
+        let index = desktop.windows.len() - 1;
+        desktop.stacking.push(index);
+
+        FakeWindow {
+            desktop: Rc::clone(&self.desktop),
+            index,
+        }
+    }
+
+    fn desktop_size(&self) -> Vector2<u32> {
+        self.desktop.borrow().size
+    }
+
+    fn desktop_mouse_position(&self) -> Vector2<i32> {
+        self.desktop.borrow().mouse_position
+    }
+
+    fn monitors(&self) -> Vec<IntRect> {
+        let size = self.desktop_size();
+        self.desktop
+            .borrow()
+            .monitors
+            .clone()
+            .unwrap_or_else(|| vec![IntRect::new(0, 0, size.x as i32, size.y as i32)])
+    }
+
+    fn get_clipboard(&self) -> String {
+        self.clipboard()
+    }
+
+    fn set_clipboard(&mut self, text: &str) {
+        self.copy(text);
+    }
+
+    // Nothing is rasterised, the canvas just stays transparent
+    fn capture(&mut self, size: Vector2<u32>, drawables: &[&dyn Drawable]) -> Option<Vec<u8>> {
+        self.desktop.borrow_mut().captures.push(drawables.len());
+
+        Some(vec![0; size.x as usize * size.y as usize * 4])
+    }
+
+    fn create_input_window(&mut self) -> Option<FakeWindow> {
+        Some(self.create_window(1, "Input"))
+    }
+}
+
+impl WindowHandle for FakeWindow {
+    fn poll_event(&mut self) -> Option<Event> {
+        self.desktop.borrow_mut().windows[self.index]
+            .events
+            .pop_front()
+    }
+
+    fn set_position(&mut self, position: Vector2<i32>) {
+        self.desktop.borrow_mut().windows[self.index].position = position;
+    }
+
+    fn set_size(&mut self, size: u32) {
+        self.desktop.borrow_mut().windows[self.index].size = size;
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.desktop.borrow_mut().windows[self.index].clear_color = color;
+    }
+
+    fn draw(&mut self, _drawable: &dyn Drawable) {}
+
+    fn display(&mut self) {}
+
+    fn mouse_position(&self) -> Vector2<i32> {
+        let desktop = self.desktop.borrow();
+        let position = desktop.windows[self.index].position;
+        Vector2::new(
+            desktop.mouse_position.x - position.x,
+            desktop.mouse_position.y - position.y,
+        )
+    }
+
+    fn has_focus(&self) -> bool {
+        self.desktop.borrow().focused == Some(self.index)
+    }
+
+    fn request_focus(&self) {
+        let mut desktop = self.desktop.borrow_mut();
+        desktop.focused = Some(self.index);
+        // Like most window managers, focusing a window also raises it
+        desktop.raise(self.index);
+    }
+
+    fn raise(&self) {
+        self.desktop.borrow_mut().raise(self.index);
+    }
+}
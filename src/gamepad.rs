@@ -0,0 +1,230 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font, io_worker,
+    profile::Profile,
+    renderer::{Renderer, WindowHandle},
+    saves::{self, Migration},
+};
+
+const WINDOW_SIZE: u32 = 300;
+
+// Changes to the mapping file's format, oldest first
+const MIGRATIONS: &[Migration] = &[];
+
+// The way a piece slides into the gap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+
+    pub fn offset(&self) -> Vector2<i8> {
+        match self {
+            Direction::Up => Vector2::new(0, -1),
+            Direction::Down => Vector2::new(0, 1),
+            Direction::Left => Vector2::new(-1, 0),
+            Direction::Right => Vector2::new(1, 0),
+        }
+    }
+}
+
+// Which controller button slides a piece in each direction. Button numbers
+// differ from one controller to the next, hence the remapping screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapping {
+    // In the order of Direction::ALL
+    pub buttons: [u32; 4],
+}
+
+impl Default for Mapping {
+    // The face buttons of an Xbox-style controller: Y, A, X, B
+    fn default() -> Self {
+        Self {
+            buttons: [3, 0, 2, 1],
+        }
+    }
+}
+
+impl Mapping {
+    pub fn direction(&self, button: u32) -> Option<Direction> {
+        Direction::ALL
+            .iter()
+            .zip(self.buttons)
+            .find(|&(_, mapped)| mapped == button)
+            .map(|(&direction, _)| direction)
+    }
+
+    // One `direction = button` line per direction
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut mapping = Self::default();
+
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (name, button) = line
+                .split_once('=')
+                .ok_or(format!("Expected \"direction = button\", got \"{}\"", line))?;
+            let index = Direction::ALL
+                .iter()
+                .position(|direction| direction.name() == name.trim())
+                .ok_or(format!("Unknown direction \"{}\"", name.trim()))?;
+            mapping.buttons[index] = button
+                .trim()
+                .parse()
+                .map_err(|_| format!("Bad button number \"{}\"", button.trim()))?;
+        }
+
+        Ok(mapping)
+    }
+
+    pub fn to_text(&self) -> String {
+        Direction::ALL
+            .iter()
+            .zip(self.buttons)
+            .map(|(direction, button)| format!("{} = {}\n", direction.name(), button))
+            .collect()
+    }
+
+    // The saved mapping, or the default one if there isn't one yet
+    pub fn load(path: &Path) -> Self {
+        match saves::read_versioned(path, MIGRATIONS) {
+            Ok(text) => Self::parse(&text).unwrap_or_else(|error| {
+                warn!("Ignoring gamepad mapping {}: {}", path.display(), error);
+                Self::default()
+            }),
+            Err(error) => {
+                debug!("No gamepad mapping at {}: {}", path.display(), error);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: PathBuf) {
+        let text = self.to_text();
+        io_worker::spawn(move || {
+            if let Err(error) = saves::write_versioned(&path, &text, MIGRATIONS) {
+                warn!(
+                    "Couldn't save gamepad mapping to {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+        });
+    }
+}
+
+pub fn default_path(profile: &Profile) -> Option<PathBuf> {
+    profile.config_dir().map(|dir| dir.join("gamepad.txt"))
+}
+
+// Asks for each direction's button in turn
+pub struct Remapper<W: WindowHandle> {
+    window: W,
+    buttons: Vec<u32>,
+    pub cancelled: bool,
+}
+
+impl<W: WindowHandle> Remapper<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Gamepad setup");
+        let desktop = renderer.desktop_size();
+        window.set_position(Vector2::new(
+            (desktop.x / 2 - WINDOW_SIZE / 2) as i32,
+            (desktop.y / 2 - WINDOW_SIZE / 2) as i32,
+        ));
+
+        Self {
+            window,
+            buttons: Vec::new(),
+            cancelled: false,
+        }
+    }
+
+    // The finished mapping once every direction has a button
+    pub fn update(&mut self) -> Option<Mapping> {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::JoystickButtonPressed { button, .. } => {
+                    // One button can't do two things
+                    if self.buttons.contains(&button) {
+                        continue;
+                    }
+                    debug!(
+                        "Button {} slides pieces {}",
+                        button,
+                        Direction::ALL[self.buttons.len()].name()
+                    );
+                    self.buttons.push(button);
+                }
+                Event::KeyPressed {
+                    code: Key::Escape, ..
+                }
+                | Event::Closed => self.cancelled = true,
+                _ => {}
+            }
+
+            if self.buttons.len() == Direction::ALL.len() {
+                let mapping = Mapping {
+                    buttons: [
+                        self.buttons[0],
+                        self.buttons[1],
+                        self.buttons[2],
+                        self.buttons[3],
+                    ],
+                };
+                info!("New gamepad mapping: {:?}", mapping.buttons);
+                return Some(mapping);
+            }
+        }
+
+        None
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        self.draw_text("Press the button to", 10.0);
+        self.draw_text("slide pieces", 40.0);
+        if let Some(direction) = Direction::ALL.get(self.buttons.len()) {
+            let mut text = Text::new(&direction.name().to_uppercase(), font(), 64);
+            text.set_fill_color(Color::rgb(0, 200, 0));
+            text.set_position(Vector2::new(10.0, 90.0));
+            self.window.draw(&text);
+        }
+        self.draw_text("Escape to cancel", 250.0);
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, y: f32) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(Color::WHITE);
+        text.set_position(Vector2::new(10.0, y));
+        self.window.draw(&text);
+    }
+}
@@ -0,0 +1,181 @@
+// Piece numbers drawn from a sprite sheet instead of the font, for pixel-art
+// themes whose digits wobble, blink or bounce. A sheet has a row for each
+// digit, 0 at the top, and each row holds that digit's animation frames
+// left to right, all the same size. A theme picks one with
+//
+//   glyphs = digits.png
+//   glyph_frames = 4
+//   glyph_fps = 8
+//
+// Each digit of a number runs a frame ahead of the one before it, so
+// numbers ripple rather than all flicking over at once.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use sfml::{
+    graphics::{Color, IntRect, Sprite, Texture, Transformable},
+    system::Vector2,
+};
+
+const DIGITS: u32 = 10;
+
+// How a theme wants its numbers drawn. Copy, like the rest of a theme, so
+// the sheet is named rather than held; see sheet() for the texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphStyle {
+    pub sheet: &'static Path,
+    pub frames: u32,
+    pub fps: f32,
+}
+
+impl GlyphStyle {
+    pub fn new(sheet: &Path) -> Self {
+        Self {
+            sheet: intern(sheet),
+            frames: 1,
+            fps: 8.0,
+        }
+    }
+
+    // Which frame a number's `digit`th digit shows `time` into its animation
+    pub fn frame_at(&self, time: Duration, digit: usize) -> u32 {
+        let frames = self.frames.max(1);
+        let tick = (time.as_secs_f32() * self.fps) as u32 % frames;
+        (tick + digit as u32) % frames
+    }
+}
+
+// Every sheet path a theme has named, kept for as long as the game runs so
+// styles can point at them. A theme that's reloaded reuses its path.
+static PATHS: Lazy<Mutex<HashSet<&'static Path>>> = Lazy::new(Default::default);
+
+pub fn intern(path: &Path) -> &'static Path {
+    let mut paths = PATHS.lock().unwrap();
+    if let Some(&interned) = paths.get(path) {
+        return interned;
+    }
+    let interned: &'static Path = Box::leak(path.to_path_buf().into_boxed_path());
+    paths.insert(interned);
+    interned
+}
+
+thread_local! {
+    // Sheets loaded so far, or None for those that couldn't be, so a bad
+    // path is only complained about once
+    static SHEETS: RefCell<HashMap<&'static Path, Option<&'static Texture>>> =
+        RefCell::new(HashMap::new());
+}
+
+// The texture for `path`, loaded the first time it's asked for. None if it
+// can't be loaded, so the font is used instead.
+pub fn sheet(path: &'static Path) -> Option<&'static Texture> {
+    SHEETS.with(|sheets| {
+        *sheets.borrow_mut().entry(path).or_insert_with(|| {
+            let texture = Texture::from_file(&path.to_string_lossy());
+            match &texture {
+                Some(_) => debug!("Loaded glyph sheet {}", path.display()),
+                None => warn!("Couldn't load glyph sheet {}", path.display()),
+            }
+            // Kept for as long as the game runs, like the font
+            texture.map(|texture| &**Box::leak(Box::new(texture)))
+        })
+    })
+}
+
+// Where `digit`'s `frame` is on a sheet of `glyph` sized cells
+pub fn frame_rect(digit: u32, frame: u32, glyph: Vector2<i32>) -> IntRect {
+    IntRect::new(
+        frame as i32 * glyph.x,
+        digit as i32 * glyph.y,
+        glyph.x,
+        glyph.y,
+    )
+}
+
+// A number laid out from a sheet, centred in a piece, one sprite a digit
+pub struct GlyphLabel {
+    style: GlyphStyle,
+    digits: Vec<(u32, Sprite<'static>)>,
+    glyph: Vector2<i32>,
+    created_at: Instant,
+}
+
+impl GlyphLabel {
+    // `number` drawn `height` pixels tall in the middle of a square
+    // `size` across whose top left is at `offset`. None without a usable
+    // sheet.
+    pub fn new(
+        number: usize,
+        style: GlyphStyle,
+        offset: Vector2<f32>,
+        size: f32,
+        height: f32,
+        color: Color,
+    ) -> Option<Self> {
+        let texture = sheet(style.sheet)?;
+        let sheet_size = texture.size();
+        let glyph = Vector2::new(
+            (sheet_size.x / style.frames.max(1)) as i32,
+            (sheet_size.y / DIGITS) as i32,
+        );
+        if glyph.x == 0 || glyph.y == 0 {
+            warn!(
+                "Glyph sheet {} is too small for {} frames of 10 digits",
+                style.sheet.display(),
+                style.frames
+            );
+            return None;
+        }
+
+        let scale = height / glyph.y as f32;
+        let digits: Vec<u32> = number
+            .to_string()
+            .chars()
+            .filter_map(|digit| digit.to_digit(10))
+            .collect();
+        let width = digits.len() as f32 * glyph.x as f32 * scale;
+        let left = offset.x + (size - width) / 2.0;
+        let top = offset.y + (size - height) / 2.0;
+
+        let digits = digits
+            .into_iter()
+            .enumerate()
+            .map(|(index, digit)| {
+                let mut sprite =
+                    Sprite::with_texture_and_rect(texture, frame_rect(digit, 0, glyph));
+                sprite.set_scale(Vector2::new(scale, scale));
+                sprite.set_position(Vector2::new(
+                    left + index as f32 * glyph.x as f32 * scale,
+                    top,
+                ));
+                sprite.set_color(color);
+                (digit, sprite)
+            })
+            .collect();
+
+        Some(Self {
+            style,
+            digits,
+            glyph,
+            created_at: Instant::now(),
+        })
+    }
+
+    // Move every digit on to its current frame, and hand them over to draw
+    pub fn sprites(&mut self) -> impl Iterator<Item = &Sprite<'static>> {
+        let time = self.created_at.elapsed();
+        for (index, (digit, sprite)) in self.digits.iter_mut().enumerate() {
+            let frame = self.style.frame_at(time, index);
+            sprite.set_texture_rect(frame_rect(*digit, frame, self.glyph));
+        }
+        self.digits.iter().map(|(_, sprite)| sprite)
+    }
+}
@@ -0,0 +1,63 @@
+use sfml::{graphics::Color, system::Vector2};
+
+use crate::{
+    puzzle::{find_in_grid, Grid},
+    MoveRecord,
+};
+
+const CELL_SIZE: u32 = 100;
+const PADDING: u32 = 10;
+
+// How many times the gap sat in each cell, counting where it started
+pub fn blank_visits(start_grid: &Grid, history: &[MoveRecord]) -> Vec<Vec<u32>> {
+    let mut visits = vec![vec![0; start_grid.width()]; start_grid.height()];
+
+    for grid in std::iter::once(start_grid).chain(history.iter().map(|record| &record.grid)) {
+        let blank = find_in_grid(grid, -1);
+        visits[blank.y as usize][blank.x as usize] += 1;
+    }
+
+    visits
+}
+
+// Draw the visits as a board of coloured cells, from dark blue for cells
+// the gap never reached up to yellow for the busiest one. Returns RGBA pixels.
+pub fn render(visits: &[Vec<u32>]) -> (Vector2<u32>, Vec<u8>) {
+    let extent = |cells: u32| CELL_SIZE * cells + PADDING * (cells - 1);
+    let size = Vector2::new(
+        extent(visits.first().map_or(0, Vec::len) as u32),
+        extent(visits.len() as u32),
+    );
+    let max = visits.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let mut pixels = vec![0; (size.x * size.y * 4) as usize];
+
+    for (y, row) in visits.iter().enumerate() {
+        for (x, &count) in row.iter().enumerate() {
+            let color = heat_color(count as f32 / max as f32);
+            let left = x as u32 * (CELL_SIZE + PADDING);
+            let top = y as u32 * (CELL_SIZE + PADDING);
+
+            for py in top..top + CELL_SIZE {
+                for px in left..left + CELL_SIZE {
+                    let i = ((py * size.x + px) * 4) as usize;
+                    pixels[i..i + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+                }
+            }
+        }
+    }
+
+    (size, pixels)
+}
+
+fn heat_color(heat: f32) -> Color {
+    let lerp = |a: u8, b: u8, t: f32| (a as f32 + (b as f32 - a as f32) * t) as u8;
+
+    // Dark blue to red over the first half, red to yellow over the second
+    if heat < 0.5 {
+        let t = heat * 2.0;
+        Color::rgb(lerp(20, 200, t), 20, lerp(80, 0, t))
+    } else {
+        let t = (heat - 0.5) * 2.0;
+        Color::rgb(lerp(200, 255, t), lerp(20, 220, t), 0)
+    }
+}
@@ -0,0 +1,69 @@
+// Callbacks a frontend can hang off the World to hear about moves, undos,
+// wins and the board locking, instead of checking its fields every frame. Each one is
+// optional and called from inside s_update, after the World has caught up
+// with whatever happened.
+
+use std::time::Duration;
+
+use crate::{puzzle::Grid, MoveRecord};
+
+// Where a game is, as far as anything watching it needs to know
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Playing,
+    Solved,
+    // Can be looked at but not played, e.g. a failed challenge
+    Locked,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Win {
+    pub start_grid: Grid,
+    pub moves: u32,
+    pub time: Duration,
+    // How many times the player asked for the next move
+    pub hints: u32,
+    // Whether it beat the best time for the board's size
+    pub best_time: bool,
+}
+
+// Also used for undos, with the move that was taken back
+pub type MoveHook = Box<dyn FnMut(&MoveRecord)>;
+pub type WinHook = Box<dyn FnMut(&Win)>;
+// Called with the old state and the new one
+pub type StateHook = Box<dyn FnMut(GameState, GameState)>;
+
+#[derive(Default)]
+pub struct Hooks {
+    pub on_move: Option<MoveHook>,
+    // A redo is heard as a move again
+    pub on_undo: Option<MoveHook>,
+    pub on_win: Option<WinHook>,
+    pub on_state_change: Option<StateHook>,
+}
+
+impl Hooks {
+    pub(crate) fn moved(&mut self, record: &MoveRecord) {
+        if let Some(on_move) = &mut self.on_move {
+            on_move(record);
+        }
+    }
+
+    pub(crate) fn undid(&mut self, record: &MoveRecord) {
+        if let Some(on_undo) = &mut self.on_undo {
+            on_undo(record);
+        }
+    }
+
+    pub(crate) fn won(&mut self, win: &Win) {
+        if let Some(on_win) = &mut self.on_win {
+            on_win(win);
+        }
+    }
+
+    pub(crate) fn state_changed(&mut self, old: GameState, new: GameState) {
+        if let Some(on_state_change) = &mut self.on_state_change {
+            on_state_change(old, new);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use once_cell::sync::Lazy;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// A single thread runs every disk write in the order it was queued, so the
+// frame loop never waits on the filesystem
+static WORKER: Lazy<Mutex<mpsc::Sender<Job>>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel::<Job>();
+
+    thread::Builder::new()
+        .name("io-worker".to_string())
+        .spawn(move || {
+            for job in receiver {
+                job();
+            }
+        })
+        .expect("Failed to spawn the I/O worker thread");
+
+    Mutex::new(sender)
+});
+
+pub fn spawn(job: impl FnOnce() + Send + 'static) {
+    if let Ok(sender) = WORKER.lock() {
+        // The worker only stops if a job panicked, in which case run it here
+        if let Err(mpsc::SendError(job)) = sender.send(Box::new(job)) {
+            job();
+        }
+    }
+}
+
+// Block until everything queued so far has been written
+pub fn flush() {
+    let (done_sender, done_receiver) = mpsc::channel();
+    spawn(move || {
+        let _ = done_sender.send(());
+    });
+    let _ = done_receiver.recv();
+}
@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use sfml::system::Vector2;
+
+use crate::{
+    io_worker,
+    profile::Profile,
+    puzzle::Grid,
+    saves::{self, Migration},
+};
+
+const MIGRATIONS: &[Migration] = &[];
+
+// Smaller pieces get fiddly to grab and their labels hard to read
+pub const MIN_PIECE_SIZE: u32 = 40;
+
+// The piece size to use so a board `width` pieces across and `height` down
+// fits on a screen `screen` big, shrinking pieces that are too big for it.
+// Errors when even the smallest usable pieces won't fit, rather than putting
+// windows off screen.
+pub fn fit_piece_size(
+    piece_size: u32,
+    padding: u32,
+    width: usize,
+    height: usize,
+    screen: Vector2<u32>,
+) -> Result<u32, String> {
+    if piece_size < MIN_PIECE_SIZE {
+        return Err(format!(
+            "Pieces must be at least {} pixels, got {}",
+            MIN_PIECE_SIZE, piece_size
+        ));
+    }
+
+    let largest = (screen.x.saturating_sub(2 * padding) / width as u32)
+        .min(screen.y.saturating_sub(2 * padding) / height as u32);
+    if largest < MIN_PIECE_SIZE {
+        return Err(format!(
+            "A {}x{} board with {} pixels between pieces can't fit on a {}x{} screen",
+            width, height, padding, screen.x, screen.y
+        ));
+    }
+
+    Ok(piece_size.min(largest))
+}
+
+// Where the board sits on the screen, and where the windows that normally
+// sit beside it go
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LayoutPreset {
+    #[default]
+    Centre,
+    // The middle of the left or right half, for ultrawide monitors
+    Left,
+    Right,
+    // Centred, with the side windows underneath, for monitors on their side
+    Stacked,
+}
+
+impl LayoutPreset {
+    pub const ALL: [Self; 4] = [Self::Centre, Self::Left, Self::Right, Self::Stacked];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Centre => "centre",
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Stacked => "stacked",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or(format!(
+                "Unknown layout \"{}\", expected centre, left, right or stacked",
+                name
+            ))
+    }
+
+    // A tall screen stacks, anything else starts centred
+    pub fn for_screen(screen: Vector2<u32>) -> Self {
+        if screen.y > screen.x {
+            Self::Stacked
+        } else {
+            Self::Centre
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Layout {
+    pub preset: LayoutPreset,
+    // The board stays at least this far from the edges of the screen
+    pub margin: u32,
+}
+
+impl Layout {
+    // Where the top left corner of the second piece along the second row
+    // goes, for a board `width` pieces across and `height` down. On a 3x3
+    // board that's the middle piece, and the rest of the board is placed
+    // around it.
+    pub fn board_center(
+        &self,
+        screen: Vector2<u32>,
+        piece_size: u32,
+        padding: u32,
+        width: usize,
+        height: usize,
+    ) -> Vector2<u32> {
+        let board_size = Vector2::new(
+            piece_size * width as u32 + padding * (width as u32 - 1),
+            piece_size * height as u32 + padding * (height as u32 - 1),
+        );
+        let middle_x = match self.preset {
+            LayoutPreset::Centre | LayoutPreset::Stacked => screen.x / 2,
+            LayoutPreset::Left => screen.x / 4,
+            LayoutPreset::Right => screen.x * 3 / 4,
+        };
+
+        let place = |middle: u32, screen: u32, board_size: u32| {
+            let furthest = screen.saturating_sub(board_size + self.margin);
+            middle
+                .saturating_sub(board_size / 2)
+                .clamp(self.margin.min(furthest), furthest)
+                + piece_size
+                + padding
+        };
+
+        Vector2::new(
+            place(middle_x, screen.x, board_size.x),
+            place(screen.y / 2, screen.y, board_size.y),
+        )
+    }
+
+    // The top left corner for a window `width` pixels wide that goes on
+    // `side` of the board. Stacked, the left side's windows go under the
+    // board's left half and the right side's under its right half.
+    pub fn side_window(
+        &self,
+        board_top_left: Vector2<i32>,
+        board_size: Vector2<u32>,
+        padding: u32,
+        side: Side,
+        width: u32,
+    ) -> Vector2<i32> {
+        let board_size = Vector2::new(board_size.x as i32, board_size.y as i32);
+        let (padding, width) = (padding as i32, width as i32);
+
+        match (self.preset, side) {
+            (LayoutPreset::Stacked, Side::Left) => Vector2::new(
+                board_top_left.x + board_size.x / 2 - width - padding / 2,
+                board_top_left.y + board_size.y + padding,
+            ),
+            (LayoutPreset::Stacked, Side::Right) => Vector2::new(
+                board_top_left.x + board_size.x / 2 + padding / 2,
+                board_top_left.y + board_size.y + padding,
+            ),
+            (_, Side::Left) => Vector2::new(board_top_left.x - width - padding, board_top_left.y),
+            (_, Side::Right) => {
+                Vector2::new(board_top_left.x + board_size.x + padding, board_top_left.y)
+            }
+        }
+    }
+}
+
+// The layout picked for each screen resolution, so a laptop that's
+// sometimes plugged into an ultrawide gets the right one for each
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutPreferences {
+    pub layouts: Vec<(Vector2<u32>, Layout)>,
+}
+
+impl LayoutPreferences {
+    // One "<width>x<height> = <preset> <margin>" line per resolution
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut preferences = Self::default();
+
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (resolution, layout) = line.split_once('=').ok_or(format!(
+                "Expected \"<resolution> = <layout>\", got \"{}\"",
+                line
+            ))?;
+            let (width, height) = resolution
+                .trim()
+                .split_once('x')
+                .ok_or(format!("Bad resolution \"{}\"", resolution.trim()))?;
+            let screen = Vector2::new(
+                width
+                    .parse()
+                    .map_err(|_| format!("Bad width \"{}\"", width))?,
+                height
+                    .parse()
+                    .map_err(|_| format!("Bad height \"{}\"", height))?,
+            );
+
+            let mut fields = layout.split_whitespace();
+            let preset = LayoutPreset::parse(fields.next().unwrap_or_default())?;
+            let margin = match fields.next() {
+                Some(margin) => margin
+                    .parse()
+                    .map_err(|_| format!("Bad margin \"{}\"", margin))?,
+                None => 0,
+            };
+            preferences.set(screen, Layout { preset, margin });
+        }
+
+        Ok(preferences)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.layouts
+            .iter()
+            .map(|(screen, layout)| {
+                format!(
+                    "{}x{} = {} {}\n",
+                    screen.x,
+                    screen.y,
+                    layout.preset.name(),
+                    layout.margin
+                )
+            })
+            .collect()
+    }
+
+    pub fn get(&self, screen: Vector2<u32>) -> Option<Layout> {
+        self.layouts
+            .iter()
+            .find(|(resolution, _)| *resolution == screen)
+            .map(|&(_, layout)| layout)
+    }
+
+    pub fn set(&mut self, screen: Vector2<u32>, layout: Layout) {
+        match self
+            .layouts
+            .iter_mut()
+            .find(|(resolution, _)| *resolution == screen)
+        {
+            Some((_, existing)) => *existing = layout,
+            None => self.layouts.push((screen, layout)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Self {
+        match saves::read_versioned(path, MIGRATIONS) {
+            Ok(text) => Self::parse(&text).unwrap_or_else(|error| {
+                warn!("Ignoring layouts {}: {}", path.display(), error);
+                Self::default()
+            }),
+            Err(error) => {
+                debug!("No layouts at {}: {}", path.display(), error);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: PathBuf) {
+        let text = self.to_text();
+        io_worker::spawn(move || {
+            if let Err(error) = saves::write_versioned(&path, &text, MIGRATIONS) {
+                warn!("Couldn't save layouts to {}: {}", path.display(), error);
+            }
+        });
+    }
+}
+
+pub fn preferences_path(profile: &Profile) -> Option<PathBuf> {
+    profile.config_dir().map(|dir| dir.join("layouts.txt"))
+}
+
+// Cells are numbered row by row, so the cell at (x, y) is y * width + x
+pub struct NeighborTable {
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl NeighborTable {
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut neighbors = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut cell_neighbors = Vec::with_capacity(4);
+
+                // Left, right, up, down
+                if x > 0 {
+                    cell_neighbors.push(y * width + x - 1);
+                }
+                if x + 1 < width {
+                    cell_neighbors.push(y * width + x + 1);
+                }
+                if y > 0 {
+                    cell_neighbors.push((y - 1) * width + x);
+                }
+                if y + 1 < height {
+                    cell_neighbors.push((y + 1) * width + x);
+                }
+
+                neighbors.push(cell_neighbors);
+            }
+        }
+
+        Self { neighbors }
+    }
+
+    pub fn neighbors(&self, cell: usize) -> &[usize] {
+        &self.neighbors[cell]
+    }
+
+    pub fn are_neighbors(&self, a: usize, b: usize) -> bool {
+        self.neighbors[a].contains(&b)
+    }
+}
+
+static TABLES: Lazy<Mutex<HashMap<(usize, usize), &'static NeighborTable>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Tables are built once per board shape and live for the rest of the program
+pub fn neighbor_table(width: usize, height: usize) -> &'static NeighborTable {
+    let mut tables = TABLES.lock().unwrap();
+
+    tables
+        .entry((width, height))
+        .or_insert_with(|| Box::leak(Box::new(NeighborTable::new(width, height))))
+}
+
+// A rearrangement of the cells of a board, such as a rotation. Entry `cell`
+// holds the cell that `cell` is moved to.
+pub type CellMap = Vec<usize>;
+
+// Every cell of a board `width` pieces across and `height` down left where
+// it is
+pub fn identity(width: usize, height: usize) -> CellMap {
+    (0..width * height).collect()
+}
+
+// A quarter turn clockwise, which only keeps the board's shape when it's
+// square
+pub fn rotate_cell(cell: usize, size: usize) -> usize {
+    let (x, y) = (cell % size, cell / size);
+    x * size + (size - 1 - y)
+}
+
+// Flipped left to right on a board `width` pieces across
+pub fn mirror_cell(cell: usize, width: usize) -> usize {
+    let (x, y) = (cell % width, cell / width);
+    y * width + (width - 1 - x)
+}
+
+// Move every cell's contents to where the map sends it
+pub fn apply_map(grid: &Grid, map: &CellMap) -> Grid {
+    let mut result = grid.clone();
+    for (cell, &target) in map.iter().enumerate() {
+        result.set_cell(target, grid.cell(cell));
+    }
+    result
+}
+
+// Undo apply_map
+pub fn unapply_map(grid: &Grid, map: &CellMap) -> Grid {
+    let mut result = grid.clone();
+    for (cell, &target) in map.iter().enumerate() {
+        result.set_cell(cell, grid.cell(target));
+    }
+    result
+}
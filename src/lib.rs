@@ -0,0 +1,3131 @@
+// The game itself, written against the Renderer and WindowHandle traits so
+// any frontend (or the tests' fake one) can drive it. The SFML windows and
+// the command line live in the binary, main.rs.
+
+pub mod analysis;
+pub mod arcade;
+pub mod board;
+pub mod bookmarks;
+pub mod capture;
+pub mod challenge;
+pub mod chaos;
+#[cfg(feature = "ranked")]
+pub mod chat;
+pub mod config;
+pub mod demo;
+pub mod descent;
+pub mod drill;
+pub mod exhibition;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fake_renderer;
+pub mod gamepad;
+pub mod glyphs;
+pub mod heatmap;
+pub mod hooks;
+pub mod io_worker;
+pub mod layout;
+pub mod locale;
+pub mod logging;
+pub mod metrics;
+pub mod modes;
+pub mod move_counter;
+pub mod notice;
+#[cfg(feature = "ranked")]
+pub mod opponent_board;
+pub mod overlay;
+pub mod packs;
+pub mod palette;
+pub mod parity;
+pub mod pattern_db;
+pub mod patterns;
+pub mod profile;
+pub mod puzzle;
+pub mod qr;
+#[cfg(feature = "ranked")]
+pub mod race;
+pub mod random;
+#[cfg(feature = "ranked")]
+pub mod ranked;
+pub mod rating;
+pub mod relay;
+pub mod renderer;
+pub mod replay;
+pub mod report;
+pub mod resize;
+pub mod resources;
+pub mod saves;
+pub mod scramble;
+pub mod share;
+pub mod simple;
+pub mod solve_job;
+pub mod solver;
+pub mod splits;
+pub mod stats;
+#[cfg(test)]
+mod tests;
+pub mod theme;
+pub mod tiles;
+pub mod timer;
+pub mod updates;
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+    time::{Duration, Instant},
+};
+
+use arcade::{PowerUp, PowerUps};
+use board::Board;
+use bookmarks::{BookmarkPrompt, Bookmarks};
+use challenge::Rules;
+use gamepad::{Direction, Mapping};
+use glyphs::{GlyphLabel, GlyphStyle};
+use hooks::{GameState, Hooks, Win};
+use layout::{
+    apply_map, mirror_cell, neighbor_table, rotate_cell, unapply_map, CellMap, Layout, Side,
+};
+use log::{debug, info, log_enabled, trace, warn, Level};
+use modes::{Classic, GameMode};
+use move_counter::MoveCounter;
+use notice::Notice;
+use once_cell::sync::Lazy;
+use overlay::Overlay;
+use palette::{Command, CommandPalette};
+use puzzle::{find_in_grid, shuffle_grid, Grid, Move};
+use qr::QrPopup;
+use rand::rngs::StdRng;
+use renderer::{Renderer, WindowHandle};
+use replay::Replay;
+use sfml::{
+    graphics::{
+        Color, Drawable, Font, Image, IntRect, RectangleShape, Shape, Sprite, Text, Texture,
+        Transformable,
+    },
+    system::Vector2,
+    window::{mouse, Event, Key},
+    SfBox,
+};
+use solve_job::{SolveJob, SolveStatus};
+use splits::{Splits, SplitsWindow};
+use stats::GameRecord;
+use theme::{PieceShape, Theme, ThemeWatcher};
+use timer::{BestTimes, TimerWindow};
+
+pub const FRAMERATE: u32 = 60;
+// Keyboard and gamepad moves beyond this many waiting are dropped
+const MOVE_BUFFER: usize = 8;
+// How often speed solving checks for input between frames
+pub const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+// How many random slides shuffle a new board
+pub const MIX_STEPS: u32 = 7;
+// The win celebration: each piece hops after the one up or left of it
+const CELEBRATION_WAVE_STEP: Duration = Duration::from_millis(120);
+const CELEBRATION_HOP_TIME: Duration = Duration::from_millis(450);
+// As a fraction of a piece
+const CELEBRATION_HOP_HEIGHT: f32 = 0.3;
+// How thick the keyboard cursor's frame is
+const CURSOR_THICKNESS: f32 = 6.0;
+// How often the screens are looked at to see if one's been unplugged
+const MONITOR_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// How many times H shows the next move each game, and how long it pulses
+pub const HINTS_PER_GAME: u32 = 3;
+const HINT_TIME: Duration = Duration::from_secs(3);
+
+pub static mut FONT: Lazy<SfBox<Font>> = Lazy::new(|| unsafe {
+    Font::from_memory(resources::load(resources::FONT))
+        .or_else(|| {
+            warn!("Installed font couldn't be loaded, using the built-in one");
+            Font::from_memory(resources::embedded(resources::FONT))
+        })
+        .unwrap()
+});
+
+pub fn font() -> &'static Font {
+    unsafe { &*FONT }
+}
+
+// How a piece stands out from the rest, e.g. to group the pieces the solver
+// is working on
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Emphasis {
+    #[default]
+    Normal,
+    // Faded towards black, out of the way
+    Dimmed,
+    // Lightened a little, part of what's being worked on
+    Focused,
+}
+
+impl Emphasis {
+    pub fn apply(self, color: Color) -> Color {
+        let mix = |channel: u8, towards: u8, amount: f32| {
+            (channel as f32 + (towards as f32 - channel as f32) * amount) as u8
+        };
+        let (towards, amount) = match self {
+            Emphasis::Normal => return color,
+            Emphasis::Dimmed => (0, 0.7),
+            Emphasis::Focused => (255, 0.25),
+        };
+
+        Color::rgb(
+            mix(color.r, towards, amount),
+            mix(color.g, towards, amount),
+            mix(color.b, towards, amount),
+        )
+    }
+}
+
+// What a piece's labels were last built for. Only a change here means new
+// text; everything else about them stays put between frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LabelKey {
+    size: u32,
+    shape: PieceShape,
+    label_color: Color,
+    goal_color: Color,
+    // Where the piece belongs, which its goal name says and the part of the
+    // image it shows comes from
+    goal_cell: usize,
+    image: bool,
+    glyphs: Option<GlyphStyle>,
+}
+
+// A piece's number, goal name and slice of the image, kept so drawing a
+// frame doesn't build them afresh
+struct PieceLabels {
+    key: LabelKey,
+    label: Text<'static>,
+    goal: Text<'static>,
+    image: Option<Sprite<'static>>,
+    // The number from the theme's sprite sheet, drawn instead of `label`
+    glyphs: Option<GlyphLabel>,
+}
+
+// What a piece's background shows. Every frame each piece is given one
+// state (World::piece_state) and the state alone picks its colour
+// (PieceState::color). `in_place` is whether the piece sits in its goal
+// cell, for the states drawn over the usual green or red.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PieceState {
+    Correct,
+    Incorrect,
+    // Under fog, where the colour would give away whether it's in place
+    Hidden,
+    // Held by the mouse, lifted a little
+    Grabbed { in_place: bool },
+    // The solver's next move after the player's been stuck a while, pulsing
+    // as it's drawn
+    Hinted { in_place: bool },
+    Dimmed { in_place: bool },
+    Focused { in_place: bool },
+    // A failed challenge, where nothing can be moved any more
+    Locked { in_place: bool },
+}
+
+impl PieceState {
+    // What an image piece is multiplied by, which can only darken it
+    pub fn image_tint(self) -> Color {
+        match self {
+            PieceState::Dimmed { .. } | PieceState::Locked { .. } => {
+                Emphasis::Dimmed.apply(Color::WHITE)
+            }
+            _ => Color::WHITE,
+        }
+    }
+
+    pub fn color(self, theme: &Theme) -> Color {
+        let base = |in_place: bool| {
+            if in_place {
+                theme.in_place
+            } else {
+                theme.out_of_place
+            }
+        };
+
+        match self {
+            PieceState::Correct => theme.in_place,
+            PieceState::Incorrect => theme.out_of_place,
+            PieceState::Hidden => theme.hidden,
+            PieceState::Hinted { in_place } => base(in_place),
+            PieceState::Grabbed { in_place } | PieceState::Focused { in_place } => {
+                Emphasis::Focused.apply(base(in_place))
+            }
+            PieceState::Dimmed { in_place } | PieceState::Locked { in_place } => {
+                Emphasis::Dimmed.apply(base(in_place))
+            }
+        }
+    }
+}
+
+pub struct PuzzlePiece<W: WindowHandle> {
+    pub window: W,
+    pub position: Vector2<f32>,
+    pub target_position: Vector2<f32>,
+    pub color: Color,
+    pub target_color: Color,
+    // Whether the label shows, which fog can take away
+    pub revealed: bool,
+    pub emphasis: Emphasis,
+    // As of the last frame drawn
+    pub state: PieceState,
+    labels: Option<PieceLabels>,
+}
+
+impl<W: WindowHandle> PuzzlePiece<W> {
+    pub fn new(window: W) -> Self {
+        Self {
+            window,
+            position: Vector2::new(0.0, 0.0),
+            target_position: Vector2::new(0.0, 0.0),
+            color: Color::BLACK,
+            target_color: Color::BLACK,
+            revealed: true,
+            emphasis: Emphasis::Normal,
+            state: PieceState::Incorrect,
+            labels: None,
+        }
+    }
+
+    pub fn set_position(&mut self, position: Vector2<f32>) {
+        self.target_position = position;
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.position != self.target_position
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.target_color = color;
+    }
+
+    // `instant` skips the glide and the colour fade, for speed solving
+    pub fn update(&mut self, instant: bool) {
+        if instant {
+            self.position = self.target_position;
+            self.color = self.target_color;
+        } else {
+            self.position = lazy_smoothing_vector2(self.position, self.target_position, 0.1);
+            self.color = lazy_smoothing_color(self.color, self.target_color, 0.1);
+        }
+        self.window
+            .set_position(Vector2::new(self.position.x as i32, self.position.y as i32));
+    }
+}
+
+// A piece the player slid, and the board as it was afterwards
+pub struct MoveRecord {
+    pub slide: Move,
+    // Time since the first move
+    pub at: Duration,
+    pub grid: Grid,
+    // Set when the piece was moved by a power-up rather than slid
+    pub power_up: Option<PowerUp>,
+}
+
+pub struct World<R: Renderer> {
+    pub renderer: R,
+    pub pieces: Vec<PuzzlePiece<R::Window>>,
+    pub grabbed_piece: Option<usize>,
+    // Pieces across the board, and down it
+    pub width: usize,
+    pub height: usize,
+    // Random slides each new board is dealt with. At least one, since a
+    // dealt board is never the solved one.
+    pub mix_steps: u32,
+    // Print how well each new board was mixed, see rating::shuffle_quality
+    pub shuffle_stats: bool,
+    pub grid: Grid,
+    pub grab_offset: Vector2<i32>,
+    pub available_move: Vector2<i8>,
+    pub piece_size: u32,
+    pub padding: u32,
+    pub center: Vector2<u32>,
+    pub layout: Layout,
+    // The screens as of the last check, so one going away can be noticed
+    monitors: Vec<IntRect>,
+    monitors_checked_at: Instant,
+    // Says why the board just moved, see check_monitors
+    notice: Option<Notice<R::Window>>,
+    pub playing: bool,
+    pub solved: bool,
+    pub exit_on_win: bool,
+    // How long the pieces celebrate a win before the results
+    pub celebration_time: Duration,
+    celebrating_since: Option<Instant>,
+    pub exit_on_input: bool,
+    pub share_results: bool,
+    pub injected_events: VecDeque<(usize, Event)>,
+    pub mouse_override: Option<Vector2<i32>>,
+    pub start_grid: Grid,
+    pub moves: u32,
+    pub started_at: Option<Instant>,
+    pub solve_time: Option<Duration>,
+    pub qr_popup: Option<QrPopup<R::Window>>,
+    // Favourite seeds, and where they're kept
+    pub bookmarks: Bookmarks,
+    pub bookmarks_path: Option<PathBuf>,
+    bookmark_prompt: Option<BookmarkPrompt<R::Window>>,
+    palette: Option<CommandPalette<R::Window>>,
+    pub history: Vec<MoveRecord>,
+    // Moves taken back with Ctrl+Z, the latest last, until a new move is made
+    pub undone: Vec<MoveRecord>,
+    pub report_path: Option<PathBuf>,
+    pub capture_dir: PathBuf,
+    pub capture_moves: bool,
+    pub overlay: Option<Overlay>,
+    pub splits: Splits,
+    pub splits_window: Option<SplitsWindow<R::Window>>,
+    pub move_counter: Option<MoveCounter<R::Window>>,
+    pub best_times: BestTimes,
+    pub timer_window: Option<TimerWindow<R::Window>>,
+    // Where every solved game is added, if anywhere
+    pub stats_path: Option<PathBuf>,
+    pub heatmap_path: Option<PathBuf>,
+    pub replay_path: Option<PathBuf>,
+    // Where the gap has been, added up over every solve this session
+    pub blank_visits: Vec<Vec<u32>>,
+    // Show a hint whenever a move lands on a known pattern
+    pub hints: bool,
+    // Write where each piece belongs under its number, e.g. r2c3
+    pub goal_labels: bool,
+    // A picture cut up across the pieces in place of their numbers, see
+    // show_image
+    image: Option<&'static Texture>,
+    pub last_hint: Option<&'static str>,
+    // How many times this game H has shown the next move, out of hint_limit
+    pub hints_used: u32,
+    pub hint_limit: u32,
+    // The piece H picked out and when, pulsing until it's moved or HINT_TIME
+    // is up
+    hinted_piece: Option<(usize, Instant)>,
+    // Pieces the solver is sliding for the player, in order
+    pub auto_moves: VecDeque<Move>,
+    // How long the solver waits before each of its moves, once the last one
+    // has landed, so a solve can be followed
+    pub auto_move_delay: Duration,
+    auto_move_landed_at: Option<Instant>,
+    // The hidden window gamepad input comes through, see share_input
+    input_window: Option<R::Window>,
+    // A solver search still running, and its progress window once it's slow
+    pub solve_job: Option<SolveJob>,
+    // The running search is for a hint, not for the solver to play
+    hint_search: bool,
+    solve_status: Option<SolveStatus<R::Window>>,
+    // Where every random choice comes from, see random.rs
+    pub rng: StdRng,
+    // Keyboard and gamepad moves waiting their turn, played one per frame
+    pub queued_moves: VecDeque<Direction>,
+    // Speed solving: pieces snap into place, buffered moves play as soon as
+    // they arrive and times are kept to the millisecond
+    pub speed: bool,
+    // The piece the keyboard cursor is on, while Tab has the arrow keys
+    // moving the cursor instead of sliding pieces
+    pub cursor: Option<usize>,
+    // Only sliding pieces is allowed, see simple.rs
+    pub simple: bool,
+    // When the latest key press, click or button press arrived, and the one
+    // behind the latest move until its piece has moved
+    input_at: Option<Instant>,
+    // When a person last pressed anything, kept after the move it led to
+    pub touched_at: Option<Instant>,
+    awaiting_motion: Option<Instant>,
+    // How long the latest move took from input to motion, for the metrics
+    pub input_latency: Option<Duration>,
+    // Where each cell of the usual layout is shown after rotating or
+    // mirroring. `grid` is the board as shown, everything that reasons about
+    // the puzzle works on canonical_grid().
+    pub orientation: CellMap,
+    // The rules the board is played by, see modes.rs
+    pub mode: Box<dyn GameMode<R>>,
+    // Only pieces next to the gap show their number
+    pub fog: bool,
+    // Arcade mode's stock of power-ups
+    pub power_ups: Option<PowerUps<R::Window>>,
+    pub shift_held: bool,
+    pub ctrl_held: bool,
+    pub theme: Theme,
+    pub theme_watcher: Option<ThemeWatcher>,
+    pub gamepad: Mapping,
+    // Pulse the solver's next piece after this long without a move
+    pub nudge_after: Option<Duration>,
+    pub last_move_at: Instant,
+    // How far into the solve the board has been rewound to, if it has
+    pub scrub: Option<usize>,
+    // The board can be looked at but not played, e.g. while the parity
+    // trainer waits for an answer
+    pub locked: bool,
+    // The 3x3-only mode being played, e.g. "--descent", which keeps the
+    // board from changing size under it
+    pub three_by_three_only: Option<&'static str>,
+    // A challenge's limits, which shared links to this board carry too
+    pub rules: Rules,
+    challenge_failed: Option<&'static str>,
+    // Told about moves, wins and state changes, see hooks.rs
+    pub hooks: Hooks,
+    // The state the hooks were last told about
+    reported_state: GameState,
+}
+
+impl<R: Renderer> World<R> {
+    // A board `width` pieces across and `height` down, with a window for
+    // every piece
+    pub fn new(
+        mut renderer: R,
+        width: usize,
+        height: usize,
+        window_size: u32,
+        padding: u32,
+        mix_steps: u32,
+    ) -> Self {
+        let mut pieces: Vec<PuzzlePiece<R::Window>> = Vec::new();
+
+        let desktop_size = renderer.desktop_size();
+        let monitors = renderer.monitors();
+        let center = Vector2::new(
+            desktop_size.x / 2 - window_size / 2,
+            desktop_size.y / 2 - window_size / 2,
+        );
+
+        let mut grid = Grid::solved(width, height);
+        for i in 0..grid.piece_count() {
+            let window = renderer.create_window(window_size, &format!("{}", i + 1));
+            debug!("Created window for piece {}", i + 1);
+
+            pieces.push(PuzzlePiece::new(window));
+        }
+
+        // Mix up the windows
+        let mut rng = random::rng();
+        shuffle_grid(&mut grid, mix_steps, &mut rng);
+        info!(
+            "Shuffled: {}",
+            rating::describe_shuffle(&rating::shuffle_quality(&grid, mix_steps))
+        );
+
+        let mut world = Self {
+            renderer,
+            pieces,
+            grabbed_piece: None,
+            width,
+            height,
+            mix_steps: mix_steps.max(1),
+            shuffle_stats: false,
+            start_grid: grid.clone(),
+            grid,
+            grab_offset: Vector2::new(0, 0),
+            available_move: Vector2::new(0, 0),
+            piece_size: window_size,
+            padding,
+            center,
+            layout: Layout::default(),
+            monitors,
+            monitors_checked_at: Instant::now(),
+            notice: None,
+            playing: true,
+            solved: false,
+            exit_on_win: true,
+            celebration_time: celebration_time(width, height),
+            celebrating_since: None,
+            exit_on_input: false,
+            share_results: true,
+            injected_events: VecDeque::new(),
+            mouse_override: None,
+            moves: 0,
+            started_at: None,
+            solve_time: None,
+            qr_popup: None,
+            bookmarks: Bookmarks::default(),
+            bookmarks_path: None,
+            bookmark_prompt: None,
+            palette: None,
+            history: Vec::new(),
+            undone: Vec::new(),
+            report_path: None,
+            capture_dir: PathBuf::from("."),
+            capture_moves: false,
+            overlay: None,
+            splits: Splits::new(),
+            splits_window: None,
+            move_counter: None,
+            best_times: BestTimes::default(),
+            timer_window: None,
+            stats_path: None,
+            heatmap_path: None,
+            replay_path: None,
+            blank_visits: vec![vec![0; width]; height],
+            hints: false,
+            goal_labels: false,
+            image: None,
+            last_hint: None,
+            hints_used: 0,
+            hint_limit: HINTS_PER_GAME,
+            hinted_piece: None,
+            auto_moves: VecDeque::new(),
+            auto_move_delay: Duration::ZERO,
+            auto_move_landed_at: None,
+            input_window: None,
+            solve_job: None,
+            hint_search: false,
+            solve_status: None,
+            rng,
+            queued_moves: VecDeque::new(),
+            speed: false,
+            cursor: None,
+            simple: false,
+            three_by_three_only: None,
+            input_at: None,
+            touched_at: None,
+            awaiting_motion: None,
+            input_latency: None,
+            orientation: layout::identity(width, height),
+            mode: Box::new(Classic),
+            fog: false,
+            power_ups: None,
+            shift_held: false,
+            ctrl_held: false,
+            theme: Theme::default(),
+            theme_watcher: None,
+            gamepad: Mapping::default(),
+            nudge_after: None,
+            last_move_at: Instant::now(),
+            scrub: None,
+            locked: false,
+            rules: Rules::default(),
+            challenge_failed: None,
+            hooks: Hooks::default(),
+            reported_state: GameState::Playing,
+        };
+        world.snap_pieces_to_grid();
+
+        world
+    }
+
+    // Replace the board layout, e.g. to set up a known position. It has to
+    // be the same shape as the board being played.
+    pub fn set_grid(&mut self, grid: Grid) {
+        if !self.fits(&grid) {
+            return;
+        }
+        debug!("Board set to {:?}", grid);
+        self.grid = apply_map(&grid, &self.orientation);
+        self.grabbed_piece = None;
+        self.reset_progress();
+        self.snap_pieces_to_grid();
+    }
+
+    fn fits(&self, grid: &Grid) -> bool {
+        let fits = (grid.width(), grid.height()) == (self.width, self.height);
+        if !fits {
+            warn!(
+                "Ignoring a {}x{} board on a {}x{} one",
+                grid.width(),
+                grid.height(),
+                self.width,
+                self.height
+            );
+        }
+        fits
+    }
+
+    // Like set_grid, but the pieces glide over to their new places
+    pub fn animate_to_grid(&mut self, grid: Grid) {
+        if !self.fits(&grid) {
+            return;
+        }
+        debug!("Board animating to {:?}", grid);
+        self.glide_to(apply_map(&grid, &self.orientation));
+        self.reset_progress();
+    }
+
+    // Show `grid`, laid out as shown rather than canonically, moving only
+    // the windows of pieces that are somewhere else on it. A piece being
+    // dragged is let go and goes back to its cell.
+    fn glide_to(&mut self, grid: Grid) {
+        let moved = Board::new(self.grid.clone()).diff(&Board::new(grid.clone()));
+        trace!("{} pieces to move", moved.len());
+        self.grid = grid;
+
+        let dropped = self.grabbed_piece.take();
+        for i in moved
+            .into_iter()
+            .map(|(piece, _, _)| piece as usize)
+            .chain(dropped)
+        {
+            let grid_px = self.get_px_from_grid(i);
+            self.pieces[i].set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+        }
+    }
+
+    // A freshly shuffled board, never one that's already solved
+    pub fn deal_board(&mut self) {
+        let solved = Grid::solved(self.width, self.height);
+        let grid = loop {
+            let mut grid = solved.clone();
+            shuffle_grid(&mut grid, self.mix_steps, &mut self.rng);
+            if grid != solved {
+                break grid;
+            }
+        };
+
+        // Rating a board runs the solver, so only when someone will see it
+        let logged = log_enabled!(Level::Info);
+        if logged {
+            match rating::rate(&grid) {
+                Some(rating) => info!("Dealt a new board, rated {}", rating::describe(&rating)),
+                None => info!("Dealt a new board"),
+            }
+        }
+        if logged || self.shuffle_stats {
+            let quality = rating::describe_shuffle(&rating::shuffle_quality(&grid, self.mix_steps));
+            info!("Shuffled: {}", quality);
+            if self.shuffle_stats {
+                println!("Shuffled {}", quality);
+            }
+        }
+        self.animate_to_grid(grid);
+    }
+
+    // After a win every piece hops in turn, a wave rolling from the top left
+    // corner to the bottom right, and then the results are shown
+    fn celebrate(&mut self) {
+        let Some(since) = self.celebrating_since else {
+            return;
+        };
+        let elapsed = since.elapsed();
+
+        if elapsed >= self.celebration_time {
+            self.celebrating_since = None;
+            for piece in &mut self.pieces {
+                piece.window.set_position(Vector2::new(
+                    piece.position.x as i32,
+                    piece.position.y as i32,
+                ));
+            }
+            self.show_results();
+            return;
+        }
+
+        let height = self.piece_size as f32 * CELEBRATION_HOP_HEIGHT;
+        for i in 0..self.pieces.len() {
+            let cell = self.get_grid_pos(i);
+            let delay = CELEBRATION_WAVE_STEP * (cell.x + cell.y) as u32;
+            let hop =
+                elapsed.saturating_sub(delay).as_secs_f32() / CELEBRATION_HOP_TIME.as_secs_f32();
+            let lift = if elapsed >= delay && hop < 1.0 {
+                (hop * std::f32::consts::PI).sin() * height
+            } else {
+                0.0
+            };
+
+            let position = self.pieces[i].position;
+            self.pieces[i]
+                .window
+                .set_position(Vector2::new(position.x as i32, (position.y - lift) as i32));
+        }
+    }
+
+    // A board that can no longer be solved within a challenge's limits is
+    // locked, and the results say it was failed
+    fn enforce_rules(&mut self) {
+        if self.solved || self.challenge_failed.is_some() || self.scrub.is_some() {
+            return;
+        }
+        let Some(reason) = self.rules.broken(self.moves, self.elapsed()) else {
+            return;
+        };
+
+        println!("Challenge failed, {}", reason);
+        self.challenge_failed = Some(reason);
+        self.release_grab();
+        self.locked = true;
+        self.show_results();
+    }
+
+    // Where the game is, for the hooks
+    pub fn game_state(&self) -> GameState {
+        if self.locked {
+            GameState::Locked
+        } else if self.solved {
+            GameState::Solved
+        } else {
+            GameState::Playing
+        }
+    }
+
+    fn show_results(&mut self) {
+        if self.share_results {
+            let mut summary = share::summary(&self.start_grid, self.moves, self.elapsed());
+            if !self.rules.is_empty() {
+                summary.push('\n');
+                summary.push_str(&self.rules.result_line(self.challenge_failed));
+            }
+            println!("{}", summary);
+            self.renderer.set_clipboard(&summary);
+        }
+
+        if self.exit_on_win {
+            self.playing = false;
+        }
+    }
+
+    // Start counting moves and time afresh from the current board
+    fn reset_progress(&mut self) {
+        self.start_grid = self.canonical_grid();
+        self.moves = 0;
+        self.hints_used = 0;
+        self.hinted_piece = None;
+        self.started_at = None;
+        self.solve_time = None;
+        self.history.clear();
+        self.undone.clear();
+        self.queued_moves.clear();
+        self.last_move_at = Instant::now();
+        self.splits.reset();
+        if let Some(power_ups) = &mut self.power_ups {
+            power_ups.reset();
+        }
+        // A failed challenge can be tried again from the start
+        if self.challenge_failed.take().is_some() {
+            self.locked = false;
+        }
+
+        if self.capture_moves {
+            self.capture_move();
+        }
+    }
+
+    // Move the board to where `layout` puts it on the screen
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        let center = layout.board_center(
+            self.renderer.desktop_size(),
+            self.piece_size,
+            self.padding,
+            self.width,
+            self.height,
+        );
+        self.set_center(center);
+    }
+
+    // Every so often see whether the screens have changed, and if the one
+    // the board was on has gone, bring the board back to the main screen
+    fn check_monitors(&mut self) {
+        if self.monitors_checked_at.elapsed() < MONITOR_CHECK_INTERVAL {
+            return;
+        }
+        self.monitors_checked_at = Instant::now();
+
+        let monitors = self.renderer.monitors();
+        if monitors == self.monitors {
+            return;
+        }
+        debug!("Screens changed from {:?} to {:?}", self.monitors, monitors);
+        self.monitors = monitors;
+
+        let top_left = self.grid_pos_to_px(0, 0);
+        let board_size = self.board_size();
+        let middle = Vector2::new(
+            top_left.x + board_size.x as i32 / 2,
+            top_left.y + board_size.y as i32 / 2,
+        );
+        let on_screen = |screen: &IntRect| {
+            (screen.left..screen.left + screen.width).contains(&middle.x)
+                && (screen.top..screen.top + screen.height).contains(&middle.y)
+        };
+        if self.monitors.iter().any(on_screen) {
+            return;
+        }
+        let Some(&screen) = self.monitors.first() else {
+            return;
+        };
+
+        let center = self.layout.board_center(
+            Vector2::new(screen.width as u32, screen.height as u32),
+            self.piece_size,
+            self.padding,
+            self.width,
+            self.height,
+        );
+        self.set_center(Vector2::new(
+            (center.x as i32 + screen.left).max(0) as u32,
+            (center.y as i32 + screen.top).max(0) as u32,
+        ));
+        info!("The board's screen went away, moved it to {:?}", screen);
+
+        let position = self.side_window_position(Side::Right, notice::WINDOW_SIZE);
+        self.notice = Some(Notice::open(
+            &mut self.renderer,
+            position,
+            &["Screen disconnected", "", "Moved the board", "to this one"],
+        ));
+    }
+
+    // The top left corner for a window `width` pixels wide beside the board
+    pub fn side_window_position(&mut self, side: Side, width: u32) -> Vector2<i32> {
+        let top_left = self.grid_pos_to_px(0, 0);
+        let board_size = self.board_size();
+        self.layout
+            .side_window(top_left, board_size, self.padding, side, width)
+    }
+
+    // How wide and tall the whole board is, gaps between pieces included
+    pub fn board_size(&self) -> Vector2<u32> {
+        let extent =
+            |pieces: usize| self.piece_size * pieces as u32 + self.padding * (pieces as u32 - 1);
+        Vector2::new(extent(self.width), extent(self.height))
+    }
+
+    // Move the whole board, e.g. to make room for another one
+    pub fn set_center(&mut self, center: Vector2<u32>) {
+        self.center = center;
+        self.snap_pieces_to_grid();
+    }
+
+    // Scale the board, keeping its top left corner where it is. The pieces
+    // glide over to their new places.
+    pub fn resize(&mut self, piece_size: u32, padding: u32) {
+        let top_left = self.grid_pos_to_px(0, 0);
+        debug!("Resizing pieces to {} with {} padding", piece_size, padding);
+        self.piece_size = piece_size;
+        self.padding = padding;
+        // The centre is the second piece along the second row's top left
+        self.center = Vector2::new(
+            (top_left.x + (piece_size + padding) as i32).max(0) as u32,
+            (top_left.y + (piece_size + padding) as i32).max(0) as u32,
+        );
+
+        for i in 0..self.pieces.len() {
+            self.pieces[i].window.set_size(piece_size);
+            let grid_px = self.get_px_from_grid(i);
+            self.pieces[i].set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+        }
+    }
+
+    // Place every window at its grid position immediately, without animating
+    fn snap_pieces_to_grid(&mut self) {
+        for i in 0..self.pieces.len() {
+            let grid_px = self.get_px_from_grid(i);
+            let grid_px_f32 = Vector2::new(grid_px.x as f32, grid_px.y as f32);
+
+            self.pieces[i].position = grid_px_f32;
+            self.pieces[i].target_position = grid_px_f32;
+
+            // TODO: Set the color of the window
+            self.pieces[i].color = Color::BLACK;
+            self.pieces[i].target_color = Color::BLACK;
+
+            self.pieces[i].window.set_position(grid_px);
+        }
+    }
+
+    // Handle an input event received by the window of piece `i`
+    pub fn handle_event(&mut self, i: usize, event: Event) {
+        match event {
+            _ if self.simple && !simple::allows(&event) => {
+                trace!("Ignoring {:?} in simple mode", event)
+            }
+            // Any key stops a search, and does nothing else
+            Event::KeyPressed { code, .. }
+                if self.solve_job.is_some() && !solve_job::is_modifier(code) =>
+            {
+                info!("Search cancelled");
+                self.cancel_solve();
+            }
+            Event::KeyPressed {
+                code: Key::Escape, ..
+            } => {
+                info!("Escape pressed, quitting");
+                self.playing = false;
+            }
+            // Click events don't say which modifiers are held, so keep track
+            Event::KeyPressed {
+                code: Key::LShift | Key::RShift,
+                ..
+            } => self.shift_held = true,
+            Event::KeyReleased {
+                code: Key::LShift | Key::RShift,
+                ..
+            } => self.shift_held = false,
+            Event::KeyPressed {
+                code: Key::LControl | Key::RControl,
+                ..
+            } => self.ctrl_held = true,
+            Event::KeyReleased {
+                code: Key::LControl | Key::RControl,
+                ..
+            } => self.ctrl_held = false,
+            // Step through a finished solve
+            Event::KeyPressed {
+                code: code @ (Key::Left | Key::Right | Key::Home | Key::End),
+                ..
+            } if self.solved => {
+                let position = self.scrub.unwrap_or(self.history.len());
+                match code {
+                    Key::Left => self.scrub_to(position.saturating_sub(1)),
+                    Key::Right => self.scrub_to(position + 1),
+                    Key::Home => self.scrub_to(0),
+                    _ => self.scrub_to(self.history.len()),
+                }
+            }
+            Event::KeyPressed { code: Key::Tab, .. } => self.toggle_cursor(),
+            // With the cursor on, the arrow keys pick a piece and Enter slides it
+            Event::KeyPressed {
+                code: code @ (Key::Up | Key::Down | Key::Left | Key::Right),
+                ..
+            } if self.cursor.is_some() => {
+                let direction = match code {
+                    Key::Up => Direction::Up,
+                    Key::Down => Direction::Down,
+                    Key::Left => Direction::Left,
+                    _ => Direction::Right,
+                };
+                self.move_cursor(direction);
+            }
+            Event::KeyPressed {
+                code: Key::Enter, ..
+            } if self.cursor.is_some() && !self.locked && self.scrub.is_none() => {
+                self.slide_selected()
+            }
+            // The arrow keys slide the piece next to the gap, like the gamepad
+            Event::KeyPressed {
+                code: code @ (Key::Up | Key::Down | Key::Left | Key::Right),
+                ..
+            } if !self.locked && self.scrub.is_none() => {
+                let direction = match code {
+                    Key::Up => Direction::Up,
+                    Key::Down => Direction::Down,
+                    Key::Left => Direction::Left,
+                    _ => Direction::Right,
+                };
+                self.queue_slide(direction);
+            }
+            // Cmd+K on macOS
+            Event::KeyPressed {
+                code: Key::K,
+                ctrl,
+                system,
+                ..
+            } if ctrl || system => self.open_palette(),
+            Event::KeyPressed { code: Key::Q, .. } => self.toggle_qr_popup(),
+            Event::KeyPressed { code: Key::B, .. } => self.open_bookmark_prompt(),
+            Event::KeyPressed { code: Key::P, .. } => self.capture_screenshot(),
+            Event::KeyPressed { code: Key::H, .. } => self.show_hint(true),
+            Event::KeyPressed { code: Key::S, .. } => self.auto_solve_board(),
+            Event::KeyPressed { code: Key::G, .. } => {
+                self.goal_labels = !self.goal_labels;
+                debug!(
+                    "Goal labels {}",
+                    if self.goal_labels { "shown" } else { "hidden" }
+                );
+            }
+            // Turning the board would turn gravity sideways, and only square
+            // boards still fit the window on their side
+            Event::KeyPressed { code: Key::R, .. }
+                if !self.mode.pieces_fall() && self.width == self.height =>
+            {
+                self.transform_board("rotated", rotate_cell)
+            }
+            Event::KeyPressed { code: Key::M, .. } if !self.mode.pieces_fall() => {
+                self.transform_board("mirrored", mirror_cell)
+            }
+            // Ctrl+1-5 solves a row, Alt+1-5 a column, as far as the board goes
+            Event::KeyPressed {
+                code: code @ (Key::Num1 | Key::Num2 | Key::Num3 | Key::Num4 | Key::Num5),
+                ctrl,
+                alt,
+                ..
+            } if ctrl || alt => {
+                let index = match code {
+                    Key::Num1 => 0,
+                    Key::Num2 => 1,
+                    Key::Num3 => 2,
+                    Key::Num4 => 3,
+                    _ => 4,
+                };
+                let (width, height) = (self.width, self.height);
+                if ctrl && index < height {
+                    let cells: Vec<usize> = (index * width..(index + 1) * width).collect();
+                    self.auto_solve(&cells);
+                } else if alt && index < width {
+                    let cells: Vec<usize> = (0..height).map(|row| row * width + index).collect();
+                    self.auto_solve(&cells);
+                }
+            }
+            // Right clicking a piece solves the row it's in
+            Event::MouseButtonPressed {
+                button: mouse::Button::Right,
+                ..
+            } => {
+                let row = self.get_grid_pos(i).y as usize;
+                let cells: Vec<usize> = (row * self.width..(row + 1) * self.width).collect();
+                self.auto_solve(&cells);
+            }
+            Event::JoystickButtonPressed { button, .. } if !self.locked => {
+                match self.gamepad.direction(button) {
+                    Some(direction) => self.queue_slide(direction),
+                    None => trace!("Gamepad button {} isn't mapped", button),
+                }
+            }
+            // Cmd+Z and Cmd+Y on macOS
+            Event::KeyPressed {
+                code: code @ (Key::Z | Key::Y),
+                ctrl,
+                system,
+                ..
+            } if ctrl || system => {
+                if code == Key::Z {
+                    self.undo()
+                } else {
+                    self.redo()
+                }
+            }
+            // Cmd+V on macOS
+            Event::KeyPressed {
+                code: Key::V,
+                ctrl,
+                system,
+                ..
+            } if ctrl || system => self.paste_scramble(),
+            // Shift+click two neighbours to swap them, Ctrl+click a piece to
+            // swap it with the gap
+            Event::MouseButtonPressed {
+                button: mouse::Button::Left,
+                ..
+            } if self.power_ups.is_some()
+                && (self.shift_held || self.ctrl_held)
+                && self.auto_moves.is_empty() =>
+            {
+                if self.ctrl_held {
+                    self.teleport(i);
+                } else {
+                    self.select_swap(i);
+                }
+            }
+            Event::MouseButtonPressed { button, x, y } => {
+                if button == mouse::Button::Left
+                    && self.auto_moves.is_empty()
+                    && self.scrub.is_none()
+                    && !self.locked
+                {
+                    // Fast hands can let go outside every window, so the
+                    // release never arrives. Settle that piece first rather
+                    // than leaving it stranded mid-slide.
+                    self.release_grab();
+
+                    self.grabbed_piece = Some(i);
+                    self.pieces[i].window.raise();
+                    self.available_move = self.get_available_move(i);
+                    if self.available_move.x != 0 || self.available_move.y != 0 {
+                        self.grab_offset = Vector2::new(x, y);
+                    }
+                    debug!(
+                        "Grabbed piece {} at ({}, {}), available move ({}, {})",
+                        i + 1,
+                        x,
+                        y,
+                        self.available_move.x,
+                        self.available_move.y
+                    );
+                }
+            }
+            Event::MouseButtonReleased {
+                button: mouse::Button::Left,
+                ..
+            } => self.release_grab(),
+            _ => {}
+        }
+    }
+
+    // Let go of the grabbed piece, which slides into the gap if it was
+    // dragged past halfway and back otherwise
+    fn release_grab(&mut self) {
+        let Some(grabbed_window) = self.grabbed_piece else {
+            return;
+        };
+
+        // If the window can move
+        if self.available_move.x != 0 || self.available_move.y != 0 {
+            let current_grid_pos = self.get_grid_pos(grabbed_window);
+            let current_grid_px = self.get_px_from_grid(grabbed_window);
+
+            let available_grid_pos = self.slide_destination(current_grid_pos);
+            let available_grid_px =
+                self.grid_pos_to_px(available_grid_pos.x as usize, available_grid_pos.y as usize);
+
+            let window_position = self.pieces[grabbed_window].position;
+            let mut moved = false;
+
+            // If the window can move horizontally
+            if self.available_move.x != 0 {
+                // If the window can move left
+                if self.available_move.x > 0 {
+                    if window_position.x
+                        > current_grid_px.x as f32
+                            + (self.padding / 2) as f32
+                            + (self.piece_size / 2) as f32
+                    {
+                        self.grid[current_grid_pos.y as usize][current_grid_pos.x as usize] = -1;
+                        self.grid[available_grid_pos.y as usize][available_grid_pos.x as usize] =
+                            grabbed_window as i8;
+
+                        moved = true;
+                    }
+                } else {
+                    // If the window can move right
+                    if window_position.x
+                        < current_grid_px.x as f32
+                            - (self.padding / 2) as f32
+                            - (self.piece_size / 2) as f32
+                    {
+                        self.grid[current_grid_pos.y as usize][current_grid_pos.x as usize] = -1;
+                        self.grid[available_grid_pos.y as usize][available_grid_pos.x as usize] =
+                            grabbed_window as i8;
+
+                        moved = true;
+                    }
+                }
+            }
+            // If the window can move vertically
+            else {
+                // If the window can move up
+                if self.available_move.y > 0 {
+                    if window_position.y
+                        > current_grid_px.y as f32
+                            + (self.padding / 2) as f32
+                            + (self.piece_size / 2) as f32
+                    {
+                        self.grid[current_grid_pos.y as usize][current_grid_pos.x as usize] = -1;
+                        self.grid[available_grid_pos.y as usize][available_grid_pos.x as usize] =
+                            grabbed_window as i8;
+
+                        moved = true;
+                    }
+                } else {
+                    // If the window can move down
+                    if window_position.y
+                        < current_grid_px.y as f32
+                            - (self.padding / 2) as f32
+                            - (self.piece_size / 2) as f32
+                    {
+                        self.grid[current_grid_pos.y as usize][current_grid_pos.x as usize] = -1;
+                        self.grid[available_grid_pos.y as usize][available_grid_pos.x as usize] =
+                            grabbed_window as i8;
+
+                        moved = true;
+                    }
+                }
+            }
+
+            if moved {
+                self.record_move(grabbed_window);
+                info!(
+                    "Moved piece {} from ({}, {}) to ({}, {})",
+                    grabbed_window + 1,
+                    current_grid_pos.x,
+                    current_grid_pos.y,
+                    available_grid_pos.x,
+                    available_grid_pos.y
+                );
+            }
+
+            // If the window didn't move reset its position
+            if !moved {
+                self.pieces[grabbed_window].set_position(Vector2::new(
+                    current_grid_px.x as f32,
+                    current_grid_px.y as f32,
+                ));
+            } else {
+                self.pieces[grabbed_window].set_position(Vector2::new(
+                    available_grid_px.x as f32,
+                    available_grid_px.y as f32,
+                ));
+            }
+        }
+
+        // Reset the grabbed window
+        debug!("Released piece {}", grabbed_window + 1);
+        self.grabbed_piece = None;
+    }
+
+    // Handle whatever input has arrived. Speed solving also calls this
+    // between frames, so a key press never waits for the next one.
+    pub fn poll_input(&mut self) {
+        // Synthetic input goes through the same handling as real window events
+        while let Some((i, event)) = self.injected_events.pop_front() {
+            self.handle_event(i, event);
+        }
+
+        // The gamepad is heard by every window, so with an input window it's
+        // only listened to there
+        let mut gamepad_events = Vec::new();
+        if let Some(window) = &mut self.input_window {
+            while let Some(event) = window.poll_event() {
+                if is_gamepad_event(&event) {
+                    gamepad_events.push(event);
+                }
+            }
+        }
+        for event in gamepad_events {
+            self.take_input(self.cursor.unwrap_or(0), event);
+        }
+
+        for i in 0..self.pieces.len() {
+            while let Some(event) = self.pieces[i].window.poll_event() {
+                if self.input_window.is_some() && is_gamepad_event(&event) {
+                    continue;
+                }
+                self.take_input(i, event);
+            }
+        }
+
+        if self.speed {
+            self.play_queued_moves();
+        }
+    }
+
+    fn take_input(&mut self, i: usize, event: Event) {
+        // Like any screensaver, stop as soon as someone touches something
+        if self.exit_on_input
+            && matches!(
+                event,
+                Event::KeyPressed { .. } | Event::MouseButtonPressed { .. }
+            )
+        {
+            info!("Input received, exiting");
+            self.playing = false;
+            return;
+        }
+
+        if matches!(
+            event,
+            Event::KeyPressed { .. }
+                | Event::MouseButtonPressed { .. }
+                | Event::JoystickButtonPressed { .. }
+        ) {
+            self.input_at = Some(Instant::now());
+            self.touched_at = self.input_at;
+        }
+        self.handle_event(i, event);
+    }
+
+    // Take gamepad input from one hidden window instead of every piece's,
+    // so each button press arrives once. False if the renderer can't make
+    // one.
+    pub fn share_input(&mut self) -> bool {
+        if self.input_window.is_none() {
+            self.input_window = self.renderer.create_input_window();
+        }
+        self.input_window.is_some()
+    }
+
+    pub fn s_update(&mut self) {
+        self.poll_input();
+
+        for i in 0..self.pieces.len() {
+            let was_animating = self.pieces[i].is_animating();
+            self.pieces[i].update(self.speed);
+            if was_animating && !self.pieces[i].is_animating() {
+                trace!(
+                    "Piece {} settled at ({}, {})",
+                    i + 1,
+                    self.pieces[i].position.x,
+                    self.pieces[i].position.y
+                );
+            }
+        }
+        // The windows have just moved, so whatever move was waiting is on screen
+        if let Some(input_at) = self.awaiting_motion.take() {
+            self.input_latency = Some(input_at.elapsed());
+        }
+
+        // Play the next queued solver or gravity move once the last one has settled
+        if self.auto_moves.is_empty() {
+            self.auto_move_landed_at = None;
+        } else if self.grabbed_piece.is_none()
+            && self.pieces.iter().all(|piece| !piece.is_animating())
+        {
+            // Falling pieces don't wait for each other
+            let landed_at = *self.auto_move_landed_at.get_or_insert_with(Instant::now);
+            let waited = self.mode.pieces_fall() || landed_at.elapsed() >= self.auto_move_delay;
+            if let Some(step) = waited.then(|| self.auto_moves.pop_front()).flatten() {
+                self.auto_move_landed_at = None;
+                if self.slide(step.piece) {
+                    let grid_px = self.get_px_from_grid(step.piece as usize);
+                    self.pieces[step.piece as usize]
+                        .set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+                    trace!("Solver slid piece {}", step);
+                } else {
+                    warn!("Solver move for piece {} no longer fits, stopping", step);
+                    self.auto_moves.clear();
+                }
+            }
+        }
+
+        self.play_queued_moves();
+        self.update_solve();
+        // The solver's group stays picked out until its last piece lands
+        if self.solve_job.is_none()
+            && self.auto_moves.is_empty()
+            && self.pieces.iter().all(|piece| !piece.is_animating())
+        {
+            self.clear_focus();
+        }
+
+        if self.fog {
+            self.update_fog();
+        }
+
+        // Pieces fade to the new colours like any other colour change
+        if let Some(theme) = self
+            .theme_watcher
+            .as_mut()
+            .and_then(|theme_watcher| theme_watcher.poll())
+        {
+            self.theme = theme;
+        }
+
+        self.check_monitors();
+        if self.notice.as_mut().is_some_and(|notice| !notice.update()) {
+            self.notice = None;
+        }
+
+        if let Some(qr_popup) = &mut self.qr_popup {
+            if !qr_popup.update() {
+                debug!("Closed QR popup");
+                self.qr_popup = None;
+            }
+        }
+
+        if let Some(prompt) = &mut self.bookmark_prompt {
+            if let Some(name) = prompt.update() {
+                self.bookmark_prompt = None;
+                self.bookmarks.add(&name, random::seed());
+                if let Some(path) = &self.bookmarks_path {
+                    self.bookmarks.save(path.clone());
+                }
+            } else if prompt.cancelled {
+                debug!("Bookmark cancelled");
+                self.bookmark_prompt = None;
+            }
+        }
+
+        if let Some(palette) = &mut self.palette {
+            if let Some(command) = palette.update() {
+                self.palette = None;
+                self.run_command(command);
+            } else if palette.cancelled {
+                debug!("Command palette closed");
+                self.palette = None;
+            }
+        }
+
+        // Grabbed window logic
+        if let Some(grabbed_window) = self.grabbed_piece {
+            // Get the current position of the grabbed window
+            let current_grid_px = self.get_px_from_grid(grabbed_window);
+
+            // Get where the piece would be one cell over, which is off the
+            // board when it wraps around to the gap
+            let step = (self.piece_size + self.padding) as i32;
+            let available_grid_px = Vector2::new(
+                current_grid_px.x + self.available_move.x as i32 * step,
+                current_grid_px.y + self.available_move.y as i32 * step,
+            );
+
+            // Calculate the new position of the grabbed window
+            let mouse_position = self.mouse_position();
+            let new_x = if self.available_move.x != 0 {
+                (mouse_position.x - self.grab_offset.x).clamp(
+                    std::cmp::min(current_grid_px.x, available_grid_px.x),
+                    std::cmp::max(current_grid_px.x, available_grid_px.x),
+                )
+            } else {
+                current_grid_px.x
+            };
+            let new_y = if self.available_move.y != 0 {
+                (mouse_position.y - self.grab_offset.y).clamp(
+                    std::cmp::min(current_grid_px.y, available_grid_px.y),
+                    std::cmp::max(current_grid_px.y, available_grid_px.y),
+                )
+            } else {
+                current_grid_px.y
+            };
+
+            // Set the position
+            self.pieces[grabbed_window].position = Vector2::new(new_x as f32, new_y as f32);
+            self.pieces[grabbed_window].target_position = Vector2::new(new_x as f32, new_y as f32);
+            self.pieces[grabbed_window]
+                .window
+                .set_position(Vector2::new(new_x, new_y));
+        }
+
+        // Whatever the mode does by itself, with the board to itself
+        let mut mode = std::mem::replace(&mut self.mode, Box::new(Classic));
+        mode.tick(self);
+        self.mode = mode;
+
+        // Check if the player won. A rewound board is only being looked at.
+        if self.scrub.is_none() {
+            // Wait for falling pieces to land before judging the board
+            let win = self.mode.is_won(self) && self.auto_moves.is_empty();
+
+            if win && !self.solved {
+                info!("Puzzle solved");
+                self.solved = true;
+
+                // Stop the clock at the winning move rather than the frame
+                // that noticed it
+                let elapsed = match self.history.last() {
+                    Some(last_move) if self.speed => last_move.at,
+                    _ => self.elapsed(),
+                };
+                self.solve_time = Some(elapsed);
+                // Only solves done without help can set a best time
+                let best_time = self.hints_used == 0
+                    && self.best_times.record(self.width, self.height, elapsed);
+                self.hooks.won(&Win {
+                    start_grid: self.start_grid.clone(),
+                    moves: self.moves,
+                    time: elapsed,
+                    hints: self.hints_used,
+                    best_time,
+                });
+                self.print_detours();
+
+                if let Some(path) = &self.stats_path {
+                    let game = GameRecord {
+                        width: self.width,
+                        height: self.height,
+                        moves: self.moves,
+                        time: elapsed,
+                        hints: self.hints_used,
+                        seed: random::seed(),
+                        mode: self.mode.name().to_string(),
+                        rating: rating::rate(&self.start_grid).map(|rating| rating.score),
+                    };
+                    stats::record(path.clone(), &game);
+                }
+
+                if let Some(path) = &self.report_path {
+                    self.write_report(path.clone(), elapsed);
+                }
+
+                if let Some(path) = &self.heatmap_path {
+                    self.write_heatmap(path.clone());
+                }
+
+                if let Some(path) = &self.replay_path {
+                    self.write_replay(path.clone());
+                }
+
+                // The results wait for the celebration to finish
+                self.celebrating_since = Some(Instant::now());
+            } else if !win {
+                self.solved = false;
+                self.solve_time = None;
+            }
+        }
+        self.celebrate();
+        self.enforce_rules();
+
+        let state = self.game_state();
+        if state != self.reported_state {
+            debug!("Game went from {:?} to {:?}", self.reported_state, state);
+            self.hooks.state_changed(self.reported_state, state);
+            self.reported_state = state;
+        }
+
+        if self.simple && self.solved && self.last_move_at.elapsed() >= simple::NEXT_BOARD_DELAY {
+            self.deal_board();
+        }
+
+        if self.started_at.is_some() {
+            let elapsed = self.elapsed();
+            self.splits.update(&self.canonical_grid(), elapsed);
+        }
+        if let Some(splits_window) = &mut self.splits_window {
+            splits_window.update();
+        }
+        if let Some(move_counter) = &mut self.move_counter {
+            move_counter.update();
+        }
+        if let Some(timer_window) = &mut self.timer_window {
+            timer_window.update();
+        }
+        if self.power_ups.is_some() {
+            let grid = self.canonical_grid();
+            if let Some(power_ups) = &mut self.power_ups {
+                power_ups.reward(&grid);
+                power_ups.update();
+            }
+        }
+
+        if self.overlay.is_some() {
+            let stats = [
+                ("timer.txt", self.format_timer()),
+                (
+                    "moves.txt",
+                    locale::current().format_count(self.moves as u64),
+                ),
+                (
+                    "hints.txt",
+                    locale::current().format_count(self.hints_used as u64),
+                ),
+                ("scramble.txt", scramble::to_notation(&self.start_grid)),
+            ];
+            if let Some(overlay) = &mut self.overlay {
+                overlay.update(&stats);
+            }
+        }
+    }
+
+    pub fn s_render(&mut self) {
+        let nudged_piece = self.hinted_piece().or_else(|| self.nudged_piece());
+        for i in 0..self.pieces.len() {
+            let state = self.piece_state(i, nudged_piece);
+            self.pieces[i].state = state;
+            self.pieces[i].set_color(state.color(&self.theme));
+
+            let mut color = self.pieces[i].color;
+            if let PieceState::Hinted { .. } = state {
+                color = Self::m_pulse(color, self.last_move_at.elapsed());
+            }
+            match self.theme.shape.outline(self.piece_size as f32, color) {
+                Some(outline) => {
+                    self.pieces[i].window.clear(Color::BLACK);
+                    self.pieces[i].window.draw(&outline);
+                }
+                None => self.pieces[i].window.clear(color),
+            }
+
+            if self.pieces[i].revealed {
+                self.update_labels(i);
+                let piece = &mut self.pieces[i];
+                if let Some(labels) = &mut piece.labels {
+                    match &mut labels.image {
+                        Some(image) => {
+                            image.set_color(state.image_tint());
+                            piece.window.draw(image);
+                        }
+                        None => match &mut labels.glyphs {
+                            Some(glyphs) => {
+                                for sprite in glyphs.sprites() {
+                                    piece.window.draw(sprite);
+                                }
+                            }
+                            None => piece.window.draw(&labels.label),
+                        },
+                    }
+                    if self.goal_labels {
+                        piece.window.draw(&labels.goal);
+                    }
+                }
+            }
+
+            if self.cursor == Some(i) {
+                let mut frame = RectangleShape::with_size(Vector2::new(
+                    self.piece_size as f32 - 2.0 * CURSOR_THICKNESS,
+                    self.piece_size as f32 - 2.0 * CURSOR_THICKNESS,
+                ));
+                frame.set_position(Vector2::new(CURSOR_THICKNESS, CURSOR_THICKNESS));
+                frame.set_fill_color(Color::TRANSPARENT);
+                frame.set_outline_color(self.theme.cursor);
+                frame.set_outline_thickness(CURSOR_THICKNESS);
+                self.pieces[i].window.draw(&frame);
+            }
+
+            self.pieces[i].window.display();
+
+            // Get the global mouse position
+            let mouse_position = self.pieces[i].window.mouse_position();
+
+            // While dragging only the grabbed piece may take focus, otherwise
+            // hovering over a neighbour would raise it above the dragged piece
+            let should_focus = match self.grabbed_piece {
+                Some(grabbed_piece) => grabbed_piece == i,
+                // The bookmark prompt and command palette keep focus while
+                // something is typed
+                None if self.bookmark_prompt.is_some() || self.palette.is_some() => false,
+                // Check if the mouse is in the window
+                None => {
+                    mouse_position.x >= 0
+                        && mouse_position.x <= self.piece_size as i32
+                        && mouse_position.y >= 0
+                        && mouse_position.y <= self.piece_size as i32
+                }
+            };
+
+            if should_focus {
+                if !self.pieces[i].window.has_focus() {
+                    trace!("Requesting focus for piece {}", i + 1);
+                }
+                self.pieces[i].window.request_focus();
+            }
+        }
+
+        if let Some(qr_popup) = &mut self.qr_popup {
+            qr_popup.render();
+        }
+        if let Some(prompt) = &mut self.bookmark_prompt {
+            prompt.render();
+        }
+        if let Some(palette) = &mut self.palette {
+            palette.render();
+        }
+        if let (Some(status), Some(job)) = (&mut self.solve_status, &self.solve_job) {
+            status.render(job);
+        }
+        if let Some(notice) = &mut self.notice {
+            notice.render();
+        }
+
+        let elapsed = self.elapsed();
+        if let Some(splits_window) = &mut self.splits_window {
+            splits_window.render(&self.splits, elapsed);
+        }
+        if let Some(move_counter) = &mut self.move_counter {
+            move_counter.render(self.moves, self.solved);
+        }
+        if self.timer_window.is_some() {
+            let timer = self.format_timer();
+            let best = self.best_times.get(self.width, self.height);
+            if let Some(timer_window) = &mut self.timer_window {
+                timer_window.render(&timer, elapsed, best, self.solved);
+            }
+        }
+        if let Some(power_ups) = &mut self.power_ups {
+            power_ups.render();
+        }
+    }
+
+    // Play by `mode`'s rules from now on
+    pub fn set_mode(&mut self, mut mode: Box<dyn GameMode<R>>) {
+        info!("Playing {}", mode.name());
+        mode.start(self);
+        self.mode = mode;
+    }
+
+    // Whether the solver can help under the mode's rules, saying so if not
+    fn solver_helps(&self) -> bool {
+        let helps = self.mode.solver_helps();
+        if !helps {
+            println!("The solver can't help in {} mode", self.mode.name());
+        }
+        helps
+    }
+
+    // Keep split times against the personal best in `path`, shown left of the board
+    pub fn show_splits(&mut self, path: PathBuf) {
+        self.splits = Splits::load(path);
+
+        let position = self.side_window_position(Side::Left, splits::WINDOW_SIZE);
+        self.splits_window = Some(SplitsWindow::open(&mut self.renderer, position));
+    }
+
+    // Show the clock against the best time for this board size, left of the
+    // board and under the splits if they're there too
+    pub fn show_timer(&mut self) {
+        let mut position = self.side_window_position(Side::Left, timer::WINDOW_SIZE);
+        if self.splits_window.is_some() {
+            position.y += (splits::WINDOW_SIZE + self.padding) as i32;
+        }
+        self.timer_window = Some(TimerWindow::open(&mut self.renderer, position));
+    }
+
+    // Count the solve's moves in a window right of the board
+    pub fn show_move_counter(&mut self) {
+        let position = self.side_window_position(Side::Right, move_counter::WINDOW_SIZE);
+        self.move_counter = Some(MoveCounter::open(&mut self.renderer, position));
+    }
+
+    // Everything that follows the player sliding a piece into the gap
+    fn record_move(&mut self, piece: usize) {
+        self.moves += 1;
+        if let Some(input_at) = self.input_at.take() {
+            self.awaiting_motion.get_or_insert(input_at);
+        }
+        self.last_move_at = Instant::now();
+        // The clock starts with the first move
+        let started_at = *self.started_at.get_or_insert_with(Instant::now);
+        self.history.push(MoveRecord {
+            slide: Move::new(piece as i8),
+            at: started_at.elapsed(),
+            grid: self.canonical_grid(),
+            power_up: None,
+        });
+        self.undone.clear();
+        if let Some(record) = self.history.last() {
+            self.hooks.moved(record);
+        }
+        self.board_changed();
+        if self.mode.pieces_fall() {
+            self.queue_gravity(Some(piece as i8));
+        }
+    }
+
+    // Bring everything that follows the board up to date after a move, or
+    // one being taken back
+    fn board_changed(&mut self) {
+        if self.capture_moves {
+            self.capture_move();
+        }
+        if self.hints {
+            self.show_hint(false);
+        }
+        if self.fog {
+            self.update_fog();
+        }
+    }
+
+    // Whether moves can be taken back or put back right now. Not while the
+    // board is busy or finished, nor in a challenge, where it would undo
+    // the move limit, nor with gravity, which would just drop them again.
+    fn can_undo(&self) -> bool {
+        !self.solved
+            && !self.locked
+            && !self.mode.pieces_fall()
+            && self.rules.is_empty()
+            && self.scrub.is_none()
+            && self.grabbed_piece.is_none()
+            && self.auto_moves.is_empty()
+    }
+
+    // Slide the last piece moved back where it came from. It no longer
+    // counts as a move, but Ctrl+Y can play it again.
+    pub fn undo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+        // A power-up's swap isn't a slide, so it can't be slid back
+        let Some(record) = self.history.pop_if(|record| record.power_up.is_none()) else {
+            debug!("Nothing to undo");
+            return;
+        };
+
+        if !self.slide(record.slide.piece) {
+            warn!("Piece {} can't be slid back", record.slide);
+            self.history.push(record);
+            return;
+        }
+        let grid_px = self.get_px_from_grid(record.slide.piece as usize);
+        self.pieces[record.slide.piece as usize]
+            .set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+        self.moves -= 1;
+        self.last_move_at = Instant::now();
+        debug!("Undid sliding piece {}", record.slide);
+        self.hooks.undid(&record);
+        self.undone.push(record);
+        self.board_changed();
+    }
+
+    // Play the last undone move again
+    pub fn redo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+        let Some(record) = self.undone.pop() else {
+            debug!("Nothing to redo");
+            return;
+        };
+
+        if !self.slide(record.slide.piece) {
+            warn!("Piece {} can't be slid again", record.slide);
+            self.undone.clear();
+            return;
+        }
+        let grid_px = self.get_px_from_grid(record.slide.piece as usize);
+        self.pieces[record.slide.piece as usize]
+            .set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+        debug!("Redid sliding piece {}", record.slide);
+        // It counts like any other move, except the rest can still be redone
+        let undone = std::mem::take(&mut self.undone);
+        self.record_move(record.slide.piece as usize);
+        self.undone = undone;
+    }
+
+    // Play the next buffered keyboard or gamepad move, or every one of them
+    // when speed solving. Anything still gliding from the last one jumps into
+    // place, so mashed keys are never waited on.
+    fn play_queued_moves(&mut self) {
+        while self.grabbed_piece.is_none() && self.auto_moves.is_empty() {
+            let Some(direction) = self.queued_moves.pop_front() else {
+                break;
+            };
+            for piece in &mut self.pieces {
+                piece.position = piece.target_position;
+            }
+            self.slide_towards(direction);
+
+            if !self.speed {
+                break;
+            }
+        }
+    }
+
+    fn queue_slide(&mut self, direction: Direction) {
+        if self.queued_moves.len() < MOVE_BUFFER {
+            self.queued_moves.push_back(direction);
+        } else {
+            debug!("Move buffer full, dropping a slide {}", direction.name());
+        }
+    }
+
+    fn toggle_cursor(&mut self) {
+        self.cursor = match self.cursor {
+            Some(_) => None,
+            // Start next to the gap, so Enter does something straight away
+            None => {
+                let gap = find_in_grid(&self.grid, -1);
+                Direction::ALL.into_iter().find_map(|direction| {
+                    let offset = direction.offset();
+                    let cell = Vector2::new(gap.x - offset.x, gap.y - offset.y);
+                    self.grid
+                        .cell_at(cell)
+                        .map(|cell| self.grid.cell(cell) as usize)
+                })
+            }
+        };
+        debug!(
+            "Keyboard cursor {}",
+            if self.cursor.is_some() { "on" } else { "off" }
+        );
+    }
+
+    // Step the cursor to the next piece that way, hopping over the gap
+    fn move_cursor(&mut self, direction: Direction) {
+        let Some(piece) = self.cursor else {
+            return;
+        };
+        let offset = direction.offset();
+        let mut cell = self.get_grid_pos(piece);
+
+        loop {
+            cell = Vector2::new(cell.x + offset.x, cell.y + offset.y);
+            let Some(index) = self.grid.cell_at(cell) else {
+                trace!("Cursor can't go further {}", direction.name());
+                return;
+            };
+            let next = self.grid.cell(index);
+            if next >= 0 {
+                self.cursor = Some(next as usize);
+                return;
+            }
+        }
+    }
+
+    // Slide the piece under the cursor into the gap, if it's next to it
+    fn slide_selected(&mut self) {
+        let Some(piece) = self.cursor else {
+            return;
+        };
+        let offset = self.get_available_move(piece);
+
+        match Direction::ALL
+            .into_iter()
+            .find(|direction| direction.offset() == offset)
+        {
+            Some(direction) => self.queue_slide(direction),
+            None => debug!("Piece {} isn't next to the gap", piece + 1),
+        }
+    }
+
+    fn slide_towards(&mut self, direction: Direction) {
+        let gap = find_in_grid(&self.grid, -1);
+        let offset = direction.offset();
+        // The piece moving up is the one below the gap
+        let from = Vector2::new(gap.x - offset.x, gap.y - offset.y);
+        // Off the edge is only somewhere to slide from if the board wraps
+        let wrapped = Vector2::new(
+            from.x.rem_euclid(self.width as i8),
+            from.y.rem_euclid(self.height as i8),
+        );
+        let Some(from) = self.grid.cell_at(wrapped) else {
+            trace!("Nothing can slide {}", direction.name());
+            return;
+        };
+
+        let piece = self.grid.cell(from);
+        if piece == -1 || self.get_available_move(piece as usize) != offset {
+            trace!("Nothing can slide {}", direction.name());
+            return;
+        }
+        self.slide(piece);
+        let grid_px = self.get_px_from_grid(piece as usize);
+        self.pieces[piece as usize].set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+
+        self.record_move(piece as usize);
+        info!("Slid piece {} {}", piece + 1, direction.name());
+    }
+
+    // Arcade mode, with the power-up counters shown right of the board
+    pub fn show_power_ups(&mut self) {
+        let position = self.side_window_position(Side::Right, 0);
+        self.power_ups = Some(PowerUps::open(&mut self.renderer, position));
+    }
+
+    // The first click picks a piece, a second click on a neighbour swaps the two
+    fn select_swap(&mut self, piece: usize) {
+        let Some(power_ups) = &mut self.power_ups else {
+            return;
+        };
+        if power_ups.count(PowerUp::Swap) == 0 {
+            println!("No swaps left");
+            return;
+        }
+
+        let Some(selected) = power_ups.selected.take() else {
+            debug!("Selected piece {} to swap", piece + 1);
+            power_ups.selected = Some(piece);
+            return;
+        };
+
+        let a = self.get_grid_pos(selected);
+        let b = self.get_grid_pos(piece);
+        let (Some(a_cell), Some(b_cell)) = (self.grid.cell_at(a), self.grid.cell_at(b)) else {
+            return;
+        };
+        if !neighbor_table(self.width, self.height).are_neighbors(a_cell, b_cell) {
+            debug!("Piece {} isn't next to piece {}", piece + 1, selected + 1);
+            if let Some(power_ups) = &mut self.power_ups {
+                power_ups.selected = Some(piece);
+            }
+            return;
+        }
+
+        let mut grid = self.grid.clone();
+        grid[a.y as usize][a.x as usize] = piece as i8;
+        grid[b.y as usize][b.x as usize] = selected as i8;
+        self.apply_power_up(PowerUp::Swap, piece, grid);
+    }
+
+    fn teleport(&mut self, piece: usize) {
+        let gap = find_in_grid(&self.grid, -1);
+        let cell = self.get_grid_pos(piece);
+
+        let mut grid = self.grid.clone();
+        grid[gap.y as usize][gap.x as usize] = piece as i8;
+        grid[cell.y as usize][cell.x as usize] = -1;
+        self.apply_power_up(PowerUp::Teleport, piece, grid);
+    }
+
+    // Spend a power-up to put the board in `grid`, recording it like a move
+    fn apply_power_up(&mut self, power_up: PowerUp, piece: usize, grid: Grid) {
+        let Some(power_ups) = &mut self.power_ups else {
+            return;
+        };
+        if !power_ups.take(power_up) {
+            println!("No {}s left", power_up.name());
+            return;
+        }
+        power_ups.selected = None;
+
+        self.grid = grid;
+        for i in 0..self.pieces.len() {
+            let grid_px = self.get_px_from_grid(i);
+            self.pieces[i].set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+        }
+
+        self.moves += 1;
+        let started_at = *self.started_at.get_or_insert_with(Instant::now);
+        self.history.push(MoveRecord {
+            slide: Move::new(piece as i8),
+            at: started_at.elapsed(),
+            grid: self.canonical_grid(),
+            power_up: Some(power_up),
+        });
+        self.undone.clear();
+        if let Some(record) = self.history.last() {
+            self.hooks.moved(record);
+        }
+        info!("Used a {} on piece {}", power_up.name(), piece + 1);
+
+        // Breaking the rules can leave the board in a state sliding can't fix
+        if !Board::new(self.canonical_grid()).is_solvable() {
+            println!("The board can't be solved by sliding any more, use another power-up");
+        }
+        self.board_changed();
+    }
+
+    // The piece the solver would slide next, once the player has been stuck
+    // for long enough
+    pub fn nudged_piece(&self) -> Option<usize> {
+        let nudge_after = self.nudge_after?;
+        if self.solved
+            || self.grabbed_piece.is_some()
+            || !self.auto_moves.is_empty()
+            || self.last_move_at.elapsed() < nudge_after
+        {
+            return None;
+        }
+
+        solver::next_move(&self.canonical_grid()).map(|step| step.piece as usize)
+    }
+
+    // The piece H last picked out, until it's moved or has pulsed long enough
+    pub fn hinted_piece(&self) -> Option<usize> {
+        let (piece, at) = self.hinted_piece?;
+        (!self.solved && self.last_move_at <= at && at.elapsed() < HINT_TIME).then_some(piece)
+    }
+
+    // Brighten `color` towards white and back about once a second
+    fn m_pulse(color: Color, time: Duration) -> Color {
+        let amount = (1.0 - (time.as_secs_f32() * std::f32::consts::TAU).cos()) / 2.0 * 0.5;
+        let lighten = |channel: u8| channel + ((255 - channel) as f32 * amount) as u8;
+
+        Color::rgb(lighten(color.r), lighten(color.g), lighten(color.b))
+    }
+
+    // Which state a piece is in, where more than one applies the first of
+    // hidden, locked, grabbed, hinted, emphasised, then whether it's in place
+    fn piece_state(&mut self, index: usize, nudged_piece: Option<usize>) -> PieceState {
+        let grid_pos = self.get_grid_pos(index);
+        let in_place =
+            grid_pos.y as usize * self.width + grid_pos.x as usize == self.orientation[index];
+
+        if !self.pieces[index].revealed {
+            PieceState::Hidden
+        } else if self.locked {
+            PieceState::Locked { in_place }
+        } else if self.grabbed_piece == Some(index) {
+            PieceState::Grabbed { in_place }
+        } else if nudged_piece == Some(index) {
+            PieceState::Hinted { in_place }
+        } else {
+            match self.pieces[index].emphasis {
+                Emphasis::Dimmed => PieceState::Dimmed { in_place },
+                Emphasis::Focused => PieceState::Focused { in_place },
+                Emphasis::Normal if in_place => PieceState::Correct,
+                Emphasis::Normal => PieceState::Incorrect,
+            }
+        }
+    }
+
+    // Pick out `pieces` by dimming everything else
+    pub fn focus_on(&mut self, pieces: &[i8]) {
+        for (index, piece) in self.pieces.iter_mut().enumerate() {
+            piece.emphasis = if pieces.contains(&(index as i8)) {
+                Emphasis::Focused
+            } else {
+                Emphasis::Dimmed
+            };
+        }
+    }
+
+    pub fn clear_focus(&mut self) {
+        for piece in &mut self.pieces {
+            piece.emphasis = Emphasis::Normal;
+        }
+    }
+
+    // The window number, centred in a piece `size` pixels across whose top
+    // left is at `offset`
+    fn m_piece_label(
+        index: usize,
+        offset: Vector2<f32>,
+        size: f32,
+        color: Color,
+        shape: PieceShape,
+    ) -> Text<'static> {
+        let label = format!("{}", index + 1);
+        let mut text = match shape {
+            PieceShape::Square => {
+                let mut text = Text::new(&label, font(), size as u32);
+                text.set_origin(Vector2::new(
+                    text.local_bounds().width / 2.0,
+                    text.local_bounds().height / 2.0,
+                ));
+                text.set_position(Vector2::new(
+                    offset.x + size * 0.425,
+                    offset.y + size * 0.05,
+                ));
+                text
+            }
+            // Smaller and centred on the glyph itself, so it stays clear of
+            // the edge where the corners are cut away
+            PieceShape::Hexagon | PieceShape::Circle => {
+                let mut text = Text::new(&label, font(), (size * 0.7) as u32);
+                let bounds = text.local_bounds();
+                text.set_origin(Vector2::new(
+                    bounds.left + bounds.width / 2.0,
+                    bounds.top + bounds.height / 2.0,
+                ));
+                text.set_position(Vector2::new(offset.x + size / 2.0, offset.y + size / 2.0));
+                text
+            }
+        };
+        text.set_fill_color(color);
+
+        text
+    }
+
+    // Rebuild a piece's labels if the size, shape, colours or goal they were
+    // built for have changed since
+    fn update_labels(&mut self, index: usize) {
+        let key = LabelKey {
+            size: self.piece_size,
+            shape: self.theme.shape,
+            label_color: self.theme.label,
+            goal_color: self.theme.goal,
+            goal_cell: self.orientation[index],
+            image: self.image.is_some(),
+            glyphs: self.theme.glyphs,
+        };
+        if self.pieces[index]
+            .labels
+            .as_ref()
+            .is_some_and(|labels| labels.key == key)
+        {
+            return;
+        }
+
+        let origin = Vector2::new(0.0, 0.0);
+        let size = self.piece_size as f32;
+        self.pieces[index].labels = Some(PieceLabels {
+            key,
+            label: Self::m_piece_label(index, origin, size, key.label_color, key.shape),
+            goal: Self::m_goal_label(
+                &self.goal_name(index),
+                origin,
+                size,
+                key.goal_color,
+                key.shape,
+            ),
+            image: self
+                .image
+                .map(|texture| self.image_slice(texture, key.goal_cell)),
+            glyphs: key.glyphs.and_then(|style| {
+                GlyphLabel::new(index + 1, style, origin, size, size * 0.6, key.label_color)
+            }),
+        });
+    }
+
+    // Show `path` across the board instead of numbers, each piece with the
+    // part of the picture from its goal cell, so it comes back together as
+    // the board is solved. The picture is stretched to the board's shape.
+    pub fn show_image(&mut self, path: &Path) -> Result<(), String> {
+        let texture = Texture::from_file(&path.to_string_lossy())
+            .ok_or(format!("Couldn't load an image from {}", path.display()))?;
+        let size = texture.size();
+        if size.x < self.width as u32 || size.y < self.height as u32 {
+            return Err(format!(
+                "A {}x{} image is too small to cut into a {}x{} board",
+                size.x, size.y, self.width, self.height
+            ));
+        }
+
+        info!("Showing {} across the board", path.display());
+        // Kept for as long as the game runs, like the font
+        self.image = Some(Box::leak(Box::new(texture)));
+        Ok(())
+    }
+
+    // Show the tile pack in `dir` on the pieces, a picture for each, see
+    // tiles.rs. Tiles are scaled to the piece size unless the pack says.
+    pub fn show_tiles(&mut self, dir: &Path) -> Result<(), String> {
+        let image = tiles::board_image(dir, self.width, self.height, self.piece_size)?;
+        let texture = Texture::from_image(&image, IntRect::default())
+            .ok_or("Couldn't make a texture from the tiles")?;
+
+        info!("Showing the tiles from {}", dir.display());
+        self.image = Some(Box::leak(Box::new(texture)));
+        Ok(())
+    }
+
+    // Cut the desktop behind the board out of `screenshot`, taken before
+    // any piece opened, and show it across the pieces so they look like
+    // chunks of the desktop. The gaps between pieces are left out, so every
+    // piece starts out showing exactly what it covers.
+    pub fn show_desktop(&mut self, screenshot: &Path) -> Result<(), String> {
+        let desktop = Image::from_file(&screenshot.to_string_lossy()).ok_or(format!(
+            "Couldn't load the screenshot from {}",
+            screenshot.display()
+        ))?;
+        // Screenshots are in pixels, which can be smaller than the units
+        // windows are placed in
+        let scale = desktop.size().x as f32 / self.renderer.desktop_size().x as f32;
+        let cells = self.desktop_cells(scale);
+        let Some(cell_size) = cells
+            .first()
+            .map(|cell| cell.width as u32)
+            .filter(|&size| size > 0)
+        else {
+            return Err("The screenshot is empty".to_string());
+        };
+
+        let mut board = Image::new(
+            cell_size * self.width as u32,
+            cell_size * self.height as u32,
+        );
+        for (i, cell) in cells.into_iter().enumerate() {
+            let (x, y) = ((i % self.width) as u32, (i / self.width) as u32);
+            board.copy_image(&desktop, x * cell_size, y * cell_size, cell, false);
+        }
+        let texture = Texture::from_image(&board, IntRect::default())
+            .ok_or("Couldn't make a texture from the screenshot")?;
+
+        info!("Showing the desktop behind the board across it");
+        self.image = Some(Box::leak(Box::new(texture)));
+        Ok(())
+    }
+
+    // Where each cell of the board is on a screenshot `scale` pixels to a
+    // unit, row by row
+    fn desktop_cells(&mut self, scale: f32) -> Vec<IntRect> {
+        let size = (self.piece_size as f32 * scale) as i32;
+        let mut cells = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let corner = self.grid_pos_to_px(x, y);
+                cells.push(IntRect::new(
+                    (corner.x as f32 * scale) as i32,
+                    (corner.y as f32 * scale) as i32,
+                    size,
+                    size,
+                ));
+            }
+        }
+        cells
+    }
+
+    // The piece of the image for `goal_cell`, scaled to fill a piece
+    fn image_slice(&self, texture: &'static Texture, goal_cell: usize) -> Sprite<'static> {
+        let size = texture.size();
+        let (slice_width, slice_height) = (size.x / self.width as u32, size.y / self.height as u32);
+        let (x, y) = (goal_cell % self.width, goal_cell / self.width);
+
+        let mut sprite = Sprite::with_texture_and_rect(
+            texture,
+            IntRect::new(
+                (x as u32 * slice_width) as i32,
+                (y as u32 * slice_height) as i32,
+                slice_width as i32,
+                slice_height as i32,
+            ),
+        );
+        sprite.set_scale(Vector2::new(
+            self.piece_size as f32 / slice_width as f32,
+            self.piece_size as f32 / slice_height as f32,
+        ));
+        sprite
+    }
+
+    // Where a piece belongs, by row and column counting from 1. Rotating or
+    // mirroring the board moves the goal too.
+    pub fn goal_name(&self, index: usize) -> String {
+        let cell = self.orientation[index];
+        format!("r{}c{}", cell / self.width + 1, cell % self.width + 1)
+    }
+
+    // A small goal name along the bottom of a piece `size` pixels across,
+    // in the corner on square pieces and centred on round ones
+    fn m_goal_label(
+        name: &str,
+        offset: Vector2<f32>,
+        size: f32,
+        color: Color,
+        shape: PieceShape,
+    ) -> Text<'static> {
+        let mut text = Text::new(name, font(), (size * 0.18) as u32);
+        let bounds = text.local_bounds();
+        let position = match shape {
+            PieceShape::Square => {
+                text.set_origin(Vector2::new(
+                    bounds.left + bounds.width,
+                    bounds.top + bounds.height,
+                ));
+                Vector2::new(size * 0.95, size * 0.95)
+            }
+            PieceShape::Hexagon | PieceShape::Circle => {
+                text.set_origin(Vector2::new(
+                    bounds.left + bounds.width / 2.0,
+                    bounds.top + bounds.height,
+                ));
+                Vector2::new(size / 2.0, size * 0.88)
+            }
+        };
+        text.set_position(Vector2::new(offset.x + position.x, offset.y + position.y));
+        text.set_fill_color(color);
+
+        text
+    }
+
+    // Save the board exactly as it looks right now
+    fn capture_screenshot(&mut self) {
+        let pieces: Vec<(Vector2<i32>, Color)> = self
+            .pieces
+            .iter()
+            .map(|piece| {
+                (
+                    Vector2::new(piece.position.x as i32, piece.position.y as i32),
+                    piece.color,
+                )
+            })
+            .collect();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let path = self.capture_dir.join(format!("board-{}.png", timestamp));
+        self.capture_board(path, &pieces);
+    }
+
+    // Save the board as it will look once the last move settles, numbered
+    // by move so the files play back in order
+    pub fn capture_move(&mut self) {
+        let pieces: Vec<(Vector2<i32>, Color)> = (0..self.pieces.len())
+            .map(|i| {
+                let state = self.piece_state(i, None);
+                (self.get_px_from_grid(i), state.color(&self.theme))
+            })
+            .collect();
+
+        let path = self.capture_dir.join(format!("move-{:04}.png", self.moves));
+        self.capture_board(path, &pieces);
+    }
+
+    // Composite every piece at its desktop position onto a desktop-sized canvas
+    fn capture_board(&mut self, path: PathBuf, pieces: &[(Vector2<i32>, Color)]) {
+        let mut backgrounds = Vec::new();
+        let mut labels = Vec::new();
+        let mut goals = Vec::new();
+
+        for (i, &(position, color)) in pieces.iter().enumerate() {
+            let position = Vector2::new(position.x as f32, position.y as f32);
+
+            let background: Box<dyn Drawable> =
+                match self.theme.shape.outline(self.piece_size as f32, color) {
+                    Some(mut outline) => {
+                        outline.set_position(position);
+                        Box::new(outline)
+                    }
+                    None => {
+                        let mut background = RectangleShape::with_size(Vector2::new(
+                            self.piece_size as f32,
+                            self.piece_size as f32,
+                        ));
+                        background.set_position(position);
+                        background.set_fill_color(color);
+                        Box::new(background)
+                    }
+                };
+            backgrounds.push(background);
+
+            labels.push(self.pieces[i].revealed.then(|| {
+                Self::m_piece_label(
+                    i,
+                    position,
+                    self.piece_size as f32,
+                    self.theme.label,
+                    self.theme.shape,
+                )
+            }));
+            goals.push((self.pieces[i].revealed && self.goal_labels).then(|| {
+                Self::m_goal_label(
+                    &self.goal_name(i),
+                    position,
+                    self.piece_size as f32,
+                    self.theme.goal,
+                    self.theme.shape,
+                )
+            }));
+        }
+
+        let mut drawables: Vec<&dyn Drawable> = Vec::new();
+        for ((background, label), goal) in backgrounds.iter().zip(&labels).zip(&goals) {
+            drawables.push(background.as_ref());
+            if let Some(label) = label {
+                drawables.push(label);
+            }
+            if let Some(goal) = goal {
+                drawables.push(goal);
+            }
+        }
+
+        let size = self.renderer.desktop_size();
+        match self.renderer.capture(size, &drawables) {
+            Some(pixels) => capture::save_png(path, size, pixels),
+            None => warn!("Couldn't capture the board"),
+        }
+    }
+
+    fn write_heatmap(&mut self, path: PathBuf) {
+        let visits = heatmap::blank_visits(&self.start_grid, &self.history);
+        for (total_row, row) in self.blank_visits.iter_mut().zip(visits) {
+            for (total, count) in total_row.iter_mut().zip(row) {
+                *total += count;
+            }
+        }
+        debug!("Gap visits so far: {:?}", self.blank_visits);
+
+        let (size, pixels) = heatmap::render(&self.blank_visits);
+        capture::save_png(path, size, pixels);
+    }
+
+    fn write_replay(&self, path: PathBuf) {
+        let replay = Replay::from_game(random::seed(), self.start_grid.clone(), &self.history);
+
+        io_worker::spawn(move || {
+            let result = replay
+                .encode()
+                .and_then(|bytes| std::fs::write(&path, bytes).map_err(|error| error.to_string()));
+            match result {
+                Ok(()) => info!("Wrote replay to {}", path.display()),
+                Err(error) => warn!("Couldn't write replay to {}: {}", path.display(), error),
+            }
+        });
+    }
+
+    // Animate the board to how it was after `position` moves. Going all the
+    // way to the end leaves the rewound view.
+    fn scrub_to(&mut self, position: usize) {
+        let position = position.min(self.history.len());
+        let grid = match position {
+            0 => self.start_grid.clone(),
+            _ => self.history[position - 1].grid.clone(),
+        };
+
+        self.scrub = (position < self.history.len()).then_some(position);
+        self.glide_to(apply_map(&grid, &self.orientation));
+        println!("Move {}/{}", position, self.history.len());
+    }
+
+    // How the solve compares with optimal play, and the worst detours
+    fn print_detours(&self) {
+        let Some(optimal) = solver::distance(&self.start_grid) else {
+            return;
+        };
+        let detours = analysis::detours(&self.start_grid, &self.history);
+        if detours.is_empty() {
+            println!("{} moves, the fewest possible", self.moves);
+            return;
+        }
+
+        println!("{} moves, {} is optimal", self.moves, optimal);
+        for detour in detours.iter().take(3) {
+            println!("  {}", detour.describe());
+        }
+    }
+
+    // Written on the I/O worker so the win frame doesn't wait on the disk
+    fn write_report(&self, path: PathBuf, elapsed: Duration) {
+        let format = report::Format::from_path(&path);
+        let text = report::generate(format, &self.start_grid, &self.history, elapsed);
+
+        io_worker::spawn(move || match std::fs::write(&path, text) {
+            Ok(()) => info!("Wrote solve report to {}", path.display()),
+            Err(error) => warn!(
+                "Couldn't write solve report to {}: {}",
+                path.display(),
+                error
+            ),
+        });
+    }
+
+    // The moves, in order, that solve the board from here in as few as
+    // possible, found the same way as the solve job finds hints and solves
+    // for S. 3x3 boards are looked up, bigger ones searched for, which can
+    // take a long time on a well shuffled board, so setting `cancelled` from
+    // another thread gives up. None then, or if a power-up has left the
+    // board unsolvable.
+    pub fn solve(&self, cancelled: &AtomicBool) -> Option<Vec<Move>> {
+        solver::solve_any(&self.canonical_grid(), cancelled)
+    }
+
+    // The board as it would look without any rotating or mirroring
+    pub fn canonical_grid(&self) -> Grid {
+        unapply_map(&self.grid, &self.orientation)
+    }
+
+    // The solved board as shown
+    pub fn goal(&self) -> Grid {
+        apply_map(&Grid::solved(self.width, self.height), &self.orientation)
+    }
+
+    // Rotate or mirror the whole board, goal included, around its centre.
+    // `cell_map` is given each cell and the board's width.
+    fn transform_board(&mut self, name: &str, cell_map: fn(usize, usize) -> usize) {
+        let map: CellMap = (0..self.width * self.height)
+            .map(|cell| cell_map(cell, self.width))
+            .collect();
+
+        for target in self.orientation.iter_mut() {
+            *target = map[*target];
+        }
+        self.grid = apply_map(&self.grid, &map);
+        self.grabbed_piece = None;
+        self.auto_moves.clear();
+        info!("Board {}", name);
+
+        for i in 0..self.pieces.len() {
+            let grid_px = self.get_px_from_grid(i);
+            self.pieces[i].set_position(Vector2::new(grid_px.x as f32, grid_px.y as f32));
+        }
+    }
+
+    // Drop every piece above the gap, nearest first, each falling once the
+    // one below has landed. The piece the player just moved stays put, or
+    // pushing a piece up would be undone straight away.
+    pub fn queue_gravity(&mut self, moved_piece: Option<i8>) {
+        let blank = find_in_grid(&self.grid, -1);
+
+        for y in (0..blank.y as usize).rev() {
+            let piece = self.grid[y][blank.x as usize];
+            if Some(piece) == moved_piece {
+                break;
+            }
+            self.auto_moves.push_back(Move::new(piece));
+        }
+
+        if !self.auto_moves.is_empty() {
+            debug!("Pieces falling: {:?}", self.auto_moves);
+        }
+    }
+
+    // Let the solver place the pieces that belong in the given cells,
+    // leaving the rest of the board for the player
+    fn auto_solve(&mut self, cells: &[usize]) {
+        if self.rules.no_hints {
+            println!("No help from the solver in this challenge");
+            return;
+        }
+        if !self.solver_helps() {
+            return;
+        }
+
+        // The gap's home cell has no piece to place
+        let goal = self.goal();
+        let pieces: Vec<i8> = cells
+            .iter()
+            .map(|&cell| goal.cell(cell))
+            .filter(|&piece| piece != -1)
+            .collect();
+
+        // A new search replaces any still running
+        self.cancel_solve();
+        // The pieces being placed stand out until they're in place
+        self.focus_on(&pieces);
+        self.solve_job = Some(SolveJob::start(self.canonical_grid(), pieces));
+    }
+
+    // Have the solver work out the whole solve and play it, one move at a
+    // time, auto_move_delay apart
+    pub fn auto_solve_board(&mut self) {
+        if self.rules.no_hints {
+            println!("No help from the solver in this challenge");
+            return;
+        }
+        if !self.solver_helps() {
+            return;
+        }
+        if self.solved {
+            return;
+        }
+        // Asked again while it plays, it stops where it is
+        if !self.mode.pieces_fall() && !self.auto_moves.is_empty() {
+            info!("Stopped solving with {} moves to go", self.auto_moves.len());
+            self.auto_moves.clear();
+            return;
+        }
+
+        self.cancel_solve();
+        self.solve_job = Some(SolveJob::start_whole(self.canonical_grid()));
+    }
+
+    fn cancel_solve(&mut self) {
+        self.solve_job = None;
+        self.solve_status = None;
+        self.hint_search = false;
+    }
+
+    // Play a search's solution once it's found, or show how it's going
+    fn update_solve(&mut self) {
+        let grid = self.canonical_grid();
+        let Some(job) = &mut self.solve_job else {
+            return;
+        };
+
+        match job.poll() {
+            Some(solution) => {
+                let pieces: Vec<i8> = job.pieces.iter().map(|piece| piece + 1).collect();
+                let moved = job.grid != grid;
+                let for_hint = self.hint_search;
+                self.cancel_solve();
+
+                match solution {
+                    // The solution is for a board that's gone
+                    Some(_) if moved => {
+                        debug!("Board changed during the search, dropping its solution")
+                    }
+                    Some(solution) if for_hint => {
+                        if let Some(&first) = solution.first() {
+                            self.give_hint(first);
+                        }
+                    }
+                    None if for_hint => warn!("The solver can't find a move on this board"),
+                    Some(solution) => {
+                        info!(
+                            "Solver placing pieces {:?} in {} moves",
+                            pieces,
+                            solution.len()
+                        );
+                        println!(
+                            "Placing pieces {} in {} moves",
+                            pieces
+                                .iter()
+                                .map(|piece| piece.to_string())
+                                .collect::<Vec<_>>()
+                                .join(" "),
+                            solution.len()
+                        );
+                        self.grabbed_piece = None;
+                        self.auto_moves = solution.into();
+                    }
+                    None => warn!("The solver can't place those pieces on this board"),
+                }
+            }
+            None if self.solve_status.is_none()
+                && job.started_at.elapsed() >= solve_job::STATUS_DELAY =>
+            {
+                let position = self.side_window_position(Side::Right, 0);
+                self.solve_status = Some(SolveStatus::open(&mut self.renderer, position));
+            }
+            None => {}
+        }
+
+        if self
+            .solve_status
+            .as_mut()
+            .is_some_and(|status| status.update())
+        {
+            self.cancel_solve();
+        }
+    }
+
+    // Name the technique for the current board, if it's a known pattern.
+    // Unless asked for, the same hint isn't repeated move after move.
+    fn show_hint(&mut self, asked: bool) {
+        if self.rules.no_hints {
+            if asked {
+                println!("No hints in this challenge");
+            }
+            return;
+        }
+
+        if asked {
+            self.hint_next_move();
+        }
+
+        let grid = self.canonical_grid();
+        let pattern = patterns::find(&grid);
+        let name = pattern.map(|pattern| pattern.name.as_str());
+        if !asked && name == self.last_hint {
+            return;
+        }
+        self.last_hint = name;
+
+        match pattern {
+            Some(pattern) => {
+                let pieces = patterns::apply_moves(&grid, &pattern.moves)
+                    .map(|(_, pieces)| {
+                        pieces
+                            .iter()
+                            .map(|piece| (piece + 1).to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .unwrap_or_default();
+                info!("Hint: {} ({})", pattern.name, pattern.moves);
+                println!(
+                    "Hint: {}. Moves {}, sliding pieces {}",
+                    pattern.name, pattern.moves, pieces
+                );
+            }
+            None if asked => println!("No known pattern on this board"),
+            None => {}
+        }
+    }
+
+    // Pulse the piece the solver would slide next, a few times a game
+    fn hint_next_move(&mut self) {
+        if self.solved || self.locked {
+            return;
+        }
+        if self.hints_used >= self.hint_limit {
+            println!("No hints left this game");
+            return;
+        }
+        if !self.solver_helps() {
+            return;
+        }
+
+        let grid = self.canonical_grid();
+        match solver::next_move(&grid) {
+            Some(step) => self.give_hint(step),
+            // Only 3x3 boards are looked up. Others are searched for on the
+            // solver's thread, and the hint is given once it's found.
+            None => {
+                self.cancel_solve();
+                self.solve_job = Some(SolveJob::start_whole(grid));
+                self.hint_search = true;
+            }
+        }
+    }
+
+    fn give_hint(&mut self, hint: Move) {
+        self.hints_used += 1;
+        self.hinted_piece = Some((hint.piece as usize, Instant::now()));
+        info!("Hinted piece {}", hint);
+        println!(
+            "Slide {} ({} of {} hints used)",
+            hint, self.hints_used, self.hint_limit
+        );
+    }
+
+    // Load a scramble someone copied, either as notation or as a link
+    fn paste_scramble(&mut self) {
+        let text = self.renderer.get_clipboard();
+
+        match challenge::parse(&text) {
+            Ok((grid, rules)) => {
+                info!("Pasted scramble {}", scramble::to_notation(&grid));
+                self.qr_popup = None;
+                self.set_rules(rules);
+                self.animate_to_grid(grid);
+            }
+            Err(error) => warn!("Couldn't paste scramble: {}", error),
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        if !rules.is_empty() {
+            println!("Challenge: {}", rules.describe());
+        }
+        // Nothing that would tell the player which piece to move
+        if rules.no_hints {
+            self.hints = false;
+            self.nudge_after = None;
+        }
+        self.rules = rules;
+    }
+
+    // Show the starting scramble as a QR code next to the board, so another
+    // player can scan the link and open the same puzzle
+    fn toggle_qr_popup(&mut self) {
+        if self.qr_popup.take().is_some() {
+            debug!("Closed QR popup");
+            return;
+        }
+
+        let link = challenge::to_link(&self.start_grid, &self.rules);
+        let position = self.side_window_position(Side::Right, 0);
+        info!("Showing scramble {} as a QR code", link);
+        self.qr_popup = QrPopup::open(&mut self.renderer, &link, position);
+    }
+
+    // Ask for a name to keep this run's seed under, so it can be picked from
+    // the favourites and played again
+    fn open_bookmark_prompt(&mut self) {
+        if self.bookmark_prompt.is_some() {
+            return;
+        }
+
+        let position = self.side_window_position(Side::Right, 300);
+        self.bookmark_prompt = Some(BookmarkPrompt::open(
+            &mut self.renderer,
+            random::seed(),
+            position,
+        ));
+    }
+
+    fn open_palette(&mut self) {
+        if self.palette.is_some() {
+            return;
+        }
+
+        let position = self.side_window_position(Side::Right, palette::WINDOW_SIZE);
+        self.palette = Some(CommandPalette::open(&mut self.renderer, position));
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::NewGame => self.deal_board(),
+            Command::Theme(name) => {
+                if let Some((_, theme)) = Theme::presets()
+                    .into_iter()
+                    .find(|(preset, _)| *preset == name)
+                {
+                    info!("Switched to the {} theme", name);
+                    self.theme = theme;
+                }
+            }
+            Command::BoardSize(width, height) => self.set_board_size(width, height),
+            Command::ShowStats => self.show_stats(),
+            Command::Hint => self.show_hint(true),
+            Command::Solve => self.auto_solve_board(),
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::Rotate if !self.mode.pieces_fall() && self.width == self.height => {
+                self.transform_board("rotated", rotate_cell)
+            }
+            Command::Mirror if !self.mode.pieces_fall() => {
+                self.transform_board("mirrored", mirror_cell)
+            }
+            Command::Rotate | Command::Mirror => println!("The board can't be turned right now"),
+            Command::GoalLabels => self.goal_labels = !self.goal_labels,
+            Command::Screenshot => self.capture_screenshot(),
+            Command::Bookmark => self.open_bookmark_prompt(),
+        }
+    }
+
+    // Play on a `width` by `height` board from now on, starting with a
+    // freshly shuffled one. Pieces are added or closed to fit.
+    pub fn set_board_size(&mut self, width: usize, height: usize) {
+        if (width, height) != (self.width, self.height)
+            && (self.splits_window.is_some() || self.power_ups.is_some())
+        {
+            println!("Splits and arcade mode only work on the 3x3 board");
+            return;
+        }
+        if let Some(mode) = self
+            .three_by_three_only
+            .filter(|_| (width, height) != (3, 3))
+        {
+            println!("{} only works on the 3x3 board", mode);
+            return;
+        }
+
+        if (width, height) != (self.width, self.height) {
+            info!("Changing the board to {}x{}", width, height);
+            self.release_grab();
+            self.cancel_solve();
+            self.auto_moves.clear();
+            self.scrub = None;
+            self.cursor = None;
+
+            let pieces = width * height - 1;
+            self.pieces.truncate(pieces);
+            while self.pieces.len() < pieces {
+                let title = format!("{}", self.pieces.len() + 1);
+                let window = self.renderer.create_window(self.piece_size, &title);
+                self.pieces.push(PuzzlePiece::new(window));
+            }
+
+            self.width = width;
+            self.height = height;
+            self.celebration_time = celebration_time(width, height);
+            self.orientation = layout::identity(width, height);
+            self.blank_visits = vec![vec![0; width]; height];
+            self.grid = Grid::solved(width, height);
+            self.set_layout(self.layout);
+            // As at startup, get the 4x4 solver's tables going straight away.
+            // Tests build small ones of their own instead.
+            if !cfg!(test) && !self.simple && (width, height) == (4, 4) {
+                pattern_db::prepare();
+            }
+        }
+        self.deal_board();
+        if self.fog {
+            self.update_fog();
+        }
+    }
+
+    // How the solve is going so far, and the time to beat on this size
+    fn show_stats(&self) {
+        println!("{}x{} board", self.width, self.height);
+        println!("Moves: {}", self.moves);
+        println!("Time: {}", self.format_timer());
+        println!("Hints used: {}", self.hints_used);
+        match self.best_times.get(self.width, self.height) {
+            Some(best) => println!("Best time: {}", share::format_duration(best)),
+            None => println!("No best time yet"),
+        }
+        // Games from earlier runs too, if they're being kept
+        let earlier = self
+            .stats_path
+            .as_deref()
+            .and_then(|path| stats::load(path).ok())
+            .and_then(|games| stats::summarize(&games).remove(&(self.width, self.height)));
+        if let Some(summary) = earlier {
+            println!(
+                "Solved {} times, averaging {}",
+                summary.games,
+                share::format_duration(summary.average_time)
+            );
+        }
+    }
+
+    fn format_timer(&self) -> String {
+        if self.speed {
+            locale::current().format_duration_millis(self.elapsed())
+        } else {
+            share::format_duration(self.elapsed())
+        }
+    }
+
+    // Time since the first move, stopped once the puzzle is solved
+    pub fn elapsed(&self) -> Duration {
+        self.solve_time.unwrap_or_else(|| {
+            self.started_at
+                .map_or(Duration::ZERO, |started_at| started_at.elapsed())
+        })
+    }
+
+    // The desktop mouse position, unless synthetic input is driving the mouse
+    pub fn mouse_position(&self) -> Vector2<i32> {
+        self.mouse_override
+            .unwrap_or_else(|| self.renderer.desktop_mouse_position())
+    }
+
+    // The way a piece can slide: one step towards the gap, or off the edge
+    // when the mode wraps the board around to it. Nothing if it can't.
+    pub fn get_available_move(&mut self, index: usize) -> Vector2<i8> {
+        let grid_pos = self.get_grid_pos(index);
+        let gap = find_in_grid(&self.grid, -1);
+        let (Some(cell), Some(gap_cell)) = (self.grid.cell_at(grid_pos), self.grid.cell_at(gap))
+        else {
+            return Vector2::new(0, 0);
+        };
+        if !self.mode.can_slide(&self.grid, cell, gap_cell) {
+            return Vector2::new(0, 0);
+        }
+
+        let step = |from: i8, to: i8| match to - from {
+            -1..=1 => to - from,
+            far => -far.signum(),
+        };
+        Vector2::new(step(grid_pos.x, gap.x), step(grid_pos.y, gap.y))
+    }
+
+    // The cell a piece slides to, taking the available move from where it is
+    fn slide_destination(&self, grid_pos: Vector2<i8>) -> Vector2<i8> {
+        Vector2::new(
+            (grid_pos.x + self.available_move.x).rem_euclid(self.width as i8),
+            (grid_pos.y + self.available_move.y).rem_euclid(self.height as i8),
+        )
+    }
+
+    // Slide a piece into the gap, if the mode allows it from where it is
+    fn slide(&mut self, piece: i8) -> bool {
+        if self.get_available_move(piece as usize) == Vector2::new(0, 0) {
+            return false;
+        }
+        let from = find_in_grid(&self.grid, piece);
+        let gap = find_in_grid(&self.grid, -1);
+        self.grid[gap.y as usize][gap.x as usize] = piece;
+        self.grid[from.y as usize][from.x as usize] = -1;
+        true
+    }
+
+    pub fn get_px_from_grid(&mut self, index: usize) -> Vector2<i32> {
+        for x_index in 0..self.width {
+            for y_index in 0..self.height {
+                if self.grid[y_index][x_index] == index as i8 {
+                    return self.grid_pos_to_px(x_index, y_index);
+                }
+            }
+        }
+
+        return Vector2::new(0, 0);
+    }
+
+    pub fn grid_pos_to_px(&mut self, x_index: usize, y_index: usize) -> Vector2<i32> {
+        return Self::m_grid_pos_to_px(
+            self.piece_size,
+            self.padding,
+            self.center,
+            x_index,
+            y_index,
+        );
+    }
+
+    fn m_grid_pos_to_px(
+        window_size: u32,
+        padding: u32,
+        center: Vector2<u32>,
+        x_index: usize,
+        y_index: usize,
+    ) -> Vector2<i32> {
+        let position = Vector2::new(
+            (x_index as i32 - 1) * (window_size + padding) as i32 + center.x as i32,
+            (y_index as i32 - 1) * (window_size + padding) as i32 + center.y as i32,
+        );
+
+        return position;
+    }
+
+    // Reveal the pieces around the gap and hide the rest. The piece in hand
+    // stays revealed so it doesn't vanish mid-drag, and a solved board shows
+    // everything.
+    pub fn update_fog(&mut self) {
+        let width = self.width;
+        let blank = find_in_grid(&self.grid, -1);
+        let blank_cell = blank.y as usize * width + blank.x as usize;
+
+        for i in 0..self.pieces.len() {
+            let grid_pos = self.get_grid_pos(i);
+            let cell = grid_pos.y as usize * width + grid_pos.x as usize;
+            let revealed = self.solved
+                || self.grabbed_piece == Some(i)
+                || neighbor_table(width, self.height).are_neighbors(cell, blank_cell);
+
+            if revealed != self.pieces[i].revealed {
+                trace!(
+                    "Piece {} {}",
+                    i + 1,
+                    if revealed { "revealed" } else { "hidden" }
+                );
+                self.pieces[i].revealed = revealed;
+            }
+        }
+    }
+
+    pub fn get_grid_pos(&mut self, index: usize) -> Vector2<i8> {
+        return find_in_grid(&self.grid, index as i8);
+    }
+}
+
+// Long enough for the wave to reach the far corner of a `width` by
+// `height` board and the last piece to land
+fn celebration_time(width: usize, height: usize) -> Duration {
+    CELEBRATION_WAVE_STEP * (width + height - 2) as u32 + CELEBRATION_HOP_TIME
+}
+
+fn is_gamepad_event(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::JoystickButtonPressed { .. }
+            | Event::JoystickButtonReleased { .. }
+            | Event::JoystickMoved { .. }
+            | Event::JoystickConnected { .. }
+            | Event::JoystickDisconnected { .. }
+    )
+}
+
+pub fn lazy_smoothing_vector2(
+    current: Vector2<f32>,
+    target: Vector2<f32>,
+    threshold: f32,
+) -> Vector2<f32> {
+    Vector2::new(
+        lazy_smoothing(current.x, target.x, threshold),
+        lazy_smoothing(current.y, target.y, threshold),
+    )
+}
+
+pub fn lazy_smoothing(current: f32, target: f32, threshold: f32) -> f32 {
+    if (current - target).abs() < threshold {
+        target
+    } else {
+        current + (target - current) * 0.15
+    }
+}
+
+pub fn lazy_smoothing_color(current: Color, target: Color, threshold: f32) -> Color {
+    Color::rgb(
+        lazy_smoothing(current.r as f32, target.r as f32, threshold) as u8,
+        lazy_smoothing(current.g as f32, target.g as f32, threshold) as u8,
+        lazy_smoothing(current.b as f32, target.b as f32, threshold) as u8,
+    )
+}
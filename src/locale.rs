@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use log::debug;
+use once_cell::sync::OnceCell;
+
+// Set once at startup. Until then, e.g. in tests, numbers are formatted the
+// plain C way.
+static CURRENT: OnceCell<Locale> = OnceCell::new();
+
+// How numbers are written where the player lives
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Locale {
+    pub decimal: char,
+    // Between groups of three digits
+    pub group: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            decimal: '.',
+            group: ',',
+        }
+    }
+}
+
+impl Locale {
+    // From a POSIX locale name like de_DE.UTF-8. Only the separators are
+    // looked up, so unknown languages just get the defaults.
+    pub fn from_name(name: &str) -> Self {
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        let (language, region) = name.split_once(['_', '-']).unwrap_or((name, ""));
+
+        match (language, region) {
+            // Swiss German and Italian keep the point but group with apostrophes
+            ("de" | "it", "CH") => Self {
+                decimal: '.',
+                group: '\'',
+            },
+            ("de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el", _) => Self {
+                decimal: ',',
+                group: '.',
+            },
+            ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu", _) => {
+                Self {
+                    decimal: ',',
+                    group: '\u{a0}',
+                }
+            }
+            _ => Self::default(),
+        }
+    }
+
+    // Checked in the same order as the C library does for numbers
+    pub fn from_env() -> Self {
+        let name = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|variable| std::env::var(variable).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        let locale = Self::from_name(&name);
+        debug!("Formatting numbers for \"{}\": {:?}", name, locale);
+
+        locale
+    }
+
+    pub fn format_count(&self, count: u64) -> String {
+        let digits = count.to_string();
+        let mut text = String::new();
+
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                text.push(self.group);
+            }
+            text.push(digit);
+        }
+
+        text
+    }
+
+    pub fn format_decimal(&self, value: f32, places: usize) -> String {
+        let text = format!("{:.*}", places, value);
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let (sign, whole) = match whole.strip_prefix('-') {
+            Some(whole) => ("-", whole),
+            None => ("", whole),
+        };
+        let whole = self.format_count(whole.parse().unwrap_or(0));
+
+        if fraction.is_empty() {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}{}{}", sign, whole, self.decimal, fraction)
+        }
+    }
+
+    // m:ss.t, with the locale's decimal separator before the tenths
+    pub fn format_duration(&self, duration: Duration) -> String {
+        let tenths = duration.as_millis() / 100;
+
+        format!(
+            "{}:{:02}{}{}",
+            tenths / 600,
+            tenths / 10 % 60,
+            self.decimal,
+            tenths % 10
+        )
+    }
+
+    // m:ss.mmm, for times worth keeping a record of
+    pub fn format_duration_millis(&self, duration: Duration) -> String {
+        let millis = duration.as_millis();
+
+        format!(
+            "{}:{:02}{}{:03}",
+            millis / 60_000,
+            millis / 1000 % 60,
+            self.decimal,
+            millis % 1000
+        )
+    }
+}
+
+pub fn init(locale: Locale) {
+    let _ = CURRENT.set(locale);
+}
+
+pub fn current() -> Locale {
+    CURRENT.get().copied().unwrap_or_default()
+}
@@ -0,0 +1,148 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+
+use crate::io_worker;
+
+// Rotate the log file once it grows past this many bytes
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+// Number of rotated files to keep around (game.log.1, game.log.2, ...)
+const MAX_LOG_BACKUPS: u32 = 3;
+
+static START: Lazy<Instant> = Lazy::new(Instant::now);
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.size + line.len() as u64 + 1 > MAX_LOG_SIZE {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Shift the existing backups up by one, dropping the oldest
+        for i in (1..MAX_LOG_BACKUPS).rev() {
+            let from = backup_path(&self.path, i);
+            if from.exists() {
+                fs::rename(&from, backup_path(&self.path, i + 1))?;
+            }
+        }
+        fs::rename(&self.path, backup_path(&self.path, 1))?;
+
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+fn backup_path(path: &Path, index: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+struct Logger {
+    level: LevelFilter,
+    file: Option<Arc<Mutex<RotatingFile>>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "[{:>9.3} {:<5} {}] {}",
+            START.elapsed().as_secs_f32(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        eprintln!("{}", line);
+
+        if let Some(file) = &self.file {
+            let file = Arc::clone(file);
+            io_worker::spawn(move || {
+                if let Ok(mut file) = file.lock() {
+                    // Nowhere sensible to report a failing log file, so drop the line
+                    let _ = file.write_line(&line);
+                }
+            });
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let file = Arc::clone(file);
+            io_worker::spawn(move || {
+                if let Ok(mut file) = file.lock() {
+                    let _ = file.file.flush();
+                }
+            });
+        }
+        io_worker::flush();
+    }
+}
+
+pub fn level_from_verbosity(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+// Write out everything still queued, log lines and saves alike, before the
+// process exits and takes the I/O worker with it
+pub fn shutdown() {
+    log::logger().flush();
+    // The logger's flush drains the worker too, but there may be no logger
+    io_worker::flush();
+}
+
+pub fn init(verbosity: u8, log_file: Option<PathBuf>) -> io::Result<()> {
+    Lazy::force(&START);
+
+    let level = level_from_verbosity(verbosity);
+    let file = match log_file {
+        Some(path) => Some(Arc::new(Mutex::new(RotatingFile::open(path)?))),
+        None => None,
+    };
+
+    log::set_boxed_logger(Box::new(Logger { level, file })).map_err(io::Error::other)?;
+    log::set_max_level(level);
+
+    Ok(())
+}
@@ -1,544 +1,1107 @@
+mod platform;
+mod screenshot;
+mod sfml_renderer;
+
 use std::{
+    path::PathBuf,
     thread::sleep,
     time::{Duration, Instant},
 };
 
-use once_cell::sync::Lazy;
+use clap::{error::ErrorKind, ArgAction, CommandFactory, Parser};
+use log::{debug, info, warn};
 use rand::Rng;
-use sfml::{
-    graphics::{Color, Font, RenderTarget, RenderWindow, Text, Transformable},
-    system::Vector2,
-    window::{mouse, Event, Style, VideoMode},
-    SfBox,
+use sfml::system::Vector2;
+use sfml_renderer::SfmlRenderer;
+#[cfg(feature = "ranked")]
+use slide_puzzle::ranked::{Match, Matchmaker, MatchmakingWindow, Ranked};
+use slide_puzzle::{
+    bookmarks::{self, Bookmark, Bookmarks, FavouritesPicker},
+    challenge::{self, Rules},
+    chaos::{self, Chaos},
+    config::{self, Config},
+    demo::Demo,
+    descent::Descent,
+    drill::{Drill, DrillKind},
+    exhibition::Exhibition,
+    gamepad::{self, Mapping, Remapper},
+    hooks::WinHook,
+    io_worker,
+    layout::{self, fit_piece_size, Layout, LayoutPreferences, LayoutPreset},
+    locale, logging,
+    metrics::{FrameMetrics, MetricsWindow},
+    modes,
+    overlay::Overlay,
+    packs,
+    parity::Quiz,
+    pattern_db,
+    profile::{self, Profile, ProfilePicker},
+    puzzle, random, rating,
+    relay::Relay,
+    renderer::{Renderer, WindowHandle},
+    replay,
+    resize::ResizeHandle,
+    scramble, simple, solver, stats,
+    theme::ThemeWatcher,
+    timer::{self, BestTimes},
+    updates::{UpdateCheck, UpdateNotice},
+    World, INPUT_POLL_INTERVAL,
 };
 
-pub const FRAMERATE: u32 = 60;
+#[derive(Parser)]
+#[command(
+    name = "slide-puzzle",
+    about = "A sliding puzzle with a window for every piece",
+    after_help = "See the Usage section of the README for what every option does"
+)]
+struct Args {
+    #[arg(short = 'v', long = "verbose", action = ArgAction::Count, help = "Log more, repeat for more detail")]
+    verbosity: u8,
+    #[arg(long, value_name = "PATH", help = "Also write logs to this file")]
+    log_file: Option<PathBuf>,
+    #[arg(long, help = "Play a looping scripted solve")]
+    demo: bool,
+    #[arg(long, value_name = "PATH", help = "Run the demo from this script")]
+    demo_script: Option<PathBuf>,
+    #[arg(long, help = "Shuffle and slowly solve the board until a key or click")]
+    screensaver: bool,
+    #[arg(
+        long,
+        value_name = "SCRAMBLE",
+        help = "Start from this scramble, such as 123/456/7_8"
+    )]
+    scramble: Option<String>,
+    #[arg(long, value_name = "PATH", help = "Write a report of the solve here")]
+    report: Option<PathBuf>,
+    #[arg(long, value_name = "PATH", help = "Where captured PNGs are saved")]
+    capture_dir: Option<PathBuf>,
+    #[arg(long, help = "Save a PNG of the board after every move")]
+    capture_moves: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Keep text files for streaming overlays here"
+    )]
+    overlay_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Show speedrun splits, with personal bests kept here"
+    )]
+    splits: Option<PathBuf>,
+    #[arg(long, help = "Show the move count beside the board")]
+    move_counter: bool,
+    #[arg(long, help = "Show the clock and the best time beside the board")]
+    timer: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Save a heatmap of where the gap went here"
+    )]
+    heatmap: Option<PathBuf>,
+    #[arg(long, value_name = "DRILL", value_parser = ["three-cycle", "last-two-rows"], help = "Practise one technique")]
+    drill: Option<String>,
+    #[arg(long, help = "Name known patterns as moves land on them")]
+    hints: bool,
+    #[arg(long, group = "game_mode", help = "Pieces above the gap fall into it")]
+    gravity: bool,
+    #[arg(long, value_name = "MODE", value_parser = modes::NAMES, group = "game_mode", help = "Play by another mode's rules")]
+    mode: Option<String>,
+    #[arg(long, help = "Only pieces next to the gap show their numbers")]
+    fog: bool,
+    #[arg(long, value_name = "SECONDS", value_parser = positive_seconds, group = "game_mode", help = "Slide a random piece every this many seconds")]
+    chaos: Option<Duration>,
+    #[arg(long, value_name = "MOVES", default_value_t = chaos::DEFAULT_MOVES, help = "Slides chaos makes each time")]
+    chaos_moves: u32,
+    #[arg(long, help = "Play two mirrored boards side by side")]
+    relay: bool,
+    #[arg(long, help = "Play with swap and teleport power-ups")]
+    arcade: bool,
+    #[arg(long, help = "Solve scrambles against a shrinking clock")]
+    descent: bool,
+    #[arg(long, value_name = "URL", help = "Fetch a scramble pack from this URL")]
+    pack_url: Option<String>,
+    #[arg(
+        long,
+        value_name = "KEY",
+        help = "Minisign public key scramble packs are signed with"
+    )]
+    pack_key: Option<String>,
+    #[arg(long, help = "Print every cached scramble pack and exit")]
+    list_packs: bool,
+    #[arg(long, help = "Print stats for every game solved so far and exit")]
+    stats: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Colour the board from this theme file"
+    )]
+    theme: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        group = "picture",
+        help = "Cut this picture up across the pieces"
+    )]
+    image: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FOLDER",
+        group = "picture",
+        help = "Show a picture from this folder on each piece"
+    )]
+    tiles: Option<PathBuf>,
+    #[arg(
+        long,
+        group = "picture",
+        help = "Show the desktop behind the board on the pieces"
+    )]
+    desktop: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Read the gamepad buttons from this file"
+    )]
+    gamepad_map: Option<PathBuf>,
+    #[arg(long, help = "Pick the gamepad buttons before playing")]
+    remap_gamepad: bool,
+    #[arg(long, help = "Listen for the gamepad through one hidden window")]
+    shared_input: bool,
+    #[arg(long, value_name = "SECONDS", value_parser = positive_seconds, help = "Pulse the next piece to move after this long idle")]
+    nudge: Option<Duration>,
+    #[arg(long, help = "Have the solver slide the pieces home")]
+    autosolve: bool,
+    #[arg(long, help = "Print how well each board was shuffled")]
+    shuffle_stats: bool,
+    #[arg(long, value_name = "SECONDS", value_parser = seconds, help = "Seconds between the solver's moves")]
+    move_delay: Option<Duration>,
+    #[arg(long, help = "Stay open after solving to step through the solve")]
+    review: bool,
+    #[arg(long, help = "Learn to spot boards that can't be solved")]
+    parity_trainer: bool,
+    #[arg(long, help = "Check for a newer release at startup")]
+    check_updates: bool,
+    // Pieces across and down
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, help = "Pieces across, or across x down like 4x3")]
+    size: Option<(usize, usize)>,
+    // These three default to the config file's, see config.rs
+    #[arg(
+        long,
+        alias = "piece-px",
+        value_name = "PIXELS",
+        help = "How big each piece is"
+    )]
+    piece_size: Option<u32>,
+    // Random slides to shuffle each board with
+    #[arg(long, value_name = "SLIDES", value_parser = clap::value_parser!(u32).range(1..), help = "Random slides to shuffle each board with")]
+    shuffle: Option<u32>,
+    #[arg(long, value_name = "PIXELS", help = "Gap between pieces")]
+    padding: Option<u32>,
+    #[arg(long, help = "Add a grip for resizing the board")]
+    resizable: bool,
+    #[arg(long, help = "Speed solving, with no animation and millisecond times")]
+    speed: bool,
+    #[arg(long, help = "Show how long frames take to draw")]
+    metrics: bool,
+    #[arg(long, help = "Start with goal positions shown")]
+    goal_labels: bool,
+    #[arg(long, help = "Make every random choice from this seed")]
+    seed: Option<u64>,
+    #[arg(long, value_name = "PATH", help = "Save a replay of the solve here")]
+    record: Option<PathBuf>,
+    #[arg(long, value_name = "PATH", help = "Play a replay back")]
+    replay: Option<PathBuf>,
+    #[arg(long, value_name = "NAME", help = "Play as this player")]
+    profile: Option<String>,
+    #[arg(long, help = "Simple mode, with only sliding allowed")]
+    simple: bool,
+    #[arg(long, help = "Loop the solver for a demo booth")]
+    exhibition: bool,
+    #[arg(long, value_name = "PRESET", value_parser = LayoutPreset::parse, help = "Where the board goes")]
+    layout: Option<LayoutPreset>,
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        help = "Space kept clear at the screen's edges"
+    )]
+    margin: Option<u32>,
+    #[arg(long, help = "Pick a bookmarked seed to play again")]
+    favourites: bool,
+    #[arg(
+        long,
+        value_name = "MOVES",
+        help = "Lock the board once it can't be solved in this many moves"
+    )]
+    move_limit: Option<u32>,
+    #[arg(long, value_name = "SECONDS", value_parser = whole_seconds, help = "Lock the board after this many seconds")]
+    time_limit: Option<Duration>,
+    #[arg(long, help = "Turn off hints and the solver")]
+    no_hints: bool,
+    #[cfg(feature = "ranked")]
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Play a ranked match through this relay server"
+    )]
+    ranked: Option<String>,
+    // Only there to say why it can't be used
+    #[cfg(not(feature = "ranked"))]
+    #[arg(long = "ranked", value_name = "URL", hide = true)]
+    ranked_unavailable: Option<String>,
+}
 
-pub static SCREEN_WIDTH: Lazy<u32> = Lazy::new(|| VideoMode::desktop_mode().width);
-pub static SCREEN_HEIGHT: Lazy<u32> = Lazy::new(|| VideoMode::desktop_mode().height);
+// Exits with a usage error if anything's wrong with the arguments
+fn parse_args() -> Args {
+    let mut args = Args::parse_from(windows_args(std::env::args()));
+
+    #[cfg(not(feature = "ranked"))]
+    if args.ranked_unavailable.is_some() {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "This build has no ranked play, rebuild with --features ranked",
+            )
+            .exit();
+    }
 
-const FONT_DATA: &'static [u8] = include_bytes!("../assets/VT323-Regular.ttf");
-pub static mut FONT: Lazy<SfBox<Font>> =
-    Lazy::new(|| unsafe { Font::from_memory(FONT_DATA).unwrap() });
+    if args.demo_script.is_some() {
+        args.demo = true;
+    }
+    if let Some(path) = &mut args.record {
+        if path.extension().is_none() {
+            path.set_extension(replay::EXTENSION);
+        }
+    }
 
-struct PuzzlePiece {
-    pub window: RenderWindow,
-    pub position: Vector2<f32>,
-    pub target_position: Vector2<f32>,
-    pub color: Color,
-    pub target_color: Color,
+    args
 }
 
-impl PuzzlePiece {
-    pub fn new(window: RenderWindow) -> Self {
-        Self {
-            window,
-            position: Vector2::new(0.0, 0.0),
-            target_position: Vector2::new(0.0, 0.0),
-            color: Color::BLACK,
-            target_color: Color::BLACK,
+// The arguments as clap expects them, from how Windows launches a
+// screensaver and how a shared link opens the game
+fn windows_args(raw_args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut args = Vec::new();
+    for arg in raw_args {
+        match arg.as_str() {
+            "/s" | "/S" => args.push("--screensaver".to_string()),
+            // Screensaver preview and settings aren't supported, so there's nothing to show
+            "/p" | "/P" | "/c" | "/C" => std::process::exit(0),
+            // Opened through a shared link
+            _ if arg.starts_with(scramble::URL_SCHEME) => {
+                args.push("--scramble".to_string());
+                args.push(arg);
+            }
+            _ => args.push(arg),
         }
     }
+    args
+}
 
-    pub fn set_position(&mut self, position: Vector2<f32>) {
-        self.target_position = position;
+fn seconds(text: &str) -> Result<Duration, String> {
+    match text.parse::<f32>() {
+        Ok(seconds) if seconds >= 0.0 && seconds.is_finite() => {
+            Ok(Duration::from_secs_f32(seconds))
+        }
+        _ => Err("expected a number of seconds".to_string()),
     }
+}
 
-    pub fn set_color(&mut self, color: Color) {
-        self.target_color = color;
+fn positive_seconds(text: &str) -> Result<Duration, String> {
+    match seconds(text) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err("expected a number of seconds, more than 0".to_string()),
     }
+}
 
-    pub fn update(&mut self) {
-        self.position = lazy_smoothing_vector2(self.position, self.target_position, 0.1);
-        self.window
-            .set_position(Vector2::new(self.position.x as i32, self.position.y as i32));
+fn whole_seconds(text: &str) -> Result<Duration, String> {
+    text.parse()
+        .map(Duration::from_secs)
+        .map_err(|_| "expected a whole number of seconds".to_string())
+}
 
-        self.color = lazy_smoothing_color(self.color, self.target_color, 0.1);
+// "4" for a square board, or "4x3" for one 4 pieces across and 3 down
+fn parse_size(text: &str) -> Result<(usize, usize), String> {
+    let error = || {
+        format!(
+            "expected a number of pieces across, or across x down like 4x3, each from {} to {}",
+            puzzle::MIN_SIZE,
+            puzzle::MAX_SIZE
+        )
+    };
+    let (width, height) = text.split_once('x').unwrap_or((text, text));
+    let (width, height): (usize, usize) = (
+        width.parse().map_err(|_| error())?,
+        height.parse().map_err(|_| error())?,
+    );
+    let sizes = puzzle::MIN_SIZE..=puzzle::MAX_SIZE;
+
+    if sizes.contains(&width) && sizes.contains(&height) {
+        Ok((width, height))
+    } else {
+        Err(error())
     }
 }
 
-struct World {
-    pub pieces: Vec<PuzzlePiece>,
-    pub grabbed_piece: Option<usize>,
-    pub grid: [[i8; 3]; 3],
-    pub grab_offset: Vector2<i32>,
-    pub available_move: Vector2<i8>,
-    pub piece_size: u32,
-    pub padding: u32,
-    pub center: Vector2<u32>,
-    pub playing: bool,
+// Runs the profile picker until someone is chosen
+fn pick_profile(profiles: Vec<Profile>) -> Profile {
+    let mut picker = ProfilePicker::open(&mut SfmlRenderer, profiles);
+    let frame_duration = sfml_renderer::frame_duration();
+
+    loop {
+        if let Some(profile) = picker.update() {
+            return profile;
+        }
+        picker.render();
+        sleep(frame_duration);
+    }
 }
 
-impl World {
-    fn new(window_size: u32, padding: u32, mix_steps: u32) -> Self {
-        let mut rng = rand::thread_rng();
+// Asks which favourite seed to play, if any
+fn pick_favourite(bookmarks: Vec<Bookmark>) -> Option<Bookmark> {
+    let mut picker = FavouritesPicker::open(&mut SfmlRenderer, bookmarks);
+    let frame_duration = sfml_renderer::frame_duration();
 
-        let mut pieces: Vec<PuzzlePiece> = Vec::new();
+    while !picker.cancelled {
+        if let Some(bookmark) = picker.update() {
+            info!("Playing favourite \"{}\"", bookmark.name);
+            return Some(bookmark);
+        }
+        picker.render();
+        sleep(frame_duration);
+    }
 
-        let center = Vector2::new(
-            *SCREEN_WIDTH / 2 - window_size / 2,
-            *SCREEN_HEIGHT / 2 - window_size / 2,
-        );
+    None
+}
 
-        for i in 0..8 {
-            let mut window = RenderWindow::new(
-                VideoMode::new(window_size, window_size, 32),
-                &format!("{}", i + 1),
-                Style::NONE,
-                &Default::default(),
-            );
-            window.set_framerate_limit(FRAMERATE);
+// Waits for the relay server to find an opponent, or for the player to give up
+#[cfg(feature = "ranked")]
+fn find_match(server: &str, player: &str) -> Result<Match, String> {
+    let mut matchmaker = Matchmaker::start(server, player);
+    let mut window = MatchmakingWindow::open(&mut SfmlRenderer, server);
+    let frame_duration = sfml_renderer::frame_duration();
 
-            pieces.push(PuzzlePiece::new(window));
+    loop {
+        if let Some(result) = matchmaker.poll() {
+            return result;
         }
-
-        // Make a 3x3 grid of ints
-        let mut grid: [[i8; 3]; 3] = [[0; 3]; 3];
-        for i in 0..3 {
-            for j in 0..3 {
-                let mut num: i8 = i * 3 + j;
-                if num == 8 {
-                    num = -1;
-                }
-                grid[i as usize][j as usize] = num;
-            }
+        window.update();
+        // Wait for the server to take us out of the queue
+        if window.cancelled {
+            matchmaker.cancel();
         }
+        window.render();
+        sleep(frame_duration);
+    }
+}
 
-        let mut last_swap = Vector2::new(0, 0);
-
-        // Mix up the windows
-        for _ in 0..mix_steps {
-            let available_grid_pos = Self::m_get_grid_pos(grid, -1);
+// Runs the remapping screen until every direction has a button
+fn remap_gamepad() -> Option<Mapping> {
+    let mut remapper = Remapper::open(&mut SfmlRenderer);
+    let frame_duration = sfml_renderer::frame_duration();
 
-            // Get all adjacent positions
-            let mut adjacent_positions: Vec<Vector2<i8>> = Vec::new();
-            if available_grid_pos.x > 0 && last_swap.x != available_grid_pos.x - 1 {
-                adjacent_positions
-                    .push(Vector2::new(available_grid_pos.x - 1, available_grid_pos.y));
-            }
-            if available_grid_pos.x < 2 && last_swap.x != available_grid_pos.x + 1 {
-                adjacent_positions
-                    .push(Vector2::new(available_grid_pos.x + 1, available_grid_pos.y));
-            }
-            if available_grid_pos.y > 0 && last_swap.y != available_grid_pos.y - 1 {
-                adjacent_positions
-                    .push(Vector2::new(available_grid_pos.x, available_grid_pos.y - 1));
-            }
-            if available_grid_pos.y < 2 && last_swap.y != available_grid_pos.y + 1 {
-                adjacent_positions
-                    .push(Vector2::new(available_grid_pos.x, available_grid_pos.y + 1));
-            }
+    while !remapper.cancelled {
+        if let Some(mapping) = remapper.update() {
+            return Some(mapping);
+        }
+        remapper.render();
+        sleep(frame_duration);
+    }
 
-            // Get a random adjacent position
-            let random_index = rng.gen_range(0..adjacent_positions.len());
+    None
+}
 
-            // Swap the two positions
-            let adjacent_position = adjacent_positions[random_index];
-            let adjacent_index = grid[adjacent_position.y as usize][adjacent_position.x as usize];
-            grid[adjacent_position.y as usize][adjacent_position.x as usize] = -1;
-            grid[available_grid_pos.y as usize][available_grid_pos.x as usize] = adjacent_index;
+fn main() {
+    play();
+    // Whichever way play ended, let queued saves reach the disk first
+    logging::shutdown();
+}
 
-            // Update the last swap
-            last_swap = available_grid_pos;
+fn play() {
+    let args = parse_args();
+    if let Err(error) = logging::init(args.verbosity, args.log_file) {
+        eprintln!("Failed to set up logging: {}", error);
+    }
+    platform::init();
+    locale::init(locale::Locale::from_env());
+    // Shared by every profile, and under anything given on the command line
+    let config = Profile::default()
+        .config_dir()
+        .map(|dir| Config::load(&dir.join(config::FILE_NAME)))
+        .unwrap_or_default();
+    sfml_renderer::set_framerate(config.framerate);
+    let wanted_piece_size = args.piece_size.unwrap_or(config.piece_size);
+    let padding = args.padding.unwrap_or(config.padding);
+    let shuffle = args.shuffle.unwrap_or(config.shuffle);
+    let replay = match args.replay.as_deref().map(replay::load) {
+        Some(Ok(replay)) => Some(replay),
+        Some(Err(error)) => {
+            eprintln!("Couldn't load replay: {}", error);
+            return;
         }
-
-        // Set the positions of the windows
-        for i in 0..8 {
-            let grid_pos = Self::m_get_grid_pos(grid, i as i8);
-            let grid_px = Self::m_grid_pos_to_px(
-                window_size,
-                padding,
-                center,
-                grid_pos.x as usize,
-                grid_pos.y as usize,
-            );
-
-            let grid_px_f32 = Vector2::new(grid_px.x as f32, grid_px.y as f32);
-
-            pieces[i].position = grid_px_f32;
-            pieces[i].target_position = grid_px_f32;
-
-            // TODO: Set the color of the window
-            pieces[i].color = Color::BLACK;
-            pieces[i].target_color = Color::BLACK;
-
-            pieces[i].window.set_position(grid_px);
-        }
-
-        Self {
-            pieces,
-            grabbed_piece: None,
-            grid,
-            grab_offset: Vector2::new(0, 0),
-            available_move: Vector2::new(0, 0),
-            piece_size: window_size,
-            padding,
-            center,
-            playing: true,
-        }
-    }
-
-    pub fn s_update(&mut self) {
-        for i in 0..8 {
-            while let Some(event) = self.pieces[i].window.poll_event() {
-                match event {
-                    Event::MouseButtonPressed { button, x, y } => {
-                        if button == mouse::Button::Left {
-                            self.grabbed_piece = Some(i);
-                            self.available_move = self.get_available_move(i);
-                            if self.available_move.x != 0 || self.available_move.y != 0 {
-                                self.grab_offset = Vector2::new(x, y);
-                            }
-                        }
-                    }
-                    Event::MouseButtonReleased { button, x: _, y: _ } => {
-                        if button == mouse::Button::Left {
-                            // If a window is grabbed
-                            if let Some(grabbed_window) = self.grabbed_piece {
-                                // If the window can move
-                                if self.available_move.x != 0 || self.available_move.y != 0 {
-                                    let current_grid_pos = self.get_grid_pos(grabbed_window);
-                                    let current_grid_px = self.get_px_from_grid(grabbed_window);
-
-                                    let available_grid_pos = Vector2::new(
-                                        current_grid_pos.x + self.available_move.x,
-                                        current_grid_pos.y + self.available_move.y,
-                                    );
-                                    let available_grid_px = self.grid_pos_to_px(
-                                        available_grid_pos.x as usize,
-                                        available_grid_pos.y as usize,
-                                    );
-
-                                    let window_position = self.pieces[i].position;
-                                    let mut moved = false;
-
-                                    // If the window can move horizontally
-                                    if self.available_move.x != 0 {
-                                        // If the window can move left
-                                        if self.available_move.x > 0 {
-                                            if window_position.x
-                                                > current_grid_px.x as f32
-                                                    + (self.padding / 2) as f32
-                                                    + (self.piece_size / 2) as f32
-                                            {
-                                                self.grid[current_grid_pos.y as usize]
-                                                    [current_grid_pos.x as usize] = -1;
-                                                self.grid[available_grid_pos.y as usize]
-                                                    [available_grid_pos.x as usize] =
-                                                    grabbed_window as i8;
-
-                                                moved = true;
-                                            }
-                                        } else {
-                                            // If the window can move right
-                                            if window_position.x
-                                                < current_grid_px.x as f32
-                                                    - (self.padding / 2) as f32
-                                                    - (self.piece_size / 2) as f32
-                                            {
-                                                self.grid[current_grid_pos.y as usize]
-                                                    [current_grid_pos.x as usize] = -1;
-                                                self.grid[available_grid_pos.y as usize]
-                                                    [available_grid_pos.x as usize] =
-                                                    grabbed_window as i8;
-
-                                                moved = true;
-                                            }
-                                        }
-                                    }
-                                    // If the window can move vertically
-                                    else {
-                                        // If the window can move up
-                                        if self.available_move.y > 0 {
-                                            if window_position.y
-                                                > current_grid_px.y as f32
-                                                    + (self.padding / 2) as f32
-                                                    + (self.piece_size / 2) as f32
-                                            {
-                                                self.grid[current_grid_pos.y as usize]
-                                                    [current_grid_pos.x as usize] = -1;
-                                                self.grid[available_grid_pos.y as usize]
-                                                    [available_grid_pos.x as usize] =
-                                                    grabbed_window as i8;
-
-                                                moved = true;
-                                            }
-                                        } else {
-                                            // If the window can move down
-                                            if window_position.y
-                                                < current_grid_px.y as f32
-                                                    - (self.padding / 2) as f32
-                                                    - (self.piece_size / 2) as f32
-                                            {
-                                                self.grid[current_grid_pos.y as usize]
-                                                    [current_grid_pos.x as usize] = -1;
-                                                self.grid[available_grid_pos.y as usize]
-                                                    [available_grid_pos.x as usize] =
-                                                    grabbed_window as i8;
-
-                                                moved = true;
-                                            }
-                                        }
-                                    }
-
-                                    // If the window didn't move reset its position
-                                    if !moved {
-                                        self.pieces[grabbed_window].set_position(Vector2::new(
-                                            current_grid_px.x as f32,
-                                            current_grid_px.y as f32,
-                                        ));
-                                    } else {
-                                        self.pieces[grabbed_window].set_position(Vector2::new(
-                                            available_grid_px.x as f32,
-                                            available_grid_px.y as f32,
-                                        ));
-                                    }
-                                }
-
-                                // Reset the grabbed window
-                                self.grabbed_piece = None;
-                            }
-                        }
-                    }
-                    _ => {}
+        None => None,
+    };
+    if args.pack_url.is_some() || args.list_packs {
+        let Some(key) = &args.pack_key else {
+            eprintln!("Scramble packs need --pack-key to check they're genuine");
+            return;
+        };
+
+        let mut packs = packs::cached(key);
+        if let Some(url) = &args.pack_url {
+            match packs::fetch(url, key) {
+                Ok(pack) => {
+                    packs.retain(|cached| cached.title != pack.title);
+                    packs.push(pack);
                 }
+                Err(error) => warn!("{}, falling back to cached packs", error),
             }
-
-            self.pieces[i].update();
-        }
-
-        // Grabbed window logic
-        if let Some(grabbed_window) = self.grabbed_piece {
-            // Get the current position of the grabbed window (grid and px)
-            let current_grid_pos = self.get_grid_pos(grabbed_window);
-            let current_grid_px = self.get_px_from_grid(grabbed_window);
-
-            // Get the position of the available space (grid and px)
-            let available_grid_pos = Vector2::new(
-                current_grid_pos.x + self.available_move.x,
-                current_grid_pos.y + self.available_move.y,
-            );
-            let available_grid_px =
-                self.grid_pos_to_px(available_grid_pos.x as usize, available_grid_pos.y as usize);
-
-            // Calculate the new position of the grabbed window
-            let mouse_position = mouse::desktop_position();
-            let new_x = if self.available_move.x != 0 {
-                (mouse_position.x - self.grab_offset.x).clamp(
-                    std::cmp::min(current_grid_px.x, available_grid_px.x),
-                    std::cmp::max(current_grid_px.x, available_grid_px.x),
-                )
-            } else {
-                current_grid_px.x
-            };
-            let new_y = if self.available_move.y != 0 {
-                (mouse_position.y - self.grab_offset.y).clamp(
-                    std::cmp::min(current_grid_px.y, available_grid_px.y),
-                    std::cmp::max(current_grid_px.y, available_grid_px.y),
-                )
-            } else {
-                current_grid_px.y
-            };
-
-            // Set the position
-            self.pieces[grabbed_window].position = Vector2::new(new_x as f32, new_y as f32);
-            self.pieces[grabbed_window].target_position = Vector2::new(new_x as f32, new_y as f32);
-            self.pieces[grabbed_window]
-                .window
-                .set_position(Vector2::new(new_x, new_y));
         }
 
-        // Check if the player won
-        {
-            let mut win = true;
-
-            for i in 0..8 {
-                let grid_pos = self.get_grid_pos(i);
-
-                if grid_pos.y * 3 + grid_pos.x != i as i8 {
-                    win = false;
-                    break;
+        if args.list_packs {
+            if packs.is_empty() {
+                println!("No scramble packs yet");
+            }
+            for pack in &packs {
+                println!("{}", pack.title);
+                for (name, grid) in &pack.levels {
+                    println!("  {}: {}", name, scramble::to_link(grid));
                 }
             }
+            io_worker::flush();
+            return;
+        }
+    }
 
-            if win {
-                println!("You win!");
-                self.playing = false;
+    if args.stats {
+        // The picker's not shown just to print stats, so it's the default
+        // profile's unless --profile says otherwise
+        let profile = match args.profile.as_deref().map(Profile::named) {
+            Some(Ok(profile)) => profile,
+            Some(Err(error)) => {
+                eprintln!("{}", error);
+                return;
             }
+            None => Profile::default(),
+        };
+        let Some(dir) = profile.data_dir() else {
+            eprintln!("Couldn't find the data directory");
+            return;
+        };
+        match stats::load(&dir.join(stats::FILE_NAME)) {
+            Ok(games) => print!("{}", stats::report(&games, &locale::current())),
+            Err(error) => eprintln!("{}", error),
         }
+        return;
     }
 
-    pub fn s_render(&mut self) {
-        for i in 0..8 {
-            let grid_pos = self.get_grid_pos(i);
+    let (scramble, mut rules) = match args.scramble.as_deref().map(challenge::parse) {
+        Some(Ok((grid, rules))) => (Some(grid), rules),
+        Some(Err(error)) => {
+            eprintln!("Couldn't load scramble: {}", error);
+            return;
+        }
+        None => (None, Rules::default()),
+    };
+    // Limits given on the command line win over a challenge link's
+    rules.move_limit = args.move_limit.or(rules.move_limit);
+    rules.time_limit = args.time_limit.or(rules.time_limit);
+    rules.no_hints |= args.no_hints;
+
+    // A scramble or replay brings its own board size
+    let given_size = scramble
+        .as_ref()
+        .or(replay.as_ref().map(|replay| &replay.start_grid))
+        .map(|grid| (grid.width(), grid.height()));
+    let (width, height) = match (args.size, given_size) {
+        (Some(size), Some(given)) if size != given => {
+            eprintln!(
+                "--size {}x{} doesn't match the {}x{} board given",
+                size.0, size.1, given.0, given.1
+            );
+            return;
+        }
+        (size, given) => given.or(size).unwrap_or((3, 3)),
+    };
+    if args.shuffle.is_some() && given_size.is_some() {
+        eprintln!("--shuffle can't be used with a scramble or replay, they bring their own board");
+        return;
+    }
+    // These modes are built around the 3x3 board
+    #[cfg(feature = "ranked")]
+    let ranked_play = args.ranked.is_some();
+    #[cfg(not(feature = "ranked"))]
+    let ranked_play = false;
+    let three_by_three_only = [
+        ("--drill", args.drill.is_some()),
+        ("--descent", args.descent),
+        // Scripted demos, that is, not replays
+        (
+            "--demo",
+            args.demo && !args.screensaver && (args.demo_script.is_some() || replay.is_none()),
+        ),
+        ("--parity-trainer", args.parity_trainer),
+        ("--exhibition", args.exhibition),
+        ("--splits", args.splits.is_some()),
+        ("--arcade", args.arcade),
+        ("--ranked", ranked_play),
+    ];
+    let three_by_three_only = three_by_three_only
+        .iter()
+        .find(|(_, used)| *used)
+        .map(|(flag, _)| *flag);
+    if let Some(flag) = three_by_three_only.filter(|_| (width, height) != (3, 3)) {
+        eprintln!("{} only works on the 3x3 board", flag);
+        return;
+    }
 
-            let bg_color = if grid_pos.y * 3 + grid_pos.x == i as i8 {
-                Color::rgb(0, 200, 0)
+    let mut drill = match args.drill.as_deref().map(DrillKind::parse) {
+        Some(Ok(kind)) => Some(Drill::new(kind)),
+        Some(Err(error)) => {
+            eprintln!("{}", error);
+            return;
+        }
+        None => None,
+    };
+
+    let mut demo = if args.screensaver {
+        Some(Demo::screensaver())
+    } else if let Some(path) = &args.demo_script {
+        match Demo::load(path) {
+            Ok(demo) => Some(demo),
+            Err(error) => {
+                eprintln!("Couldn't load demo script: {}", error);
+                return;
+            }
+        }
+    } else if let Some(replay) = &replay {
+        match Demo::from_replay(replay) {
+            Ok(demo) => Some(demo),
+            Err(error) => {
+                eprintln!("Couldn't play replay: {}", error);
+                return;
+            }
+        }
+    } else if args.demo {
+        Some(Demo::builtin())
+    } else {
+        None
+    };
+
+    let profile = match args.profile.as_deref().map(Profile::named) {
+        Some(Ok(profile)) => profile,
+        Some(Err(error)) => {
+            eprintln!("{}", error);
+            return;
+        }
+        // Nobody to ask while a demo, screensaver or exhibition plays
+        None if demo.is_some() || args.exhibition => Profile::default(),
+        None => {
+            let profiles = profile::list();
+            if profiles.is_empty() {
+                Profile::default()
             } else {
-                Color::rgb(200, 0, 0)
-            };
-            self.pieces[i].set_color(bg_color);
-
-            let color = self.pieces[i].color;
-            self.pieces[i].window.clear(color);
-
-            // Write the window number in the middle of the window
-            let mut text = Text::new(&format!("{}", i + 1), unsafe { &*FONT }, 100);
-            text.set_fill_color(Color::WHITE);
-            text.set_origin(Vector2::new(
-                text.local_bounds().width / 2.0,
-                text.local_bounds().height / 2.0,
-            ));
-            text.set_position(Vector2::new(42.5, 5.0));
-            self.pieces[i].window.draw(&text);
-
-            self.pieces[i].window.display();
-
-            // Get the global mouse position
-            let mouse_position = self.pieces[i].window.mouse_position();
-
-            // Check if the mouse is in the window
-            if mouse_position.x >= 0
-                && mouse_position.x <= self.piece_size as i32
-                && mouse_position.y >= 0
-                && mouse_position.y <= self.piece_size as i32
-            {
-                self.pieces[i].window.request_focus();
+                pick_profile(profiles)
             }
         }
+    };
+    // So a new profile shows up in the picker from then on
+    if let Some(dir) = profile.config_dir() {
+        if let Err(error) = std::fs::create_dir_all(&dir) {
+            warn!("Couldn't create {}: {}", dir.display(), error);
+        }
     }
-
-    pub fn get_available_move(&mut self, index: usize) -> Vector2<i8> {
-        let grid_pos = self.get_grid_pos(index);
-
-        // Check left
-        if grid_pos.x > 0 {
-            if self.grid[grid_pos.y as usize][(grid_pos.x - 1) as usize] == -1 {
-                return Vector2::new(-1, 0);
-            }
+    info!("Playing as {}", profile.title());
+
+    #[cfg(feature = "ranked")]
+    let ranked_match = match &args.ranked {
+        // The server keeps a rating per player, so it needs a name to go by
+        Some(_) if profile.name.is_none() => {
+            eprintln!("Ranked play needs a profile, pick one with --profile");
+            return;
         }
-
-        // Check right
-        if grid_pos.x < 2 {
-            if self.grid[grid_pos.y as usize][(grid_pos.x + 1) as usize] == -1 {
-                return Vector2::new(1, 0);
+        Some(server) => match find_match(server, profile.title()) {
+            Ok(found) => Some((server.clone(), found)),
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
             }
+        },
+        None => None,
+    };
+    #[cfg(feature = "ranked")]
+    let ranked_seed = ranked_match.as_ref().map(|(_, found)| found.seed);
+    #[cfg(not(feature = "ranked"))]
+    let ranked_seed = None;
+
+    let bookmarks_path = bookmarks::default_path(&profile);
+    let bookmarks = bookmarks_path
+        .as_deref()
+        .map(Bookmarks::load)
+        .unwrap_or_default();
+    let favourite = if args.favourites && args.seed.is_none() && replay.is_none() {
+        if bookmarks.entries.is_empty() {
+            println!("No favourite seeds yet, press B during a game to bookmark one");
+            None
+        } else {
+            pick_favourite(bookmarks.entries.clone())
         }
-
-        // Check up
-        if grid_pos.y > 0 {
-            if self.grid[(grid_pos.y - 1) as usize][grid_pos.x as usize] == -1 {
-                return Vector2::new(0, -1);
+    } else {
+        None
+    };
+    // Whether the board is one picked out beforehand, rather than a new one
+    let seeded = ranked_seed.is_some() || args.seed.is_some() || favourite.is_some();
+    // A replay plays out with the same luck it was recorded with, and both
+    // sides of a ranked match get the same board
+    let seed = ranked_seed
+        .or(args.seed)
+        .or(replay.as_ref().map(|replay| replay.seed))
+        .or(favourite.map(|favourite| favourite.seed))
+        .unwrap_or_else(|| rand::thread_rng().gen());
+    random::init(seed);
+    info!(
+        "Random seed {}, use --seed {} to play this run again",
+        seed, seed
+    );
+
+    let gamepad_path = args
+        .gamepad_map
+        .clone()
+        .or_else(|| gamepad::default_path(&profile));
+    let mut gamepad = gamepad_path
+        .as_deref()
+        .map(Mapping::load)
+        .unwrap_or_default();
+    if args.remap_gamepad {
+        match remap_gamepad() {
+            Some(mapping) => {
+                if let Some(path) = gamepad_path {
+                    mapping.save(path);
+                }
+                gamepad = mapping;
             }
+            None => println!("Gamepad setup cancelled, keeping the old buttons"),
         }
+    }
 
-        // Check down
-        if grid_pos.y < 2 {
-            if self.grid[(grid_pos.y + 1) as usize][grid_pos.x as usize] == -1 {
-                return Vector2::new(0, 1);
+    // A layout asked for on the command line is remembered for this
+    // resolution, and used again next time the screen is this size
+    let desktop_size = SfmlRenderer.desktop_size();
+    let mut layouts =
+        layout::preferences_path(&profile).map(|path| (LayoutPreferences::load(&path), path));
+    let remembered = layouts
+        .as_ref()
+        .and_then(|(preferences, _)| preferences.get(desktop_size));
+    let layout = Layout {
+        preset: args
+            .layout
+            .or(remembered.map(|layout| layout.preset))
+            .unwrap_or_else(|| LayoutPreset::for_screen(desktop_size)),
+        margin: args
+            .margin
+            .or(remembered.map(|layout| layout.margin))
+            .unwrap_or(0),
+    };
+    if args.layout.is_some() || args.margin.is_some() {
+        if let Some((preferences, path)) = &mut layouts {
+            preferences.set(desktop_size, layout);
+            preferences.save(path.clone());
+        }
+    }
+    debug!(
+        "Using {:?} on a {}x{} screen",
+        layout, desktop_size.x, desktop_size.y
+    );
+
+    let room = Vector2::new(
+        desktop_size.x.saturating_sub(2 * layout.margin),
+        desktop_size.y.saturating_sub(2 * layout.margin),
+    );
+    let piece_size = match fit_piece_size(wanted_piece_size, padding, width, height, room) {
+        Ok(size) => {
+            if size < wanted_piece_size {
+                println!(
+                    "Shrinking pieces to {} pixels so the board fits on screen",
+                    size
+                );
             }
+            size
         }
-
-        return Vector2::new(0, 0);
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+
+    // Taken before the pieces open, so it's the desktop behind them
+    let desktop_shot = args.desktop.then(|| {
+        let path =
+            std::env::temp_dir().join(format!("slide-puzzle-desktop-{}.png", std::process::id()));
+        screenshot::capture(&path).map(|()| path)
+    });
+
+    let mut world = World::new(SfmlRenderer, width, height, piece_size, padding, shuffle);
+    world.set_layout(layout);
+    world.gamepad = gamepad;
+    if args.shared_input && !world.share_input() {
+        println!("Couldn't open a window for input, every piece will listen for the gamepad");
     }
-
-    pub fn get_px_from_grid(&mut self, index: usize) -> Vector2<i32> {
-        for x_index in 0..3 {
-            for y_index in 0..3 {
-                if self.grid[y_index][x_index] == index as i8 {
-                    return self.grid_pos_to_px(x_index, y_index);
-                }
+    world.nudge_after = args.nudge;
+    world.bookmarks = bookmarks;
+    world.bookmarks_path = bookmarks_path;
+    // Stay open after solving so the solve can be stepped through
+    world.exit_on_win = !args.review;
+    let simple = args.simple || simple::enabled_by_config();
+    if simple {
+        world.simple = true;
+        // Boards keep coming, and the clipboard is left alone
+        world.exit_on_win = false;
+        world.share_results = false;
+        if scramble.is_some() {
+            println!("Simple mode deals its own boards, ignoring the scramble");
+        }
+    } else if let Some(grid) = &scramble {
+        world.set_grid(grid.clone());
+    }
+    world.shuffle_stats = args.shuffle_stats;
+    if args.shuffle_stats && scramble.is_none() {
+        let quality = rating::shuffle_quality(&world.start_grid, world.mix_steps);
+        println!("Shuffled {}", rating::describe_shuffle(&quality));
+    }
+    // The board's known in advance, so have its solution ready for the
+    // first hint or analysis
+    if !simple && (seeded || scramble.is_some()) {
+        solver::warm_up(world.start_grid.clone());
+    }
+    // 4x4 boards are solved with the pattern database, which takes a while
+    // to build the first time
+    if !simple && (width, height) == (4, 4) {
+        pattern_db::prepare();
+    }
+    world.three_by_three_only = three_by_three_only;
+    world.report_path = args.report;
+    world.heatmap_path = args.heatmap;
+    world.replay_path = args.record;
+    world.hints = args.hints;
+    world.goal_labels = args.goal_labels;
+    if !simple {
+        world.set_rules(rules);
+    }
+    // A theme file, if there is one, takes over from the config's colours
+    world.theme = config.theme;
+    world.theme_watcher = args
+        .theme
+        .or_else(|| profile.config_dir().map(|dir| dir.join("theme.txt")))
+        .map(ThemeWatcher::new);
+    let mode = match (&args.mode, args.chaos) {
+        (Some(name), _) => match modes::by_name(name) {
+            Ok(mode) => Some(mode),
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
             }
+        },
+        (None, Some(interval)) => Some(Box::new(Chaos::new(interval, args.chaos_moves)) as _),
+        (None, None) => args.gravity.then(|| Box::new(modes::Gravity) as _),
+    };
+    if let Some(mode) = mode {
+        world.set_mode(mode);
+    }
+    if args.arcade {
+        world.show_power_ups();
+    }
+    if let Some(delay) = args.move_delay {
+        world.auto_move_delay = delay;
+    }
+    if args.autosolve && !simple {
+        world.auto_solve_board();
+    }
+    if let Some(path) = &args.image {
+        if let Err(error) = world.show_image(path) {
+            eprintln!("{}", error);
+            return;
         }
-
-        return Vector2::new(0, 0);
     }
-
-    pub fn grid_pos_to_px(&mut self, x_index: usize, y_index: usize) -> Vector2<i32> {
-        return Self::m_grid_pos_to_px(
-            self.piece_size,
-            self.padding,
-            self.center,
-            x_index,
-            y_index,
-        );
+    if let Some(dir) = &args.tiles {
+        if let Err(error) = world.show_tiles(dir) {
+            eprintln!("{}", error);
+            return;
+        }
     }
-
-    fn m_grid_pos_to_px(
-        window_size: u32,
-        padding: u32,
-        center: Vector2<u32>,
-        x_index: usize,
-        y_index: usize,
-    ) -> Vector2<i32> {
-        let position = Vector2::new(
-            (x_index as i32 - 1) * (window_size + padding) as i32 + center.x as i32,
-            (y_index as i32 - 1) * (window_size + padding) as i32 + center.y as i32,
-        );
-
-        return position;
+    if let Some(shot) = desktop_shot {
+        let shown = shot.and_then(|path| {
+            let shown = world.show_desktop(&path);
+            let _ = std::fs::remove_file(path);
+            shown
+        });
+        if let Err(error) = shown {
+            eprintln!("{}", error);
+            return;
+        }
+    }
+    if args.fog {
+        world.fog = true;
+        world.update_fog();
+    }
+    if let Some(capture_dir) = args.capture_dir {
+        world.capture_dir = capture_dir;
+    }
+    world.overlay = args.overlay_dir.map(Overlay::new);
+    if args.speed {
+        world.speed = true;
+        // The arrow keys work from the first press, without clicking the board
+        world.pieces[0].window.request_focus();
+    }
+    world.hooks.on_win = Some(announce_win(world.speed));
+    if let Some(path) = args.splits {
+        world.show_splits(profile.data_path(path));
+    }
+    if args.move_counter {
+        world.show_move_counter();
+    }
+    if let Some(dir) = profile.data_dir() {
+        world.best_times = BestTimes::load(dir.join(timer::FILE_NAME));
+        world.stats_path = Some(dir.join(stats::FILE_NAME));
+    }
+    if args.timer {
+        world.show_timer();
+    }
+    if args.capture_moves {
+        world.capture_moves = true;
+        world.capture_move();
+    }
+    info!("Started a new puzzle");
+
+    if let Some(demo) = &mut demo {
+        world.exit_on_win = false;
+        world.exit_on_input = args.screensaver;
+        // Don't clobber the clipboard or report every time the demo solves the board
+        world.share_results = false;
+        world.report_path = None;
+        world.best_times.path = None;
+        world.stats_path = None;
+        demo.start(&mut world);
     }
 
-    pub fn get_grid_pos(&mut self, index: usize) -> Vector2<i8> {
-        return Self::m_get_grid_pos(self.grid, index as i8);
+    if let Some(drill) = &mut drill {
+        // Drills keep coming until the player quits
+        world.exit_on_win = false;
+        world.share_results = false;
+        drill.start(&mut world);
     }
 
-    pub fn m_get_grid_pos(grid: [[i8; 3]; 3], index: i8) -> Vector2<i8> {
-        for y_index in 0..3 {
-            for x_index in 0..3 {
-                if grid[y_index][x_index] == index as i8 {
-                    return Vector2::new(x_index as i8, y_index as i8);
-                }
-            }
+    let mut quiz = args.parity_trainer.then(|| {
+        // Boards keep coming until the player quits
+        world.exit_on_win = false;
+        world.share_results = false;
+        Quiz::start(&mut world)
+    });
+
+    let mut exhibition = args.exhibition.then(|| {
+        // Runs until the booth closes, without filling the clipboard or
+        // disk with every visitor's solve
+        world.exit_on_win = false;
+        world.share_results = false;
+        world.report_path = None;
+        world.replay_path = None;
+        world.stats_path = None;
+        Exhibition::start(&mut world)
+    });
+
+    #[cfg(feature = "ranked")]
+    let mut ranked = ranked_match.map(|(server, found)| {
+        // Stay open to hear how the match went, with no help on the way
+        world.exit_on_win = false;
+        world.set_rules(Rules {
+            no_hints: true,
+            ..Rules::default()
+        });
+        Ranked::start(&mut world, &server, profile.title(), found)
+    });
+
+    let mut descent = args.descent.then(|| {
+        // The run only ends when the clock does
+        world.exit_on_win = false;
+        world.share_results = false;
+        Descent::start(&mut world)
+    });
+
+    // The second board only takes the scramble, everything else is about
+    // the first one
+    let mut relay = args.relay.then(|| {
+        let mut partner = World::new(SfmlRenderer, width, height, piece_size, padding, shuffle);
+        partner.set_layout(layout);
+        partner.hooks.on_win = Some(announce_win(false));
+        if let Some(grid) = scramble {
+            partner.set_grid(grid);
         }
+        Relay::place(&mut world, &mut partner);
 
-        return Vector2::new(-1, -1);
-    }
-}
+        // Neither board is done until both are
+        for world in [&mut world, &mut partner] {
+            world.exit_on_win = false;
+            world.share_results = false;
+        }
+        info!("Started a relay");
+        (Relay::new(), partner)
+    });
 
-fn main() {
-    let mut world = World::new(100, 10, 7);
+    let mut resize_handle = args.resizable.then(|| ResizeHandle::open(&mut world));
+
+    // Only ever asks the network when the player opted in
+    let mut update_check = args.check_updates.then(UpdateCheck::start);
+    let mut update_notice = None;
 
     let mut last_update = Instant::now();
-    let frame_duration = Duration::from_secs_f32(1.0 / FRAMERATE as f32);
+    let frame_duration = sfml_renderer::frame_duration();
+
+    let mut metrics = args.metrics.then(|| {
+        (
+            FrameMetrics::new(frame_duration),
+            MetricsWindow::open(&mut world.renderer),
+        )
+    });
+
+    while world.playing && relay.as_ref().is_none_or(|(_, partner)| partner.playing) {
+        if let Some(demo) = &mut demo {
+            demo.update(&mut world);
+        }
+        if let Some(drill) = &mut drill {
+            drill.update(&mut world);
+        }
+        if let Some(descent) = &mut descent {
+            descent.update(&mut world);
+        }
+        if let Some(exhibition) = &mut exhibition {
+            exhibition.update(&mut world);
+        }
+        #[cfg(feature = "ranked")]
+        if let Some(ranked) = &mut ranked {
+            ranked.update(&mut world);
+        }
+        if let Some(quiz) = &mut quiz {
+            quiz.update(&mut world);
+        }
+        if let Some(resize_handle) = &mut resize_handle {
+            resize_handle.update(&mut world);
+        }
 
-    while world.playing {
         world.s_update();
+        if let Some((relay, partner)) = &mut relay {
+            partner.s_update();
+            relay.update(&mut world, partner);
+
+            if world.solved && partner.solved {
+                println!("Both boards solved!");
+                world.playing = false;
+            }
+            partner.s_render();
+        }
+        if let Some(release) = update_check.as_mut().and_then(UpdateCheck::poll) {
+            update_notice = Some(UpdateNotice::open(&mut world.renderer, &release));
+            update_check = None;
+        }
+        if let Some(notice) = &mut update_notice {
+            notice.update();
+            if notice.closed {
+                update_notice = None;
+            }
+        }
+
         world.s_render();
+        if let Some(descent) = &mut descent {
+            descent.render();
+        }
+        if let Some(exhibition) = &mut exhibition {
+            exhibition.render();
+        }
+        #[cfg(feature = "ranked")]
+        if let Some(ranked) = &mut ranked {
+            ranked.render();
+        }
+        if let Some(notice) = &mut update_notice {
+            notice.render();
+        }
+        if let Some(quiz) = &mut quiz {
+            quiz.render();
+        }
+        if let Some(resize_handle) = &mut resize_handle {
+            resize_handle.render();
+        }
+        if let Some((frame_metrics, metrics_window)) = &mut metrics {
+            if let Some(latency) = world.input_latency.take() {
+                frame_metrics.record_latency(latency);
+            }
+            metrics_window.update();
+            metrics_window.render(frame_metrics);
+        }
+        let work = last_update.elapsed();
 
-        // Wait for next frame
-        if let Some(sleep_duration) =
+        // Wait for next frame, still taking input meanwhile when speed solving
+        while let Some(sleep_duration) =
             (frame_duration).checked_sub(Instant::now().duration_since(last_update))
         {
-            sleep(sleep_duration);
+            if !world.speed {
+                sleep(sleep_duration);
+                break;
+            }
+            world.poll_input();
+            sleep(sleep_duration.min(INPUT_POLL_INTERVAL));
+        }
+        if let Some((frame_metrics, _)) = &mut metrics {
+            frame_metrics.record_frame(work, last_update.elapsed());
         }
         last_update = Instant::now();
     }
-}
 
-pub fn lazy_smoothing_vector2(
-    current: Vector2<f32>,
-    target: Vector2<f32>,
-    threshold: f32,
-) -> Vector2<f32> {
-    Vector2::new(
-        lazy_smoothing(current.x, target.x, threshold),
-        lazy_smoothing(current.y, target.y, threshold),
-    )
-}
+    #[cfg(feature = "ranked")]
+    if let Some(ranked) = &mut ranked {
+        ranked.finish();
+    }
 
-pub fn lazy_smoothing(current: f32, target: f32, threshold: f32) -> f32 {
-    if (current - target).abs() < threshold {
-        target
-    } else {
-        current + (target - current) * 0.15
+    if let Some(drill) = &drill {
+        if drill.attempts > 0 {
+            println!(
+                "{} drills: {}/{} at par ({:.0}%)",
+                drill.kind.name(),
+                drill.successes,
+                drill.attempts,
+                drill.success_rate() * 100.0
+            );
+        }
+    }
+
+    if let Some((frame_metrics, _)) = &metrics {
+        frame_metrics.log_summary();
+        println!("{}", frame_metrics.lines().join("\n"));
     }
+
+    if let Some(quiz) = &quiz {
+        if quiz.asked > 0 {
+            println!("Parity trainer: {}/{} right", quiz.correct, quiz.asked);
+        }
+    }
+
+    debug!("Closing piece windows");
 }
 
-pub fn lazy_smoothing_color(current: Color, target: Color, threshold: f32) -> Color {
-    Color::rgb(
-        lazy_smoothing(current.r as f32, target.r as f32, threshold) as u8,
-        lazy_smoothing(current.g as f32, target.g as f32, threshold) as u8,
-        lazy_smoothing(current.b as f32, target.b as f32, threshold) as u8,
-    )
+// Tells the player they've won, with the time to the millisecond when speed
+// solving
+fn announce_win(speed: bool) -> WinHook {
+    Box::new(move |win| {
+        println!("You win!");
+        if speed {
+            println!(
+                "Time: {}",
+                locale::current().format_duration_millis(win.time)
+            );
+        }
+        if win.hints > 0 {
+            println!("Hints used: {}", win.hints);
+        }
+        if win.best_time {
+            println!(
+                "New best time: {}",
+                locale::current().format_duration(win.time)
+            );
+        }
+    })
 }
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use log::{debug, trace};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    font, locale,
+    renderer::{Renderer, WindowHandle},
+};
+
+const WINDOW_SIZE: u32 = 400;
+const LINE_HEIGHT: f32 = 28.0;
+// A frame this much longer than it should be means at least one was skipped
+const DROPPED_FRAME_SLACK: f32 = 1.5;
+
+// How well the game keeps up: how long frames take, how many are missed and
+// how long a move takes to show on screen
+pub struct FrameMetrics {
+    // How long a frame should take
+    pub budget: Duration,
+    pub frames: u64,
+    pub dropped: u64,
+    // Time spent updating and drawing, not counting the wait for the next frame
+    pub last_frame: Duration,
+    pub worst_frame: Duration,
+    // From the input behind a move to its piece moving
+    pub last_latency: Option<Duration>,
+    pub worst_latency: Option<Duration>,
+}
+
+impl FrameMetrics {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            frames: 0,
+            dropped: 0,
+            last_frame: Duration::ZERO,
+            worst_frame: Duration::ZERO,
+            last_latency: None,
+            worst_latency: None,
+        }
+    }
+
+    // `work` is how long the frame took to update and draw, `interval` how
+    // long it was since the frame before started
+    pub fn record_frame(&mut self, work: Duration, interval: Duration) {
+        self.frames += 1;
+        self.last_frame = work;
+        self.worst_frame = self.worst_frame.max(work);
+
+        let dropped = if interval.as_secs_f32() > self.budget.as_secs_f32() * DROPPED_FRAME_SLACK {
+            (interval.as_secs_f32() / self.budget.as_secs_f32()).round() as u64 - 1
+        } else {
+            0
+        };
+        self.dropped += dropped;
+
+        trace!(
+            "Frame {} took {:.2} ms, {:.2} ms since the last one{}",
+            self.frames,
+            work.as_secs_f32() * 1000.0,
+            interval.as_secs_f32() * 1000.0,
+            if dropped > 0 {
+                format!(", {} dropped", dropped)
+            } else {
+                String::new()
+            }
+        );
+    }
+
+    pub fn record_latency(&mut self, latency: Duration) {
+        trace!(
+            "Input to motion took {:.2} ms",
+            latency.as_secs_f32() * 1000.0
+        );
+        self.last_latency = Some(latency);
+        self.worst_latency = Some(
+            self.worst_latency
+                .map_or(latency, |worst| worst.max(latency)),
+        );
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let locale = locale::current();
+        let millis = |duration: Duration| locale.format_decimal(duration.as_secs_f32() * 1000.0, 1);
+
+        let mut lines = vec![
+            format!(
+                "Frame: {} ms (worst {})",
+                millis(self.last_frame),
+                millis(self.worst_frame)
+            ),
+            format!(
+                "Dropped: {} of {}",
+                locale.format_count(self.dropped),
+                locale.format_count(self.frames)
+            ),
+        ];
+        match (self.last_latency, self.worst_latency) {
+            (Some(last), Some(worst)) => lines.push(format!(
+                "Input lag: {} ms (worst {})",
+                millis(last),
+                millis(worst)
+            )),
+            _ => lines.push("Input lag: no moves yet".to_string()),
+        }
+
+        lines
+    }
+
+    pub fn log_summary(&self) {
+        debug!(
+            "Performance over {} frames: {}",
+            self.frames,
+            self.lines().join(", ")
+        );
+    }
+}
+
+// Shows the metrics while playing, in the top left corner of the desktop
+pub struct MetricsWindow<W: WindowHandle> {
+    window: W,
+}
+
+impl<W: WindowHandle> MetricsWindow<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Performance");
+        window.set_position(Vector2::new(20, 20));
+
+        Self { window }
+    }
+
+    pub fn update(&mut self) {
+        // Nothing to click, but the events still need draining
+        while self.window.poll_event().is_some() {}
+    }
+
+    pub fn render(&mut self, metrics: &FrameMetrics) {
+        self.window.clear(Color::BLACK);
+
+        self.draw_text("Performance", 0, Color::WHITE);
+        for (index, line) in metrics.lines().iter().enumerate() {
+            self.draw_text(line, index as u32 + 1, Color::WHITE);
+        }
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
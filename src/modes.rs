@@ -0,0 +1,188 @@
+// Game modes: the rules a board is played by. A mode says which slides are
+// allowed, when the board counts as solved and what happens to it on its
+// own every frame, so a new way to play is a new GameMode here rather than
+// more special cases in World.
+
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::{
+    chaos::{self, Chaos},
+    layout::neighbor_table,
+    puzzle::Grid,
+    renderer::Renderer,
+    simple, World,
+};
+
+pub const NAMES: [&str; 5] = ["classic", "time-attack", "chaos", "gravity", "toroidal"];
+// How long a time attack lasts
+pub const TIME_ATTACK_LIMIT: Duration = Duration::from_secs(120);
+
+pub trait GameMode<R: Renderer> {
+    fn name(&self) -> &'static str;
+
+    // Set the board up for the mode, once, as play starts
+    fn start(&mut self, _world: &mut World<R>) {}
+
+    // Whether the piece in `cell` may slide into the gap in `gap`, both as
+    // laid out on screen
+    fn can_slide(&self, grid: &Grid, cell: usize, gap: usize) -> bool {
+        neighbor_table(grid.width(), grid.height()).are_neighbors(cell, gap)
+    }
+
+    fn is_won(&self, world: &World<R>) -> bool {
+        world.canonical_grid() == Grid::solved(world.width, world.height)
+    }
+
+    // Whatever the mode does to the board by itself, once a frame
+    fn tick(&mut self, _world: &mut World<R>) {}
+
+    // Pieces above the gap drop into it after every move, so the board
+    // can't be turned or moves taken back
+    fn pieces_fall(&self) -> bool {
+        false
+    }
+
+    // Whether the solver's moves are ones the mode allows. Hints and
+    // auto-solving are turned off when they aren't.
+    fn solver_helps(&self) -> bool {
+        true
+    }
+}
+
+// The mode called `name`, with its usual settings
+pub fn by_name<R: Renderer>(name: &str) -> Result<Box<dyn GameMode<R>>, String> {
+    match name {
+        "classic" => Ok(Box::new(Classic)),
+        "time-attack" => Ok(Box::new(TimeAttack::new(TIME_ATTACK_LIMIT))),
+        "chaos" => Ok(Box::new(Chaos::new(
+            chaos::DEFAULT_INTERVAL,
+            chaos::DEFAULT_MOVES,
+        ))),
+        "gravity" => Ok(Box::new(Gravity)),
+        "toroidal" => Ok(Box::new(Toroidal)),
+        other => Err(format!(
+            "Unknown mode \"{}\", pick one of {}",
+            other,
+            NAMES.join(", ")
+        )),
+    }
+}
+
+// Slide pieces into the gap until they're all home
+pub struct Classic;
+
+impl<R: Renderer> GameMode<R> for Classic {
+    fn name(&self) -> &'static str {
+        "classic"
+    }
+}
+
+// As many boards as can be solved before the clock runs out, each one
+// dealt a moment after the last is solved
+pub struct TimeAttack {
+    pub limit: Duration,
+    pub boards_solved: u32,
+    // From the first move of the first board
+    started_at: Option<Instant>,
+    counted: bool,
+}
+
+impl TimeAttack {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            limit,
+            boards_solved: 0,
+            started_at: None,
+            counted: false,
+        }
+    }
+}
+
+impl<R: Renderer> GameMode<R> for TimeAttack {
+    fn name(&self) -> &'static str {
+        "time-attack"
+    }
+
+    fn start(&mut self, world: &mut World<R>) {
+        world.exit_on_win = false;
+    }
+
+    fn tick(&mut self, world: &mut World<R>) {
+        let Some(started_at) = self.started_at.or(world.started_at) else {
+            return;
+        };
+        self.started_at = Some(started_at);
+
+        if world.solved && !self.counted {
+            self.counted = true;
+            self.boards_solved += 1;
+            info!("Time attack: {} boards solved", self.boards_solved);
+        }
+        if started_at.elapsed() >= self.limit {
+            println!("Time's up! Boards solved: {}", self.boards_solved);
+            world.release_grab();
+            world.locked = true;
+            world.playing = false;
+            return;
+        }
+
+        if world.solved && world.last_move_at.elapsed() >= simple::NEXT_BOARD_DELAY {
+            world.deal_board();
+            self.counted = false;
+        }
+    }
+}
+
+impl<R: Renderer> GameMode<R> for Chaos {
+    fn name(&self) -> &'static str {
+        "chaos"
+    }
+
+    fn tick(&mut self, world: &mut World<R>) {
+        self.update(world);
+    }
+}
+
+pub struct Gravity;
+
+impl<R: Renderer> GameMode<R> for Gravity {
+    fn name(&self) -> &'static str {
+        "gravity"
+    }
+
+    // Let the shuffled board settle before play starts
+    fn start(&mut self, world: &mut World<R>) {
+        world.queue_gravity(None);
+    }
+
+    fn pieces_fall(&self) -> bool {
+        true
+    }
+}
+
+// The board wraps around: a piece on one edge can slide into the gap on the
+// opposite edge of its row or column
+pub struct Toroidal;
+
+impl<R: Renderer> GameMode<R> for Toroidal {
+    fn name(&self) -> &'static str {
+        "toroidal"
+    }
+
+    fn can_slide(&self, grid: &Grid, cell: usize, gap: usize) -> bool {
+        let (width, height) = (grid.width(), grid.height());
+        let (x, y) = (cell % width, cell / width);
+        let (gap_x, gap_y) = (gap % width, gap / width);
+        let across =
+            |a: usize, b: usize, size: usize| a.abs_diff(b) == 1 || a.abs_diff(b) == size - 1;
+
+        (y == gap_y && across(x, gap_x, width)) || (x == gap_x && across(y, gap_y, height))
+    }
+
+    // Wrapping around reaches boards the solver thinks can't be solved
+    fn solver_helps(&self) -> bool {
+        false
+    }
+}
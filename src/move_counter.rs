@@ -0,0 +1,71 @@
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    font, locale,
+    renderer::{Renderer, WindowHandle},
+};
+
+pub const WINDOW_SIZE: u32 = 150;
+const COUNT_SIZE: u32 = 56;
+const CAPTION_SIZE: u32 = 24;
+const SOLVED_COLOR: Color = Color::rgb(0, 200, 0);
+
+// The number of moves so far, under the count
+pub fn caption(moves: u32) -> &'static str {
+    if moves == 1 {
+        "move"
+    } else {
+        "moves"
+    }
+}
+
+// How many moves the solve has taken so far, in a small window beside the
+// board. The count turns green once the board is solved.
+pub struct MoveCounter<W: WindowHandle> {
+    window: W,
+}
+
+impl<W: WindowHandle> MoveCounter<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, position: Vector2<i32>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Moves");
+        window.set_position(position);
+
+        Self { window }
+    }
+
+    pub fn update(&mut self) {
+        // Nothing to interact with, but events still need draining
+        while self.window.poll_event().is_some() {}
+    }
+
+    pub fn render(&mut self, moves: u32, solved: bool) {
+        self.window.clear(Color::BLACK);
+
+        let count = locale::current().format_count(moves as u64);
+        let color = if solved { SOLVED_COLOR } else { Color::WHITE };
+        self.draw_centered(&count, COUNT_SIZE, WINDOW_SIZE as f32 * 0.4, color);
+        self.draw_centered(
+            caption(moves),
+            CAPTION_SIZE,
+            WINDOW_SIZE as f32 * 0.75,
+            Color::WHITE,
+        );
+
+        self.window.display();
+    }
+
+    fn draw_centered(&mut self, string: &str, size: u32, y: f32, color: Color) {
+        let mut text = Text::new(string, font(), size);
+        let bounds = text.local_bounds();
+        text.set_origin(Vector2::new(
+            bounds.left + bounds.width / 2.0,
+            bounds.top + bounds.height / 2.0,
+        ));
+        text.set_position(Vector2::new(WINDOW_SIZE as f32 / 2.0, y));
+        text.set_fill_color(color);
+        self.window.draw(&text);
+    }
+}
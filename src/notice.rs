@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+use log::debug;
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::Event,
+};
+
+use crate::{
+    font,
+    renderer::{Renderer, WindowHandle},
+};
+
+pub const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+// Long enough to read, short enough not to need closing
+const SHOW_TIME: Duration = Duration::from_secs(4);
+
+// A few lines telling the player about something that happened to the board
+// rather than anything they did, which closes itself after a moment
+pub struct Notice<W: WindowHandle> {
+    window: W,
+    lines: Vec<String>,
+    opened_at: Instant,
+}
+
+impl<W: WindowHandle> Notice<W> {
+    pub fn open<R: Renderer<Window = W>>(
+        renderer: &mut R,
+        position: Vector2<i32>,
+        lines: &[&str],
+    ) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Notice");
+        window.set_position(position);
+        debug!("Showing notice {:?}", lines);
+
+        Self {
+            window,
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+            opened_at: Instant::now(),
+        }
+    }
+
+    // False once it's been up long enough or the player closed it
+    pub fn update(&mut self) -> bool {
+        let mut open = self.opened_at.elapsed() < SHOW_TIME;
+        while let Some(event) = self.window.poll_event() {
+            if let Event::Closed | Event::KeyPressed { .. } | Event::MouseButtonPressed { .. } =
+                event
+            {
+                open = false;
+            }
+        }
+        open
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+        for (index, line) in self.lines.iter().enumerate() {
+            let mut text = Text::new(line, font(), 24);
+            text.set_fill_color(Color::WHITE);
+            text.set_position(Vector2::new(5.0, index as f32 * LINE_HEIGHT + 5.0));
+            self.window.draw(&text);
+        }
+        self.window.display();
+    }
+}
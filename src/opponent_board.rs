@@ -0,0 +1,78 @@
+// A miniature of the opponent's board in a network match, redrawn from the
+// progress they send (see race.rs), so it's plain how close they are.
+
+use sfml::{
+    graphics::{Color, RectangleShape, Shape, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    font,
+    puzzle::Grid,
+    renderer::{Renderer, WindowHandle},
+};
+
+const WINDOW_SIZE: u32 = 150;
+const GAP: f32 = 4.0;
+const LABEL_SIZE: u32 = 24;
+const IN_PLACE_COLOR: Color = Color::rgb(0, 160, 0);
+const OUT_OF_PLACE_COLOR: Color = Color::rgb(90, 90, 90);
+
+pub struct OpponentBoard<W: WindowHandle> {
+    window: W,
+}
+
+impl<W: WindowHandle> OpponentBoard<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, position: Vector2<i32>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Opponent");
+        window.set_position(position);
+
+        Self { window }
+    }
+
+    pub fn update(&mut self) {
+        // Only there to look at, but the events still need draining
+        while self.window.poll_event().is_some() {}
+    }
+
+    // Blank until the opponent's first board arrives
+    pub fn render(&mut self, board: Option<&Grid>) {
+        self.window.clear(Color::BLACK);
+
+        let (width, height) = board.map_or((3, 3), |board| (board.width(), board.height()));
+        let solved = Grid::solved(width, height);
+        let across = width.max(height) as f32;
+        let cell_size = (WINDOW_SIZE as f32 - GAP * (across + 1.0)) / across;
+        for (y, row) in board.iter().flat_map(|board| board.iter()).enumerate() {
+            for (x, &piece) in row.iter().enumerate() {
+                if piece == -1 {
+                    continue;
+                }
+                let position = Vector2::new(
+                    GAP + x as f32 * (cell_size + GAP),
+                    GAP + y as f32 * (cell_size + GAP),
+                );
+
+                let mut cell = RectangleShape::with_size(Vector2::new(cell_size, cell_size));
+                cell.set_position(position);
+                cell.set_fill_color(if piece == solved[y][x] {
+                    IN_PLACE_COLOR
+                } else {
+                    OUT_OF_PLACE_COLOR
+                });
+                self.window.draw(&cell);
+
+                let mut label = Text::new(&(piece + 1).to_string(), font(), LABEL_SIZE);
+                label.set_fill_color(Color::WHITE);
+                let bounds = label.local_bounds();
+                label.set_position(Vector2::new(
+                    position.x + (cell_size - bounds.width) / 2.0 - bounds.left,
+                    position.y + (cell_size - bounds.height) / 2.0 - bounds.top,
+                ));
+                self.window.draw(&label);
+            }
+        }
+
+        self.window.display();
+    }
+}
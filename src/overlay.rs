@@ -0,0 +1,48 @@
+use std::{fs, path::PathBuf};
+
+use log::warn;
+
+use crate::io_worker;
+
+// Live stats as one small text file each, for streaming software to show
+// as text sources
+pub struct Overlay {
+    dir: PathBuf,
+    // What each file was last written with, so unchanged files are left alone
+    written: Vec<(&'static str, String)>,
+}
+
+impl Overlay {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            written: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, stats: &[(&'static str, String)]) {
+        for (name, value) in stats {
+            let index = match self.written.iter().position(|(written, _)| written == name) {
+                Some(index) if self.written[index].1 == *value => continue,
+                Some(index) => index,
+                None => {
+                    self.written.push((name, String::new()));
+                    self.written.len() - 1
+                }
+            };
+            self.written[index].1 = value.clone();
+
+            let path = self.dir.join(name);
+            let value = value.clone();
+            io_worker::spawn(move || {
+                // Swap the file in whole so a reader never sees it half written
+                let temp_path = path.with_extension("tmp");
+                if let Err(error) =
+                    fs::write(&temp_path, value).and_then(|()| fs::rename(&temp_path, &path))
+                {
+                    warn!("Couldn't write {}: {}", path.display(), error);
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use directories::ProjectDirs;
+use log::{debug, info, warn};
+use minisign_verify::{PublicKey, Signature};
+
+use crate::{io_worker, puzzle::Grid, scramble};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+// A set of scrambles published together, e.g. one week's worth
+pub struct Pack {
+    pub title: String,
+    pub levels: Vec<(String, Grid)>,
+}
+
+// A `title: ...` line, then one `name | scramble` line per level. Blank
+// lines and lines starting with # are skipped.
+pub fn parse(text: &str) -> Result<Pack, String> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+    let title = lines
+        .next()
+        .and_then(|line| line.strip_prefix("title:"))
+        .map(|title| title.trim().to_string())
+        .ok_or("Pack doesn't start with a title line")?;
+
+    let levels = lines
+        .map(|line| {
+            let (name, notation) = line
+                .split_once('|')
+                .ok_or(format!("Expected \"name | scramble\", got \"{}\"", line))?;
+            let grid = scramble::parse(notation.trim())?;
+            Ok((name.trim().to_string(), grid))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(Pack { title, levels })
+}
+
+// Check `text` was signed with the key, using a minisign signature
+pub fn verify(text: &str, signature: &str, key: &str) -> Result<(), String> {
+    let key = PublicKey::from_base64(key).map_err(|error| format!("Bad pack key: {}", error))?;
+    let signature =
+        Signature::decode(signature).map_err(|error| format!("Bad pack signature: {}", error))?;
+
+    key.verify(text.as_bytes(), &signature, false)
+        .map_err(|error| format!("Pack signature doesn't match: {}", error))
+}
+
+// Download the pack at `url` and its signature at `url`.minisig, and keep a
+// copy for when there's no connection
+pub fn fetch(url: &str, key: &str) -> Result<Pack, String> {
+    let get = |url: &str| -> Result<String, String> {
+        ureq::get(url)
+            .timeout(FETCH_TIMEOUT)
+            .call()
+            .map_err(|error| format!("Couldn't fetch {}: {}", url, error))?
+            .into_string()
+            .map_err(|error| format!("Couldn't read {}: {}", url, error))
+    };
+
+    let text = get(url)?;
+    let signature = get(&format!("{}.minisig", url))?;
+    verify(&text, &signature, key)?;
+    let pack = parse(&text)?;
+    info!(
+        "Fetched pack \"{}\" with {} levels",
+        pack.title,
+        pack.levels.len()
+    );
+
+    if let Some(dir) = cache_dir() {
+        let name: String = pack
+            .title
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        io_worker::spawn(move || {
+            let result = fs::create_dir_all(&dir)
+                .and_then(|()| fs::write(dir.join(format!("{}.txt", name)), text))
+                .and_then(|()| fs::write(dir.join(format!("{}.txt.minisig", name)), signature));
+            if let Err(error) = result {
+                warn!("Couldn't cache pack in {}: {}", dir.display(), error);
+            }
+        });
+    }
+
+    Ok(pack)
+}
+
+// Every pack saved by an earlier fetch, checked again in case the files
+// were tampered with
+pub fn cached(key: &str) -> Vec<Pack> {
+    let Some(dir) = cache_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        debug!("No cached packs in {}", dir.display());
+        return Vec::new();
+    };
+
+    let mut packs: Vec<Pack> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .filter_map(|path| {
+            let load = || -> Result<Pack, String> {
+                let text = fs::read_to_string(&path).map_err(|error| error.to_string())?;
+                let signature = fs::read_to_string(path.with_extension("txt.minisig"))
+                    .map_err(|error| error.to_string())?;
+                verify(&text, &signature, key)?;
+                parse(&text)
+            };
+            load()
+                .map_err(|error| warn!("Skipping cached pack {}: {}", path.display(), error))
+                .ok()
+        })
+        .collect();
+
+    packs.sort_by(|a, b| a.title.cmp(&b.title));
+    packs
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "slide-puzzle").map(|dirs| dirs.cache_dir().join("packs"))
+}
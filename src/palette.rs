@@ -0,0 +1,232 @@
+// The command palette: Ctrl+K (Cmd+K on macOS) opens a small window where
+// typing a few letters of an action finds it, for everything that otherwise
+// needs its key remembered or a restart with different flags.
+
+use log::{debug, info};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font,
+    renderer::{Renderer, WindowHandle},
+    theme::Theme,
+};
+
+pub const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+const MAX_QUERY_LENGTH: usize = 32;
+// Matches shown under the query, best first
+const SHOWN: usize = 8;
+// Board sizes offered, each way
+const BOARD_SIZES: [usize; 4] = [3, 4, 5, 6];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    NewGame,
+    // One of Theme::presets(), by name
+    Theme(&'static str),
+    BoardSize(usize, usize),
+    ShowStats,
+    Hint,
+    Solve,
+    Undo,
+    Redo,
+    Rotate,
+    Mirror,
+    GoalLabels,
+    Screenshot,
+    Bookmark,
+}
+
+impl Command {
+    // Every command, in the order they're listed before anything's typed
+    pub fn all() -> Vec<Command> {
+        let mut commands = vec![Command::NewGame, Command::ShowStats];
+        commands.extend(BOARD_SIZES.map(|size| Command::BoardSize(size, size)));
+        commands.extend(Theme::presets().map(|(name, _)| Command::Theme(name)));
+        commands.extend([
+            Command::Hint,
+            Command::Solve,
+            Command::Undo,
+            Command::Redo,
+            Command::Rotate,
+            Command::Mirror,
+            Command::GoalLabels,
+            Command::Screenshot,
+            Command::Bookmark,
+        ]);
+        commands
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Command::NewGame => "New game".to_string(),
+            Command::Theme(name) => format!("Theme: {}", name),
+            Command::BoardSize(width, height) => format!("Set grid {}x{}", width, height),
+            Command::ShowStats => "Show stats".to_string(),
+            Command::Hint => "Hint".to_string(),
+            Command::Solve => "Solve the board".to_string(),
+            Command::Undo => "Undo".to_string(),
+            Command::Redo => "Redo".to_string(),
+            Command::Rotate => "Rotate the board".to_string(),
+            Command::Mirror => "Mirror the board".to_string(),
+            Command::GoalLabels => "Show or hide goal labels".to_string(),
+            Command::Screenshot => "Take a screenshot".to_string(),
+            Command::Bookmark => "Bookmark this seed".to_string(),
+        }
+    }
+}
+
+// How well `query` matches `name`, higher being better, or None if the
+// query's letters don't all appear in it in order. Letters that follow on
+// from the last match or start a word count for more, so "ng" picks "New
+// game" over "Show or hide goal labels".
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut last = None;
+
+    for wanted in query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+    {
+        let found = from + name[from..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if last.is_some_and(|last| last + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !name[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Gaps cost a little, so tighter matches win ties
+        score -= (found - from) as i32 / 4;
+        last = Some(found);
+        from = found + 1;
+    }
+
+    Some(score)
+}
+
+// `commands` that match `query`, best first, ties keeping their order
+pub fn search(query: &str, commands: &[Command]) -> Vec<Command> {
+    let mut scored: Vec<(i32, Command)> = commands
+        .iter()
+        .filter_map(|&command| Some((fuzzy_score(query, &command.name())?, command)))
+        .collect();
+    scored.sort_by_key(|&(score, _)| -score);
+    scored.into_iter().map(|(_, command)| command).collect()
+}
+
+pub struct CommandPalette<W: WindowHandle> {
+    window: W,
+    commands: Vec<Command>,
+    query: String,
+    matches: Vec<Command>,
+    // Which of the matches Enter runs
+    selected: usize,
+    pub cancelled: bool,
+}
+
+impl<W: WindowHandle> CommandPalette<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, position: Vector2<i32>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Commands");
+        window.set_position(position);
+        window.raise();
+        window.request_focus();
+        debug!("Opened the command palette");
+
+        let commands = Command::all();
+        Self {
+            window,
+            matches: commands.clone(),
+            commands,
+            query: String::new(),
+            selected: 0,
+            cancelled: false,
+        }
+    }
+
+    // The command picked once Enter is pressed
+    pub fn update(&mut self) -> Option<Command> {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::KeyPressed {
+                    code: Key::Enter, ..
+                } => {
+                    if let Some(&command) = self.matches.get(self.selected) {
+                        info!("Picked \"{}\" from the command palette", command.name());
+                        return Some(command);
+                    }
+                }
+                Event::KeyPressed {
+                    code: Key::Escape, ..
+                }
+                | Event::Closed => self.cancelled = true,
+                Event::KeyPressed { code: Key::Up, .. } => {
+                    self.selected = self.selected.saturating_sub(1);
+                }
+                Event::KeyPressed {
+                    code: Key::Down, ..
+                } => {
+                    self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+                }
+                Event::KeyPressed {
+                    code: Key::Backspace,
+                    ..
+                } => {
+                    self.query.pop();
+                    self.refresh();
+                }
+                Event::TextEntered { unicode }
+                    if !unicode.is_control() && self.query.chars().count() < MAX_QUERY_LENGTH =>
+                {
+                    self.query.push(unicode);
+                    self.refresh();
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn refresh(&mut self) {
+        self.matches = search(&self.query, &self.commands);
+        self.selected = 0;
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        let query = format!("> {}_", self.query);
+        self.draw_text(&query, 0, Color::WHITE);
+        if self.matches.is_empty() {
+            self.draw_text("No matches", 1, Color::rgb(128, 128, 128));
+        }
+        // Scrolled so the selected match stays in view
+        let first = self.selected.saturating_sub(SHOWN - 1);
+        for (line, index) in (first..self.matches.len()).take(SHOWN).enumerate() {
+            let color = if index == self.selected {
+                Color::rgb(0, 200, 0)
+            } else {
+                Color::rgb(180, 180, 180)
+            };
+            let name = self.matches[index].name();
+            self.draw_text(&name, line as u32 + 1, color);
+        }
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 20);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
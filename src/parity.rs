@@ -0,0 +1,217 @@
+use log::{debug, info};
+use rand::{seq::SliceRandom, Rng};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font,
+    layout::Side,
+    puzzle::{shuffle_grid, Grid},
+    renderer::{Renderer, WindowHandle},
+    World,
+};
+
+const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+
+// Pairs of pieces that are the wrong way round, reading the board row by
+// row and skipping the gap
+pub fn inversions(grid: &Grid) -> usize {
+    let pieces: Vec<i8> = grid.cells().filter(|&piece| piece != -1).collect();
+
+    pieces
+        .iter()
+        .enumerate()
+        .map(|(i, a)| pieces[i + 1..].iter().filter(|&b| b < a).count())
+        .sum()
+}
+
+// Sliding sideways doesn't change the reading order, and sliding up or down
+// jumps a piece over the two between it and the gap, which changes the count
+// by 0 or 2. So the count stays even or odd forever, and only even boards
+// can reach the solved board's zero. That's the 3x3 board the trainer deals.
+pub fn explain(grid: &Grid) -> String {
+    let count = inversions(grid);
+    let (parity, verdict) = if count.is_multiple_of(2) {
+        ("even", "it can be solved")
+    } else {
+        ("odd", "it can never be solved")
+    };
+
+    format!(
+        "Reading row by row and skipping the gap, {} pairs of pieces are the wrong way round. \
+         Every slide changes that by 0 or 2, so an {} count stays {}, and the solved board has 0: {}.",
+        count, parity, parity, verdict
+    )
+}
+
+// A scrambled board, and whether it was left solvable. Swapping any two
+// pieces flips the parity, which is how the unsolvable ones are made.
+pub fn deal(rng: &mut impl Rng) -> (Grid, bool) {
+    let mut grid = Grid::solved(3, 3);
+    shuffle_grid(&mut grid, 40, rng);
+
+    let solvable = rng.gen_bool(0.5);
+    if !solvable {
+        let mut cells: Vec<usize> = (0..9).filter(|&cell| grid.cell(cell) != -1).collect();
+        cells.shuffle(rng);
+        let (a, b) = (cells[0], cells[1]);
+
+        let piece = grid.cell(a);
+        grid.set_cell(a, grid.cell(b));
+        grid.set_cell(b, piece);
+    }
+
+    (grid, solvable)
+}
+
+// Deals boards, some impossible, and asks whether each can be solved before
+// it can be played
+pub struct Quiz<W: WindowHandle> {
+    pub solvable: bool,
+    pub answer: Option<bool>,
+    pub asked: u32,
+    pub correct: u32,
+    inversions: usize,
+    window: W,
+}
+
+impl<W: WindowHandle> Quiz<W> {
+    // Opens the question left of the board and deals the first board
+    pub fn start<R: Renderer<Window = W>>(world: &mut World<R>) -> Self {
+        let mut window = world.renderer.create_window(WINDOW_SIZE, "Parity trainer");
+        window.set_position(world.side_window_position(Side::Left, WINDOW_SIZE));
+
+        let mut quiz = Self {
+            solvable: true,
+            answer: None,
+            asked: 0,
+            correct: 0,
+            inversions: 0,
+            window,
+        };
+        quiz.next_board(world);
+
+        quiz
+    }
+
+    fn next_board<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        let (grid, solvable) = deal(&mut world.rng);
+        self.solvable = solvable;
+        self.answer = None;
+        self.inversions = inversions(&grid);
+
+        debug!("Parity question {}: {:?}", self.asked + 1, grid);
+        world.animate_to_grid(grid);
+        // No peeking by trying a few moves first
+        world.locked = true;
+    }
+
+    fn answer<R: Renderer<Window = W>>(&mut self, world: &mut World<R>, solvable: bool) {
+        self.answer = Some(solvable);
+        self.asked += 1;
+        let right = solvable == self.solvable;
+        if right {
+            self.correct += 1;
+        }
+        info!(
+            "Parity question {} answered {}",
+            self.asked,
+            if right { "right" } else { "wrong" }
+        );
+
+        println!(
+            "{} {}",
+            if right { "Right!" } else { "Wrong." },
+            explain(&world.canonical_grid())
+        );
+        if self.solvable {
+            println!("Solve it, or press Space to skip to the next board");
+            world.locked = false;
+        } else {
+            println!("Press Space for the next board");
+        }
+    }
+
+    pub fn update<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::KeyPressed { code: Key::Y, .. } if self.answer.is_none() => {
+                    self.answer(world, true)
+                }
+                Event::KeyPressed { code: Key::N, .. } if self.answer.is_none() => {
+                    self.answer(world, false)
+                }
+                Event::KeyPressed {
+                    code: Key::Space | Key::Enter,
+                    ..
+                } if self.answer.is_some() => self.next_board(world),
+                Event::KeyPressed {
+                    code: Key::Escape, ..
+                }
+                | Event::Closed => world.playing = false,
+                _ => {}
+            }
+        }
+
+        if self.answer.is_some() && world.solved && world.moves > 0 {
+            println!("Solved in {} moves", world.moves);
+            self.next_board(world);
+        }
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        self.draw_text("Parity trainer", 0, Color::WHITE);
+        match self.answer {
+            None => {
+                self.draw_text("Can it be solved?", 1, Color::WHITE);
+                self.draw_text("Y: yes  N: no", 2, Color::WHITE);
+            }
+            Some(answer) => {
+                let (verdict, color) = if answer == self.solvable {
+                    ("Right!", Color::rgb(0, 200, 0))
+                } else {
+                    ("Wrong", Color::rgb(200, 0, 0))
+                };
+                self.draw_text(verdict, 1, color);
+
+                let parity = if self.inversions.is_multiple_of(2) {
+                    "even"
+                } else {
+                    "odd"
+                };
+                self.draw_text(
+                    &format!("{} inversions: {}", self.inversions, parity),
+                    2,
+                    Color::WHITE,
+                );
+                let next = if self.solvable {
+                    "So solve it!"
+                } else {
+                    "Can't be solved"
+                };
+                self.draw_text(next, 3, Color::WHITE);
+                self.draw_text("Space: next board", 4, Color::WHITE);
+            }
+        }
+        self.draw_text(
+            &format!("Score: {}/{}", self.correct, self.asked),
+            6,
+            Color::WHITE,
+        );
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
@@ -0,0 +1,340 @@
+// Pattern databases, which let IDA* (see solver.rs) solve hard 4x4 boards
+// in reasonable time. The pieces are split into groups of 6, 6 and 3, and
+// for every way a group's pieces could be placed the database holds how many
+// times those pieces have to slide to get home, ignoring every other piece.
+// No two groups share a piece, so adding the three up still never guesses
+// more moves than a board needs, and it's far closer than Manhattan distance.
+//
+// Working the tables out takes a while, so it's done once, with one thread
+// per group, and kept in the cache directory. A file is the magic bytes
+// "SPDB" and a format version byte, then a zstd frame holding each group's
+// table in turn.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use directories::ProjectDirs;
+use log::{debug, info, warn};
+use once_cell::sync::OnceCell;
+
+use crate::layout::neighbor_table;
+
+// Numbered from 0, so the first group is the left column and the two cells
+// beside its top
+const GROUPS: [&[i8]; 3] = [&[0, 4, 5, 8, 9, 12], &[6, 7, 10, 11, 13, 14], &[1, 2, 3]];
+const SIDE: usize = 4;
+const CELLS: usize = SIDE * SIDE;
+
+const FILE_NAME: &str = "patterns-4x4.spdb";
+const MAGIC: &[u8; 4] = b"SPDB";
+const VERSION: u8 = 1;
+const COMPRESSION_LEVEL: i32 = 3;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+static DATABASE: OnceCell<PatternDatabase> = OnceCell::new();
+
+pub struct PatternDatabase {
+    groups: Vec<Group>,
+    // Which group each piece is in, and where in it
+    group_of: [Option<(usize, usize)>; CELLS],
+}
+
+struct Group {
+    pieces: Vec<i8>,
+    // Moves to bring the pieces home, by where they are, see index()
+    table: Vec<u8>,
+}
+
+// Where a group's pieces are, as one number: each piece's cell is a digit in
+// base 16, the group's first piece the most significant
+fn index(cells: impl Iterator<Item = usize>) -> usize {
+    cells.fold(0, |index, cell| index * CELLS + cell)
+}
+
+impl PatternDatabase {
+    // Work out the 6-6-3 tables, one thread per group. `report` hears how
+    // far along the whole thing is, from 0 to 1, every so often.
+    pub fn build(report: impl FnMut(f32)) -> Self {
+        Self::build_groups(&GROUPS, report)
+    }
+
+    pub(crate) fn build_groups(groups: &[&[i8]], mut report: impl FnMut(f32)) -> Self {
+        let started = Instant::now();
+        let done: Vec<AtomicUsize> = groups.iter().map(|_| AtomicUsize::new(0)).collect();
+        let total: usize = groups.iter().map(|pieces| states(pieces.len())).sum();
+
+        let tables = thread::scope(|scope| {
+            let builders: Vec<_> = groups
+                .iter()
+                .zip(&done)
+                .map(|(pieces, done)| scope.spawn(move || build_table(pieces, done)))
+                .collect();
+
+            while !builders.iter().all(|builder| builder.is_finished()) {
+                let done: usize = done.iter().map(|done| done.load(Ordering::Relaxed)).sum();
+                report(done as f32 / total as f32);
+                thread::sleep(PROGRESS_INTERVAL);
+            }
+            builders
+                .into_iter()
+                .map(|builder| builder.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        report(1.0);
+        info!("Built the pattern database in {:?}", started.elapsed());
+
+        Self::from_tables(groups, tables)
+    }
+
+    fn from_tables(groups: &[&[i8]], tables: Vec<Vec<u8>>) -> Self {
+        let mut group_of = [None; CELLS];
+        for (group, pieces) in groups.iter().enumerate() {
+            for (slot, &piece) in pieces.iter().enumerate() {
+                group_of[piece as usize] = Some((group, slot));
+            }
+        }
+
+        Self {
+            groups: groups
+                .iter()
+                .zip(tables)
+                .map(|(pieces, table)| Group {
+                    pieces: pieces.to_vec(),
+                    table,
+                })
+                .collect(),
+            group_of,
+        }
+    }
+
+    // The fewest moves the 4x4 board laid out in `cells`, rows top to
+    // bottom, could be solved in as far as the database can tell
+    pub fn estimate(&self, cells: &[i8]) -> u32 {
+        (0..self.groups.len())
+            .map(|group| self.group_estimate(group, cells))
+            .sum()
+    }
+
+    pub(crate) fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    // Which group `piece` is in, if any
+    pub(crate) fn group_of(&self, piece: i8) -> Option<usize> {
+        self.group_of
+            .get(piece as usize)
+            .copied()
+            .flatten()
+            .map(|(group, _)| group)
+    }
+
+    pub(crate) fn group_estimate(&self, group: usize, cells: &[i8]) -> u32 {
+        let mut positions = [0; CELLS];
+        for (cell, &piece) in cells.iter().enumerate() {
+            if let Some((in_group, slot)) = self.group_of.get(piece as usize).copied().flatten() {
+                if in_group == group {
+                    positions[slot] = cell;
+                }
+            }
+        }
+
+        let group = &self.groups[group];
+        let index = index(positions[..group.pieces.len()].iter().copied());
+        group.table[index] as u32
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let payload: Vec<u8> = self
+            .groups
+            .iter()
+            .flat_map(|group| group.table.iter().copied())
+            .collect();
+        let compressed = zstd::bulk::compress(&payload, COMPRESSION_LEVEL)
+            .map_err(|error| format!("Couldn't compress the pattern database: {}", error))?;
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend(compressed);
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        Self::decode_groups(&GROUPS, bytes)
+    }
+
+    pub(crate) fn decode_groups(groups: &[&[i8]], bytes: &[u8]) -> Result<Self, String> {
+        let payload = bytes.strip_prefix(MAGIC).ok_or("Not a pattern database")?;
+        let (&version, compressed) = payload
+            .split_first()
+            .ok_or("Pattern database is cut short")?;
+        if version != VERSION {
+            return Err(format!(
+                "Pattern database format {} isn't supported, this version reads format {}",
+                version, VERSION
+            ));
+        }
+
+        let sizes: Vec<usize> = groups
+            .iter()
+            .map(|pieces| CELLS.pow(pieces.len() as u32))
+            .collect();
+        let total: usize = sizes.iter().sum();
+        let payload = zstd::bulk::decompress(compressed, total)
+            .map_err(|error| format!("Pattern database is damaged: {}", error))?;
+        if payload.len() != total {
+            return Err("Pattern database is cut short".to_string());
+        }
+
+        let mut rest = payload.as_slice();
+        let tables = sizes
+            .iter()
+            .map(|&size| {
+                let (table, after) = rest.split_at(size);
+                rest = after;
+                table.to_vec()
+            })
+            .collect();
+        Ok(Self::from_tables(groups, tables))
+    }
+}
+
+// How many ways `pieces` pieces and the gap can be placed on the board.
+// The other pieces can't be told apart, so every one of them can be reached.
+fn states(pieces: usize) -> usize {
+    (CELLS - pieces..=CELLS).product()
+}
+
+// The moves each placement of `pieces` needs, found by searching outwards
+// from home. Only slides of the group's own pieces count, so the gap
+// wandering among the others is free. `done` counts placements looked at.
+fn build_table(pieces: &[i8], done: &AtomicUsize) -> Vec<u8> {
+    let neighbors = neighbor_table(SIDE, SIDE);
+    let count = pieces.len();
+    let mut table = vec![u8::MAX; CELLS.pow(count as u32)];
+    // Whether each placement, gap included, has been looked at
+    let mut seen = vec![0u64; CELLS.pow(count as u32 + 1).div_ceil(64)];
+
+    // A placement is the index of the pieces' cells with the gap's cell as
+    // the last digit
+    let home = index(
+        pieces
+            .iter()
+            .map(|&piece| piece as usize)
+            .chain([CELLS - 1]),
+    );
+    let mut level = vec![home];
+    let mut moves = 0;
+
+    while !level.is_empty() {
+        let mut next = Vec::new();
+        let mut i = 0;
+        while i < level.len() {
+            let state = level[i];
+            i += 1;
+            if seen[state / 64] & 1 << (state % 64) != 0 {
+                continue;
+            }
+            seen[state / 64] |= 1 << (state % 64);
+            done.fetch_add(1, Ordering::Relaxed);
+
+            let gap = state % CELLS;
+            let placement = state / CELLS;
+            table[placement] = table[placement].min(moves);
+
+            // Which of the group's pieces is in each cell
+            let mut occupant = [None; CELLS];
+            let mut rest = placement;
+            for slot in (0..count).rev() {
+                occupant[rest % CELLS] = Some(slot);
+                rest /= CELLS;
+            }
+
+            for &cell in neighbors.neighbors(gap) {
+                match occupant[cell] {
+                    // One of the group slides into the gap, which costs a move
+                    Some(slot) => {
+                        let shift = CELLS.pow((count - 1 - slot) as u32);
+                        let placement = placement - cell * shift + gap * shift;
+                        next.push(placement * CELLS + cell);
+                    }
+                    // Some other piece does, which is free
+                    None => level.push(placement * CELLS + cell),
+                }
+            }
+        }
+
+        debug!(
+            "Pattern {:?}: {} placements are {} moves away",
+            pieces,
+            level.len(),
+            moves
+        );
+        level = next;
+        moves += 1;
+    }
+
+    table
+}
+
+fn cache_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "slide-puzzle").map(|dirs| dirs.cache_dir().join(FILE_NAME))
+}
+
+// The database, once prepare() has loaded or built it
+pub fn get() -> Option<&'static PatternDatabase> {
+    DATABASE.get()
+}
+
+// Load the database from the cache on a background thread, or build it
+// there and save it for next time, printing how it's going
+pub fn prepare() -> JoinHandle<()> {
+    thread::spawn(|| {
+        if DATABASE.get().is_some() {
+            return;
+        }
+        let path = cache_path();
+
+        if let Some(path) = &path {
+            match fs::read(path).map_err(|error| error.to_string()) {
+                Ok(bytes) => match PatternDatabase::decode(&bytes) {
+                    Ok(database) => {
+                        debug!("Loaded the pattern database from {}", path.display());
+                        let _ = DATABASE.set(database);
+                        return;
+                    }
+                    Err(error) => warn!("Rebuilding {}: {}", path.display(), error),
+                },
+                Err(error) => debug!("No pattern database at {}: {}", path.display(), error),
+            }
+        }
+
+        println!("Building the 4x4 solver's tables, which only happens once");
+        let mut shown = 0;
+        let database = PatternDatabase::build(|progress| {
+            let percent = (progress * 100.0) as u32 / 10 * 10;
+            if percent > shown {
+                println!("Solver tables {}% built", percent);
+                shown = percent;
+            }
+        });
+
+        if let Some(path) = &path {
+            let saved = database.encode().and_then(|bytes| {
+                path.parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|()| fs::write(path, bytes))
+                    .map_err(|error| error.to_string())
+            });
+            match saved {
+                Ok(()) => info!("Saved the pattern database to {}", path.display()),
+                Err(error) => warn!("Couldn't save {}: {}", path.display(), error),
+            }
+        }
+        let _ = DATABASE.set(database);
+    })
+}
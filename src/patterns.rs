@@ -0,0 +1,133 @@
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use sfml::system::Vector2;
+
+use crate::{
+    puzzle::{find_in_grid, Grid},
+    resources,
+};
+
+static PATTERNS: Lazy<Vec<Pattern>> = Lazy::new(|| {
+    let installed = String::from_utf8_lossy(resources::load(resources::PATTERNS));
+    let patterns = parse_library(&installed).unwrap_or_else(|error| {
+        warn!(
+            "Installed pattern library is invalid, using the built-in one: {}",
+            error
+        );
+        let built_in = String::from_utf8_lossy(resources::embedded(resources::PATTERNS));
+        parse_library(&built_in).expect("Built-in pattern library is invalid")
+    });
+    debug!("Loaded {} hint patterns", patterns.len());
+    patterns
+});
+
+// A recognisable position on a 3x3 board and the named technique that
+// solves it
+pub struct Pattern {
+    pub name: String,
+    // Row by row, None where any piece will do
+    pub cells: [Option<i8>; 9],
+    pub moves: String,
+}
+
+impl Pattern {
+    pub fn matches(&self, grid: &Grid) -> bool {
+        (grid.width(), grid.height()) == (3, 3)
+            && self
+                .cells
+                .iter()
+                .zip(grid.cells())
+                .all(|(cell, piece)| cell.is_none_or(|cell| cell == piece))
+    }
+}
+
+pub fn library() -> &'static [Pattern] {
+    &PATTERNS
+}
+
+// The first pattern in the library that fits the board
+pub fn find(grid: &Grid) -> Option<&'static Pattern> {
+    library().iter().find(|pattern| pattern.matches(grid))
+}
+
+pub fn parse_library(text: &str) -> Result<Vec<Pattern>, String> {
+    let mut patterns = Vec::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        let [name, pattern, moves] = fields.as_slice() else {
+            return Err(format!(
+                "Line {}: expected \"<name> | <pattern> | <moves>\"",
+                line_number + 1
+            ));
+        };
+
+        let cells = parse_cells(pattern)
+            .ok_or_else(|| format!("Line {}: invalid pattern \"{}\"", line_number + 1, pattern))?;
+        if !moves.chars().all(|c| "LRUD".contains(c)) {
+            return Err(format!(
+                "Line {}: moves may only use L, R, U and D",
+                line_number + 1
+            ));
+        }
+
+        patterns.push(Pattern {
+            name: name.to_string(),
+            cells,
+            moves: moves.to_string(),
+        });
+    }
+
+    Ok(patterns)
+}
+
+fn parse_cells(pattern: &str) -> Option<[Option<i8>; 9]> {
+    let chars: Vec<char> = pattern.chars().filter(|&c| c != '/').collect();
+    if chars.len() != 9 || pattern.split('/').any(|row| row.chars().count() != 3) {
+        return None;
+    }
+
+    let mut cells = [None; 9];
+    for (cell, c) in cells.iter_mut().zip(chars) {
+        *cell = match c {
+            '?' => None,
+            '_' => Some(-1),
+            '1'..='8' => Some(c as i8 - b'1' as i8),
+            _ => return None,
+        };
+    }
+
+    Some(cells)
+}
+
+// Play a move sequence, returning the final board and the pieces slid, or
+// None if a move runs off the board
+pub fn apply_moves(grid: &Grid, moves: &str) -> Option<(Grid, Vec<i8>)> {
+    let mut grid = grid.clone();
+    let mut pieces = Vec::new();
+
+    for direction in moves.chars() {
+        let blank = find_in_grid(&grid, -1);
+        // The piece that slides comes from the opposite side of the gap
+        let (dx, dy) = match direction {
+            'L' => (1, 0),
+            'R' => (-1, 0),
+            'U' => (0, 1),
+            'D' => (0, -1),
+            _ => return None,
+        };
+        let cell = grid.cell_at(Vector2::new(blank.x + dx, blank.y + dy))?;
+
+        let piece = grid.cell(cell);
+        grid[blank.y as usize][blank.x as usize] = piece;
+        grid.set_cell(cell, -1);
+        pieces.push(piece);
+    }
+
+    Some((grid, pieces))
+}
@@ -0,0 +1,40 @@
+// Fixes for quirks of each OS's window system, picked at compile time. Every
+// platform provides the same functions.
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+pub use windows::*;
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+pub use macos::*;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod other {
+    use sfml::graphics::{IntRect, RenderWindow};
+
+    // Called once, before any window is created
+    pub fn init() {}
+
+    pub fn setup_window(_window: &RenderWindow) {}
+
+    pub fn raise(window: &RenderWindow) {
+        // SFML has no stacking API, but window managers raise the window they focus
+        window.request_focus();
+    }
+
+    // How many desktop pixels make up one unit of window position
+    pub fn display_scale() -> f32 {
+        1.0
+    }
+
+    // X11 and Wayland have no common way to ask, so the desktop is taken
+    // as one screen and only a change in its size is noticed
+    pub fn monitors() -> Option<Vec<IntRect>> {
+        None
+    }
+}
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub use other::*;
@@ -0,0 +1,138 @@
+use std::ffi::{c_char, c_void, CStr};
+
+use log::debug;
+use sfml::graphics::{IntRect, RenderWindow};
+
+type Id = *mut c_void;
+type Sel = *mut c_void;
+
+const NS_APPLICATION_ACTIVATION_POLICY_REGULAR: isize = 0;
+const NS_WINDOW_COLLECTION_BEHAVIOR_CAN_JOIN_ALL_SPACES: usize = 1 << 0;
+
+#[link(name = "objc")]
+extern "C" {
+    fn objc_getClass(name: *const c_char) -> Id;
+    fn sel_registerName(name: *const c_char) -> Sel;
+    fn objc_msgSend();
+}
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {}
+
+#[repr(C)]
+struct CgRect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGMainDisplayID() -> u32;
+    fn CGGetActiveDisplayList(max: u32, displays: *mut u32, count: *mut u32) -> i32;
+    fn CGDisplayBounds(display: u32) -> CgRect;
+}
+
+// More screens than anyone plugs in at once
+const MAX_DISPLAYS: usize = 16;
+
+// objc_msgSend has to be called through a pointer of the right type for
+// each method signature
+unsafe fn send<Ret>(receiver: Id, selector: &CStr) -> Ret {
+    let send: unsafe extern "C" fn(Id, Sel) -> Ret =
+        std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    send(receiver, sel_registerName(selector.as_ptr()))
+}
+
+unsafe fn send_with<Arg, Ret>(receiver: Id, selector: &CStr, arg: Arg) -> Ret {
+    let send: unsafe extern "C" fn(Id, Sel, Arg) -> Ret =
+        std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    send(receiver, sel_registerName(selector.as_ptr()), arg)
+}
+
+unsafe fn shared_application() -> Id {
+    send(
+        objc_getClass(c"NSApplication".as_ptr()),
+        c"sharedApplication",
+    )
+}
+
+// A plain binary isn't treated as a proper app, so its windows don't get
+// clicks until the app has been activated some other way
+pub fn init() {
+    unsafe {
+        let app = shared_application();
+        let _: bool = send_with(
+            app,
+            c"setActivationPolicy:",
+            NS_APPLICATION_ACTIVATION_POLICY_REGULAR,
+        );
+        let _: () = send_with(app, c"activateIgnoringOtherApps:", true);
+    }
+    debug!("Activated as a regular app");
+}
+
+// Show every piece on every Space, otherwise new windows open on whichever
+// Space happens to be active and the board gets split up
+pub fn setup_window(window: &RenderWindow) {
+    let ns_window = window.system_handle() as Id;
+    unsafe {
+        let _: () = send_with(
+            ns_window,
+            c"setCollectionBehavior:",
+            NS_WINDOW_COLLECTION_BEHAVIOR_CAN_JOIN_ALL_SPACES,
+        );
+    }
+}
+
+pub fn raise(window: &RenderWindow) {
+    let ns_window = window.system_handle() as Id;
+    unsafe {
+        let _: () = send(ns_window, c"orderFrontRegardless");
+    }
+    window.request_focus();
+}
+
+// The desktop mode is in pixels but windows and the mouse are placed in
+// points, which are twice as big on Retina screens
+pub fn display_scale() -> f32 {
+    unsafe {
+        let screen: Id = send(objc_getClass(c"NSScreen".as_ptr()), c"mainScreen");
+        if screen.is_null() {
+            return 1.0;
+        }
+        let scale: f64 = send(screen, c"backingScaleFactor");
+        scale as f32
+    }
+}
+
+// Every screen in points, measured from the top left of the main one as
+// windows are, with the main one first
+pub fn monitors() -> Option<Vec<IntRect>> {
+    let mut displays = [0u32; MAX_DISPLAYS];
+    let mut count = 0;
+    let error =
+        unsafe { CGGetActiveDisplayList(MAX_DISPLAYS as u32, displays.as_mut_ptr(), &mut count) };
+    if error != 0 || count == 0 {
+        return None;
+    }
+
+    let main = unsafe { CGMainDisplayID() };
+    let mut displays = displays[..count as usize].to_vec();
+    displays.sort_by_key(|&display| display != main);
+    Some(
+        displays
+            .into_iter()
+            .map(|display| {
+                let bounds = unsafe { CGDisplayBounds(display) };
+                IntRect::new(
+                    bounds.x as i32,
+                    bounds.y as i32,
+                    bounds.width as i32,
+                    bounds.height as i32,
+                )
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,150 @@
+use std::{ffi::c_void, mem, ptr};
+
+use log::{debug, warn};
+use sfml::graphics::{IntRect, RenderWindow};
+
+type Hwnd = *mut c_void;
+type Hmonitor = *mut c_void;
+
+const DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2: isize = -4;
+const GWL_EXSTYLE: i32 = -20;
+const WS_EX_TOOLWINDOW: i32 = 0x80;
+const SWP_NOSIZE: u32 = 0x1;
+const SWP_NOMOVE: u32 = 0x2;
+const SWP_NOACTIVATE: u32 = 0x10;
+const MONITORINFOF_PRIMARY: u32 = 0x1;
+
+#[repr(C)]
+#[derive(Default)]
+struct Rect {
+    left: i32,
+    top: i32,
+    right: i32,
+    bottom: i32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct MonitorInfo {
+    size: u32,
+    monitor: Rect,
+    work: Rect,
+    flags: u32,
+}
+
+type MonitorEnumProc = unsafe extern "system" fn(Hmonitor, *mut c_void, *mut Rect, isize) -> i32;
+
+#[link(name = "user32")]
+extern "system" {
+    fn SetProcessDpiAwarenessContext(value: isize) -> i32;
+    fn GetWindowLongW(hwnd: Hwnd, index: i32) -> i32;
+    fn SetWindowLongW(hwnd: Hwnd, index: i32, value: i32) -> i32;
+    fn SetWindowPos(
+        hwnd: Hwnd,
+        insert_after: Hwnd,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        flags: u32,
+    ) -> i32;
+    fn EnumDisplayMonitors(
+        hdc: *mut c_void,
+        clip: *const Rect,
+        callback: MonitorEnumProc,
+        data: isize,
+    ) -> i32;
+    fn GetMonitorInfoW(monitor: Hmonitor, info: *mut MonitorInfo) -> i32;
+}
+
+// Without DPI awareness, Windows scales the windows of a game it thinks
+// predates high DPI screens, and the mouse position no longer lines up
+// with window positions on scaled monitors. Per-monitor awareness keeps
+// everything in physical pixels.
+pub fn init() {
+    let aware =
+        unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+    if aware == 0 {
+        warn!("Couldn't make the game DPI aware, dragging may drift on scaled screens");
+    } else {
+        debug!("Using per-monitor DPI awareness");
+    }
+}
+
+// Borderless windows still get a taskbar button each, so mark the pieces as
+// tool windows to keep them out of the taskbar and Alt+Tab
+pub fn setup_window(window: &RenderWindow) {
+    let hwnd = window.system_handle() as Hwnd;
+    unsafe {
+        let style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+        SetWindowLongW(hwnd, GWL_EXSTYLE, style | WS_EX_TOOLWINDOW);
+    }
+}
+
+// Windows only lets the foreground app take focus, and moving focus alone
+// doesn't always change the stacking order, so raise the window directly
+pub fn raise(window: &RenderWindow) {
+    let hwnd = window.system_handle() as Hwnd;
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            ptr::null_mut(),
+            0,
+            0,
+            0,
+            0,
+            SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+        );
+    }
+    window.request_focus();
+}
+
+// DPI awareness puts windows, the mouse and the desktop all in pixels
+pub fn display_scale() -> f32 {
+    1.0
+}
+
+unsafe extern "system" fn add_monitor(
+    monitor: Hmonitor,
+    _hdc: *mut c_void,
+    _clip: *mut Rect,
+    data: isize,
+) -> i32 {
+    let monitors = &mut *(data as *mut Vec<(bool, IntRect)>);
+    let mut info = MonitorInfo {
+        size: mem::size_of::<MonitorInfo>() as u32,
+        ..Default::default()
+    };
+    if GetMonitorInfoW(monitor, &mut info) != 0 {
+        let Rect {
+            left,
+            top,
+            right,
+            bottom,
+        } = info.monitor;
+        monitors.push((
+            info.flags & MONITORINFOF_PRIMARY != 0,
+            IntRect::new(left, top, right - left, bottom - top),
+        ));
+    }
+    1
+}
+
+// Every screen, in the desktop's pixels, with the primary one first
+pub fn monitors() -> Option<Vec<IntRect>> {
+    let mut monitors: Vec<(bool, IntRect)> = Vec::new();
+    let listed = unsafe {
+        EnumDisplayMonitors(
+            ptr::null_mut(),
+            ptr::null(),
+            add_monitor,
+            &mut monitors as *mut _ as isize,
+        )
+    };
+    if listed == 0 || monitors.is_empty() {
+        return None;
+    }
+
+    monitors.sort_by_key(|&(primary, _)| !primary);
+    Some(monitors.into_iter().map(|(_, bounds)| bounds).collect())
+}
@@ -0,0 +1,187 @@
+use std::{fs, path::PathBuf};
+
+use directories::ProjectDirs;
+use log::debug;
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font,
+    renderer::{Renderer, WindowHandle},
+};
+
+const PROFILES_DIR: &str = "profiles";
+const MAX_NAME_LENGTH: usize = 32;
+const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+// Profiles are picked with the number keys
+const NUMBER_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+// Whose settings and bests to use, so several players can share a machine.
+// Without a name everything lives straight in the config and data
+// directories, where it was before there were profiles.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub name: Option<String>,
+}
+
+impl Profile {
+    pub fn named(name: &str) -> Result<Self, String> {
+        if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+            return Err(format!(
+                "Profile names are 1 to {} characters long",
+                MAX_NAME_LENGTH
+            ));
+        }
+        // The name becomes a directory, so nothing that could climb out of it
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "\"{}\" can only use letters, digits, - and _",
+                name
+            ));
+        }
+
+        Ok(Self {
+            name: Some(name.to_string()),
+        })
+    }
+
+    // Settings like the gamepad mapping and theme
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        ProjectDirs::from("", "", "slide-puzzle").map(|dirs| self.within(dirs.config_dir().into()))
+    }
+
+    // Records like personal best splits
+    pub fn data_dir(&self) -> Option<PathBuf> {
+        ProjectDirs::from("", "", "slide-puzzle").map(|dirs| self.within(dirs.data_dir().into()))
+    }
+
+    fn within(&self, dir: PathBuf) -> PathBuf {
+        match &self.name {
+            Some(name) => dir.join(PROFILES_DIR).join(name),
+            None => dir,
+        }
+    }
+
+    // Relative paths to records are kept in a named profile's data directory,
+    // so each player gets their own bests from the same command line
+    pub fn data_path(&self, path: PathBuf) -> PathBuf {
+        match (&self.name, self.data_dir()) {
+            (Some(_), Some(dir)) if path.is_relative() => dir.join(path),
+            _ => path,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        self.name.as_deref().unwrap_or("Default")
+    }
+}
+
+// Every named profile that has been played, in alphabetical order
+pub fn list() -> Vec<Profile> {
+    let Some(dir) = Profile::default()
+        .config_dir()
+        .map(|dir| dir.join(PROFILES_DIR))
+    else {
+        return Vec::new();
+    };
+
+    let mut profiles: Vec<Profile> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| Profile::named(entry.file_name().to_str()?).ok())
+            .collect(),
+        Err(error) => {
+            debug!("No profiles in {}: {}", dir.display(), error);
+            Vec::new()
+        }
+    };
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    profiles
+}
+
+// Asks who's playing before the game starts
+pub struct ProfilePicker<W: WindowHandle> {
+    window: W,
+    profiles: Vec<Profile>,
+}
+
+impl<W: WindowHandle> ProfilePicker<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, mut profiles: Vec<Profile>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Who's playing?");
+        let desktop = renderer.desktop_size();
+        window.set_position(Vector2::new(
+            (desktop.x / 2 - WINDOW_SIZE / 2) as i32,
+            (desktop.y / 2 - WINDOW_SIZE / 2) as i32,
+        ));
+        profiles.truncate(NUMBER_KEYS.len());
+
+        Self { window, profiles }
+    }
+
+    // The chosen profile once a number is pressed. Escape or closing the
+    // window picks the default one.
+    pub fn update(&mut self) -> Option<Profile> {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::KeyPressed {
+                    code: Key::Escape | Key::Num0 | Key::Enter,
+                    ..
+                }
+                | Event::Closed => return Some(Profile::default()),
+                Event::KeyPressed { code, .. } => {
+                    let index = NUMBER_KEYS.iter().position(|&key| key == code);
+                    if let Some(profile) = index.and_then(|index| self.profiles.get(index)) {
+                        return Some(profile.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        self.draw_text("Who's playing?", 0, Color::WHITE);
+        self.draw_text("0: Default", 1, Color::WHITE);
+        let lines: Vec<String> = self
+            .profiles
+            .iter()
+            .enumerate()
+            .map(|(index, profile)| format!("{}: {}", index + 1, profile.title()))
+            .collect();
+        for (index, line) in lines.iter().enumerate() {
+            self.draw_text(line, index as u32 + 2, Color::WHITE);
+        }
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
@@ -0,0 +1,214 @@
+// The rules of the puzzle on their own: where pieces are, sliding one into
+// the gap and shuffling. Grids are rectangular, rows top to bottom, with
+// pieces numbered from 0 and -1 for the gap.
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use log::trace;
+use rand::Rng;
+use sfml::system::Vector2;
+
+pub use crate::board::Board;
+use crate::layout::neighbor_table;
+
+// How many pieces across or down a board can be. A 2x2 board is barely a
+// puzzle, but narrow boards like 5x2 play well.
+pub const MIN_SIZE: usize = 2;
+pub const MAX_SIZE: usize = 5;
+
+// A board `width` pieces across and `height` down. Indexing gives a row, so
+// `grid[y][x]` is the piece at (x, y), and cells are numbered row by row
+// from 0.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid(Vec<Vec<i8>>);
+
+impl Grid {
+    // Every piece at home, with the gap in the bottom right corner
+    pub fn solved(width: usize, height: usize) -> Self {
+        let mut grid = Self::from_cells(width, height, (0..width * height).map(|cell| cell as i8));
+        grid[height - 1][width - 1] = -1;
+        grid
+    }
+
+    // `cells` row by row, which must fill the board exactly
+    pub fn from_cells(width: usize, height: usize, cells: impl IntoIterator<Item = i8>) -> Self {
+        let cells: Vec<i8> = cells.into_iter().collect();
+        assert_eq!(
+            cells.len(),
+            width * height,
+            "Wrong number of cells for the board"
+        );
+
+        Self(cells.chunks(width).map(<[i8]>::to_vec).collect())
+    }
+
+    pub fn width(&self) -> usize {
+        self.0.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.0.len()
+    }
+
+    // Whether `other` has as many columns and rows as this board
+    pub fn same_shape(&self, other: &Grid) -> bool {
+        self.width() == other.width() && self.height() == other.height()
+    }
+
+    // Not counting the gap
+    pub fn piece_count(&self) -> usize {
+        self.width() * self.height() - 1
+    }
+
+    pub fn cell(&self, cell: usize) -> i8 {
+        self[cell / self.width()][cell % self.width()]
+    }
+
+    pub fn set_cell(&mut self, cell: usize, piece: i8) {
+        let width = self.width();
+        self[cell / width][cell % width] = piece;
+    }
+
+    // The cell at (x, y), or None off the edge of the board
+    pub fn cell_at(&self, position: Vector2<i8>) -> Option<usize> {
+        ((0..self.width() as i8).contains(&position.x)
+            && (0..self.height() as i8).contains(&position.y))
+        .then(|| position.y as usize * self.width() + position.x as usize)
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = i8> + '_ {
+        self.0.iter().flatten().copied()
+    }
+}
+
+impl<const W: usize, const H: usize> From<[[i8; W]; H]> for Grid {
+    fn from(rows: [[i8; W]; H]) -> Self {
+        Self(rows.iter().map(|row| row.to_vec()).collect())
+    }
+}
+
+impl<const W: usize, const H: usize> PartialEq<[[i8; W]; H]> for Grid {
+    fn eq(&self, rows: &[[i8; W]; H]) -> bool {
+        self.height() == H && self.iter().zip(rows).all(|(row, other)| row == other)
+    }
+}
+
+impl Deref for Grid {
+    type Target = [Vec<i8>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Grid {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+// Sliding one piece into the gap beside it. Solutions, the undo history and
+// queued solver moves are all lists of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    // Numbered from 0, like the pieces in a grid
+    pub piece: i8,
+}
+
+impl Move {
+    pub fn new(piece: i8) -> Self {
+        Self { piece }
+    }
+
+    // Make the move on `grid`, or false if the piece isn't beside the gap
+    pub fn apply(self, grid: &mut Grid) -> bool {
+        slide_piece(grid, self.piece)
+    }
+}
+
+// The piece's label, counting from 1
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.piece + 1)
+    }
+}
+
+pub fn find_in_grid(grid: &Grid, index: i8) -> Vector2<i8> {
+    for y_index in 0..grid.height() {
+        for x_index in 0..grid.width() {
+            if grid[y_index][x_index] == index as i8 {
+                return Vector2::new(x_index as i8, y_index as i8);
+            }
+        }
+    }
+
+    return Vector2::new(-1, -1);
+}
+
+// Slide a piece into the gap if it's next to it
+pub fn slide_piece(grid: &mut Grid, piece: i8) -> bool {
+    let piece_pos = find_in_grid(grid, piece);
+    let blank_pos = find_in_grid(grid, -1);
+
+    let (Some(piece_cell), Some(blank_cell)) = (grid.cell_at(piece_pos), grid.cell_at(blank_pos))
+    else {
+        return false;
+    };
+    if !neighbor_table(grid.width(), grid.height()).are_neighbors(piece_cell, blank_cell) {
+        return false;
+    }
+
+    grid[blank_pos.y as usize][blank_pos.x as usize] = piece;
+    grid[piece_pos.y as usize][piece_pos.x as usize] = -1;
+
+    true
+}
+
+// Randomly slide pieces into the gap, never undoing the previous slide.
+// Returns the pieces that were moved, in order.
+pub fn shuffle_grid(grid: &mut Grid, mix_steps: u32, rng: &mut impl Rng) -> Vec<i8> {
+    let mut moved_pieces = Vec::new();
+
+    let width = grid.width();
+    let neighbors = neighbor_table(width, grid.height());
+    let mut last_blank_cell = None;
+
+    for _ in 0..mix_steps {
+        let available_grid_pos = find_in_grid(grid, -1);
+        let blank_cell = available_grid_pos.y as usize * width + available_grid_pos.x as usize;
+
+        // Get all adjacent cells, except the one the gap just came from
+        let adjacent_cells: Vec<usize> = neighbors
+            .neighbors(blank_cell)
+            .iter()
+            .copied()
+            .filter(|&cell| Some(cell) != last_blank_cell)
+            .collect();
+
+        // Get a random adjacent cell
+        let adjacent_cell = adjacent_cells[rng.gen_range(0..adjacent_cells.len())];
+
+        // Swap the two positions
+        let adjacent_index = grid.cell(adjacent_cell);
+        grid.set_cell(adjacent_cell, -1);
+        grid[available_grid_pos.y as usize][available_grid_pos.x as usize] = adjacent_index;
+        trace!(
+            "Shuffle moved piece {} from ({}, {}) to ({}, {})",
+            adjacent_index + 1,
+            adjacent_cell % width,
+            adjacent_cell / width,
+            available_grid_pos.x,
+            available_grid_pos.y
+        );
+
+        moved_pieces.push(adjacent_index);
+
+        // Update the last swap
+        last_blank_cell = Some(blank_cell);
+    }
+
+    moved_pieces
+}
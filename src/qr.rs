@@ -0,0 +1,85 @@
+use log::{debug, warn};
+use qrcode::QrCode;
+use sfml::{
+    graphics::{Color, RectangleShape, Shape, Transformable},
+    system::Vector2,
+};
+
+use crate::renderer::{Renderer, WindowHandle};
+
+// Size of a single QR module in pixels
+const MODULE_SIZE: u32 = 6;
+// Scanners need a light border of at least 4 modules around the code
+const QUIET_ZONE: u32 = 4;
+
+// A popup window showing a QR code, closed by clicking it or pressing a key
+pub struct QrPopup<W: WindowHandle> {
+    window: W,
+    code: QrCode,
+}
+
+impl<W: WindowHandle> QrPopup<W> {
+    pub fn open<R: Renderer<Window = W>>(
+        renderer: &mut R,
+        text: &str,
+        position: Vector2<i32>,
+    ) -> Option<Self> {
+        let code = match QrCode::new(text.as_bytes()) {
+            Ok(code) => code,
+            Err(error) => {
+                warn!("Couldn't encode \"{}\" as a QR code: {}", text, error);
+                return None;
+            }
+        };
+
+        let size = (code.width() as u32 + QUIET_ZONE * 2) * MODULE_SIZE;
+        let mut window = renderer.create_window(size, text);
+        window.set_position(position);
+        window.raise();
+        debug!("Opened QR popup for \"{}\"", text);
+
+        Some(Self { window, code })
+    }
+
+    // Returns false once the popup should be closed
+    pub fn update(&mut self) -> bool {
+        let mut open = true;
+
+        while let Some(event) = self.window.poll_event() {
+            if matches!(
+                event,
+                sfml::window::Event::KeyPressed { .. }
+                    | sfml::window::Event::MouseButtonPressed { .. }
+            ) {
+                open = false;
+            }
+        }
+
+        open
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::WHITE);
+
+        let width = self.code.width();
+        let mut module =
+            RectangleShape::with_size(Vector2::new(MODULE_SIZE as f32, MODULE_SIZE as f32));
+        module.set_fill_color(Color::BLACK);
+
+        for (i, color) in self.code.to_colors().iter().enumerate() {
+            if *color != qrcode::Color::Dark {
+                continue;
+            }
+
+            let x = (i % width) as u32 + QUIET_ZONE;
+            let y = (i / width) as u32 + QUIET_ZONE;
+            module.set_position(Vector2::new(
+                (x * MODULE_SIZE) as f32,
+                (y * MODULE_SIZE) as f32,
+            ));
+            self.window.draw(&module);
+        }
+
+        self.window.display();
+    }
+}
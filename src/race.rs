@@ -0,0 +1,194 @@
+// The opponent's progress in a race, smoothed over network jitter.
+//
+// Each update the opponent sends is stamped with how far into the match it
+// was taken, on their clock. Updates are shown a little after they're due,
+// which leaves time for the next one to arrive, and in between two of them
+// the progress is interpolated, so the bar glides instead of jumping
+// whenever a packet turns up.
+//
+// Their clock and ours started at slightly different moments, so the
+// difference between when an update was stamped and when it arrived is
+// tracked and the smallest one kept, which is the one with the least delay
+// on the way. If that stops holding, because updates start arriving far
+// later than usual or the shown progress falls far behind the newest
+// update, the estimate is thrown away and the bar jumps straight to the
+// newest update. That's a desync, and is flagged on screen for a moment so
+// it's obvious the jump wasn't a real move.
+
+use std::{collections::VecDeque, time::Duration};
+
+use log::warn;
+
+use crate::{puzzle::Grid, scramble};
+
+// How far behind the newest update the opponent is shown
+const PLAYBACK_DELAY: Duration = Duration::from_millis(500);
+// Falling further behind than this is a desync
+const MAX_LAG: Duration = Duration::from_secs(2);
+// How long a desync stays flagged
+const DESYNC_FLAG_TIME: Duration = Duration::from_secs(2);
+// Older updates are only needed to interpolate from
+const MAX_SAMPLES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    // Counts up with every update sent, so repeats can be spotted
+    pub seq: u64,
+    // Time since the match started on the sender's clock
+    pub at: Duration,
+    pub moves: u32,
+    // Pieces in their goal cells, 0 to 8
+    pub in_place: u8,
+    // Their board the right way up, if they sent it
+    pub board: Option<Grid>,
+}
+
+// Where the opponent is shown to be. Fractional while gliding between
+// updates.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    pub moves: f32,
+    pub in_place: f32,
+}
+
+// The opponent's latest update, or None if they haven't sent one yet
+pub fn parse_sample(json: &str) -> Result<Option<Sample>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|error| format!("Bad progress JSON: {}", error))?;
+    let Some(sample) = value.get("opponent").filter(|sample| !sample.is_null()) else {
+        return Ok(None);
+    };
+    let number = |name: &str| {
+        sample
+            .get(name)
+            .and_then(|field| field.as_u64())
+            .ok_or(format!("Progress has no {}", name))
+    };
+
+    Ok(Some(Sample {
+        seq: number("seq")?,
+        at: Duration::from_millis(number("at_ms")?),
+        moves: number("moves")? as u32,
+        in_place: number("in_place")?.min(8) as u8,
+        board: sample
+            .get("board")
+            .and_then(|board| board.as_str())
+            .map(scramble::parse)
+            .transpose()?,
+    }))
+}
+
+// The opponent's updates so far. Times passed in are our time since the
+// match started.
+#[derive(Debug, Default)]
+pub struct OpponentTrack {
+    samples: VecDeque<Sample>,
+    // Our clock minus theirs, as best we know it
+    offset: Option<Duration>,
+    desynced_at: Option<Duration>,
+}
+
+impl OpponentTrack {
+    pub fn push(&mut self, sample: Sample, received: Duration) {
+        // Repeats and stragglers overtaken by a newer update add nothing
+        if self
+            .samples
+            .back()
+            .is_some_and(|last| sample.seq <= last.seq || sample.at < last.at)
+        {
+            return;
+        }
+
+        let offset = received.saturating_sub(sample.at);
+        match self.offset {
+            Some(known) if offset > known + MAX_LAG => {
+                warn!(
+                    "Opponent's progress arrived {}ms later than usual, resyncing",
+                    (offset - known).as_millis()
+                );
+                self.resync(sample, received);
+                return;
+            }
+            Some(known) => self.offset = Some(known.min(offset)),
+            None => self.offset = Some(offset),
+        }
+
+        self.samples.push_back(sample);
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    // The moment on their clock that's being shown
+    fn shown(&self, now: Duration) -> Option<Duration> {
+        self.offset
+            .map(|offset| now.saturating_sub(offset + PLAYBACK_DELAY))
+    }
+
+    pub fn progress(&mut self, now: Duration) -> Progress {
+        let (Some(shown), Some(newest)) = (self.shown(now), self.samples.back().cloned()) else {
+            return Progress::default();
+        };
+
+        if newest.at > shown + MAX_LAG {
+            warn!(
+                "Opponent's progress is {}ms behind, resyncing",
+                (newest.at - shown).as_millis()
+            );
+            let progress = to_progress(&newest);
+            self.resync(newest, now);
+            return progress;
+        }
+
+        let before = self.samples.iter().rev().find(|sample| sample.at <= shown);
+        let after = self.samples.iter().find(|sample| sample.at > shown);
+        match (before, after) {
+            (Some(before), Some(after)) => {
+                let t = (shown - before.at).as_secs_f32() / (after.at - before.at).as_secs_f32();
+                Progress {
+                    moves: lerp(before.moves as f32, after.moves as f32, t),
+                    in_place: lerp(before.in_place as f32, after.in_place as f32, t),
+                }
+            }
+            (Some(sample), None) | (None, Some(sample)) => to_progress(sample),
+            (None, None) => Progress::default(),
+        }
+    }
+
+    // The opponent's board as of the progress being shown. Boards can't be
+    // interpolated, so it changes at the update that moved the piece.
+    pub fn board(&self, now: Duration) -> Option<&Grid> {
+        let shown = self.shown(now)?;
+        self.samples
+            .iter()
+            .rev()
+            .find(|sample| sample.at <= shown)
+            .or(self.samples.front())
+            .and_then(|sample| sample.board.as_ref())
+    }
+
+    // Start over from `newest`, shown as it stands from `now` on
+    fn resync(&mut self, newest: Sample, now: Duration) {
+        self.desynced_at = Some(now);
+        self.offset = Some(now.saturating_sub(newest.at));
+        self.samples.clear();
+        self.samples.push_back(newest);
+    }
+
+    // Whether to show that the opponent's bar just jumped to catch up
+    pub fn desynced(&self, now: Duration) -> bool {
+        self.desynced_at
+            .is_some_and(|desynced_at| now.saturating_sub(desynced_at) < DESYNC_FLAG_TIME)
+    }
+}
+
+fn to_progress(sample: &Sample) -> Progress {
+    Progress {
+        moves: sample.moves as f32,
+        in_place: sample.in_place as f32,
+    }
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
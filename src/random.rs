@@ -0,0 +1,21 @@
+use once_cell::sync::OnceCell;
+use rand::{rngs::StdRng, SeedableRng};
+
+// Every random choice the game makes, from the shuffle to chaos moves, comes
+// from a generator seeded with this, so a run can be repeated exactly. Until
+// it's set, e.g. in tests, the seed is 0.
+static SEED: OnceCell<u64> = OnceCell::new();
+
+pub fn init(seed: u64) {
+    let _ = SEED.set(seed);
+}
+
+pub fn seed() -> u64 {
+    SEED.get().copied().unwrap_or(0)
+}
+
+// A fresh generator from the run's seed. Two made from the same seed make
+// the same choices.
+pub fn rng() -> StdRng {
+    StdRng::seed_from_u64(seed())
+}
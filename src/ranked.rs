@@ -0,0 +1,564 @@
+// Ranked play against someone else online, built with `--features ranked`.
+// The relay server pairs up players and keeps their Elo ratings; all the
+// game does is ask it for a match, play the seed it hands out and report
+// how the solve went. It speaks JSON over HTTP:
+//
+//   POST /queue                  {"player"}    -> {"ticket"}
+//   GET  /queue/<ticket>                       -> {"status": "waiting"} or
+//        {"status": "matched", "match", "seed", "opponent", "rating", "opponent_rating"}
+//   DELETE /queue/<ticket>                     gives up waiting
+//   POST /matches/<id>/result    {"player", "moves", "time_ms", "solved",
+//                                 "board_rating", "board_difficulty"}
+//   GET  /matches/<id>?player=<name>           -> {"status": "pending"} or
+//        {"status": "finished", "won", "rating", "change"}
+//
+//   POST /matches/<id>/progress  {"player", "seq", "at_ms", "moves", "in_place", "board"}
+//   GET  /matches/<id>/progress?player=<name>  -> {"opponent": null} or
+//        {"opponent": {"seq", "at_ms", "moves", "in_place", "board"}}
+//
+// A match is decided once both players have reported, or the other one gives
+// up, so the result is polled for like the match itself. Progress goes back
+// and forth all through the match so each player can see how the other is
+// doing, smoothed as described in race.rs. The board is in scramble notation
+// (see scramble.rs) and may be left out.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, warn};
+use sfml::{
+    graphics::{Color, RectangleShape, Shape, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    chat::ChatWindow,
+    font,
+    layout::Side,
+    opponent_board::OpponentBoard,
+    puzzle::Grid,
+    race::{self, OpponentTrack, Sample},
+    rating::{self, Rating},
+    renderer::{Renderer, WindowHandle},
+    scramble,
+    share::format_duration,
+    World,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// Progress is sent and fetched more often, so there's less to smooth over
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(300);
+const BAR_HEIGHT: f32 = 20.0;
+
+const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub id: String,
+    // Both players get the same seed, and so the same board
+    pub seed: u64,
+    pub opponent: String,
+    pub rating: i32,
+    pub opponent_rating: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub won: bool,
+    pub rating: i32,
+    pub change: i32,
+}
+
+fn parse_json(json: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(json).map_err(|error| format!("Bad reply from the server: {}", error))
+}
+
+// The match once the server has found one, or None while still waiting
+pub fn parse_match(json: &str) -> Result<Option<Match>, String> {
+    let value = parse_json(json)?;
+    let text = |name: &str| value.get(name).and_then(|field| field.as_str());
+    let number = |name: &str| value.get(name).and_then(|field| field.as_i64());
+
+    match text("status") {
+        Some("waiting") => Ok(None),
+        Some("matched") => Ok(Some(Match {
+            id: text("match").ok_or("Match has no id")?.to_string(),
+            seed: value
+                .get("seed")
+                .and_then(|seed| seed.as_u64())
+                .ok_or("Match has no seed")?,
+            opponent: text("opponent").ok_or("Match has no opponent")?.to_string(),
+            rating: number("rating").ok_or("Match has no rating")? as i32,
+            opponent_rating: number("opponent_rating").ok_or("Match has no opponent rating")?
+                as i32,
+        })),
+        status => Err(format!("Unexpected queue status {:?}", status)),
+    }
+}
+
+// How the match went once it's decided, or None while the other player is
+// still solving
+pub fn parse_outcome(json: &str) -> Result<Option<Outcome>, String> {
+    let value = parse_json(json)?;
+    let number = |name: &str| value.get(name).and_then(|field| field.as_i64());
+
+    match value.get("status").and_then(|status| status.as_str()) {
+        Some("pending") => Ok(None),
+        Some("finished") => Ok(Some(Outcome {
+            won: value
+                .get("won")
+                .and_then(|won| won.as_bool())
+                .ok_or("Result doesn't say who won")?,
+            rating: number("rating").ok_or("Result has no rating")? as i32,
+            change: number("change").ok_or("Result has no rating change")? as i32,
+        })),
+        status => Err(format!("Unexpected match status {:?}", status)),
+    }
+}
+
+pub fn get(url: &str) -> Result<String, String> {
+    ureq::get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .call()
+        .map_err(|error| error.to_string())?
+        .into_string()
+        .map_err(|error| error.to_string())
+}
+
+pub fn post(url: &str, body: serde_json::Value) -> Result<String, String> {
+    ureq::post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+        .map_err(|error| error.to_string())?
+        .into_string()
+        .map_err(|error| error.to_string())
+}
+
+// Waits in the server's queue on a background thread until an opponent
+// turns up
+pub struct Matchmaker {
+    receiver: Receiver<Result<Match, String>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Matchmaker {
+    pub fn start(server: &str, player: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let server = server.trim_end_matches('/').to_string();
+        let player = player.to_string();
+
+        let stop = cancelled.clone();
+        thread::spawn(move || {
+            let result = (|| {
+                let reply = post(
+                    &format!("{}/queue", server),
+                    serde_json::json!({ "player": player }),
+                )?;
+                let ticket = parse_json(&reply)?
+                    .get("ticket")
+                    .and_then(|ticket| ticket.as_str())
+                    .ok_or("The server didn't give out a ticket")?
+                    .to_string();
+                debug!("Queued for a ranked match with ticket {}", ticket);
+
+                let url = format!("{}/queue/{}", server, ticket);
+                loop {
+                    if stop.load(Ordering::Relaxed) {
+                        if let Err(error) = ureq::delete(&url).timeout(REQUEST_TIMEOUT).call() {
+                            debug!("Couldn't leave the queue: {}", error);
+                        }
+                        return Err("Matchmaking cancelled".to_string());
+                    }
+                    if let Some(found) = parse_match(&get(&url)?)? {
+                        return Ok(found);
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+            })();
+            let _ = sender.send(result);
+        });
+
+        Self {
+            receiver,
+            cancelled,
+        }
+    }
+
+    // The match, or why there won't be one, once the wait is over
+    pub fn poll(&mut self) -> Option<Result<Match, String>> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+// Sends our progress and fetches the opponent's on a background thread,
+// which stops when the match it belongs to is over
+struct ProgressClient {
+    outgoing: Sender<Sample>,
+    incoming: Receiver<Sample>,
+}
+
+impl ProgressClient {
+    fn start(server: &str, match_id: &str, player: &str) -> Self {
+        let (outgoing, to_send) = mpsc::channel::<Sample>();
+        let (received, incoming) = mpsc::channel();
+        let url = format!("{}/matches/{}/progress", server, match_id);
+        let player = player.to_string();
+
+        thread::spawn(move || loop {
+            // Only the newest of our updates is worth sending
+            let mut newest = None;
+            loop {
+                match to_send.try_recv() {
+                    Ok(sample) => newest = Some(sample),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                }
+            }
+            if let Some(sample) = newest {
+                let body = serde_json::json!({
+                    "player": player,
+                    "seq": sample.seq,
+                    "at_ms": sample.at.as_millis() as u64,
+                    "moves": sample.moves,
+                    "in_place": sample.in_place,
+                    "board": sample.board.as_ref().map(scramble::to_notation),
+                });
+                if let Err(error) = post(&url, body) {
+                    debug!("Couldn't send progress: {}", error);
+                }
+            }
+
+            match get(&format!("{}?player={}", url, player))
+                .and_then(|json| race::parse_sample(&json))
+            {
+                Ok(Some(sample)) => {
+                    if received.send(sample).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => debug!("Couldn't fetch the opponent's progress: {}", error),
+            }
+
+            thread::sleep(PROGRESS_INTERVAL);
+        });
+
+        Self { outgoing, incoming }
+    }
+}
+
+// Shown while waiting for an opponent, which Escape gives up on
+pub struct MatchmakingWindow<W: WindowHandle> {
+    window: W,
+    server: String,
+    pub cancelled: bool,
+}
+
+impl<W: WindowHandle> MatchmakingWindow<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, server: &str) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Ranked");
+        let desktop = renderer.desktop_size();
+        window.set_position(Vector2::new(
+            (desktop.x / 2 - WINDOW_SIZE / 2) as i32,
+            (desktop.y / 2 - WINDOW_SIZE / 2) as i32,
+        ));
+
+        Self {
+            window,
+            server: server.to_string(),
+            cancelled: false,
+        }
+    }
+
+    pub fn update(&mut self) {
+        while let Some(event) = self.window.poll_event() {
+            if let Event::Closed
+            | Event::KeyPressed {
+                code: Key::Escape, ..
+            } = event
+            {
+                self.cancelled = true;
+            }
+        }
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+        draw_lines(
+            &mut self.window,
+            &[
+                "Finding an opponent".to_string(),
+                format!("on {}", self.server),
+                String::new(),
+                "Escape to cancel".to_string(),
+            ],
+        );
+        self.window.display();
+    }
+}
+
+// A ranked game in progress: who it's against, and the new rating once the
+// server has decided the match
+pub struct Ranked<W: WindowHandle> {
+    server: String,
+    player: String,
+    found: Match,
+    window: W,
+    reported: bool,
+    outcome: Option<Receiver<Result<Outcome, String>>>,
+    lines: Vec<String>,
+    chat: ChatWindow<W>,
+    opponent_board: OpponentBoard<W>,
+    started: Instant,
+    progress: ProgressClient,
+    sent: u64,
+    last_sent: Option<Instant>,
+    opponent: OpponentTrack,
+    // How hard the board both players were dealt is, sent with the result so
+    // the server can group results by difficulty
+    board_rating: Option<Rating>,
+}
+
+impl<W: WindowHandle> Ranked<W> {
+    pub fn start<R: Renderer<Window = W>>(
+        world: &mut World<R>,
+        server: &str,
+        player: &str,
+        found: Match,
+    ) -> Self {
+        let mut window = world.renderer.create_window(WINDOW_SIZE, "Ranked");
+        let position = world.side_window_position(Side::Left, WINDOW_SIZE);
+        window.set_position(position);
+        let server = server.trim_end_matches('/');
+        // Under the match's window, with the opponent's board under that
+        let chat_position = position + Vector2::new(0, (WINDOW_SIZE + world.padding) as i32);
+        let chat = ChatWindow::open(
+            &mut world.renderer,
+            chat_position,
+            server,
+            &found.id,
+            player,
+        );
+        let opponent_board = OpponentBoard::open(
+            &mut world.renderer,
+            chat_position + Vector2::new(0, (WINDOW_SIZE + world.padding) as i32),
+        );
+        info!(
+            "Ranked match {} against {} ({})",
+            found.id, found.opponent, found.opponent_rating
+        );
+
+        let board_rating = rating::rate(&world.start_grid);
+        let mut lines = vec![
+            format!("vs {} ({})", found.opponent, found.opponent_rating),
+            format!("You: {}", found.rating),
+        ];
+        if let Some(board_rating) = &board_rating {
+            lines.push(format!("Board: {}", rating::describe(board_rating)));
+        }
+        Self {
+            server: server.to_string(),
+            player: player.to_string(),
+            progress: ProgressClient::start(server, &found.id, player),
+            found,
+            window,
+            reported: false,
+            outcome: None,
+            lines,
+            chat,
+            opponent_board,
+            started: Instant::now(),
+            sent: 0,
+            last_sent: None,
+            opponent: OpponentTrack::default(),
+            board_rating,
+        }
+    }
+
+    pub fn update<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        // Nothing to click, but the events still need draining
+        while self.window.poll_event().is_some() {}
+        self.chat.update();
+        self.opponent_board.update();
+        self.exchange_progress(world);
+
+        if world.solved && !self.reported {
+            self.lines.push(format!(
+                "Solved in {}",
+                format_duration(world.solve_time.unwrap_or_default())
+            ));
+            self.lines.push("Waiting for the result".to_string());
+            self.report(world.moves, world.solve_time, true);
+        }
+
+        let Some(receiver) = &self.outcome else {
+            return;
+        };
+        let Ok(result) = receiver.try_recv() else {
+            return;
+        };
+        self.outcome = None;
+        self.lines.pop();
+        match result {
+            Ok(outcome) => {
+                let line = format!(
+                    "{}! Rating {} ({:+})",
+                    if outcome.won { "Won" } else { "Lost" },
+                    outcome.rating,
+                    outcome.change
+                );
+                println!("{}", line);
+                self.lines.push(line);
+            }
+            Err(error) => {
+                warn!("Couldn't get the ranked result: {}", error);
+                self.lines.push("No result from the server".to_string());
+            }
+        }
+    }
+
+    fn exchange_progress<R: Renderer<Window = W>>(&mut self, world: &World<R>) {
+        let now = self.started.elapsed();
+
+        if self
+            .last_sent
+            .is_none_or(|last_sent| last_sent.elapsed() >= PROGRESS_INTERVAL)
+        {
+            let grid = world.canonical_grid();
+            let in_place = grid
+                .cells()
+                .zip(Grid::solved(world.width, world.height).cells())
+                .filter(|&(piece, goal)| piece != -1 && piece == goal)
+                .count();
+            self.sent += 1;
+            let _ = self.progress.outgoing.send(Sample {
+                seq: self.sent,
+                at: now,
+                moves: world.moves,
+                in_place: in_place as u8,
+                board: Some(grid),
+            });
+            self.last_sent = Some(Instant::now());
+        }
+
+        for sample in self.progress.incoming.try_iter() {
+            self.opponent.push(sample, now);
+        }
+    }
+
+    // Send how the game went, then wait for the server to decide the match
+    fn report(&mut self, moves: u32, time: Option<Duration>, solved: bool) {
+        self.reported = true;
+        let (sender, receiver) = mpsc::channel();
+        let base = format!("{}/matches/{}", self.server, self.found.id);
+        let body = serde_json::json!({
+            "player": self.player,
+            "moves": moves,
+            "time_ms": time.map(|time| time.as_millis() as u64),
+            "solved": solved,
+            "board_rating": self.board_rating.map(|rating| rating.score),
+            "board_difficulty": self.board_rating.map(|rating| rating.difficulty.name()),
+        });
+        let url = format!("{}?player={}", base, self.player);
+
+        thread::spawn(move || {
+            let result = (|| {
+                post(&format!("{}/result", base), body)?;
+                loop {
+                    if let Some(outcome) = parse_outcome(&get(&url)?)? {
+                        return Ok(outcome);
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+            })();
+            let _ = sender.send(result);
+        });
+        self.outcome = Some(receiver);
+    }
+
+    // Leaving before solving counts as a loss. The result is sent before
+    // the game closes, without waiting for the verdict.
+    pub fn finish(&mut self) {
+        if self.reported {
+            return;
+        }
+
+        println!("Left the ranked match unsolved, it counts as a loss");
+        let body = serde_json::json!({
+            "player": self.player,
+            "moves": serde_json::Value::Null,
+            "time_ms": serde_json::Value::Null,
+            "solved": false,
+            "board_rating": self.board_rating.map(|rating| rating.score),
+            "board_difficulty": self.board_rating.map(|rating| rating.difficulty.name()),
+        });
+        let url = format!("{}/matches/{}/result", self.server, self.found.id);
+        if let Err(error) = post(&url, body) {
+            warn!("Couldn't tell the server the match was left: {}", error);
+        }
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+        draw_lines(&mut self.window, &self.lines);
+
+        // The opponent's progress along the bottom, filling as their pieces
+        // go into place
+        let now = self.started.elapsed();
+        let progress = self.opponent.progress(now);
+        let bar_top = WINDOW_SIZE as f32 - BAR_HEIGHT - 5.0;
+        let label = if self.opponent.desynced(now) {
+            "Opponent (resynced)".to_string()
+        } else {
+            format!("Opponent: {:.0} moves", progress.moves)
+        };
+        let mut text = Text::new(&label, font(), 24);
+        text.set_fill_color(if self.opponent.desynced(now) {
+            Color::rgb(255, 160, 0)
+        } else {
+            Color::WHITE
+        });
+        text.set_position(Vector2::new(5.0, bar_top - LINE_HEIGHT - 5.0));
+        self.window.draw(&text);
+
+        let width = WINDOW_SIZE as f32 - 10.0;
+        let mut track = RectangleShape::with_size(Vector2::new(width, BAR_HEIGHT));
+        track.set_position(Vector2::new(5.0, bar_top));
+        track.set_fill_color(Color::rgb(60, 60, 60));
+        self.window.draw(&track);
+        let mut bar =
+            RectangleShape::with_size(Vector2::new(width * progress.in_place / 8.0, BAR_HEIGHT));
+        bar.set_position(Vector2::new(5.0, bar_top));
+        bar.set_fill_color(Color::rgb(0, 200, 0));
+        self.window.draw(&bar);
+
+        self.window.display();
+        self.chat.render();
+        self.opponent_board.render(self.opponent.board(now));
+    }
+}
+
+fn draw_lines<W: WindowHandle>(window: &mut W, lines: &[String]) {
+    for (index, line) in lines.iter().enumerate() {
+        let mut text = Text::new(line, font(), 24);
+        text.set_fill_color(Color::WHITE);
+        text.set_position(Vector2::new(5.0, index as f32 * LINE_HEIGHT + 5.0));
+        window.draw(&text);
+    }
+}
@@ -0,0 +1,166 @@
+// How hard a scramble is, judged from what the solver knows about it, so
+// results can be grouped with others of the same difficulty. Only 3x3 boards
+// can be rated, since they're the only ones the solver has every distance
+// for.
+
+use crate::{
+    board::Board,
+    puzzle::{find_in_grid, Grid},
+    solver,
+};
+
+// How much each part counts towards the score, out of 1
+const LENGTH_WEIGHT: f32 = 0.6;
+const TRAP_WEIGHT: f32 = 0.2;
+const RARITY_WEIGHT: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub fn from_score(score: u32) -> Self {
+        match score {
+            0..=39 => Difficulty::Easy,
+            40..=59 => Difficulty::Medium,
+            60..=79 => Difficulty::Hard,
+            _ => Difficulty::Expert,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    // The fewest moves that solve it
+    pub optimal: u8,
+    // How many of the slides on offer keep to a shortest solution, on
+    // average along one. Fewer means fewer ways to get it right.
+    pub branching: f32,
+    // The share of solvable boards that are nearer to solved than this one,
+    // 0 for the solved board and almost 1 for the furthest
+    pub rarity: f32,
+    // 0 to 100, combining the three above
+    pub score: u32,
+    pub difficulty: Difficulty,
+}
+
+// None if the board can't be solved, or isn't 3x3
+pub fn rate(grid: &Grid) -> Option<Rating> {
+    let optimal = solver::distance(grid)?;
+    let solution = solver::solve(grid)?;
+
+    // At every step, how many slides there were and how many of them kept
+    // to a shortest solution
+    let mut current = grid.clone();
+    let mut good = 0;
+    let mut offered = 0;
+    for step in solution {
+        let distance = solver::distance(&current)?;
+        let moves = solver::moves(&current);
+        offered += moves.len();
+        good += moves
+            .iter()
+            .filter(|(_, next)| solver::distance(next).is_some_and(|next| next < distance))
+            .count();
+        current = moves.into_iter().find(|&(moved, _)| moved == step)?.1;
+    }
+    let branching = if optimal == 0 {
+        0.0
+    } else {
+        good as f32 / optimal as f32
+    };
+    // The share of slides on offer that lead away from a shortest solution
+    let traps = if offered == 0 {
+        0.0
+    } else {
+        1.0 - good as f32 / offered as f32
+    };
+
+    let counts = solver::distance_counts();
+    let nearer: usize = counts[..optimal as usize].iter().sum();
+    let rarity = nearer as f32 / counts.iter().sum::<usize>() as f32;
+    let length = optimal as f32 / (counts.len() - 1) as f32;
+
+    let score = (100.0 * (LENGTH_WEIGHT * length + TRAP_WEIGHT * traps + RARITY_WEIGHT * rarity))
+        .round() as u32;
+
+    Some(Rating {
+        optimal,
+        branching,
+        rarity,
+        score,
+        difficulty: Difficulty::from_score(score),
+    })
+}
+
+// "hard (64)", for showing alongside a result
+pub fn describe(rating: &Rating) -> String {
+    format!("{} ({})", rating.difficulty.name(), rating.score)
+}
+
+// How well a shuffle mixed the board. Random slides often undo each other's
+// work, so a board can end up much nearer solved than the slides suggest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuffleQuality {
+    pub mix_steps: u32,
+    // The fewest moves that solve it, for 3x3 boards
+    pub optimal: Option<u32>,
+    // It's at least this many moves from solved, whatever the size
+    pub lower_bound: u32,
+    // How far the gap is from its home in the bottom right, in cells along
+    // rows and columns
+    pub blank_displacement: u32,
+}
+
+pub fn shuffle_quality(grid: &Grid, mix_steps: u32) -> ShuffleQuality {
+    let blank = find_in_grid(grid, -1);
+    let home = (grid.width() as i32 - 1, grid.height() as i32 - 1);
+
+    ShuffleQuality {
+        mix_steps,
+        optimal: solver::distance(grid).map(u32::from),
+        lower_bound: Board::new(grid.clone()).distance_to_goal_lower_bound(),
+        blank_displacement: (home.0 - blank.x as i32).unsigned_abs()
+            + (home.1 - blank.y as i32).unsigned_abs(),
+    }
+}
+
+// "7 slides, 5 moves from solved (71%), gap 3 cells from home"
+pub fn describe_shuffle(quality: &ShuffleQuality) -> String {
+    let plural = |count: u32| if count == 1 { "" } else { "s" };
+    let (distance, moves) = match quality.optimal {
+        Some(optimal) => (format!("{} move{}", optimal, plural(optimal)), optimal),
+        None => (
+            format!(
+                "at least {} move{}",
+                quality.lower_bound,
+                plural(quality.lower_bound)
+            ),
+            quality.lower_bound,
+        ),
+    };
+    let share = (moves * 100).checked_div(quality.mix_steps).unwrap_or(0);
+
+    format!(
+        "{} slide{}, {} from solved ({}%), gap {} cell{} from home",
+        quality.mix_steps,
+        plural(quality.mix_steps),
+        distance,
+        share,
+        quality.blank_displacement,
+        plural(quality.blank_displacement)
+    )
+}
@@ -0,0 +1,78 @@
+use log::{debug, trace};
+use sfml::system::Vector2;
+
+use crate::{
+    layout::apply_map,
+    puzzle::{find_in_grid, Move},
+    renderer::Renderer,
+    World,
+};
+
+// Space between the two boards
+const GAP: u32 = 60;
+
+// Two boards side by side with their gaps linked. Every move the player
+// makes on one board is mirrored left to right onto the other, if the
+// other board has a piece in the right place to make it.
+#[derive(Default)]
+pub struct Relay {
+    // How many of each board's moves have been passed on
+    relayed: [usize; 2],
+}
+
+impl Relay {
+    pub fn new() -> Self {
+        Self { relayed: [0, 0] }
+    }
+
+    // Move both boards off centre so they sit side by side
+    pub fn place<R: Renderer>(left: &mut World<R>, right: &mut World<R>) {
+        let board_size = left.board_size().x;
+        let offset = (board_size + GAP) / 2;
+
+        left.set_center(Vector2::new(left.center.x - offset, left.center.y));
+        right.set_center(Vector2::new(right.center.x + offset, right.center.y));
+    }
+
+    pub fn update<R: Renderer>(&mut self, left: &mut World<R>, right: &mut World<R>) {
+        self.relay(0, left, right);
+        self.relay(1, right, left);
+    }
+
+    fn relay<R: Renderer>(&mut self, index: usize, from: &World<R>, to: &mut World<R>) {
+        // A fresh solve starts the history over
+        if from.history.len() < self.relayed[index] {
+            self.relayed[index] = 0;
+        }
+
+        while self.relayed[index] < from.history.len() {
+            let before = match self.relayed[index] {
+                0 => &from.start_grid,
+                n => &from.history[n - 1].grid,
+            };
+            let record = &from.history[self.relayed[index]];
+            self.relayed[index] += 1;
+            // Power-ups aren't slides, so there's nothing to mirror
+            if record.power_up.is_some() {
+                continue;
+            }
+            let after = &record.grid;
+
+            // Compare the boards as shown, so "mirrored" means what the player sees
+            let gap_before = find_in_grid(&apply_map(before, &from.orientation), -1);
+            let gap_after = find_in_grid(&apply_map(after, &from.orientation), -1);
+            let step = Vector2::new(gap_after.x - gap_before.x, gap_after.y - gap_before.y);
+
+            let gap = find_in_grid(&to.grid, -1);
+            let target = Vector2::new(gap.x - step.x, gap.y + step.y);
+            let Some(target) = to.grid.cell_at(target) else {
+                trace!("Relayed move doesn't fit the other board");
+                continue;
+            };
+
+            let step = Move::new(to.grid.cell(target));
+            debug!("Relaying a move as piece {}", step);
+            to.auto_moves.push_back(step);
+        }
+    }
+}
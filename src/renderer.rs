@@ -0,0 +1,46 @@
+use sfml::{
+    graphics::{Color, Drawable, IntRect},
+    system::Vector2,
+    window::Event,
+};
+
+// A single piece window, abstracted so the game loop can run without a display
+pub trait WindowHandle {
+    fn poll_event(&mut self) -> Option<Event>;
+    fn set_position(&mut self, position: Vector2<i32>);
+    // Windows are square, so one side is enough
+    fn set_size(&mut self, size: u32);
+    fn clear(&mut self, color: Color);
+    fn draw(&mut self, drawable: &dyn Drawable);
+    fn display(&mut self);
+    fn mouse_position(&self) -> Vector2<i32>;
+    fn has_focus(&self) -> bool;
+    fn request_focus(&self);
+    // Bring the window above every other piece window
+    fn raise(&self);
+}
+
+// Creates piece windows and answers questions about the desktop they live on
+pub trait Renderer {
+    type Window: WindowHandle;
+
+    fn create_window(&mut self, size: u32, title: &str) -> Self::Window;
+    fn desktop_size(&self) -> Vector2<u32>;
+    fn desktop_mouse_position(&self) -> Vector2<i32>;
+    // Where each connected screen is, in the same units as window positions,
+    // the main one first. Without a way to tell screens apart that's the
+    // whole desktop.
+    fn monitors(&self) -> Vec<IntRect> {
+        let desktop = self.desktop_size();
+        vec![IntRect::new(0, 0, desktop.x as i32, desktop.y as i32)]
+    }
+    fn get_clipboard(&self) -> String;
+    fn set_clipboard(&mut self, text: &str);
+    // Draw onto a transparent offscreen canvas and read it back as RGBA pixels
+    fn capture(&mut self, size: Vector2<u32>, drawables: &[&dyn Drawable]) -> Option<Vec<u8>>;
+    // A window that's never shown, to take gamepad input in one place rather
+    // than from every piece's window. None if the frontend can't make one.
+    fn create_input_window(&mut self) -> Option<Self::Window> {
+        None
+    }
+}
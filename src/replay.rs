@@ -0,0 +1,248 @@
+// Replays (.sprp) record a solve compactly enough to share. A file is the
+// magic bytes "SPRP" and a format version byte, then a zstd frame holding:
+//
+//   seed         8 bytes, little endian
+//   grid size    1 byte, the board's width in the low nibble and its height
+//                in the high one, left 0 when the board is square
+//   start grid   one byte per cell, rows top to bottom, piece + 1 with 0 for the gap
+//   move count   varint
+//   moves        per move, a byte (the piece in the low nibble, any power-up
+//                in the high one) and the ms since the move before as a varint
+//
+// A nibble only numbers 16 pieces, so boards with more, like 5x4 and 5x5,
+// can't be recorded.
+//
+// Varints are LEB128: 7 bits at a time, low bits first, with the top bit set
+// on every byte but the last.
+
+use std::{fs, path::Path, time::Duration};
+
+use crate::{
+    arcade::PowerUp,
+    puzzle::{Grid, MAX_SIZE, MIN_SIZE},
+    MoveRecord,
+};
+
+pub const EXTENSION: &str = "sprp";
+
+const MAGIC: &[u8; 4] = b"SPRP";
+const VERSION: u8 = 1;
+const COMPRESSION_LEVEL: i32 = 19;
+// Far more than any real solve needs, and keeps a hostile file from
+// unpacking into gigabytes
+const MAX_PAYLOAD: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayMove {
+    pub piece: i8,
+    // Time since the first move
+    pub at: Duration,
+    pub power_up: Option<PowerUp>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    // The seed the game was started with, see random.rs
+    pub seed: u64,
+    pub start_grid: Grid,
+    pub moves: Vec<ReplayMove>,
+}
+
+impl Replay {
+    pub fn from_game(seed: u64, start_grid: Grid, history: &[MoveRecord]) -> Self {
+        Self {
+            seed,
+            start_grid,
+            moves: history
+                .iter()
+                .map(|record| ReplayMove {
+                    piece: record.slide.piece,
+                    at: record.at,
+                    power_up: record.power_up,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        if self.start_grid.piece_count() > 16 {
+            return Err(format!(
+                "{}x{} boards have too many pieces to record",
+                self.start_grid.width(),
+                self.start_grid.height()
+            ));
+        }
+
+        let (width, height) = (self.start_grid.width(), self.start_grid.height());
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&self.seed.to_le_bytes());
+        payload.push(if width == height {
+            width as u8
+        } else {
+            (height << 4 | width) as u8
+        });
+        payload.extend(self.start_grid.cells().map(|piece| (piece + 1) as u8));
+
+        write_varint(&mut payload, self.moves.len() as u64);
+        let mut last_ms = 0;
+        for replay_move in &self.moves {
+            let power_up = match replay_move.power_up {
+                None => 0,
+                Some(PowerUp::Swap) => 1,
+                Some(PowerUp::Teleport) => 2,
+            };
+            payload.push(power_up << 4 | replay_move.piece as u8);
+
+            let ms = replay_move.at.as_millis() as u64;
+            write_varint(&mut payload, ms.saturating_sub(last_ms));
+            last_ms = ms.max(last_ms);
+        }
+
+        let compressed = zstd::bulk::compress(&payload, COMPRESSION_LEVEL)
+            .map_err(|error| format!("Couldn't compress replay: {}", error))?;
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend(compressed);
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let payload = bytes.strip_prefix(MAGIC).ok_or("Not a replay file")?;
+        let (&version, compressed) = payload.split_first().ok_or("Replay is cut short")?;
+        if version != VERSION {
+            return Err(format!(
+                "Replay format {} isn't supported, this version reads format {}",
+                version, VERSION
+            ));
+        }
+
+        let payload = zstd::bulk::decompress(compressed, MAX_PAYLOAD)
+            .map_err(|error| format!("Replay is damaged: {}", error))?;
+        Self::parse_payload(&payload)
+    }
+
+    // Everything after decompression, kept apart so it can be fuzzed directly
+    pub fn parse_payload(payload: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes: payload };
+
+        let seed = u64::from_le_bytes(
+            reader
+                .take(8)?
+                .try_into()
+                .map_err(|_| "Replay is cut short")?,
+        );
+        let size = reader.byte()? as usize;
+        let width = size & 0xf;
+        let height = match size >> 4 {
+            0 => width,
+            height => height,
+        };
+        let sizes = MIN_SIZE..=MAX_SIZE;
+        if !sizes.contains(&width) || !sizes.contains(&height) {
+            return Err(format!("{}x{} boards aren't supported", width, height));
+        }
+
+        let cell_count = width * height;
+        let mut start_grid = Grid::solved(width, height);
+        let mut seen = vec![false; cell_count];
+        for (cell, &byte) in reader.take(cell_count)?.iter().enumerate() {
+            if byte as usize >= cell_count || seen[byte as usize] {
+                return Err(format!("Start grid has a bad cell {}", byte));
+            }
+            seen[byte as usize] = true;
+            start_grid.set_cell(cell, byte as i8 - 1);
+        }
+
+        let count = reader.varint()?;
+        // Every move takes at least two bytes, so a bigger count is a lie
+        if count > reader.bytes.len() as u64 / 2 {
+            return Err(format!("Replay claims {} moves but is too short", count));
+        }
+
+        let mut moves = Vec::with_capacity(count as usize);
+        let mut ms: u64 = 0;
+        for _ in 0..count {
+            let byte = reader.byte()?;
+            let piece = (byte & 0xf) as i8;
+            if piece as usize >= start_grid.piece_count() {
+                return Err(format!("Move has a bad piece {}", piece + 1));
+            }
+            let power_up = match byte >> 4 {
+                0 => None,
+                1 => Some(PowerUp::Swap),
+                2 => Some(PowerUp::Teleport),
+                other => return Err(format!("Move has an unknown power-up {}", other)),
+            };
+            ms = ms
+                .checked_add(reader.varint()?)
+                .ok_or("Move times overflow")?;
+
+            moves.push(ReplayMove {
+                piece,
+                at: Duration::from_millis(ms),
+                power_up,
+            });
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(format!(
+                "{} unexpected bytes after the last move",
+                reader.bytes.len()
+            ));
+        }
+
+        Ok(Self {
+            seed,
+            start_grid,
+            moves,
+        })
+    }
+}
+
+pub fn load(path: &Path) -> Result<Replay, String> {
+    let bytes = fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Replay::decode(&bytes).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < count {
+            return Err("Replay is cut short".to_string());
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64)
+                .checked_shl(shift)
+                .filter(|&bits| bits >> shift == (byte & 0x7f) as u64)
+                .ok_or("Number too big")?;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Number too big".to_string())
+    }
+}
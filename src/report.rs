@@ -0,0 +1,234 @@
+use std::{fmt::Write, path::Path, time::Duration};
+
+use crate::{
+    analysis, locale, puzzle::Grid, rating, scramble, share::format_duration, solver, MoveRecord,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    // HTML for .html/.htm files, Markdown for anything else
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension)
+                if extension.eq_ignore_ascii_case("html")
+                    || extension.eq_ignore_ascii_case("htm") =>
+            {
+                Format::Html
+            }
+            _ => Format::Markdown,
+        }
+    }
+}
+
+// A write-up of a finished game: stats, the board at each key move and the
+// full move list
+pub fn generate(
+    format: Format,
+    start_grid: &Grid,
+    history: &[MoveRecord],
+    elapsed: Duration,
+) -> String {
+    let moves_per_second = if elapsed.is_zero() {
+        0.0
+    } else {
+        history.len() as f32 / elapsed.as_secs_f32()
+    };
+    let locale = locale::current();
+    let optimal = solver::distance(start_grid).map_or("-".to_string(), |optimal| {
+        locale.format_count(optimal as u64)
+    });
+    let stats = [
+        ("Moves", locale.format_count(history.len() as u64)),
+        ("Optimal moves", optimal),
+        (
+            "Difficulty",
+            rating::rate(start_grid).map_or("-".to_string(), |rating| rating::describe(&rating)),
+        ),
+        ("Time", format_duration(elapsed)),
+        (
+            "Moves per second",
+            locale.format_decimal(moves_per_second, 2),
+        ),
+        ("Scramble", scramble::to_notation(start_grid)),
+    ];
+
+    let mut boards = vec![("Start".to_string(), start_grid.clone())];
+    for index in key_moves(start_grid, history) {
+        let record = &history[index];
+        boards.push((
+            format!(
+                "Move {}: {}, {} in place ({})",
+                index + 1,
+                describe(record).to_lowercase(),
+                pieces_in_place(&record.grid),
+                format_duration(record.at)
+            ),
+            record.grid.clone(),
+        ));
+    }
+
+    let title = format!(
+        "Sliding Puzzle {}x{} solve",
+        start_grid.width(),
+        start_grid.height()
+    );
+    let detours: Vec<String> = analysis::detours(start_grid, history)
+        .iter()
+        .map(analysis::Detour::describe)
+        .collect();
+
+    match format {
+        Format::Markdown => markdown(&title, &stats, &boards, &detours, history),
+        Format::Html => html(&title, &stats, &boards, &detours, history),
+    }
+}
+
+// Moves that put more pieces in place than ever before, plus the last move
+fn key_moves(start_grid: &Grid, history: &[MoveRecord]) -> Vec<usize> {
+    let mut best = pieces_in_place(start_grid);
+    let mut key_moves = Vec::new();
+
+    for (index, record) in history.iter().enumerate() {
+        let in_place = pieces_in_place(&record.grid);
+        if in_place > best || index + 1 == history.len() {
+            best = best.max(in_place);
+            key_moves.push(index);
+        }
+    }
+
+    key_moves
+}
+
+fn pieces_in_place(grid: &Grid) -> usize {
+    grid.cells()
+        .enumerate()
+        .filter(|&(cell, piece)| piece as usize == cell)
+        .count()
+}
+
+fn markdown(
+    title: &str,
+    stats: &[(&str, String)],
+    boards: &[(String, Grid)],
+    detours: &[String],
+    history: &[MoveRecord],
+) -> String {
+    let mut text = format!("# {}\n\n| Stat | Value |\n| --- | --- |\n", title);
+    for (name, value) in stats {
+        let _ = writeln!(text, "| {} | {} |", name, value);
+    }
+
+    text.push_str("\n## Key moves\n");
+    for (title, grid) in boards {
+        let _ = write!(text, "\n### {}\n\n```\n", title);
+        for row in grid.iter() {
+            let labels: Vec<String> = row.iter().map(|&piece| label(piece)).collect();
+            let _ = writeln!(text, "{}", labels.join(" "));
+        }
+        text.push_str("```\n");
+    }
+
+    if !detours.is_empty() {
+        text.push_str("\n## Detours\n\n");
+        for detour in detours {
+            let _ = writeln!(text, "- {}", detour);
+        }
+    }
+
+    text.push_str("\n## Move list\n\n");
+    for (index, record) in history.iter().enumerate() {
+        let _ = writeln!(
+            text,
+            "{}. {} at {}",
+            index + 1,
+            describe(record),
+            format_duration(record.at)
+        );
+    }
+
+    text
+}
+
+fn html(
+    title: &str,
+    stats: &[(&str, String)],
+    boards: &[(String, Grid)],
+    detours: &[String],
+    history: &[MoveRecord],
+) -> String {
+    let mut text = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+         .board {{ border-collapse: separate; border-spacing: 4px; }}\n\
+         .board td {{ width: 40px; height: 40px; text-align: center; font: bold 24px monospace; color: white; }}\n\
+         .in-place {{ background: rgb(0, 200, 0); }}\n\
+         .out-of-place {{ background: rgb(200, 0, 0); }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n<table>\n",
+    );
+    for (name, value) in stats {
+        let _ = writeln!(text, "<tr><th>{}</th><td>{}</td></tr>", name, value);
+    }
+    text.push_str("</table>\n<h2>Key moves</h2>\n");
+
+    for (title, grid) in boards {
+        let _ = writeln!(text, "<h3>{}</h3>\n<table class=\"board\">", title);
+        for (y, row) in grid.iter().enumerate() {
+            text.push_str("<tr>");
+            for (x, &piece) in row.iter().enumerate() {
+                if piece == -1 {
+                    text.push_str("<td></td>");
+                } else {
+                    let class = if piece as usize == y * grid.width() + x {
+                        "in-place"
+                    } else {
+                        "out-of-place"
+                    };
+                    let _ = write!(text, "<td class=\"{}\">{}</td>", class, piece + 1);
+                }
+            }
+            text.push_str("</tr>\n");
+        }
+        text.push_str("</table>\n");
+    }
+
+    if !detours.is_empty() {
+        text.push_str("<h2>Detours</h2>\n<ul>\n");
+        for detour in detours {
+            let _ = writeln!(text, "<li>{}</li>", detour);
+        }
+        text.push_str("</ul>\n");
+    }
+
+    text.push_str("<h2>Move list</h2>\n<ol>\n");
+    for record in history {
+        let _ = writeln!(
+            text,
+            "<li>{} at {}</li>",
+            describe(record),
+            format_duration(record.at)
+        );
+    }
+    text.push_str("</ol>\n</body>\n</html>\n");
+
+    text
+}
+
+fn label(piece: i8) -> String {
+    if piece == -1 {
+        "_".to_string()
+    } else {
+        (piece + 1).to_string()
+    }
+}
+
+// "Piece 3", or "Piece 3 (swap)" when a power-up moved it
+fn describe(record: &MoveRecord) -> String {
+    match record.power_up {
+        Some(power_up) => format!("Piece {} ({})", record.slide, power_up.name()),
+        None => format!("Piece {}", record.slide),
+    }
+}
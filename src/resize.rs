@@ -0,0 +1,108 @@
+use log::debug;
+use sfml::{
+    graphics::{Color, RectangleShape, Shape, Transformable},
+    system::Vector2,
+    window::{mouse, Event},
+};
+
+use crate::{
+    layout::{fit_piece_size, MIN_PIECE_SIZE},
+    renderer::{Renderer, WindowHandle},
+    World,
+};
+
+const HANDLE_SIZE: u32 = 24;
+
+// A grip just off the board's bottom right corner. Dragging it scales the
+// whole board, pieces and gaps alike, with the top left corner staying put.
+pub struct ResizeHandle<W: WindowHandle> {
+    window: W,
+    dragging: bool,
+    // Gaps stay the same fraction of a piece however big the board gets
+    padding_ratio: f32,
+}
+
+impl<W: WindowHandle> ResizeHandle<W> {
+    pub fn open<R: Renderer<Window = W>>(world: &mut World<R>) -> Self {
+        let window = world.renderer.create_window(HANDLE_SIZE, "Resize");
+
+        let mut handle = Self {
+            window,
+            dragging: false,
+            padding_ratio: world.padding as f32 / world.piece_size as f32,
+        };
+        handle.follow_board(world);
+
+        handle
+    }
+
+    fn follow_board<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        let bottom_right = world.grid_pos_to_px(world.width - 1, world.height - 1);
+        self.window.set_position(Vector2::new(
+            bottom_right.x + world.piece_size as i32,
+            bottom_right.y + world.piece_size as i32,
+        ));
+    }
+
+    pub fn update<R: Renderer<Window = W>>(&mut self, world: &mut World<R>) {
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::MouseButtonPressed {
+                    button: mouse::Button::Left,
+                    ..
+                } => self.dragging = true,
+                Event::MouseButtonReleased {
+                    button: mouse::Button::Left,
+                    ..
+                } => self.dragging = false,
+                _ => {}
+            }
+        }
+
+        if self.dragging {
+            // Pieces big enough for the board to reach the mouse across or
+            // down, whichever needs them bigger
+            let top_left = world.grid_pos_to_px(0, 0);
+            let mouse = world.renderer.desktop_mouse_position();
+            let fit = |extent: i32, pieces: usize| {
+                let pieces = pieces as f32;
+                extent.max(0) as f32 / (pieces + (pieces - 1.0) * self.padding_ratio)
+            };
+            let piece_size = (fit(mouse.x - top_left.x, world.width)
+                .max(fit(mouse.y - top_left.y, world.height)) as u32)
+                .max(MIN_PIECE_SIZE);
+            let padding = (piece_size as f32 * self.padding_ratio) as u32;
+            match fit_piece_size(
+                piece_size,
+                padding,
+                world.width,
+                world.height,
+                world.renderer.desktop_size(),
+            ) {
+                Ok(piece_size) if piece_size != world.piece_size => {
+                    let padding = (piece_size as f32 * self.padding_ratio) as u32;
+                    world.resize(piece_size, padding);
+                }
+                Ok(_) => {}
+                Err(error) => debug!("Not resizing: {}", error),
+            }
+        }
+
+        self.follow_board(world);
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::rgb(60, 60, 60));
+
+        // Three dots along the diagonal, the usual sign for a resize grip
+        for step in 1..=3 {
+            let offset = (step * 6) as f32;
+            let mut dot = RectangleShape::with_size(Vector2::new(4.0, 4.0));
+            dot.set_fill_color(Color::WHITE);
+            dot.set_position(Vector2::new(offset, offset));
+            self.window.draw(&dot);
+        }
+
+        self.window.display();
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+use log::{debug, trace};
+
+pub const FONT: &str = "VT323-Regular.ttf";
+pub const PATTERNS: &str = "patterns.txt";
+
+// Built into the binary, so the game runs even with nothing installed
+const EMBEDDED: &[(&str, &[u8])] = &[
+    (FONT, include_bytes!("../assets/VT323-Regular.ttf")),
+    (PATTERNS, include_bytes!("../assets/patterns.txt")),
+];
+
+const APP_NAME: &str = "slide-puzzle";
+
+// Where to look for assets, most specific first: an assets folder next to
+// the executable, the macOS bundle's Resources, the user's data directory
+// and then the system XDG data directories
+pub fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        dirs.push(exe_dir.join("assets"));
+        // Slide Puzzle.app/Contents/MacOS/slide-puzzle
+        dirs.push(exe_dir.join("../Resources"));
+    }
+
+    if let Some(project_dirs) = ProjectDirs::from("", "", APP_NAME) {
+        dirs.push(project_dirs.data_dir().to_path_buf());
+    }
+
+    let data_dirs =
+        std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".into());
+    for dir in data_dirs.split(':').filter(|dir| !dir.is_empty()) {
+        dirs.push(Path::new(dir).join(APP_NAME));
+    }
+
+    dirs
+}
+
+// The asset called `name` from the first directory that has it, or the
+// built-in copy. Each asset is only loaded once, so the file's contents are
+// leaked to live as long as the embedded ones.
+pub fn load(name: &str) -> &'static [u8] {
+    load_from(&search_dirs(), name)
+}
+
+pub fn load_from(dirs: &[PathBuf], name: &str) -> &'static [u8] {
+    for dir in dirs {
+        let path = dir.join(name);
+        match fs::read(&path) {
+            Ok(data) => {
+                debug!("Loaded {} from {}", name, path.display());
+                return Box::leak(data.into_boxed_slice());
+            }
+            Err(error) => trace!("No {} at {}: {}", name, path.display(), error),
+        }
+    }
+
+    debug!("Using the built-in {}", name);
+    embedded(name)
+}
+
+// For when a file on disk turns out to be unusable
+pub fn embedded(name: &str) -> &'static [u8] {
+    EMBEDDED
+        .iter()
+        .find(|(embedded_name, _)| *embedded_name == name)
+        .map(|(_, data)| *data)
+        .unwrap_or_else(|| panic!("No built-in asset called {}", name))
+}
@@ -0,0 +1,177 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use log::{debug, warn};
+
+use crate::io_worker;
+
+// The last line of every save, so a file cut short or scribbled over can be
+// told apart from a good one
+const CHECKSUM_PREFIX: &str = "# checksum ";
+// The first line of a versioned save. Files from before versioning count as
+// version 0.
+const VERSION_PREFIX: &str = "# version ";
+
+// One step in the history of a save format, upgrading a file written by the
+// version before it. A format's current version is how many steps it has.
+pub struct Migration {
+    pub description: &'static str,
+    pub upgrade: fn(&str) -> Result<String, String>,
+}
+
+// Replace the save at `path` without ever leaving it half written. The new
+// contents go to a temporary file first, the old save is kept as a backup
+// and only then is the new one moved into place.
+pub fn write(path: &Path, text: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // The checksum goes on a line of its own
+    let mut text = text.to_string();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+
+    let temp_path = sibling(path, "tmp");
+    let mut file = File::create(&temp_path)?;
+    file.write_all(text.as_bytes())?;
+    writeln!(file, "{}{:016x}", CHECKSUM_PREFIX, checksum(&text))?;
+    file.sync_all()?;
+
+    // A crash between these two renames still leaves the backup to load
+    if path.exists() {
+        fs::rename(path, sibling(path, "bak"))?;
+    }
+    fs::rename(&temp_path, path)
+}
+
+// The save at `path`, or its backup if the save is missing or damaged
+pub fn read(path: &Path) -> Result<String, String> {
+    let error = match read_checked(path) {
+        Ok(text) => return Ok(text),
+        Err(error) => error,
+    };
+
+    let backup_path = sibling(path, "bak");
+    match read_checked(&backup_path) {
+        Ok(text) => {
+            warn!(
+                "{}: {}, using the backup {}",
+                path.display(),
+                error,
+                backup_path.display()
+            );
+            Ok(text)
+        }
+        Err(backup_error) => {
+            debug!("No backup at {}: {}", backup_path.display(), backup_error);
+            Err(error)
+        }
+    }
+}
+
+// Like write, marking the file with the format's current version
+pub fn write_versioned(path: &Path, text: &str, migrations: &[Migration]) -> io::Result<()> {
+    write(path, &with_version(text, migrations))
+}
+
+fn with_version(text: &str, migrations: &[Migration]) -> String {
+    format!("{}{}\n{}", VERSION_PREFIX, migrations.len(), text)
+}
+
+// Like read, but files written by older versions are upgraded step by step
+// and saved again in the current format
+pub fn read_versioned(path: &Path, migrations: &[Migration]) -> Result<String, String> {
+    let (version, mut text) = split_version(read(path)?)?;
+
+    if version > migrations.len() {
+        return Err(format!(
+            "Written by a newer version of the game (format {}, this one reads up to {})",
+            version,
+            migrations.len()
+        ));
+    }
+
+    for (step, migration) in migrations.iter().enumerate().skip(version) {
+        text = (migration.upgrade)(&text)
+            .map_err(|error| format!("Couldn't upgrade to format {}: {}", step + 1, error))?;
+        println!(
+            "Upgraded {} to format {}: {}",
+            path.display(),
+            step + 1,
+            migration.description
+        );
+    }
+
+    if version < migrations.len() {
+        let path = path.to_path_buf();
+        let upgraded = with_version(&text, migrations);
+        io_worker::spawn(move || {
+            if let Err(error) = write(&path, &upgraded) {
+                warn!("Couldn't save upgraded {}: {}", path.display(), error);
+            }
+        });
+    }
+
+    Ok(text)
+}
+
+// The format version a save was written in, and the rest of the file
+pub fn split_version(text: String) -> Result<(usize, String), String> {
+    match text
+        .split_once('\n')
+        .and_then(|(first, rest)| Some((first.strip_prefix(VERSION_PREFIX)?, rest)))
+    {
+        Some((version, rest)) => Ok((
+            version
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("Bad version line \"{}\"", version))?,
+            rest.to_string(),
+        )),
+        None => Ok((0, text)),
+    }
+}
+
+fn read_checked(path: &Path) -> Result<String, String> {
+    let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    verify(&contents)
+}
+
+// The contents of a save without its checksum line, if the checksum matches
+pub fn verify(contents: &str) -> Result<String, String> {
+    let body = contents.strip_suffix('\n').unwrap_or(contents);
+    let (text, last_line) = match body.rfind('\n') {
+        Some(index) => (&contents[..index + 1], &body[index + 1..]),
+        None => ("", body),
+    };
+    let Some(expected) = last_line.strip_prefix(CHECKSUM_PREFIX) else {
+        // Saved before saves had checksums
+        return Ok(contents.to_string());
+    };
+
+    if u64::from_str_radix(expected, 16).ok() == Some(checksum(text)) {
+        Ok(text.to_string())
+    } else {
+        Err("checksum doesn't match, the file is damaged".to_string())
+    }
+}
+
+// 64-bit FNV-1a, which is plenty to catch a torn or corrupted write
+fn checksum(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// e.g. splits.txt -> splits.txt.bak
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
@@ -0,0 +1,103 @@
+// Scramble notation lists the rows top to bottom, separated by '/', using the
+// piece labels and '_' for the gap. The solved board is "123/456/78_".
+// Pieces past 9 on bigger boards are lettered from 'a', so the solved 4x4
+// board is "1234/5678/9abc/def_". Every row has the same number of cells,
+// so "12/34/5_" is a board two pieces across and three down. Links shared
+// online put the same notation after the URL scheme.
+
+use crate::{
+    board::Board,
+    puzzle::{Grid, MAX_SIZE, MIN_SIZE},
+};
+
+pub const URL_SCHEME: &str = "slidingpuzzle://";
+
+pub fn to_notation(grid: &Grid) -> String {
+    grid.iter()
+        .map(|row| {
+            row.iter()
+                .map(|&piece| {
+                    if piece == -1 {
+                        '_'
+                    } else {
+                        char::from_digit(piece as u32 + 1, 36).unwrap_or('?')
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn to_link(grid: &Grid) -> String {
+    format!("{}{}", URL_SCHEME, to_notation(grid))
+}
+
+// Accepts either bare notation or a slidingpuzzle:// link
+pub fn parse(text: &str) -> Result<Grid, String> {
+    let text = text.trim();
+    let notation = match text.strip_prefix(URL_SCHEME) {
+        // Browsers sometimes add a trailing slash to links
+        Some(notation) => notation.trim_end_matches('/'),
+        None => text,
+    };
+
+    let rows: Vec<&str> = notation.split('/').collect();
+    let height = rows.len();
+    if !(MIN_SIZE..=MAX_SIZE).contains(&height) {
+        return Err(format!(
+            "Expected {} to {} rows, found {}",
+            MIN_SIZE, MAX_SIZE, height
+        ));
+    }
+    let width = rows[0].chars().count();
+    if !(MIN_SIZE..=MAX_SIZE).contains(&width) {
+        return Err(format!(
+            "Expected {} to {} cells in a row, found {}",
+            MIN_SIZE, MAX_SIZE, width
+        ));
+    }
+
+    let cell_count = width * height;
+    let mut grid = Grid::solved(width, height);
+    let mut seen = vec![false; cell_count];
+
+    for (y, row) in rows.iter().enumerate() {
+        let cells: Vec<char> = row.chars().collect();
+        if cells.len() != width {
+            return Err(format!(
+                "Row {} should have {} cells: \"{}\"",
+                y + 1,
+                width,
+                row
+            ));
+        }
+
+        for (x, &cell) in cells.iter().enumerate() {
+            let piece = match cell.to_digit(36) {
+                _ if cell == '_' => -1,
+                Some(label) if label >= 1 && (label as usize) < cell_count => label as i8 - 1,
+                _ => return Err(format!("Unexpected '{}' in row {}", cell, y + 1)),
+            };
+
+            // The gap is tracked in the last slot
+            let slot = if piece == -1 {
+                cell_count - 1
+            } else {
+                piece as usize
+            };
+            if seen[slot] {
+                return Err(format!("'{}' appears more than once", cell));
+            }
+            seen[slot] = true;
+
+            grid[y][x] = piece;
+        }
+    }
+
+    if !Board::new(grid.clone()).is_solvable() {
+        return Err(format!("\"{}\" can't be solved", notation));
+    }
+
+    Ok(grid)
+}
@@ -0,0 +1,80 @@
+// A picture of the whole desktop, for --desktop. SFML can only read back
+// its own windows, so this goes through whatever screenshot tool the OS
+// comes with, which writes a PNG for the game to load.
+
+use std::{path::Path, process::Command};
+
+use log::debug;
+
+// The commands to try in order, each writing a PNG to `path`
+#[cfg(target_os = "macos")]
+fn commands(path: &Path) -> Vec<Command> {
+    let mut screencapture = Command::new("screencapture");
+    screencapture.arg("-x").arg(path);
+    vec![screencapture]
+}
+
+#[cfg(target_os = "windows")]
+fn commands(path: &Path) -> Vec<Command> {
+    // Quotes inside a PowerShell string are escaped by doubling them
+    let path = path.to_string_lossy().replace('\'', "''");
+    let mut powershell = Command::new("powershell");
+    powershell.args([
+        "-NoProfile",
+        "-Command",
+        &format!(
+            "Add-Type -AssemblyName System.Windows.Forms, System.Drawing; \
+             $screen = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+             $bitmap = New-Object System.Drawing.Bitmap $screen.Width, $screen.Height; \
+             [System.Drawing.Graphics]::FromImage($bitmap).CopyFromScreen($screen.Left, $screen.Top, 0, 0, $bitmap.Size); \
+             $bitmap.Save('{}')",
+            path
+        ),
+    ]);
+    vec![powershell]
+}
+
+// Wayland first, then the X11 tools
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn commands(path: &Path) -> Vec<Command> {
+    let tools: [(&str, &[&str]); 5] = [
+        ("grim", &[]),
+        ("gnome-screenshot", &["-f"]),
+        ("spectacle", &["-b", "-n", "-o"]),
+        ("import", &["-window", "root"]),
+        ("scrot", &["-o"]),
+    ];
+    tools
+        .into_iter()
+        .map(|(tool, args)| {
+            let mut command = Command::new(tool);
+            command.args(args).arg(path);
+            command
+        })
+        .collect()
+}
+
+pub fn capture(path: &Path) -> Result<(), String> {
+    let mut tried = Vec::new();
+    for mut command in commands(path) {
+        let tool = command.get_program().to_string_lossy().into_owned();
+        match command.output() {
+            Ok(output) if output.status.success() && path.exists() => {
+                debug!("Took a screenshot with {}", tool);
+                return Ok(());
+            }
+            Ok(output) => debug!(
+                "{} couldn't take a screenshot: {}",
+                tool,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(error) => debug!("Couldn't run {}: {}", tool, error),
+        }
+        tried.push(tool);
+    }
+
+    Err(format!(
+        "Couldn't take a screenshot of the desktop, tried {}",
+        tried.join(", ")
+    ))
+}
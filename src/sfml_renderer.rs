@@ -0,0 +1,296 @@
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use log::trace;
+
+use sfml::{
+    graphics::{
+        Color, Drawable, FloatRect, IntRect, RenderTarget, RenderTexture, RenderWindow, View,
+    },
+    system::Vector2,
+    window::{clipboard, mouse, Event, Style, VideoMode},
+};
+use slide_puzzle::{
+    renderer::{Renderer, WindowHandle},
+    FRAMERATE,
+};
+
+use crate::platform;
+
+// Opening every window at once makes a visible hitch on some systems, so
+// they open one at a time, at most one every OPEN_INTERVAL, each the first
+// time it's drawn once its turn comes. A fresh window grows from
+// OPEN_SCALE of its size to full size over SCALE_IN_TIME, around its middle.
+const OPEN_INTERVAL: Duration = Duration::from_millis(15);
+const SCALE_IN_TIME: Duration = Duration::from_millis(150);
+const OPEN_SCALE: f32 = 0.3;
+
+// When the latest window opened, and when the windows waiting now started
+// waiting, for the trace log
+static LAST_OPENED: Mutex<Option<Instant>> = Mutex::new(None);
+static WAITING_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+static WAITING: AtomicUsize = AtomicUsize::new(0);
+static OPENED: AtomicUsize = AtomicUsize::new(0);
+
+// The real windows, one per piece and side window. Until it opens, where
+// it's been asked to be and how big is kept for when it does.
+pub struct SfmlWindow {
+    window: Option<RenderWindow>,
+    title: String,
+    size: u32,
+    position: Vector2<i32>,
+    wants_focus: Cell<bool>,
+    opened_at: Option<Instant>,
+}
+
+impl SfmlWindow {
+    // Open the window if it's its turn
+    fn open(&mut self) {
+        let mut last_opened = LAST_OPENED.lock().unwrap();
+        if last_opened.is_some_and(|last_opened| last_opened.elapsed() < OPEN_INTERVAL) {
+            return;
+        }
+
+        let mut window = RenderWindow::new(
+            VideoMode::new(self.size, self.size, 32),
+            &self.title,
+            Style::NONE,
+            &Default::default(),
+        );
+        window.set_framerate_limit(FRAMERATE_LIMIT.load(Ordering::Relaxed));
+        platform::setup_window(&window);
+        if self.wants_focus.get() {
+            window.request_focus();
+        }
+
+        let now = Instant::now();
+        *last_opened = Some(now);
+        self.opened_at = Some(now);
+        self.window = Some(window);
+        self.scale_in();
+
+        OPENED.fetch_add(1, Ordering::Relaxed);
+        if WAITING.fetch_sub(1, Ordering::Relaxed) == 1 {
+            if let Some(since) = WAITING_SINCE.lock().unwrap().take() {
+                trace!(
+                    "Opened {} windows in {:?}",
+                    OPENED.swap(0, Ordering::Relaxed),
+                    since.elapsed()
+                );
+            }
+        }
+    }
+
+    // Size and place the window for how far it is through growing in
+    fn scale_in(&mut self) {
+        let Some(window) = &mut self.window else {
+            return;
+        };
+        let growing = self
+            .opened_at
+            .map_or(1.0, |opened_at| {
+                opened_at.elapsed().as_secs_f32() / SCALE_IN_TIME.as_secs_f32()
+            })
+            .min(1.0);
+        if growing >= 1.0 {
+            self.opened_at = None;
+        }
+
+        let scale = OPEN_SCALE + (1.0 - OPEN_SCALE) * growing;
+        let size = ((self.size as f32 * scale) as u32).max(1);
+        let inset = (self.size - size.min(self.size)) as i32 / 2;
+        window.set_size(Vector2::new(size, size));
+        window.set_position(self.position + Vector2::new(inset, inset));
+        // Keep drawing in pixels at full size, so the picture shrinks with
+        // the window rather than being cut off
+        window.set_view(&View::from_rect(FloatRect::new(
+            0.0,
+            0.0,
+            self.size as f32,
+            self.size as f32,
+        )));
+    }
+}
+
+// A window that never got to open isn't waiting any more. If it was the
+// last one, the wait is over, so the next window starts timing afresh.
+impl Drop for SfmlWindow {
+    fn drop(&mut self) {
+        if self.window.is_none() && WAITING.fetch_sub(1, Ordering::Relaxed) == 1 {
+            WAITING_SINCE.lock().unwrap().take();
+            OPENED.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl WindowHandle for SfmlWindow {
+    fn poll_event(&mut self) -> Option<Event> {
+        self.window.as_mut()?.poll_event()
+    }
+
+    fn set_position(&mut self, position: Vector2<i32>) {
+        self.position = position;
+        if self.opened_at.is_some() {
+            self.scale_in();
+        } else if let Some(window) = &mut self.window {
+            window.set_position(position);
+        }
+    }
+
+    fn set_size(&mut self, size: u32) {
+        self.size = size;
+        if self.window.is_some() {
+            self.scale_in();
+        }
+    }
+
+    fn clear(&mut self, color: Color) {
+        if let Some(window) = &mut self.window {
+            window.clear(color);
+        }
+    }
+
+    fn draw(&mut self, drawable: &dyn Drawable) {
+        if let Some(window) = &mut self.window {
+            window.draw(drawable);
+        }
+    }
+
+    fn display(&mut self) {
+        if self.window.is_none() {
+            self.open();
+        } else if self.opened_at.is_some() {
+            self.scale_in();
+        }
+        if let Some(window) = &mut self.window {
+            window.display();
+        }
+    }
+
+    fn mouse_position(&self) -> Vector2<i32> {
+        self.window
+            .as_ref()
+            .map_or(Vector2::new(0, 0), |window| window.mouse_position())
+    }
+
+    fn has_focus(&self) -> bool {
+        self.window
+            .as_ref()
+            .is_some_and(|window| window.has_focus())
+    }
+
+    fn request_focus(&self) {
+        match &self.window {
+            Some(window) => window.request_focus(),
+            None => self.wants_focus.set(true),
+        }
+    }
+
+    fn raise(&self) {
+        if let Some(window) = &self.window {
+            platform::raise(window);
+        }
+    }
+}
+
+// Frames a second every window is held to, FRAMERATE unless the config says
+// otherwise
+static FRAMERATE_LIMIT: AtomicU32 = AtomicU32::new(FRAMERATE);
+
+pub fn set_framerate(framerate: u32) {
+    FRAMERATE_LIMIT.store(framerate, Ordering::Relaxed);
+}
+
+// How long each frame gets at that rate
+pub fn frame_duration() -> Duration {
+    Duration::from_secs_f32(1.0 / FRAMERATE_LIMIT.load(Ordering::Relaxed) as f32)
+}
+
+pub struct SfmlRenderer;
+
+impl Renderer for SfmlRenderer {
+    type Window = SfmlWindow;
+
+    // The window opens later, see SfmlWindow
+    fn create_window(&mut self, size: u32, title: &str) -> SfmlWindow {
+        if WAITING.fetch_add(1, Ordering::Relaxed) == 0 {
+            *WAITING_SINCE.lock().unwrap() = Some(Instant::now());
+        }
+
+        SfmlWindow {
+            window: None,
+            title: title.to_string(),
+            size,
+            position: Vector2::new(0, 0),
+            wants_focus: Cell::new(false),
+            opened_at: None,
+        }
+    }
+
+    fn desktop_size(&self) -> Vector2<u32> {
+        let desktop = VideoMode::desktop_mode();
+        let scale = platform::display_scale();
+        Vector2::new(
+            (desktop.width as f32 / scale) as u32,
+            (desktop.height as f32 / scale) as u32,
+        )
+    }
+
+    fn desktop_mouse_position(&self) -> Vector2<i32> {
+        mouse::desktop_position()
+    }
+
+    fn monitors(&self) -> Vec<IntRect> {
+        platform::monitors().unwrap_or_else(|| {
+            let desktop = self.desktop_size();
+            vec![IntRect::new(0, 0, desktop.x as i32, desktop.y as i32)]
+        })
+    }
+
+    fn get_clipboard(&self) -> String {
+        clipboard::get_string()
+    }
+
+    fn set_clipboard(&mut self, text: &str) {
+        clipboard::set_string(text);
+    }
+
+    fn capture(&mut self, size: Vector2<u32>, drawables: &[&dyn Drawable]) -> Option<Vec<u8>> {
+        let mut canvas = RenderTexture::new(size.x, size.y)?;
+        canvas.clear(Color::TRANSPARENT);
+        for drawable in drawables {
+            canvas.draw(*drawable);
+        }
+        canvas.display();
+
+        let image = canvas.texture().copy_to_image()?;
+        Some(image.pixel_data().to_vec())
+    }
+
+    // SFML reads the gamepads afresh for every window it polls, so a hidden
+    // window still hears them. It opens straight away, there's nothing to see.
+    fn create_input_window(&mut self) -> Option<SfmlWindow> {
+        let mut window = RenderWindow::new(
+            VideoMode::new(1, 1, 32),
+            "Input",
+            Style::NONE,
+            &Default::default(),
+        );
+        window.set_visible(false);
+
+        Some(SfmlWindow {
+            window: Some(window),
+            title: "Input".to_string(),
+            size: 1,
+            position: Vector2::new(0, 0),
+            wants_focus: Cell::new(false),
+            opened_at: None,
+        })
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use crate::{locale, puzzle::Grid, rating};
+
+// Wordle-style summary of a solve, meant to be pasted into chat. The grid
+// shows the starting scramble: green pieces began in place, red ones didn't.
+// 3x3 scrambles are rated too, so solves of the same difficulty can be
+// compared.
+pub fn summary(start_grid: &Grid, moves: u32, elapsed: Duration) -> String {
+    let width = start_grid.width();
+    let mut text = format!(
+        "Sliding Puzzle {}x{}: {} {} in {}\n",
+        width,
+        start_grid.height(),
+        locale::current().format_count(moves as u64),
+        if moves == 1 { "move" } else { "moves" },
+        format_duration(elapsed)
+    );
+    if let Some(rating) = rating::rate(start_grid) {
+        text.push_str(&format!("Rated {}\n", rating::describe(&rating)));
+    }
+
+    for (y, row) in start_grid.iter().enumerate() {
+        for (x, &piece) in row.iter().enumerate() {
+            text.push(if piece == -1 {
+                '⬛'
+            } else if piece as usize == y * width + x {
+                '🟩'
+            } else {
+                '🟥'
+            });
+        }
+        text.push('\n');
+    }
+
+    text.trim_end().to_string()
+}
+
+// m:ss.t, in the player's locale
+pub fn format_duration(duration: Duration) -> String {
+    locale::current().format_duration(duration)
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use log::debug;
+use sfml::window::{mouse, Event, Key};
+
+use crate::profile::Profile;
+
+// Its presence in the config directory turns simple mode on for everyone.
+// It sits outside the profiles so picking another one can't get around it.
+const CONFIG_FILE: &str = "simple-mode";
+// How long a solved board stays up before the next one is dealt
+pub const NEXT_BOARD_DELAY: Duration = Duration::from_secs(3);
+
+// Simple mode is for kids and public displays: pieces can be slid and
+// nothing else, so there's no solver, no quitting and no changing settings
+pub fn enabled_by_config() -> bool {
+    let Some(path) = Profile::default()
+        .config_dir()
+        .map(|dir| dir.join(CONFIG_FILE))
+    else {
+        return false;
+    };
+
+    let enabled = path.exists();
+    if enabled {
+        debug!("Simple mode turned on by {}", path.display());
+    }
+    enabled
+}
+
+// Whether simple mode lets an event through. Slides by mouse, arrow keys,
+// keyboard cursor or gamepad are all it allows.
+pub fn allows(event: &Event) -> bool {
+    match *event {
+        Event::KeyPressed { code, .. } => matches!(
+            code,
+            Key::Up
+                | Key::Down
+                | Key::Left
+                | Key::Right
+                | Key::Tab
+                | Key::Enter
+                | Key::LShift
+                | Key::RShift
+                | Key::LControl
+                | Key::RControl
+        ),
+        Event::MouseButtonPressed { button, .. } => button == mouse::Button::Left,
+        _ => true,
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font, locale,
+    puzzle::{Grid, Move},
+    renderer::{Renderer, WindowHandle},
+    solver::{self, Progress},
+};
+
+const WINDOW_SIZE: u32 = 300;
+const LINE_HEIGHT: f32 = 28.0;
+// Quick searches finish before anyone could read a status window
+pub const STATUS_DELAY: Duration = Duration::from_millis(250);
+
+enum Update {
+    Progress(Progress),
+    Done(Option<Vec<Move>>),
+}
+
+// A solver search on its own thread, so a long one never freezes the board.
+// Dropping the job cancels the search.
+pub struct SolveJob {
+    // The board the search started from
+    pub grid: Grid,
+    pub pieces: Vec<i8>,
+    pub progress: Progress,
+    pub started_at: Instant,
+    receiver: Receiver<Update>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SolveJob {
+    pub fn start(grid: Grid, pieces: Vec<i8>) -> Self {
+        Self::spawn(grid, pieces, |grid, pieces, cancelled, report| {
+            solver::solve_pieces(grid, pieces, cancelled, report)
+        })
+    }
+
+    // Solve the whole board as few moves as possible. These searches don't
+    // report progress, but stop as soon as they're cancelled.
+    pub fn start_whole(grid: Grid) -> Self {
+        let pieces = (0..grid.piece_count() as i8).collect();
+        Self::spawn(grid, pieces, |grid, _, cancelled, _| {
+            solver::solve_any(grid, cancelled)
+        })
+    }
+
+    fn spawn(
+        grid: Grid,
+        pieces: Vec<i8>,
+        search: impl FnOnce(&Grid, &[i8], &AtomicBool, &mut dyn FnMut(Progress)) -> Option<Vec<Move>>
+            + Send
+            + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let thread_grid = grid.clone();
+        let thread_pieces = pieces.clone();
+        let thread_cancelled = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            let progress_sender = sender.clone();
+            let solution = search(
+                &thread_grid,
+                &thread_pieces,
+                &thread_cancelled,
+                &mut |progress| {
+                    let _ = progress_sender.send(Update::Progress(progress));
+                },
+            );
+            let _ = sender.send(Update::Done(solution));
+        });
+
+        Self {
+            grid,
+            pieces,
+            progress: Progress::default(),
+            started_at: Instant::now(),
+            receiver,
+            cancelled,
+        }
+    }
+
+    // Catches up on progress. Some once the search is over, holding the
+    // solution if there is one.
+    pub fn poll(&mut self) -> Option<Option<Vec<Move>>> {
+        while let Ok(update) = self.receiver.try_recv() {
+            match update {
+                Update::Progress(progress) => self.progress = progress,
+                Update::Done(solution) => return Some(solution),
+            }
+        }
+        None
+    }
+}
+
+impl Drop for SolveJob {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+// Shows a search's progress while it runs. Any key cancels it.
+pub struct SolveStatus<W: WindowHandle> {
+    window: W,
+}
+
+impl<W: WindowHandle> SolveStatus<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, position: Vector2<i32>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Solving");
+        window.set_position(position);
+        debug!("Search is taking a while, showing its progress");
+
+        Self { window }
+    }
+
+    // True when the player asked to cancel
+    pub fn update(&mut self) -> bool {
+        let mut cancel = false;
+        while let Some(event) = self.window.poll_event() {
+            match event {
+                Event::KeyPressed { code, .. } if !is_modifier(code) => cancel = true,
+                Event::Closed => cancel = true,
+                _ => {}
+            }
+        }
+        if cancel {
+            info!("Search cancelled");
+        }
+        cancel
+    }
+
+    pub fn render(&mut self, job: &SolveJob) {
+        let locale = locale::current();
+        self.window.clear(Color::BLACK);
+
+        self.draw_text("Solving...", 0, Color::WHITE);
+        let pieces: Vec<String> = job
+            .pieces
+            .iter()
+            .map(|piece| (piece + 1).to_string())
+            .collect();
+        self.draw_text(&format!("Placing {}", pieces.join(" ")), 1, Color::WHITE);
+        self.draw_text(&format!("Depth {}", job.progress.bound), 2, Color::WHITE);
+        self.draw_text(
+            &format!(
+                "{} boards",
+                locale.format_count(job.progress.expanded as u64)
+            ),
+            3,
+            Color::WHITE,
+        );
+        self.draw_text(
+            &format!(
+                "Best: {} of {} placed",
+                job.progress.best_placed,
+                job.pieces.len()
+            ),
+            4,
+            Color::WHITE,
+        );
+        self.draw_text("Any key to cancel", 6, Color::WHITE);
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, line: u32, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(Vector2::new(5.0, line as f32 * LINE_HEIGHT + 5.0));
+        self.window.draw(&text);
+    }
+}
+
+// Whether a key only modifies other keys, and so shouldn't cancel anything
+pub fn is_modifier(key: Key) -> bool {
+    matches!(
+        key,
+        Key::LShift
+            | Key::RShift
+            | Key::LControl
+            | Key::RControl
+            | Key::LAlt
+            | Key::RAlt
+            | Key::LSystem
+            | Key::RSystem
+    )
+}
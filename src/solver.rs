@@ -0,0 +1,550 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use log::debug;
+use once_cell::sync::Lazy;
+
+use crate::{
+    board::Board,
+    layout::{neighbor_table, NeighborTable},
+    pattern_db::{self, PatternDatabase},
+    puzzle::{find_in_grid, Grid, Move, MAX_SIZE},
+};
+
+// How many boards a search looks at between progress reports
+const PROGRESS_INTERVAL: usize = 1000;
+
+// The fewest moves needed to solve every solvable 3x3 board. There are only
+// 181440 of them, so searching outwards from the solved board once is cheap.
+// Bigger boards have far too many to list.
+static DISTANCES: Lazy<HashMap<u128, u8>> = Lazy::new(|| {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    let solved = Grid::solved(3, 3);
+    distances.insert(pack(&solved), 0);
+    queue.push_back(solved);
+
+    while let Some(grid) = queue.pop_front() {
+        let distance = distances[&pack(&grid)];
+
+        for next in successors(&grid) {
+            if let Entry::Vacant(entry) = distances.entry(pack(&next)) {
+                entry.insert(distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    debug!("Solver table has {} boards", distances.len());
+    distances
+});
+
+// How many solvable 3x3 boards are each number of moves from solved,
+// nearest first
+static DISTANCE_COUNTS: Lazy<Vec<usize>> = Lazy::new(|| {
+    let mut counts = Vec::new();
+    for &distance in DISTANCES.values() {
+        let distance = distance as usize;
+        if counts.len() <= distance {
+            counts.resize(distance + 1, 0);
+        }
+        counts[distance] += 1;
+    }
+    counts
+});
+
+// Shortest solutions worked out ahead of time by warm_up, by packed board
+static SOLUTIONS: Lazy<Mutex<HashMap<u128, Vec<Move>>>> = Lazy::new(Default::default);
+
+// 5 bits per cell, with the gap stored as 31, which is room for a 5x5 board
+pub fn pack(grid: &Grid) -> u128 {
+    grid.cells()
+        .fold(0, |packed, piece| packed << 5 | (piece as u128 & 0x1f))
+}
+
+// Every board one slide away, paired with the move that gets there
+pub fn moves(grid: &Grid) -> Vec<(Move, Grid)> {
+    let width = grid.width();
+    let blank = find_in_grid(grid, -1);
+    let blank_cell = blank.y as usize * width + blank.x as usize;
+
+    neighbor_table(width, grid.height())
+        .neighbors(blank_cell)
+        .iter()
+        .map(|&cell| {
+            let mut next = grid.clone();
+            let piece = next.cell(cell);
+            next.set_cell(blank_cell, piece);
+            next.set_cell(cell, -1);
+
+            (Move::new(piece), next)
+        })
+        .collect()
+}
+
+fn successors(grid: &Grid) -> impl Iterator<Item = Grid> {
+    moves(grid).into_iter().map(|(_, next)| next)
+}
+
+// None if the board can't be solved, or isn't 3x3
+pub fn distance(grid: &Grid) -> Option<u8> {
+    if (grid.width(), grid.height()) != (3, 3) {
+        return None;
+    }
+
+    DISTANCES.get(&pack(grid)).copied()
+}
+
+// How many solvable 3x3 boards are each number of moves from solved, so
+// the last entry is the furthest any board can be
+pub fn distance_counts() -> &'static [usize] {
+    &DISTANCE_COUNTS
+}
+
+// The next move on a shortest solution
+pub fn next_move(grid: &Grid) -> Option<Move> {
+    let current = distance(grid)?;
+
+    moves(grid)
+        .into_iter()
+        .find(|(_, next)| distance(next).is_some_and(|next| next < current))
+        .map(|(step, _)| step)
+}
+
+// The moves, in order, that solve the board in as few as possible
+pub fn solve(grid: &Grid) -> Option<Vec<Move>> {
+    if let Some(solution) = SOLUTIONS.lock().unwrap().get(&pack(grid)) {
+        return Some(solution.clone());
+    }
+    distance(grid)?;
+
+    let mut grid = grid.clone();
+    let mut solution = Vec::new();
+    while let Some(step) = next_move(&grid) {
+        step.apply(&mut grid);
+        solution.push(step);
+    }
+
+    Some(solution)
+}
+
+// What the game solves whole boards with, through World::solve and the
+// solve job: solve() for 3x3 boards, otherwise ida_star() helped by the
+// pattern database, which can take a while on a well shuffled board. Gives
+// up with None once `cancelled` is set.
+pub fn solve_any(grid: &Grid, cancelled: &AtomicBool) -> Option<Vec<Move>> {
+    solve(grid).or_else(|| ida_star_until(grid, Heuristic::PatternDatabase, cancelled))
+}
+
+// How IDA* guesses the moves left from a board. None of them ever guess
+// too many, so the solutions found are always the shortest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    // How far every piece is from home, counting rows and columns
+    Manhattan,
+    // Manhattan, plus two moves for every piece that has to step out of its
+    // row or column to let another past, when both belong in that line.
+    // Much closer to the truth, which is what makes 4x4 boards feasible.
+    LinearConflict,
+    // Looked up in the pattern database (see pattern_db.rs), which is
+    // closer still and what the hardest 4x4 boards need. Other boards, and
+    // 4x4 ones before the database is ready, fall back to LinearConflict.
+    PatternDatabase,
+}
+
+// The moves, in order, that solve a board of any size in as few as
+// possible, found with IDA*: depth first searches that give up on
+// any path `heuristic` says can't finish within a bound, raising the bound
+// until one does. It keeps nothing but the current path, so it can go as
+// deep as 4x4 boards need. None if the board can't be solved.
+pub fn ida_star(grid: &Grid, heuristic: Heuristic) -> Option<Vec<Move>> {
+    ida_star_until(grid, heuristic, &AtomicBool::new(false))
+}
+
+// ida_star() that gives up with None once `cancelled` is set
+pub fn ida_star_until(
+    grid: &Grid,
+    heuristic: Heuristic,
+    cancelled: &AtomicBool,
+) -> Option<Vec<Move>> {
+    match heuristic {
+        Heuristic::PatternDatabase => match pattern_db::get() {
+            Some(database) if (grid.width(), grid.height()) == (4, 4) => {
+                run_ida_star(grid, Heuristic::PatternDatabase, Some(database), cancelled)
+            }
+            _ => {
+                debug!("No pattern database for this board, using linear conflict");
+                run_ida_star(grid, Heuristic::LinearConflict, None, cancelled)
+            }
+        },
+        heuristic => run_ida_star(grid, heuristic, None, cancelled),
+    }
+}
+
+// ida_star() guided by `database`, for a 4x4 board, so tests can use a
+// database of their own
+#[cfg(test)]
+pub(crate) fn ida_star_with_patterns(grid: &Grid, database: &PatternDatabase) -> Option<Vec<Move>> {
+    run_ida_star(
+        grid,
+        Heuristic::PatternDatabase,
+        Some(database),
+        &AtomicBool::new(false),
+    )
+}
+
+fn run_ida_star(
+    grid: &Grid,
+    heuristic: Heuristic,
+    patterns: Option<&PatternDatabase>,
+    cancelled: &AtomicBool,
+) -> Option<Vec<Move>> {
+    if !Board::new(grid.clone()).is_solvable() {
+        return None;
+    }
+
+    let started = Instant::now();
+    let mut search = IdaSearch::new(grid, heuristic, patterns, cancelled);
+    let mut bound = search.estimate();
+    loop {
+        if search.cancelled.load(Ordering::Relaxed) {
+            debug!("IDA* cancelled after {} boards", search.expanded);
+            return None;
+        }
+        match search.deeper(0, bound, None) {
+            Ok(()) => {
+                debug!(
+                    "IDA* solved in {} moves, {} boards expanded in {:?}",
+                    search.path.len(),
+                    search.expanded,
+                    started.elapsed()
+                );
+                return Some(search.path);
+            }
+            Err(next_bound) => {
+                debug!("IDA* found nothing within {} moves", bound);
+                bound = next_bound;
+            }
+        }
+    }
+}
+
+// The state of an IDA* search. The board is kept as one flat list of cells,
+// with each part of the estimate kept up to date as pieces slide rather than
+// worked out afresh for every board. Nothing is allocated per board either:
+// the one board slides back and forth in place, and the path is a single Vec
+// that only grows as deep as the search goes. Packing the cells 4 bits each
+// into a u64 was tried and came out slower, see benches/solver.rs: the time
+// goes on the estimate, not on reading cells.
+struct IdaSearch<'a> {
+    cells: Vec<i8>,
+    width: usize,
+    height: usize,
+    neighbors: &'static NeighborTable,
+    gap: usize,
+    heuristic: Heuristic,
+    manhattan: u32,
+    // Pieces that have to step out of each row and each column, see
+    // line_conflicts
+    row_conflicts: Vec<u32>,
+    column_conflicts: Vec<u32>,
+    // Each group's part of the pattern database's estimate
+    patterns: Option<&'a PatternDatabase>,
+    pattern_estimates: Vec<u32>,
+    path: Vec<Move>,
+    expanded: usize,
+    // Checked at every board, so a search nobody wants stops straight away
+    cancelled: &'a AtomicBool,
+}
+
+impl<'a> IdaSearch<'a> {
+    fn new(
+        grid: &Grid,
+        heuristic: Heuristic,
+        patterns: Option<&'a PatternDatabase>,
+        cancelled: &'a AtomicBool,
+    ) -> Self {
+        let cells: Vec<i8> = grid.cells().collect();
+        let (width, height) = (grid.width(), grid.height());
+        let mut search = Self {
+            gap: cells.iter().position(|&piece| piece == -1).unwrap_or(0),
+            cells,
+            width,
+            height,
+            neighbors: neighbor_table(width, height),
+            heuristic,
+            manhattan: 0,
+            row_conflicts: vec![0; height],
+            column_conflicts: vec![0; width],
+            patterns,
+            pattern_estimates: Vec::new(),
+            path: Vec::new(),
+            expanded: 0,
+            cancelled,
+        };
+
+        search.manhattan = (0..search.cells.len())
+            .map(|cell| search.piece_distance(cell))
+            .sum();
+        if heuristic == Heuristic::LinearConflict {
+            for row in 0..height {
+                search.row_conflicts[row] = search.line_conflicts(row, false);
+            }
+            for column in 0..width {
+                search.column_conflicts[column] = search.line_conflicts(column, true);
+            }
+        }
+        if let Some(patterns) = patterns {
+            search.pattern_estimates = (0..patterns.group_count())
+                .map(|group| patterns.group_estimate(group, &search.cells))
+                .collect();
+        }
+        search
+    }
+
+    fn estimate(&self) -> u32 {
+        let conflicts: u32 =
+            self.row_conflicts.iter().sum::<u32>() + self.column_conflicts.iter().sum::<u32>();
+        let patterns: u32 = self.pattern_estimates.iter().sum();
+        (self.manhattan + 2 * conflicts).max(patterns)
+    }
+
+    // How far the piece in `cell` is from home, 0 for the gap
+    fn piece_distance(&self, cell: usize) -> u32 {
+        let piece = self.cells[cell];
+        if piece == -1 {
+            return 0;
+        }
+        let (home, cell) = (piece as usize, cell);
+        ((home % self.width).abs_diff(cell % self.width)
+            + (home / self.width).abs_diff(cell / self.width)) as u32
+    }
+
+    // The fewest pieces that have to leave a row (or a column) so the rest of
+    // the pieces that belong in it are in order. Each of them costs two moves
+    // on top of its distance: one out of the way and one back.
+    fn line_conflicts(&self, line: usize, column: bool) -> u32 {
+        let (start, step, length) = if column {
+            (line, self.width, self.height)
+        } else {
+            (line * self.width, 1, self.width)
+        };
+        let belongs = |home: usize| {
+            if column {
+                home % self.width == line
+            } else {
+                home / self.width == line
+            }
+        };
+
+        // Where along the line each piece that belongs in it wants to be, in
+        // the order they're in now. Those that can stay are the longest run
+        // of them already in order, kept as the smallest goal each length of
+        // run can end on.
+        let mut belonging = 0;
+        let mut in_order = [0; MAX_SIZE];
+        let mut longest = 0;
+        for i in 0..length {
+            let piece = self.cells[start + i * step];
+            if piece == -1 || !belongs(piece as usize) {
+                continue;
+            }
+            let home = piece as usize;
+            let goal = if column {
+                home / self.width
+            } else {
+                home % self.width
+            };
+
+            belonging += 1;
+            let run = in_order[..longest].partition_point(|&end| end < goal);
+            in_order[run] = goal;
+            longest = longest.max(run + 1);
+        }
+        (belonging - longest) as u32
+    }
+
+    // Slide the piece in `cell` into the gap, keeping the estimate up to date
+    fn slide(&mut self, cell: usize) {
+        let before = self.piece_distance(cell);
+        self.cells.swap(cell, self.gap);
+        let (from, to) = (cell, self.gap);
+        self.gap = from;
+        self.manhattan = self.manhattan - before + self.piece_distance(to);
+
+        if let Some(patterns) = self.patterns {
+            if let Some(group) = patterns.group_of(self.cells[to]) {
+                self.pattern_estimates[group] = patterns.group_estimate(group, &self.cells);
+            }
+        }
+
+        if self.heuristic == Heuristic::LinearConflict {
+            // Sliding across keeps every row's pieces in the same order but
+            // changes what's in two columns, and sliding up or down the same
+            // for rows
+            if from / self.width == to / self.width {
+                for column in [from % self.width, to % self.width] {
+                    self.column_conflicts[column] = self.line_conflicts(column, true);
+                }
+            } else {
+                for row in [from / self.width, to / self.width] {
+                    self.row_conflicts[row] = self.line_conflicts(row, false);
+                }
+            }
+        }
+    }
+
+    // Look for a solution within `bound` moves from here, `moves_so_far`
+    // in. Otherwise the smallest bound that would let the search see
+    // further, or u32::MAX once it's cancelled.
+    fn deeper(
+        &mut self,
+        moves_so_far: u32,
+        bound: u32,
+        came_from: Option<usize>,
+    ) -> Result<(), u32> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(u32::MAX);
+        }
+        let estimate = self.estimate();
+        if moves_so_far + estimate > bound {
+            return Err(moves_so_far + estimate);
+        }
+        if estimate == 0 {
+            return Ok(());
+        }
+        self.expanded += 1;
+
+        let mut next_bound = u32::MAX;
+        let gap = self.gap;
+        for &cell in self.neighbors.neighbors(gap) {
+            // Sliding the last piece straight back undoes it
+            if Some(cell) == came_from {
+                continue;
+            }
+
+            let piece = self.cells[cell];
+            self.slide(cell);
+            self.path.push(Move::new(piece));
+            match self.deeper(moves_so_far + 1, bound, Some(gap)) {
+                Ok(()) => return Ok(()),
+                Err(bound) => next_bound = next_bound.min(bound),
+            }
+            self.path.pop();
+            self.slide(gap);
+        }
+
+        Err(next_bound)
+    }
+}
+
+// Build the distance table and solve `grid` on a background thread, so
+// hints and analysis for a board known from the start, like a seeded one,
+// don't stall the first time they're asked for
+pub fn warm_up(grid: Grid) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let started = Instant::now();
+        Lazy::force(&DISTANCES);
+        match solve(&grid) {
+            Some(solution) => {
+                debug!(
+                    "Solved the starting board in {} moves ahead of time, took {:?}",
+                    solution.len(),
+                    started.elapsed()
+                );
+                SOLUTIONS.lock().unwrap().insert(pack(&grid), solution);
+            }
+            None => debug!("The starting board can't be solved ahead of time"),
+        }
+    })
+}
+
+// How far a search has got
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Progress {
+    // Every board this many moves away or fewer has been looked at
+    pub bound: u32,
+    pub expanded: usize,
+    // The most of the wanted pieces any board so far had in place
+    pub best_placed: usize,
+}
+
+// The moves, in order, that put just `pieces` in place as quickly as
+// possible. Everything else is free to end up anywhere. `report` hears how
+// the search is going as it goes deeper and every so often in between, and
+// it gives up with None once `cancelled` is set.
+pub fn solve_pieces(
+    grid: &Grid,
+    pieces: &[i8],
+    cancelled: &AtomicBool,
+    mut report: impl FnMut(Progress),
+) -> Option<Vec<Move>> {
+    // Boards that only differ in the pieces we don't care about count as one
+    let key = |grid: &Grid| {
+        let mut masked = grid.clone();
+        for piece in masked.iter_mut().flatten() {
+            if *piece != -1 && !pieces.contains(piece) {
+                *piece = 30;
+            }
+        }
+        pack(&masked)
+    };
+    let placed = |grid: &Grid| {
+        pieces
+            .iter()
+            .filter(|&&piece| grid.cell(piece as usize) == piece)
+            .count()
+    };
+
+    // Each board found, with the board and piece slid to reach it
+    let mut came_from: HashMap<u128, Option<(u128, Move)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    came_from.insert(key(grid), None);
+    queue.push_back((grid.clone(), 0));
+    let mut progress = Progress::default();
+
+    while let Some((current, depth)) = queue.pop_front() {
+        if cancelled.load(Ordering::Relaxed) {
+            debug!("Search cancelled after {} boards", progress.expanded);
+            return None;
+        }
+
+        let current_key = key(&current);
+        progress.expanded += 1;
+        let current_placed = placed(&current);
+        progress.best_placed = progress.best_placed.max(current_placed);
+        if depth > progress.bound || progress.expanded.is_multiple_of(PROGRESS_INTERVAL) {
+            progress.bound = progress.bound.max(depth);
+            report(progress);
+        }
+
+        if current_placed == pieces.len() {
+            let mut solution = Vec::new();
+            let mut step = came_from[&current_key];
+            while let Some((previous_key, slid)) = step {
+                solution.push(slid);
+                step = came_from[&previous_key];
+            }
+            solution.reverse();
+
+            return Some(solution);
+        }
+
+        for (slid, next) in moves(&current) {
+            if let Entry::Vacant(entry) = came_from.entry(key(&next)) {
+                entry.insert(Some((current_key, slid)));
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+
+    None
+}
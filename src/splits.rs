@@ -0,0 +1,216 @@
+use std::{path::PathBuf, time::Duration};
+
+use log::{debug, info, warn};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    font, io_worker,
+    puzzle::Grid,
+    renderer::{Renderer, WindowHandle},
+    saves::{self, Migration},
+    share::format_duration,
+};
+
+// Each phase is done once every cell before the given one holds its piece,
+// so the rows are solved top to bottom
+pub const PHASES: [(&str, usize); 3] = [("Top row", 3), ("Middle row", 6), ("Solved", 8)];
+
+// Changes to the personal best file's format, oldest first
+const MIGRATIONS: &[Migration] = &[];
+
+pub const WINDOW_SIZE: u32 = 200;
+const LINE_HEIGHT: f32 = 28.0;
+
+// Split times for the current solve, compared against the best solve so far
+#[derive(Default)]
+pub struct Splits {
+    pub times: Vec<Duration>,
+    pub best: Option<Vec<Duration>>,
+    // Where personal bests are kept, if they're kept at all
+    pub path: Option<PathBuf>,
+}
+
+impl Splits {
+    pub fn new() -> Self {
+        Self {
+            times: Vec::new(),
+            best: None,
+            path: None,
+        }
+    }
+
+    pub fn load(path: PathBuf) -> Self {
+        let best = match saves::read_versioned(&path, MIGRATIONS) {
+            Ok(text) => parse_best(&text),
+            Err(error) => {
+                debug!("No personal best splits at {}: {}", path.display(), error);
+                None
+            }
+        };
+
+        Self {
+            times: Vec::new(),
+            best,
+            path: Some(path),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        // A new best only becomes the one to beat from the next solve on
+        if self.is_best() {
+            self.best = Some(self.times.clone());
+        }
+        self.times.clear();
+    }
+
+    // Record a split for every phase done since the last update
+    pub fn update(&mut self, grid: &Grid, elapsed: Duration) {
+        let recorded = self.times.len();
+
+        while let Some(&(name, cells)) = PHASES.get(self.times.len()) {
+            let done = grid
+                .cells()
+                .take(cells)
+                .enumerate()
+                .all(|(cell, piece)| piece as usize == cell);
+            if !done {
+                return;
+            }
+
+            self.times.push(elapsed);
+            let index = self.times.len() - 1;
+            match self.delta(index) {
+                Some(delta) => info!("Split {}: {} ({})", name, format_duration(elapsed), delta),
+                None => info!("Split {}: {}", name, format_duration(elapsed)),
+            }
+        }
+
+        // The last phase was only just done
+        if recorded < PHASES.len() {
+            self.save_if_best();
+        }
+    }
+
+    // How far ahead (-) or behind (+) the personal best a split is
+    pub fn delta(&self, index: usize) -> Option<String> {
+        let time = *self.times.get(index)?;
+        let best = *self.best.as_ref()?.get(index)?;
+
+        Some(if time <= best {
+            format!("-{}", format_duration(best - time))
+        } else {
+            format!("+{}", format_duration(time - best))
+        })
+    }
+
+    // Whether every phase is done, faster than the personal best
+    fn is_best(&self) -> bool {
+        let Some(&total) = self.times.get(PHASES.len() - 1) else {
+            return false;
+        };
+
+        match &self.best {
+            Some(best) => best.last().is_none_or(|&best_total| total < best_total),
+            None => true,
+        }
+    }
+
+    fn save_if_best(&self) {
+        if !self.is_best() {
+            return;
+        }
+        info!(
+            "New personal best: {}",
+            format_duration(self.times[PHASES.len() - 1])
+        );
+
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let text: String = self
+            .times
+            .iter()
+            .map(|time| format!("{}\n", time.as_millis()))
+            .collect();
+        io_worker::spawn(move || {
+            if let Err(error) = saves::write_versioned(&path, &text, MIGRATIONS) {
+                warn!("Couldn't save splits to {}: {}", path.display(), error);
+            }
+        });
+    }
+}
+
+// One split per line, in milliseconds
+fn parse_best(text: &str) -> Option<Vec<Duration>> {
+    let best: Option<Vec<Duration>> = text
+        .lines()
+        .map(|line| line.trim().parse().ok().map(Duration::from_millis))
+        .collect();
+
+    best.filter(|best| best.len() == PHASES.len())
+}
+
+// Shows the splits live, like a speedrun timer
+pub struct SplitsWindow<W: WindowHandle> {
+    window: W,
+}
+
+impl<W: WindowHandle> SplitsWindow<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, position: Vector2<i32>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Splits");
+        window.set_position(position);
+
+        Self { window }
+    }
+
+    pub fn update(&mut self) {
+        // Nothing to interact with, but events still need draining
+        while self.window.poll_event().is_some() {}
+    }
+
+    pub fn render(&mut self, splits: &Splits, elapsed: Duration) {
+        self.window.clear(Color::BLACK);
+
+        for (index, (name, _)) in PHASES.iter().enumerate() {
+            let y = index as f32 * LINE_HEIGHT * 2.0 + 5.0;
+            self.draw_text(name, Vector2::new(5.0, y), Color::WHITE);
+
+            let Some(time) = splits.times.get(index) else {
+                continue;
+            };
+            self.draw_text(
+                &format_duration(*time),
+                Vector2::new(5.0, y + LINE_HEIGHT),
+                Color::WHITE,
+            );
+
+            if let Some(delta) = splits.delta(index) {
+                let color = if delta.starts_with('-') {
+                    Color::rgb(0, 200, 0)
+                } else {
+                    Color::rgb(200, 0, 0)
+                };
+                self.draw_text(&delta, Vector2::new(110.0, y + LINE_HEIGHT), color);
+            }
+        }
+
+        // The running clock sits under the splits
+        self.draw_text(
+            &format_duration(elapsed),
+            Vector2::new(5.0, LINE_HEIGHT * 6.0 + 5.0),
+            Color::WHITE,
+        );
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, position: Vector2<f32>, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_fill_color(color);
+        text.set_position(position);
+        self.window.draw(&text);
+    }
+}
@@ -0,0 +1,258 @@
+// Every solved game, kept between runs so `--stats` can say how solving's
+// going over time. The file has a JSON object a line, e.g.
+//
+//   {"width":3,"height":3,"moves":24,"time_ms":41250,"hints":0,"seed":1234,"mode":"classic","rating":57}
+//
+// where "rating" is the scramble's difficulty score (see rating.rs), only
+// there for 3x3 games.
+//
+// and is saved like any other (see saves.rs), so a crash mid-write can't
+// lose earlier games.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use log::{debug, warn};
+
+use crate::{
+    io_worker,
+    locale::Locale,
+    rating::Difficulty,
+    saves::{self, Migration},
+};
+
+pub const FILE_NAME: &str = "stats.jsonl";
+
+// Changes to the stats file's format, oldest first
+const MIGRATIONS: &[Migration] = &[];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub width: usize,
+    pub height: usize,
+    pub moves: u32,
+    pub time: Duration,
+    pub hints: u32,
+    // The run's seed, so the board can be dealt again
+    pub seed: u64,
+    pub mode: String,
+    // The scramble's difficulty score, for boards that can be rated
+    pub rating: Option<u32>,
+}
+
+impl GameRecord {
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "width": self.width,
+            "height": self.height,
+            "moves": self.moves,
+            "time_ms": self.time.as_millis() as u64,
+            "hints": self.hints,
+            "seed": self.seed,
+            "mode": self.mode,
+            "rating": self.rating,
+        })
+        .to_string()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|error| format!("Bad game JSON: {}", error))?;
+        let number = |name: &str| {
+            value
+                .get(name)
+                .and_then(|field| field.as_u64())
+                .ok_or(format!("Game has no {}", name))
+        };
+
+        Ok(Self {
+            width: number("width")? as usize,
+            height: number("height")? as usize,
+            moves: number("moves")? as u32,
+            time: Duration::from_millis(number("time_ms")?),
+            hints: number("hints")? as u32,
+            seed: number("seed")?,
+            // Older lines may not say, and every game was classic then
+            mode: value
+                .get("mode")
+                .and_then(|mode| mode.as_str())
+                .unwrap_or("classic")
+                .to_string(),
+            // Nor did they keep a rating
+            rating: value
+                .get("rating")
+                .and_then(|rating| rating.as_u64())
+                .map(|rating| rating as u32),
+        })
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.rating.map(Difficulty::from_score)
+    }
+}
+
+// Add `game` to the end of the file at `path`, off the main thread. The
+// file's read on the I/O thread too, so games recorded one after another
+// all make it in.
+pub fn record(path: PathBuf, game: &GameRecord) {
+    let line = game.to_json();
+    io_worker::spawn(move || {
+        let mut text = match read(&path) {
+            Ok(text) => text,
+            // Saving over a file that couldn't be read would lose every game in it
+            Err(error) => {
+                warn!("Couldn't add to the stats at {}: {}", path.display(), error);
+                return;
+            }
+        };
+        text += &line;
+        text.push('\n');
+        if let Err(error) = saves::write_versioned(&path, &text, MIGRATIONS) {
+            warn!("Couldn't save stats to {}: {}", path.display(), error);
+        }
+    });
+}
+
+// Every game kept at `path`, or none if there's no file yet
+pub fn load(path: &Path) -> Result<Vec<GameRecord>, String> {
+    read(path)
+        .map(|text| parse(&text))
+        .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    match saves::read_versioned(path, MIGRATIONS) {
+        Ok(text) => Ok(text),
+        // The backup would have been read if there was one
+        Err(_) if !path.exists() => Ok(String::new()),
+        Err(error) => Err(error),
+    }
+}
+
+// Lines that can't be read are skipped rather than losing every other
+// game, e.g. a file from before stats were saved safely can end in half a
+// line from a run that was killed
+pub fn parse(text: &str) -> Vec<GameRecord> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(line_number, line)| match GameRecord::from_json(line) {
+            Ok(game) => Some(game),
+            Err(error) => {
+                debug!("Skipped stats line {}: {}", line_number + 1, error);
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub games: u32,
+    pub average_time: Duration,
+    // Only solves done without hints count, as for best times
+    pub fastest: Option<Duration>,
+    pub average_moves: f32,
+    pub fewest_moves: u32,
+    pub hints: u32,
+    // How many of the games were rated each difficulty, easy to expert
+    pub difficulties: [u32; 4],
+}
+
+const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty::Easy,
+    Difficulty::Medium,
+    Difficulty::Hard,
+    Difficulty::Expert,
+];
+
+// How `games` went on each board size
+pub fn summarize(games: &[GameRecord]) -> BTreeMap<(usize, usize), Summary> {
+    let mut by_size: BTreeMap<(usize, usize), Vec<&GameRecord>> = BTreeMap::new();
+    for game in games {
+        by_size
+            .entry((game.width, game.height))
+            .or_default()
+            .push(game);
+    }
+
+    by_size
+        .into_iter()
+        .map(|(size, games)| {
+            let count = games.len() as u32;
+            let total_time: Duration = games.iter().map(|game| game.time).sum();
+            let total_moves: u64 = games.iter().map(|game| game.moves as u64).sum();
+            let summary = Summary {
+                games: count,
+                average_time: total_time / count,
+                fastest: games
+                    .iter()
+                    .filter(|game| game.hints == 0)
+                    .map(|game| game.time)
+                    .min(),
+                average_moves: total_moves as f32 / count as f32,
+                fewest_moves: games.iter().map(|game| game.moves).min().unwrap_or(0),
+                hints: games.iter().map(|game| game.hints).sum(),
+                difficulties: DIFFICULTIES.map(|difficulty| {
+                    games
+                        .iter()
+                        .filter(|game| game.difficulty() == Some(difficulty))
+                        .count() as u32
+                }),
+            };
+            (size, summary)
+        })
+        .collect()
+}
+
+// What `--stats` prints, with numbers and times written the way `locale` does
+pub fn report(games: &[GameRecord], locale: &Locale) -> String {
+    if games.is_empty() {
+        return "No games solved yet\n".to_string();
+    }
+
+    let mut report = format!("{} games solved\n", locale.format_count(games.len() as u64));
+    for ((width, height), summary) in summarize(games) {
+        report += &format!(
+            "{}x{}: {} games\n",
+            width,
+            height,
+            locale.format_count(summary.games as u64)
+        );
+        report += &format!(
+            "  Average time {}, fastest {}\n",
+            locale.format_duration(summary.average_time),
+            summary.fastest.map_or("-".to_string(), |fastest| {
+                locale.format_duration(fastest)
+            })
+        );
+        report += &format!(
+            "  Average moves {}, fewest {}\n",
+            locale.format_decimal(summary.average_moves, 1),
+            locale.format_count(summary.fewest_moves as u64)
+        );
+        report += &format!(
+            "  Hints used {}\n",
+            locale.format_count(summary.hints as u64)
+        );
+        let rated: Vec<String> = DIFFICULTIES
+            .iter()
+            .zip(summary.difficulties)
+            .filter(|&(_, count)| count > 0)
+            .map(|(difficulty, count)| {
+                format!(
+                    "{} {}",
+                    locale.format_count(count as u64),
+                    difficulty.name()
+                )
+            })
+            .collect();
+        if !rated.is_empty() {
+            report += &format!("  Rated {}\n", rated.join(", "));
+        }
+    }
+    report
+}
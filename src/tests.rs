@@ -0,0 +1,3115 @@
+use std::{
+    cell::RefCell,
+    path::Path,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+use sfml::{
+    graphics::{Color, IntRect, Texture},
+    system::Vector2,
+    window::{mouse, Event, Key},
+};
+
+use crate::{
+    arcade::PowerUp,
+    board::Board,
+    bookmarks::{Bookmark, Bookmarks, FavouritesPicker},
+    challenge::{self, Rules},
+    chaos::Chaos,
+    config::{self, Config},
+    demo::Demo,
+    descent::Descent,
+    drill::{Drill, DrillKind},
+    exhibition::{self, Exhibition},
+    fake_renderer::FakeRenderer,
+    gamepad::{Mapping, Remapper},
+    glyphs, heatmap,
+    hooks::GameState,
+    io_worker,
+    layout::{
+        fit_piece_size, neighbor_table, Layout, LayoutPreferences, LayoutPreset, MIN_PIECE_SIZE,
+    },
+    locale::Locale,
+    logging,
+    metrics::FrameMetrics,
+    modes::{self, Gravity, TimeAttack},
+    move_counter,
+    overlay::Overlay,
+    packs,
+    palette::{self, Command},
+    parity::{self, Quiz},
+    pattern_db::PatternDatabase,
+    patterns,
+    profile::{Profile, ProfilePicker},
+    puzzle::{slide_piece, Grid, Move},
+    random,
+    rating::{self, Difficulty},
+    relay::Relay,
+    replay::{Replay, ReplayMove},
+    resize::ResizeHandle,
+    resources, saves, scramble, shuffle_grid, simple,
+    solve_job::{SolveJob, SolveStatus},
+    solver::{self, Heuristic},
+    splits::Splits,
+    stats::{self, GameRecord},
+    theme::{PieceShape, Theme, ThemeWatcher},
+    tiles,
+    timer::{self, BestTimes},
+    updates::{self, UpdateNotice},
+    Emphasis, PieceState, World, CELEBRATION_HOP_TIME, CELEBRATION_WAVE_STEP, HINTS_PER_GAME,
+    MONITOR_CHECK_INTERVAL,
+};
+
+const SOLVED: [[i8; 3]; 3] = [[0, 1, 2], [3, 4, 5], [6, 7, -1]];
+
+fn new_world(grid: impl Into<Grid>) -> (World<FakeRenderer>, FakeRenderer) {
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop.clone(), 3, 3, 100, 10, 0);
+    // Most tests want the results the moment the board is solved
+    world.celebration_time = Duration::ZERO;
+    world.set_grid(grid.into());
+
+    (world, desktop)
+}
+
+// Moves sliding each of `pieces` in turn
+fn slides(pieces: &[i8]) -> Vec<Move> {
+    pieces.iter().map(|&piece| Move::new(piece)).collect()
+}
+
+fn run_frames(world: &mut World<FakeRenderer>, frames: u32) {
+    for _ in 0..frames {
+        world.s_update();
+        world.s_render();
+    }
+}
+
+// Feed `check` the corpus and mutations of it, with bytes changed, added,
+// removed and cut off. Each input gets 2000 mutations, or FUZZ_ITERATIONS
+// of them, so a longer hunt is `FUZZ_ITERATIONS=1000000 cargo test fuzz`.
+fn fuzz(corpus: &[&[u8]], mut check: impl FnMut(&[u8])) {
+    let iterations = std::env::var("FUZZ_ITERATIONS")
+        .ok()
+        .and_then(|iterations| iterations.parse().ok())
+        .unwrap_or(2000);
+    let mut rng = random::rng();
+
+    for input in corpus {
+        check(input);
+        for _ in 0..iterations {
+            let mut mutated = input.to_vec();
+            for _ in 0..rng.gen_range(1..4) {
+                let index = rng.gen_range(0..=mutated.len());
+                match rng.gen_range(0..3) {
+                    0 if index < mutated.len() => mutated[index] = rng.gen(),
+                    1 => mutated.insert(index, rng.gen()),
+                    _ if index < mutated.len() => {
+                        mutated.remove(index);
+                    }
+                    _ => {}
+                }
+            }
+            if rng.gen_bool(0.25) {
+                mutated.truncate(rng.gen_range(0..=mutated.len()));
+            }
+            check(&mutated);
+        }
+    }
+}
+
+// Grab a piece in the middle, move the mouse by the given offset and let go
+fn drag(
+    world: &mut World<FakeRenderer>,
+    desktop: &FakeRenderer,
+    label: &str,
+    offset: Vector2<i32>,
+) {
+    let window = desktop.find_window(label);
+    let start = desktop.window_position(window);
+
+    desktop.press_mouse(window, 50, 50);
+    run_frames(world, 1);
+
+    desktop.move_mouse(Vector2::new(
+        start.x + 50 + offset.x,
+        start.y + 50 + offset.y,
+    ));
+    run_frames(world, 1);
+
+    desktop.release_mouse(window);
+    run_frames(world, 1);
+}
+
+#[test]
+fn releases_and_grabs_follow_the_grabbed_piece() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+
+    // The release lands on a neighbour, but it's the dragged piece that counts
+    desktop.press_mouse(desktop.find_window("7"), 50, 50);
+    run_frames(&mut world, 1);
+    let start = desktop.window_position(desktop.find_window("7"));
+    desktop.move_mouse(start + Vector2::new(50 - 80, 50));
+    run_frames(&mut world, 1);
+    desktop.release_mouse(desktop.find_window("8"));
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    // The next piece can be grabbed straight away, while 7 is still gliding
+    // in, and even if the last release went missing
+    let window = desktop.find_window("8");
+    desktop.press_mouse(window, 50, 50);
+    run_frames(&mut world, 1);
+    let start = desktop.window_position(window);
+    desktop.move_mouse(start + Vector2::new(50 - 80, 50));
+    run_frames(&mut world, 1);
+    desktop.press_mouse(desktop.find_window("5"), 50, 50);
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, 7, -1]]);
+    assert_eq!(world.grabbed_piece, Some(4));
+}
+
+#[test]
+fn horizontal_drag_past_halfway_moves_piece() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+
+    assert_eq!(world.grid, SOLVED);
+    assert!(!world.playing);
+}
+
+#[test]
+fn winning_waves_the_pieces_before_the_results() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.celebration_time = Duration::from_secs(60);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert_eq!(world.grid, SOLVED);
+    assert!(world.playing);
+    assert!(desktop.clipboard().is_empty());
+
+    // The top left piece hops first, the bottom right one last
+    std::thread::sleep(Duration::from_millis(200));
+    run_frames(&mut world, 1);
+    let first = desktop.find_window("1");
+    let last = desktop.find_window("8");
+    assert!(desktop.window_position(first).y < world.pieces[0].position.y as i32);
+    assert_eq!(
+        desktop.window_position(last).y,
+        world.pieces[7].position.y as i32
+    );
+
+    world.celebrating_since = Some(Instant::now() - Duration::from_secs(60));
+    run_frames(&mut world, 1);
+    assert!(!world.playing);
+    assert!(desktop.clipboard().starts_with("Sliding Puzzle 3x3"));
+    assert_eq!(
+        desktop.window_position(first).y,
+        world.pieces[0].position.y as i32
+    );
+}
+
+#[test]
+fn the_celebration_lasts_until_the_far_corner_of_a_big_board_hops() {
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop.clone(), 5, 5, 100, 10, 0);
+    let mut grid = Grid::solved(5, 5);
+    grid[4][3] = -1;
+    grid[4][4] = 23;
+    world.set_grid(grid);
+
+    drag(&mut world, &desktop, "24", Vector2::new(-80, 0));
+    assert!(world.solved);
+
+    // Halfway through the hop of the last piece, seven steps along
+    world.celebrating_since =
+        Some(Instant::now() - (CELEBRATION_WAVE_STEP * 7 + CELEBRATION_HOP_TIME / 2));
+    run_frames(&mut world, 1);
+    assert!(world.playing);
+    let last = desktop.find_window("24");
+    assert!(desktop.window_position(last).y < world.pieces[23].position.y as i32);
+}
+
+#[test]
+fn hooks_hear_about_moves_wins_and_state_changes() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, -1], [6, 7, 5]]);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let log = events.clone();
+    world.hooks.on_move = Some(Box::new(move |record| {
+        log.borrow_mut().push(format!("move {}", record.slide))
+    }));
+    let log = events.clone();
+    world.hooks.on_win = Some(Box::new(move |win| {
+        log.borrow_mut().push(format!("win in {}", win.moves))
+    }));
+    let log = events.clone();
+    world.hooks.on_state_change = Some(Box::new(move |old, new| {
+        log.borrow_mut().push(format!("{:?} to {:?}", old, new))
+    }));
+
+    drag(&mut world, &desktop, "6", Vector2::new(0, -80));
+    assert_eq!(world.grid, SOLVED);
+    assert_eq!(world.game_state(), GameState::Solved);
+    assert_eq!(
+        *events.borrow(),
+        ["move 6", "win in 1", "Playing to Solved"]
+    );
+}
+
+#[test]
+fn challenge_links_carry_rules_that_are_enforced() {
+    let (grid, rules) =
+        challenge::parse("slidingpuzzle://123/456/_78/?moves=1&time=60&nohints").unwrap();
+    assert_eq!(grid, [[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    assert_eq!(
+        rules,
+        Rules {
+            move_limit: Some(1),
+            time_limit: Some(Duration::from_secs(60)),
+            no_hints: true,
+        }
+    );
+    assert_eq!(
+        challenge::to_link(&grid, &rules),
+        "slidingpuzzle://123/456/_78?moves=1&time=60&nohints"
+    );
+    assert_eq!(challenge::parse("123/456/_78").unwrap().1, Rules::default());
+    assert!(challenge::parse("123/456/_78?undo=3").is_err());
+
+    // Two moves are needed but only one is allowed, and the solver can't help
+    let (mut world, desktop) = new_world(grid.clone());
+    world.set_rules(rules.clone());
+    desktop.press_key_with(desktop.find_window("1"), Key::Num3, true);
+    run_frames(&mut world, 1);
+    assert!(world.solve_job.is_none() && world.auto_moves.is_empty());
+
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    assert!(world.locked);
+    assert!(!world.playing);
+    assert!(desktop
+        .clipboard()
+        .ends_with("Challenge (1 move, 1:00.0, no hints): ❌ out of moves"));
+
+    let (mut world, desktop) = new_world(grid);
+    world.set_rules(Rules {
+        move_limit: Some(2),
+        ..rules
+    });
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert_eq!(world.grid, SOLVED);
+    assert!(desktop
+        .clipboard()
+        .ends_with("Challenge (2 moves, 1:00.0, no hints): ✅"));
+}
+
+#[test]
+fn winning_copies_a_summary_to_the_clipboard() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+
+    assert_eq!(world.moves, 1);
+    let clipboard = desktop.clipboard();
+    assert!(clipboard.starts_with("Sliding Puzzle 3x3: 1 move in 0:00."));
+    assert!(clipboard.contains("\nRated easy ("));
+    assert!(clipboard.ends_with("🟩🟩🟩\n🟩🟩🟩\n🟩⬛🟥"));
+}
+
+#[test]
+fn scrambles_are_rated_by_how_hard_they_are() {
+    let solved = rating::rate(&Grid::solved(3, 3)).unwrap();
+    assert_eq!((solved.optimal, solved.score), (0, 0));
+    assert_eq!(solved.difficulty, Difficulty::Easy);
+
+    let easy = rating::rate(&[[0, 1, 2], [3, 4, 5], [-1, 6, 7]].into()).unwrap();
+    assert_eq!(easy.optimal, 2);
+    assert_eq!(easy.difficulty, Difficulty::Easy);
+
+    // One of the two boards furthest from solved
+    let hardest = rating::rate(&[[7, 5, 6], [1, 4, 3], [2, -1, 0]].into()).unwrap();
+    assert_eq!(hardest.optimal, 31);
+    assert!(hardest.rarity > 0.99);
+    assert_eq!(hardest.difficulty, Difficulty::Expert);
+    assert!(hardest.score > easy.score);
+
+    assert!(rating::rate(&Grid::solved(4, 4)).is_none());
+    assert!(rating::rate(&[[1, 0, 2], [3, 4, 5], [6, 7, -1]].into()).is_none());
+}
+
+#[test]
+fn shuffle_quality_says_how_far_the_slides_really_got() {
+    let quality = rating::shuffle_quality(&[[0, -1, 2], [3, 1, 4], [6, 7, 5]].into(), 7);
+    assert_eq!(quality.optimal, Some(3));
+    assert_eq!(quality.blank_displacement, 3);
+    assert_eq!(
+        rating::describe_shuffle(&quality),
+        "7 slides, 3 moves from solved (42%), gap 3 cells from home"
+    );
+
+    // Bigger boards only get a lower bound
+    let mut grid = Grid::solved(4, 4);
+    slide_piece(&mut grid, 14);
+    let quality = rating::shuffle_quality(&grid, 1);
+    assert_eq!((quality.optimal, quality.lower_bound), (None, 1));
+    assert_eq!(
+        rating::describe_shuffle(&quality),
+        "1 slide, at least 1 move from solved (100%), gap 1 cell from home"
+    );
+}
+
+#[test]
+fn shutdown_waits_for_queued_saves_without_a_log_file() {
+    let done = Arc::new(AtomicBool::new(false));
+    let finished = done.clone();
+    io_worker::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        finished.store(true, Ordering::SeqCst);
+    });
+
+    logging::shutdown();
+    assert!(done.load(Ordering::SeqCst));
+}
+
+#[test]
+fn winning_writes_a_report_of_the_solve() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    let path = std::env::temp_dir().join(format!("slide-puzzle-report-{}.md", std::process::id()));
+    world.report_path = Some(path.clone());
+
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    io_worker::flush();
+
+    let report = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(report.contains("| Moves | 2 |"));
+    assert!(report.contains("| Optimal moves | 2 |"));
+    assert!(report.contains("| Difficulty | easy ("));
+    assert!(!report.contains("## Detours"));
+    assert!(report.contains("| Scramble | 123/456/_78 |"));
+    assert!(report.contains("### Move 2: piece 8, 8 in place"));
+    assert!(report.contains("```\n1 2 3\n4 5 6\n7 8 _\n```"));
+    assert!(report.contains("1. Piece 7 at 0:00.0\n2. Piece 8 at "));
+}
+
+#[test]
+fn detours_measure_moves_wasted_against_optimal_play() {
+    use crate::{analysis, MoveRecord};
+
+    let start = Grid::from([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    let mut grid = start.clone();
+    let history: Vec<MoveRecord> = [4, 4, 6, 3, 3, 6, 7]
+        .iter()
+        .map(|&piece| {
+            assert!(slide_piece(&mut grid, piece));
+            MoveRecord {
+                slide: Move::new(piece),
+                at: Duration::ZERO,
+                grid: grid.clone(),
+                power_up: None,
+            }
+        })
+        .collect();
+    assert_eq!(grid, SOLVED);
+
+    let detours = analysis::detours(&start, &history);
+    assert_eq!(detours.len(), 2);
+    assert_eq!(
+        detours[0].describe(),
+        "Moves 3\u{2013}6 added 4 extra moves"
+    );
+    assert_eq!(
+        detours[1].describe(),
+        "Moves 1\u{2013}2 added 2 extra moves"
+    );
+}
+
+#[test]
+fn board_diff_lists_only_the_pieces_that_moved() {
+    let before = Board::new([[0, 1, 2], [3, 4, 5], [-1, 6, 7]].into());
+    let after = Board::new(Grid::solved(3, 3));
+    assert_eq!(
+        before.diff(&after),
+        vec![
+            (6, Vector2::new(1, 2), Vector2::new(0, 2)),
+            (7, Vector2::new(2, 2), Vector2::new(1, 2)),
+        ]
+    );
+    assert!(after.diff(&after).is_empty());
+
+    // Rewinding a move only sends the piece that moved gliding back
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.exit_on_win = false;
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    run_frames(&mut world, 60);
+    desktop.press_key(desktop.find_window("1"), Key::Left);
+    run_frames(&mut world, 1);
+    let animating: Vec<usize> = (0..8).filter(|&i| world.pieces[i].is_animating()).collect();
+    assert_eq!(animating, vec![7]);
+}
+
+#[test]
+fn solved_board_can_be_rewound_and_played_forward() {
+    let start = [[0, 1, 2], [3, 4, 5], [-1, 6, 7]];
+    let (mut world, desktop) = new_world(start);
+    world.exit_on_win = false;
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert!(world.solved);
+
+    let window = desktop.find_window("1");
+    desktop.press_key(window, Key::Left);
+    run_frames(&mut world, 1);
+    assert_eq!(world.scrub, Some(1));
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    // Rewound boards can't be played
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    desktop.press_key(window, Key::Home);
+    run_frames(&mut world, 60);
+    assert_eq!(world.grid, start);
+    assert!(world.solved);
+
+    desktop.press_key(window, Key::End);
+    run_frames(&mut world, 1);
+    assert_eq!(world.scrub, None);
+    assert_eq!(world.grid, SOLVED);
+    assert!(world.solved);
+}
+
+#[test]
+fn report_format_follows_the_extension() {
+    use crate::report::Format;
+
+    assert_eq!(Format::from_path(Path::new("solve.html")), Format::Html);
+    assert_eq!(Format::from_path(Path::new("solve.HTM")), Format::Html);
+    assert_eq!(Format::from_path(Path::new("solve.md")), Format::Markdown);
+    assert_eq!(Format::from_path(Path::new("solve")), Format::Markdown);
+}
+
+#[test]
+fn vertical_drag_past_halfway_moves_piece() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, -1], [6, 7, 5]]);
+
+    drag(&mut world, &desktop, "6", Vector2::new(0, -80));
+
+    assert_eq!(world.grid, SOLVED);
+    assert!(!world.playing);
+}
+
+#[test]
+fn short_drag_snaps_back() {
+    let grid = [[0, 1, 2], [3, 4, 5], [6, -1, 7]];
+    let (mut world, desktop) = new_world(grid);
+    let window = desktop.find_window("8");
+    let start = desktop.window_position(window);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-20, 0));
+    run_frames(&mut world, 120);
+
+    assert_eq!(world.grid, grid);
+    assert_eq!(desktop.window_position(window), start);
+    assert!(world.playing);
+}
+
+#[test]
+fn blocked_piece_does_not_move() {
+    let grid = [[0, 1, 2], [3, 4, 5], [6, -1, 7]];
+    let (mut world, desktop) = new_world(grid);
+    let window = desktop.find_window("1");
+    let start = desktop.window_position(window);
+
+    drag(&mut world, &desktop, "1", Vector2::new(80, 80));
+    run_frames(&mut world, 120);
+
+    assert_eq!(world.grid, grid);
+    assert_eq!(desktop.window_position(window), start);
+}
+
+#[test]
+fn released_piece_animates_into_the_blank() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    let window = desktop.find_window("8");
+    let target = world.grid_pos_to_px(1, 2);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    run_frames(&mut world, 120);
+
+    assert_eq!(desktop.window_position(window), target);
+}
+
+#[test]
+fn grabbed_piece_stays_on_top_while_hovering_neighbours() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    let window = desktop.find_window("8");
+    let neighbour = desktop.find_window("6");
+
+    desktop.press_mouse(window, 50, 50);
+    run_frames(&mut world, 1);
+    assert_eq!(desktop.top_window(), Some(window));
+
+    // The piece can only slide sideways, so moving up puts the mouse over "6"
+    let neighbour_position = desktop.window_position(neighbour);
+    desktop.move_mouse(Vector2::new(
+        neighbour_position.x + 50,
+        neighbour_position.y + 50,
+    ));
+    run_frames(&mut world, 1);
+    assert_eq!(desktop.top_window(), Some(window));
+
+    desktop.release_mouse(window);
+    run_frames(&mut world, 1);
+    assert_eq!(desktop.top_window(), Some(neighbour));
+}
+
+#[test]
+fn pieces_are_colored_by_correctness() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    run_frames(&mut world, 120);
+
+    // Colors ease towards their targets, so only compare the dominant channel
+    let correct = desktop.clear_color(desktop.find_window("1"));
+    let incorrect = desktop.clear_color(desktop.find_window("8"));
+    assert!(correct.g > correct.r);
+    assert!(incorrect.r > incorrect.g);
+}
+
+#[test]
+fn escape_quits() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    desktop.press_key(desktop.find_window("1"), Key::Escape);
+    run_frames(&mut world, 1);
+
+    assert!(!world.playing);
+}
+
+#[test]
+fn q_toggles_a_qr_code_of_the_scramble() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    desktop.press_key(desktop.find_window("1"), Key::Q);
+    run_frames(&mut world, 1);
+
+    let popup = desktop.find_window("slidingpuzzle://123/456/7_8");
+    assert!(world.qr_popup.is_some());
+    assert!(desktop.window_position(popup).x > desktop.window_position(desktop.find_window("3")).x);
+
+    // Clicking the popup closes it again
+    desktop.press_mouse(popup, 10, 10);
+    run_frames(&mut world, 1);
+
+    assert!(world.qr_popup.is_none());
+    assert!(world.playing);
+}
+
+#[test]
+fn scramble_notation_round_trips() {
+    let grid = Grid::from([[0, 4, 2], [-1, 7, 5], [3, 6, 1]]);
+
+    assert_eq!(scramble::to_notation(&grid), "153/_86/472");
+    assert_eq!(scramble::parse("153/_86/472"), Ok(grid));
+    assert_eq!(scramble::to_notation(&Grid::solved(3, 3)), "123/456/78_");
+}
+
+#[test]
+fn ctrl_v_loads_a_copied_scramble_link() {
+    let (mut world, desktop) = new_world(SOLVED);
+
+    desktop.copy("slidingpuzzle://123/456/7_8/");
+    desktop.press_key_with(desktop.find_window("1"), Key::V, true);
+    run_frames(&mut world, 1);
+
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    assert_eq!(world.start_grid, world.grid);
+    assert!(world.playing);
+
+    // Anything else on the clipboard leaves the board alone
+    desktop.copy("hello");
+    desktop.press_key_with(desktop.find_window("1"), Key::V, true);
+    run_frames(&mut world, 1);
+
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+}
+
+#[test]
+fn the_same_seed_makes_the_same_choices() {
+    let desktop = FakeRenderer::new(1920, 1080);
+    let a = World::new(desktop.clone(), 3, 3, 100, 10, 30);
+    let b = World::new(desktop.clone(), 3, 3, 100, 10, 30);
+    assert_eq!(a.grid, b.grid);
+    assert_ne!(a.grid, SOLVED);
+
+    let (mut a, _) = new_world(SOLVED);
+    let (mut b, _) = new_world(SOLVED);
+    for _ in 0..10 {
+        assert_eq!(parity::deal(&mut a.rng), parity::deal(&mut b.rng));
+    }
+}
+
+#[test]
+fn board_lower_bound_never_overestimates() {
+    assert_eq!(
+        Board::new(Grid::solved(3, 3)).distance_to_goal_lower_bound(),
+        0
+    );
+    // Only 8 is out of place, one cell from home
+    let board = Board::new([[0, 1, 2], [3, 4, 5], [6, -1, 7]].into());
+    assert_eq!(board.distance_to_goal_lower_bound(), 1);
+    assert!(!Board::new([[1, 0, 2], [3, 4, 5], [6, 7, -1]].into()).is_solvable());
+
+    let mut rng = random::rng();
+    for _ in 0..50 {
+        let mut grid = Grid::solved(3, 3);
+        shuffle_grid(&mut grid, 30, &mut rng);
+        let board = Board::new(grid.clone());
+        assert!(board.is_solvable());
+        assert!(board.distance_to_goal_lower_bound() <= solver::distance(&grid).unwrap() as u32);
+    }
+}
+
+#[test]
+fn bigger_boards_play_and_win_the_same() {
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop.clone(), 4, 4, 100, 10, 0);
+    world.celebration_time = Duration::ZERO;
+    assert_eq!(world.pieces.len(), 15);
+    // Boards of another size are turned away
+    world.set_grid(Grid::solved(3, 3));
+    assert_eq!(world.grid, Grid::solved(4, 4));
+
+    let grid = Grid::from([[0, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, 11], [12, 13, -1, 14]]);
+    world.set_grid(grid.clone());
+    drag(&mut world, &desktop, "15", Vector2::new(-80, 0));
+    assert_eq!(world.grid, Grid::solved(4, 4));
+    assert!(!world.playing);
+    assert!(desktop
+        .clipboard()
+        .starts_with("Sliding Puzzle 4x4: 1 move"));
+
+    // Pieces past 9 are written as letters
+    assert_eq!(scramble::to_notation(&grid), "1234/5678/9abc/de_f");
+    assert_eq!(scramble::parse("1234/5678/9abc/de_f"), Ok(grid));
+    // In order but with the gap a row up, which sliding can't reach
+    assert!(
+        !Board::new([[0, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, -1], [11, 12, 13, 14]].into())
+            .is_solvable()
+    );
+}
+
+#[test]
+fn rectangular_boards_play_and_save_the_same() {
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop.clone(), 4, 3, 100, 10, 0);
+    world.celebration_time = Duration::ZERO;
+    assert_eq!(world.pieces.len(), 11);
+    // A board turned on its side is another shape
+    world.set_grid(Grid::solved(3, 4));
+    assert_eq!(world.grid, Grid::solved(4, 3));
+
+    let grid = Grid::from([[0, 1, 2, 3], [4, 5, 6, 7], [8, 9, -1, 10]]);
+    world.set_grid(grid.clone());
+    drag(&mut world, &desktop, "11", Vector2::new(-80, 0));
+    assert_eq!(world.grid, Grid::solved(4, 3));
+    assert!(!world.playing);
+    assert!(desktop
+        .clipboard()
+        .starts_with("Sliding Puzzle 4x3: 1 move"));
+
+    assert_eq!(scramble::to_notation(&grid), "1234/5678/9a_b");
+    assert_eq!(scramble::parse("1234/5678/9a_b"), Ok(grid));
+    assert_eq!(
+        scramble::parse("12345/6789_"),
+        Ok(Grid::from([[0, 1, 2, 3, 4], [5, 6, 7, 8, -1]]))
+    );
+    assert!(scramble::parse("1234/5678/9a_").is_err());
+
+    let replay = Replay::from_game(7, world.start_grid.clone(), &world.history);
+    let decoded = Replay::decode(&replay.encode().unwrap()).unwrap();
+    assert_eq!(decoded.start_grid, world.start_grid);
+    assert_eq!(decoded.moves.len(), 1);
+    assert_eq!(decoded.moves[0].piece, 10);
+}
+
+#[test]
+fn scramble_rejects_unsolvable_boards() {
+    // Two pieces swapped can never be solved
+    assert!(scramble::parse("213/456/78_").is_err());
+    let solved = Grid::solved(3, 3);
+    assert!(Board::new(solved.clone()).is_solvable());
+    assert_eq!(scramble::parse(&scramble::to_link(&solved)), Ok(solved));
+}
+
+#[test]
+fn scramble_rejects_malformed_notation() {
+    assert!(scramble::parse("123/456").is_err());
+    assert!(scramble::parse("123/456/7_").is_err());
+    assert!(scramble::parse("123/456/789").is_err());
+    assert!(scramble::parse("113/456/78_").is_err());
+}
+
+#[test]
+fn capture_moves_saves_a_png_per_move() {
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-captures-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // A small desktop keeps the canvas cheap to encode
+    let desktop = FakeRenderer::new(400, 400);
+    let mut world = World::new(desktop.clone(), 3, 3, 100, 10, 0);
+    world.capture_dir = dir.clone();
+    world.capture_moves = true;
+    world.set_grid([[0, 1, 2], [3, 4, 5], [6, -1, 7]].into());
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    desktop.press_key(desktop.find_window("1"), Key::P);
+    run_frames(&mut world, 1);
+    io_worker::flush();
+
+    let mut files: Vec<String> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    let png = std::fs::read(dir.join("move-0001.png")).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(files.len(), 3);
+    assert!(files[0].starts_with("board-"));
+    assert_eq!(files[1..], ["move-0000.png", "move-0001.png"]);
+    assert!(png.starts_with(b"\x89PNG"));
+    // A background and a label for each of the 8 pieces
+    assert_eq!(desktop.captures(), [16, 16, 16]);
+}
+
+#[test]
+fn overlay_files_follow_the_game() {
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-overlay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.overlay = Some(Overlay::new(dir.clone()));
+
+    run_frames(&mut world, 1);
+    io_worker::flush();
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("timer.txt"), "0:00.0");
+    assert_eq!(read("moves.txt"), "0");
+    assert_eq!(read("scramble.txt"), "123/456/7_8");
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    io_worker::flush();
+    assert_eq!(read("moves.txt"), "1");
+    assert!(!dir.join("moves.tmp").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn splits_are_compared_against_the_personal_best() {
+    let path = std::env::temp_dir().join(format!("slide-puzzle-splits-{}", std::process::id()));
+    std::fs::write(&path, "1000\n2000\n3000\n").unwrap();
+
+    let mut splits = Splits::load(path.clone());
+    splits.update(
+        &[[0, 1, 2], [3, -1, 5], [6, 7, 4]].into(),
+        Duration::from_millis(1500),
+    );
+    assert_eq!(splits.times.len(), 1);
+    assert_eq!(splits.delta(0).as_deref(), Some("+0:00.5"));
+
+    // Finishing both remaining phases at once records both splits
+    splits.update(&Grid::solved(3, 3), Duration::from_millis(2500));
+    assert_eq!(splits.times.len(), 3);
+    assert_eq!(splits.delta(2).as_deref(), Some("-0:00.5"));
+
+    io_worker::flush();
+    let saved = saves::read(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(saved, "# version 0\n1500\n2500\n2500\n");
+    // The hand-written file it replaced is kept as a backup
+    let backup = path.with_file_name(format!("slide-puzzle-splits-{}.bak", std::process::id()));
+    assert_eq!(
+        std::fs::read_to_string(&backup).unwrap(),
+        "1000\n2000\n3000\n"
+    );
+    let _ = std::fs::remove_file(&backup);
+
+    splits.reset();
+    assert_eq!(
+        splits.best.as_ref().unwrap()[0],
+        Duration::from_millis(1500)
+    );
+}
+
+#[test]
+fn damaged_saves_fall_back_to_the_backup() {
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-saves-{}", std::process::id()));
+    let path = dir.join("save.txt");
+
+    saves::write(&path, "first\n").unwrap();
+    assert_eq!(saves::read(&path).unwrap(), "first\n");
+    saves::write(&path, "second").unwrap();
+    assert_eq!(saves::read(&path).unwrap(), "second\n");
+    assert!(!dir.join("save.txt.tmp").exists());
+
+    // Scribbled over: the checksum no longer matches
+    let damaged = std::fs::read_to_string(&path)
+        .unwrap()
+        .replace("second", "secnod");
+    std::fs::write(&path, damaged).unwrap();
+    assert_eq!(saves::read(&path).unwrap(), "first\n");
+
+    // Lost altogether, e.g. by a crash between the two renames
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(saves::read(&path).unwrap(), "first\n");
+
+    std::fs::remove_file(dir.join("save.txt.bak")).unwrap();
+    assert!(saves::read(&path).is_err());
+
+    // Files from before checksums still load
+    std::fs::write(&path, "old\n").unwrap();
+    assert_eq!(saves::read(&path).unwrap(), "old\n");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn add_header(text: &str) -> Result<String, String> {
+    Ok(format!("header\n{}", text))
+}
+
+fn double_numbers(text: &str) -> Result<String, String> {
+    text.lines()
+        .map(|line| {
+            let number: u32 = line.parse().map_err(|_| format!("bad number {}", line))?;
+            Ok(format!("{}\n", number * 2))
+        })
+        .collect()
+}
+
+#[test]
+fn old_saves_are_upgraded_one_step_at_a_time() {
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-migrate-{}", std::process::id()));
+    let path = dir.join("save.txt");
+    let migrations = [
+        saves::Migration {
+            description: "numbers are doubled",
+            upgrade: double_numbers,
+        },
+        saves::Migration {
+            description: "added a header",
+            upgrade: add_header,
+        },
+    ];
+
+    // Each step on its own
+    assert_eq!(double_numbers("1\n2\n").unwrap(), "2\n4\n");
+    assert!(double_numbers("x\n").is_err());
+    assert_eq!(add_header("2\n").unwrap(), "header\n2\n");
+
+    // Files from before versioning go through every step, and are saved
+    // upgraded so it only happens once
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&path, "1\n2\n").unwrap();
+    assert_eq!(
+        saves::read_versioned(&path, &migrations).unwrap(),
+        "header\n2\n4\n"
+    );
+    io_worker::flush();
+    assert!(saves::read(&path).unwrap().starts_with("# version 2\n"));
+    assert_eq!(
+        saves::read_versioned(&path, &migrations).unwrap(),
+        "header\n2\n4\n"
+    );
+
+    // Files part of the way there only get the steps they're missing
+    saves::write_versioned(&path, "3\n", &migrations[..1]).unwrap();
+    assert_eq!(
+        saves::read_versioned(&path, &migrations).unwrap(),
+        "header\n3\n"
+    );
+    io_worker::flush();
+
+    // A file from a newer game isn't guessed at
+    assert!(saves::read_versioned(&path, &migrations[..1]).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn splits_window_opens_beside_the_board() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    let path = std::env::temp_dir().join("slide-puzzle-splits-missing");
+    world.show_splits(path);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+
+    let splits_window = desktop.window_position(desktop.find_window("Splits"));
+    assert!(splits_window.x < desktop.window_position(desktop.find_window("1")).x);
+    assert_eq!(world.splits.times.len(), 3);
+}
+
+#[test]
+fn move_counter_opens_beside_the_board() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.show_move_counter();
+
+    let counter = desktop.window_position(desktop.find_window("Moves"));
+    assert!(counter.x > desktop.window_position(desktop.find_window("3")).x);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert_eq!(world.moves, 1);
+    assert_eq!(move_counter::caption(world.moves), "move");
+    assert_eq!(move_counter::caption(0), "moves");
+}
+
+#[test]
+fn timer_keeps_the_best_time_for_each_board_size() {
+    let path = std::env::temp_dir().join(format!("slide-puzzle-best-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.best_times = BestTimes::load(path.clone());
+    world.show_timer();
+    let timer = desktop.window_position(desktop.find_window("Timer"));
+    assert!(timer.x < desktop.window_position(desktop.find_window("1")).x);
+
+    let won = Rc::new(RefCell::new(None));
+    let seen = won.clone();
+    world.hooks.on_win = Some(Box::new(move |win| {
+        *seen.borrow_mut() = Some(win.best_time)
+    }));
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert_eq!(*won.borrow(), Some(true));
+    let best = world.best_times.get(3, 3).unwrap();
+    assert_eq!(best, world.elapsed());
+    assert_eq!(world.best_times.get(4, 4), None);
+
+    // Only a faster solve replaces it
+    assert!(!world.best_times.record(3, 3, best + Duration::from_secs(1)));
+    assert!(world.best_times.record(4, 4, Duration::from_secs(90)));
+    io_worker::flush();
+    let loaded = BestTimes::load(path.clone());
+    assert_eq!(
+        loaded.get(3, 3),
+        Some(Duration::from_millis(best.as_millis() as u64))
+    );
+    assert_eq!(loaded.get(4, 4), Some(Duration::from_secs(90)));
+
+    assert_eq!(
+        timer::parse("# mine\n4x3 = 1500\n").unwrap()[&(4, 3)],
+        Duration::from_millis(1500)
+    );
+    assert!(timer::parse("3x3 = fast").is_err());
+    assert!(timer::parse("big = 1000").is_err());
+
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(path.with_extension("bak"));
+}
+
+#[test]
+fn stats_keep_every_solved_game() {
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-stats-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join(stats::FILE_NAME);
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.stats_path = Some(path.clone());
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    io_worker::flush();
+    let games = stats::load(&path).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!((games[0].width, games[0].height), (3, 3));
+    assert_eq!(games[0].moves, 1);
+    assert_eq!(games[0].seed, random::seed());
+    assert_eq!(games[0].mode, "classic");
+    // Rated from the scramble, so it can be grouped by difficulty later
+    let rating = rating::rate(&[[0, 1, 2], [3, 4, 5], [6, -1, 7]].into()).unwrap();
+    assert_eq!(games[0].rating, Some(rating.score));
+    assert_eq!(games[0].difficulty(), Some(rating.difficulty));
+
+    let game = |moves, secs, hints| GameRecord {
+        width: 4,
+        height: 4,
+        moves,
+        time: Duration::from_secs(secs),
+        hints,
+        seed: 7,
+        mode: "classic".to_string(),
+        rating: None,
+    };
+    stats::record(path.clone(), &game(90, 100, 0));
+    stats::record(path.clone(), &game(70, 60, 2));
+    io_worker::flush();
+    let games = stats::load(&path).unwrap();
+    assert_eq!(games[2], game(70, 60, 2));
+
+    let summary = stats::summarize(&games)[&(4, 4)];
+    assert_eq!(summary.games, 2);
+    assert_eq!(summary.average_time, Duration::from_secs(80));
+    // The quicker solve used hints
+    assert_eq!(summary.fastest, Some(Duration::from_secs(100)));
+    assert_eq!(summary.average_moves, 80.0);
+    assert_eq!(summary.fewest_moves, 70);
+    assert_eq!(summary.hints, 2);
+    let report = stats::report(&games, &Locale::default());
+    assert!(report.contains("4x4: 2 games"));
+    assert!(report.contains("Average moves 80.0, fewest 70"));
+    assert!(report.contains(&format!("Rated 1 {}", rating.difficulty.name())));
+    assert_eq!(stats::summarize(&games)[&(4, 4)].difficulties, [0; 4]);
+    let report = stats::report(&games, &Locale::from_name("de_DE.UTF-8"));
+    assert!(report.contains("Average time 1:20,0, fastest 1:40,0"));
+    assert!(report.contains("Average moves 80,0"));
+
+    // Saved like other files, with a checksum to catch a damaged one
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        saves::verify(&text).map(|text| stats::parse(&text).len()),
+        Ok(3)
+    );
+
+    // Lines from before ratings were kept still load, unrated
+    let old = stats::parse(
+        r#"{"width":3,"height":3,"moves":24,"time_ms":41250,"hints":0,"seed":1234,"mode":"classic"}"#,
+    );
+    assert_eq!(old[0].rating, None);
+    let rated = GameRecord {
+        rating: Some(72),
+        ..game(50, 30, 0)
+    };
+    assert_eq!(GameRecord::from_json(&rated.to_json()), Ok(rated.clone()));
+    assert_eq!(rated.difficulty(), Some(Difficulty::Hard));
+
+    // Half a line left by a run from before stats were saved safely doesn't
+    // lose the rest
+    assert_eq!(
+        stats::parse(&format!("{}\n{{\"width\":3", game(1, 1, 0).to_json())).len(),
+        1
+    );
+    assert!(stats::load(&path.with_extension("missing"))
+        .unwrap()
+        .is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn heatmap_counts_where_the_gap_went() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+
+    let visits = heatmap::blank_visits(&world.start_grid, &world.history);
+    assert_eq!(visits, [[0, 0, 0], [0, 0, 0], [1, 1, 1]]);
+
+    let (size, pixels) = heatmap::render(&visits);
+    assert_eq!(pixels.len(), (size.x * size.y * 4) as usize);
+    // Visited cells are opaque, the gaps between cells aren't
+    assert_eq!(pixels[3], 255);
+    assert_eq!(pixels[(100 * 4 + 3) as usize], 0);
+}
+
+#[test]
+fn solver_finds_shortest_solutions() {
+    assert_eq!(solver::distance(&Grid::solved(3, 3)), Some(0));
+    assert_eq!(
+        solver::distance(&[[0, 1, 2], [3, 4, 5], [-1, 6, 7]].into()),
+        Some(2)
+    );
+    assert_eq!(
+        solver::distance(&[[1, 0, 2], [3, 4, 5], [6, 7, -1]].into()),
+        None
+    );
+
+    assert_eq!(
+        solver::solve(&[[0, 1, 2], [3, 4, 5], [-1, 6, 7]].into()),
+        Some(slides(&[6, 7]))
+    );
+    assert_eq!(solver::next_move(&Grid::solved(3, 3)), None);
+}
+
+#[test]
+fn world_solve_uses_the_games_own_solver() {
+    let (world, _desktop) = new_world([[0, 1, 2], [3, 4, -1], [6, 7, 5]]);
+    assert_eq!(world.solve(&AtomicBool::new(false)), Some(slides(&[5])));
+
+    // Beyond 3x3 it's searched for, and stops when asked to
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop, 4, 3, 100, 10, 0);
+    world.set_grid(Grid::from([[0, 1, 2, 3], [4, 5, -1, 7], [8, 9, 6, 10]]));
+    assert_eq!(world.solve(&AtomicBool::new(false)), Some(slides(&[6, 10])));
+    assert_eq!(world.solve(&AtomicBool::new(true)), None);
+}
+
+#[test]
+fn ida_star_finds_shortest_solutions_with_either_heuristic() {
+    let mut rng = random::rng();
+    for _ in 0..20 {
+        let mut grid = Grid::solved(3, 3);
+        shuffle_grid(&mut grid, 30, &mut rng);
+        let optimal = solver::distance(&grid).unwrap() as usize;
+        for heuristic in [Heuristic::Manhattan, Heuristic::LinearConflict] {
+            assert_eq!(solver::ida_star(&grid, heuristic).unwrap().len(), optimal);
+        }
+    }
+
+    // Linear conflict only prunes more, it doesn't change what's found
+    let mut grid = Grid::solved(4, 4);
+    shuffle_grid(&mut grid, 40, &mut rng);
+    let solution = solver::ida_star(&grid, Heuristic::LinearConflict).unwrap();
+    assert_eq!(
+        solution.len(),
+        solver::ida_star(&grid, Heuristic::Manhattan).unwrap().len()
+    );
+    for step in solution {
+        assert!(step.apply(&mut grid));
+    }
+    assert_eq!(grid, Grid::solved(4, 4));
+    assert_eq!(
+        solver::ida_star(
+            &[[1, 0, 2], [3, 4, 5], [6, 7, -1]].into(),
+            Heuristic::Manhattan
+        ),
+        None
+    );
+}
+
+#[test]
+fn cancelled_searches_stop_straight_away() {
+    // Far too well shuffled to solve before it's cancelled
+    let mut grid = Grid::solved(5, 5);
+    shuffle_grid(&mut grid, 500, &mut random::rng());
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let search_cancelled = Arc::clone(&cancelled);
+    let search = std::thread::spawn(move || solver::solve_any(&grid, &search_cancelled));
+    std::thread::sleep(Duration::from_millis(100));
+    cancelled.store(true, Ordering::Relaxed);
+
+    let asked = Instant::now();
+    assert_eq!(search.join().unwrap(), None);
+    assert!(asked.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn pattern_databases_guide_ida_star_without_overestimating() {
+    // Small groups, so the tables build in a moment
+    let groups: [&[i8]; 2] = [&[0, 1, 2], &[3, 4, 5]];
+    let mut reported = Vec::new();
+    let database = PatternDatabase::build_groups(&groups, |progress| reported.push(progress));
+    assert_eq!(reported.last(), Some(&1.0));
+    let solved: Vec<i8> = Grid::solved(4, 4).cells().collect();
+    assert_eq!(database.estimate(&solved), 0);
+
+    let mut rng = random::rng();
+    for _ in 0..5 {
+        let mut grid = Grid::solved(4, 4);
+        shuffle_grid(&mut grid, 20, &mut rng);
+        let optimal = solver::ida_star(&grid, Heuristic::Manhattan).unwrap();
+        let cells: Vec<i8> = grid.cells().collect();
+        assert!(database.estimate(&cells) <= optimal.len() as u32);
+        assert_eq!(
+            solver::ida_star_with_patterns(&grid, &database)
+                .unwrap()
+                .len(),
+            optimal.len()
+        );
+    }
+
+    // Saved and loaded, it gives the same answers
+    let bytes = database.encode().unwrap();
+    let loaded = PatternDatabase::decode_groups(&groups, &bytes).unwrap();
+    let cells: Vec<i8> = [[5, 1, 2, 3], [4, 0, 6, 7], [8, 9, 10, 11], [12, 13, 14, -1]]
+        .iter()
+        .flatten()
+        .copied()
+        .collect();
+    assert_eq!(loaded.estimate(&cells), database.estimate(&cells));
+    assert!(loaded.estimate(&cells) > 0);
+    assert!(PatternDatabase::decode_groups(&groups, b"SPDB\x01junk").is_err());
+    assert!(PatternDatabase::decode(&bytes[..10]).is_err());
+}
+
+#[test]
+fn solver_warm_up_leaves_the_solution_ready() {
+    let grid: Grid = [[0, 1, 2], [3, 4, -1], [6, 7, 5]].into();
+    solver::warm_up(grid.clone()).join().unwrap();
+    assert_eq!(solver::solve(&grid), Some(slides(&[5])));
+
+    // Nothing to work out for boards the solver doesn't know
+    solver::warm_up(Grid::solved(4, 4)).join().unwrap();
+    assert_eq!(solver::solve(&Grid::solved(4, 4)), None);
+}
+
+#[test]
+fn drills_generate_positions_for_their_technique() {
+    let mut rng = random::rng();
+
+    for _ in 0..20 {
+        let (grid, optimal) = DrillKind::LastTwoRows.generate(&mut rng);
+        assert_eq!(grid[0], SOLVED[0]);
+        assert_eq!(solver::distance(&grid), Some(optimal));
+
+        let (grid, _) = DrillKind::ThreeCycle.generate(&mut rng);
+        let out_of_place = grid
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|&(cell, &piece)| piece != -1 && piece as usize != cell)
+            .count();
+        assert_eq!(out_of_place, 3);
+        assert_eq!(grid[2][2], -1);
+    }
+}
+
+#[test]
+fn drill_scores_a_finished_position() {
+    let (mut world, desktop) = new_world(SOLVED);
+    world.exit_on_win = false;
+    let mut drill = Drill::new(DrillKind::LastTwoRows);
+    drill.start(&mut world);
+    // Let the pieces glide into the drill position
+    run_frames(&mut world, 200);
+
+    // Play the optimal solution
+    for step in solver::solve(&world.grid).unwrap() {
+        let available_move = world.get_available_move(step.piece as usize);
+        let offset = Vector2::new(available_move.x as i32 * 80, available_move.y as i32 * 80);
+        drag(&mut world, &desktop, &step.to_string(), offset);
+    }
+    drill.update(&mut world);
+
+    assert!(world.solved);
+    assert!(world.playing);
+    assert_eq!((drill.attempts, drill.successes), (1, 1));
+}
+
+#[test]
+fn every_pattern_move_sequence_fixes_its_pieces() {
+    let patterns = patterns::library();
+    assert!(!patterns.is_empty());
+
+    for pattern in patterns {
+        // Fill the ? cells with whatever pieces are left over
+        let mut spare = (0..8).filter(|piece| !pattern.cells.contains(&Some(*piece)));
+        let mut grid = Grid::solved(3, 3);
+        for (cell, piece) in pattern.cells.iter().enumerate() {
+            grid.set_cell(cell, piece.unwrap_or_else(|| spare.next().unwrap()));
+        }
+        assert!(pattern.matches(&grid), "{}", pattern.name);
+
+        let (solved, _) = patterns::apply_moves(&grid, &pattern.moves).unwrap();
+        for piece in pattern.cells.iter().flatten().filter(|&&piece| piece != -1) {
+            let cell = *piece as usize;
+            assert_eq!(solved[cell / 3][cell % 3], *piece, "{}", pattern.name);
+        }
+    }
+}
+
+#[test]
+fn hints_recognise_a_swapped_top_pair() {
+    let grid = Grid::from([[1, 0, 2], [3, -1, 5], [6, 7, 4]]);
+
+    assert_eq!(
+        patterns::find(&grid).map(|pattern| pattern.name.as_str()),
+        Some("Swapped top pair")
+    );
+    assert!(patterns::find(&Grid::solved(3, 3)).is_none());
+    assert!(patterns::parse_library("Broken | 12/345 | LR").is_err());
+}
+
+#[test]
+fn solver_places_only_the_selected_pieces() {
+    let grid = Grid::from([[7, 4, 1], [-1, 0, 5], [3, 6, 2]]);
+
+    for pieces in [&[0, 1, 2][..], &[0, 3, 6], &[6, 7]] {
+        let mut reports = Vec::new();
+        let solution = solver::solve_pieces(&grid, pieces, &AtomicBool::new(false), |progress| {
+            reports.push(progress)
+        })
+        .unwrap();
+        // The search reports each depth it reaches, with more boards each time
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].bound <= pair[1].bound && pair[0].expanded < pair[1].expanded));
+        assert_eq!(reports.last().unwrap().bound as usize, solution.len());
+
+        let mut board = grid.clone();
+        for step in solution {
+            assert!(step.apply(&mut board));
+        }
+        for &piece in pieces {
+            assert_eq!(board[piece as usize / 3][piece as usize % 3], piece);
+        }
+    }
+}
+
+#[test]
+fn auto_solving_a_row_slides_its_pieces_into_place() {
+    let (mut world, desktop) = new_world([[7, 4, 1], [-1, 0, 5], [3, 6, 2]]);
+    world.exit_on_win = false;
+
+    // Right click a piece in the top row
+    desktop.push_event(
+        desktop.find_window("8"),
+        Event::MouseButtonPressed {
+            button: mouse::Button::Right,
+            x: 50,
+            y: 50,
+        },
+    );
+    run_frames(&mut world, 1);
+    // The search runs alongside the game
+    while world.solve_job.is_some() {
+        run_frames(&mut world, 1);
+    }
+    assert!(!world.auto_moves.is_empty());
+    // The row's pieces stand out from the rest while they're placed
+    let emphasis: Vec<Emphasis> = world.pieces.iter().map(|piece| piece.emphasis).collect();
+    assert_eq!(emphasis[..3], [Emphasis::Focused; 3]);
+    assert_eq!(emphasis[3..], [Emphasis::Dimmed; 5]);
+    assert_eq!(
+        Emphasis::Dimmed.apply(Color::rgb(200, 100, 0)),
+        Color::rgb(60, 30, 0)
+    );
+
+    while !world.auto_moves.is_empty() {
+        run_frames(&mut world, 10);
+    }
+    assert_eq!(world.grid[0], [0, 1, 2]);
+    run_frames(&mut world, 100);
+    assert!(world
+        .pieces
+        .iter()
+        .all(|piece| piece.emphasis == Emphasis::Normal));
+
+    // Ctrl+3 then finishes the bottom row
+    desktop.press_key_with(desktop.find_window("1"), Key::Num3, true);
+    run_frames(&mut world, 1);
+    while world.solve_job.is_some() {
+        run_frames(&mut world, 1);
+    }
+    while !world.auto_moves.is_empty() {
+        run_frames(&mut world, 10);
+    }
+    assert_eq!(world.grid[2][..2], [6, 7]);
+}
+
+#[test]
+fn s_solves_the_whole_board_a_move_at_a_time() {
+    let (mut world, desktop) = new_world([[0, -1, 2], [3, 1, 4], [6, 7, 5]]);
+    world.exit_on_win = false;
+    world.auto_move_delay = Duration::from_millis(50);
+    let window = desktop.find_window("1");
+    let settle = |world: &mut World<FakeRenderer>| {
+        while world.pieces.iter().any(|piece| piece.is_animating()) {
+            run_frames(world, 1);
+        }
+    };
+
+    desktop.press_key(window, Key::S);
+    run_frames(&mut world, 1);
+    while world.solve_job.is_some() {
+        run_frames(&mut world, 1);
+    }
+    assert_eq!(world.auto_moves, slides(&[1, 4, 5]));
+
+    // Each move waits for the delay, once the last has landed
+    run_frames(&mut world, 1);
+    assert_eq!(world.auto_moves, slides(&[1, 4, 5]));
+    std::thread::sleep(Duration::from_millis(60));
+    run_frames(&mut world, 1);
+    assert_eq!(world.auto_moves, slides(&[4, 5]));
+    settle(&mut world);
+    run_frames(&mut world, 1);
+    assert_eq!(world.auto_moves, slides(&[4, 5]));
+    std::thread::sleep(Duration::from_millis(60));
+    run_frames(&mut world, 1);
+    assert_eq!(world.auto_moves, slides(&[5]));
+
+    // Asking again stops it, and again after that finishes the job
+    desktop.press_key(window, Key::S);
+    run_frames(&mut world, 1);
+    assert!(world.auto_moves.is_empty());
+    settle(&mut world);
+    assert!(!world.solved);
+
+    world.auto_move_delay = Duration::ZERO;
+    desktop.press_key(window, Key::S);
+    while !world.solved {
+        run_frames(&mut world, 1);
+    }
+    assert_eq!(world.grid, SOLVED);
+}
+
+#[test]
+fn any_key_cancels_a_search() {
+    let (mut world, desktop) = new_world([[7, 4, 1], [-1, 0, 5], [3, 6, 2]]);
+    let window = desktop.find_window("1");
+
+    desktop.press_key_with(window, Key::Num1, true);
+    desktop.press_key(window, Key::Space);
+    run_frames(&mut world, 1);
+    assert!(world.solve_job.is_none());
+    run_frames(&mut world, 5);
+    assert!(world.auto_moves.is_empty());
+
+    // A slow search shows its progress, and the progress window cancels too
+    let job = SolveJob::start(world.canonical_grid(), vec![0, 1, 2]);
+    let mut status = SolveStatus::open(&mut world.renderer, Vector2::new(0, 0));
+    status.render(&job);
+    let window = desktop.find_window("Solving");
+    desktop.press_key(window, Key::LShift);
+    assert!(!status.update());
+    desktop.press_key(window, Key::A);
+    assert!(status.update());
+}
+
+#[test]
+fn ctrl_z_and_ctrl_y_undo_and_redo_moves() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    world.exit_on_win = false;
+    let window = desktop.find_window("1");
+
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    assert_eq!(world.moves, 1);
+    desktop.press_key_with(window, Key::Z, true);
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    assert_eq!((world.moves, world.history.len()), (0, 0));
+    // The piece glides back rather than jumping
+    assert!(world.pieces[6].is_animating());
+    run_frames(&mut world, 100);
+
+    // Nothing left to undo
+    desktop.press_key_with(window, Key::Z, true);
+    run_frames(&mut world, 1);
+    assert_eq!(world.moves, 0);
+
+    // A redone move counts again, and the board still wins as usual
+    desktop.press_key_with(window, Key::Y, true);
+    run_frames(&mut world, 100);
+    assert_eq!(world.moves, 1);
+    assert_eq!(world.undone.len(), 0);
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert!(world.solved);
+    assert_eq!(world.moves, 2);
+
+    // A new move drops whatever could have been redone
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    world.exit_on_win = false;
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    world.undo();
+    run_frames(&mut world, 100);
+    drag(&mut world, &desktop, "4", Vector2::new(0, 80));
+    assert!(world.undone.is_empty());
+    world.redo();
+    assert_eq!(world.moves, 1);
+}
+
+#[test]
+fn undoing_a_move_brings_hints_and_fog_up_to_date() {
+    // The top pair is swapped with the gap in the middle, a known pattern
+    let (mut world, desktop) = new_world([[1, 0, 2], [3, -1, 4], [6, 7, 5]]);
+    world.exit_on_win = false;
+    world.hints = true;
+    world.fog = true;
+
+    drag(&mut world, &desktop, "5", Vector2::new(-80, 0));
+    assert_ne!(world.last_hint, Some("Swapped top pair"));
+    assert!(!world.pieces[1].revealed);
+
+    // Straight away, without waiting for a frame
+    world.undo();
+    assert_eq!(world.last_hint, Some("Swapped top pair"));
+    let revealed: Vec<usize> = (0..world.pieces.len())
+        .filter(|&i| world.pieces[i].revealed)
+        .collect();
+    assert_eq!(revealed, [0, 3, 4, 7]);
+}
+
+#[test]
+fn hooks_hear_about_undos_so_a_race_keeps_count() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.exit_on_win = false;
+    // What a race would send the opponent
+    let sent = Rc::new(RefCell::new(Vec::new()));
+    let log = sent.clone();
+    world.hooks.on_move = Some(Box::new(move |record| {
+        log.borrow_mut().push(format!("move {}", record.slide))
+    }));
+    let log = sent.clone();
+    world.hooks.on_undo = Some(Box::new(move |record| {
+        log.borrow_mut().push(format!("undo {}", record.slide))
+    }));
+
+    drag(&mut world, &desktop, "7", Vector2::new(80, 0));
+    world.undo();
+    run_frames(&mut world, 100);
+    world.redo();
+    assert_eq!(*sent.borrow(), ["move 7", "undo 7", "move 7"]);
+    assert_eq!(world.moves, 1);
+}
+
+#[test]
+fn rotating_the_board_turns_the_goal_with_it() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    desktop.press_key(desktop.find_window("1"), Key::R);
+    run_frames(&mut world, 1);
+
+    assert_eq!(world.grid, [[6, 3, 0], [-1, 4, 1], [7, 5, 2]]);
+    assert_eq!(world.canonical_grid(), [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    assert_eq!(world.goal(), [[6, 3, 0], [7, 4, 1], [-1, 5, 2]]);
+
+    // Piece 1 is still in its (rotated) place, so it stays green
+    run_frames(&mut world, 100);
+    let in_place = desktop.clear_color(desktop.find_window("1"));
+    assert!(in_place.g > in_place.r);
+
+    // The last move is now upwards instead of to the left
+    drag(&mut world, &desktop, "8", Vector2::new(0, -80));
+    assert!(world.solved);
+}
+
+#[test]
+fn four_rotations_or_two_mirrors_change_nothing() {
+    let grid = [[7, 4, 1], [-1, 0, 5], [3, 6, 2]];
+    let (mut world, desktop) = new_world(grid);
+    let window = desktop.find_window("1");
+
+    for _ in 0..4 {
+        desktop.press_key(window, Key::R);
+    }
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, grid);
+
+    desktop.press_key(window, Key::M);
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[1, 4, 7], [5, 0, -1], [2, 6, 3]]);
+    assert_eq!(world.canonical_grid(), grid);
+
+    desktop.press_key(window, Key::M);
+    run_frames(&mut world, 1);
+    assert_eq!(world.orientation, crate::layout::identity(3, 3));
+}
+
+#[test]
+fn gravity_drops_pieces_into_the_gap() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.mode = Box::new(Gravity);
+    world.exit_on_win = false;
+
+    // Sliding 8 left would solve the board, but 3 and 6 fall into the gap
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    for _ in 0..10 {
+        run_frames(&mut world, 20);
+    }
+
+    assert_eq!(world.grid, [[0, 1, -1], [3, 4, 2], [6, 7, 5]]);
+    assert!(!world.solved);
+}
+
+#[test]
+fn gravity_leaves_a_piece_pushed_upwards() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, -1], [6, 7, 5]]);
+    world.mode = Box::new(Gravity);
+
+    drag(&mut world, &desktop, "6", Vector2::new(0, -80));
+    run_frames(&mut world, 20);
+
+    assert_eq!(world.grid, SOLVED);
+    assert!(world.solved);
+}
+
+#[test]
+fn toroidal_boards_wrap_around_their_edges() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    world.exit_on_win = false;
+    // Classic rules keep 8 on its side of the board
+    assert_eq!(world.get_available_move(7), Vector2::new(0, 0));
+
+    world.set_mode(modes::by_name("toroidal").unwrap());
+    // so it leaves by the right edge to come in on the left
+    assert_eq!(world.get_available_move(7), Vector2::new(1, 0));
+    drag(&mut world, &desktop, "8", Vector2::new(80, 0));
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [7, 6, -1]]);
+    assert_eq!(world.moves, 1);
+
+    // The arrow keys wrap too, and the solver sits this one out
+    desktop.press_key(desktop.find_window("1"), Key::Left);
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    world.auto_solve_board();
+    assert!(world.solve_job.is_none());
+
+    assert!(modes::by_name::<FakeRenderer>("sideways").is_err());
+}
+
+#[test]
+fn time_attack_deals_boards_until_the_clock_runs_out() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.set_mode(Box::new(TimeAttack::new(Duration::from_secs(60))));
+    assert!(!world.exit_on_win);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert!(world.solved);
+    // The next board comes along a moment later
+    world.last_move_at -= simple::NEXT_BOARD_DELAY;
+    run_frames(&mut world, 1);
+    assert!(!world.solved);
+    assert!(world.playing);
+
+    // and once time's up the game is over
+    world.set_mode(Box::new(TimeAttack::new(Duration::ZERO)));
+    world.started_at = Some(Instant::now());
+    run_frames(&mut world, 1);
+    assert!(!world.playing);
+    assert!(world.locked);
+}
+
+#[test]
+fn command_palette_finds_actions_by_a_few_letters() {
+    let commands = Command::all();
+    assert_eq!(palette::search("ng", &commands)[0], Command::NewGame);
+    assert_eq!(
+        palette::search("dark", &commands)[0],
+        Command::Theme("dark")
+    );
+    assert_eq!(palette::search("STATS", &commands)[0], Command::ShowStats);
+    assert!(palette::search("zzz", &commands).is_empty());
+    assert_eq!(palette::fuzzy_score("", "Undo"), Some(0));
+
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    desktop.press_key_with(desktop.find_window("1"), Key::K, true);
+    run_frames(&mut world, 1);
+    let palette = desktop.find_window("Commands");
+    for unicode in "grid 4x4".chars() {
+        desktop.push_event(palette, Event::TextEntered { unicode });
+    }
+    desktop.press_key(palette, Key::Enter);
+    run_frames(&mut world, 1);
+
+    assert!(world.palette.is_none());
+    assert_eq!((world.width, world.height), (4, 4));
+    assert_eq!(world.pieces.len(), 15);
+    assert_eq!(world.grid.piece_count(), 15);
+    assert!(!world.solved);
+    desktop.find_window("15");
+
+    // Escape closes it without doing anything
+    desktop.press_key_with(desktop.find_window("1"), Key::K, true);
+    run_frames(&mut world, 1);
+    desktop.press_key(desktop.find_window("Commands"), Key::Escape);
+    run_frames(&mut world, 1);
+    assert!(world.palette.is_none());
+    assert_eq!(world.width, 4);
+}
+
+#[test]
+fn piece_states_decide_the_colours() {
+    let (mut world, _desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    run_frames(&mut world, 1);
+    assert_eq!(world.pieces[0].state, PieceState::Correct);
+    assert_eq!(world.pieces[7].state, PieceState::Incorrect);
+    assert_eq!(world.pieces[7].target_color, world.theme.out_of_place);
+
+    // Emphasis lasts as long as the auto-solve behind it, so just draw
+    world.focus_on(&[7]);
+    world.s_render();
+    assert_eq!(world.pieces[0].state, PieceState::Dimmed { in_place: true });
+    assert_eq!(
+        world.pieces[7].state,
+        PieceState::Focused { in_place: false }
+    );
+    assert_eq!(
+        world.pieces[0].target_color,
+        Emphasis::Dimmed.apply(world.theme.in_place)
+    );
+
+    // Nothing moves after a failed challenge, and it shows
+    world.clear_focus();
+    world.locked = true;
+    run_frames(&mut world, 1);
+    assert!(world
+        .pieces
+        .iter()
+        .all(|piece| matches!(piece.state, PieceState::Locked { .. })));
+
+    world.locked = false;
+    world.fog = true;
+    run_frames(&mut world, 1);
+    assert_eq!(world.pieces[0].state, PieceState::Hidden);
+    assert_eq!(world.pieces[0].target_color, world.theme.hidden);
+}
+
+#[test]
+fn image_mode_gives_every_piece_a_slice_of_the_picture() {
+    let (mut world, _desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.image = Some(Box::leak(Box::new(Texture::new().unwrap())));
+    run_frames(&mut world, 1);
+
+    assert!(world
+        .pieces
+        .iter()
+        .all(|piece| piece.labels.as_ref().unwrap().image.is_some()));
+    assert_eq!(PieceState::Correct.image_tint(), Color::WHITE);
+    assert_ne!(
+        PieceState::Locked { in_place: true }.image_tint(),
+        Color::WHITE
+    );
+}
+
+#[test]
+fn tile_packs_put_a_picture_on_each_piece() {
+    let dir = Path::new("pack");
+    let pack = tiles::parse_manifest("# Pets\nsize = 2\n2 = dog.png\n1 = cat.png\n", dir).unwrap();
+    assert_eq!(pack.size, Some(2));
+    assert_eq!(pack.tiles, [dir.join("cat.png"), dir.join("dog.png")]);
+    for manifest in [
+        "1 = a.png\n3 = c.png",
+        "1 = a.png\n1 = b.png",
+        "0 = a.png",
+        "size = 0",
+    ] {
+        assert!(
+            tiles::parse_manifest(manifest, dir).is_err(),
+            "{:?}",
+            manifest
+        );
+    }
+
+    // A 4x2 picture, white on the left and black on the right, shrinks to
+    // the average of each half
+    let mut pixels = Vec::new();
+    for _ in 0..2 {
+        pixels.extend([255; 8]);
+        pixels.extend([0, 0, 0, 255].repeat(2));
+    }
+    assert_eq!(
+        tiles::scale(&pixels, Vector2::new(4, 2), 2),
+        [[255; 4], [0, 0, 0, 255], [255; 4], [0, 0, 0, 255]].concat()
+    );
+    assert_eq!(tiles::scale(&[9, 9, 9, 9], Vector2::new(1, 1), 2), [9; 16]);
+
+    // Each tile lands in its piece's goal cell, and the gap's stays clear
+    let tiles: Vec<Vec<u8>> = (1..=3).map(|piece| vec![piece; 4]).collect();
+    assert_eq!(
+        tiles::compose(&tiles, 2, 2, 1),
+        [[1; 4], [2; 4], [3; 4], [0; 4]].concat()
+    );
+}
+
+#[test]
+fn desktop_mode_cuts_out_what_each_piece_covers() {
+    let (mut world, _desktop) = new_world(SOLVED);
+    let first = world.grid_pos_to_px(0, 0);
+    let last = world.grid_pos_to_px(2, 2);
+
+    // On a screen with two pixels to a unit, the cells are twice as far in
+    // and twice as big, and the gaps between pieces are skipped
+    let cells = world.desktop_cells(2.0);
+    assert_eq!(cells.len(), 9);
+    assert_eq!(cells[0], IntRect::new(first.x * 2, first.y * 2, 200, 200));
+    assert_eq!(cells[8], IntRect::new(last.x * 2, last.y * 2, 200, 200));
+    assert_eq!(cells[1].left - cells[0].left, 220);
+}
+
+#[test]
+fn fog_only_reveals_pieces_next_to_the_gap() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.fog = true;
+    world.exit_on_win = false;
+    run_frames(&mut world, 1);
+
+    let revealed = |world: &World<FakeRenderer>| -> Vec<i8> {
+        (0..8)
+            .filter(|&i| world.pieces[i].revealed)
+            .map(|i| i as i8)
+            .collect()
+    };
+    assert_eq!(revealed(&world), vec![4, 6, 7]);
+
+    // Moving the gap left hides 5 and 8 again and reveals 4
+    drag(&mut world, &desktop, "7", Vector2::new(80, 0));
+    run_frames(&mut world, 1);
+    assert_eq!(revealed(&world), vec![3, 6]);
+}
+
+#[test]
+fn chaos_slides_pieces_once_play_starts() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    let mut chaos = Chaos::new(Duration::ZERO, 2);
+
+    // Nothing happens before the first move
+    chaos.update(&mut world);
+    assert!(world.auto_moves.is_empty());
+
+    drag(&mut world, &desktop, "5", Vector2::new(0, 80));
+    let after_move = world.grid.clone();
+    for _ in 0..10 {
+        chaos.update(&mut world);
+        run_frames(&mut world, 20);
+    }
+
+    assert_eq!(chaos.remaining, 0);
+    assert!(world.auto_moves.is_empty());
+    assert_ne!(world.grid, after_move);
+    // Chaos moves aren't the player's
+    assert_eq!(world.moves, 1);
+    assert!(!world.solved);
+}
+
+#[test]
+fn relay_mirrors_moves_onto_the_other_board() {
+    let grid = [[0, 1, 2], [3, 4, 5], [6, -1, 7]];
+    let (mut left, desktop) = new_world(grid);
+    let (mut right, _) = new_world(grid);
+    Relay::place(&mut left, &mut right);
+    assert!(left.get_px_from_grid(0).x < right.get_px_from_grid(0).x);
+
+    let mut relay = Relay::new();
+    left.exit_on_win = false;
+
+    // The gap moves right on the left board, so left on the right board
+    drag(&mut left, &desktop, "8", Vector2::new(-80, 0));
+    relay.update(&mut left, &mut right);
+    run_frames(&mut right, 20);
+
+    assert_eq!(left.grid, SOLVED);
+    assert_eq!(right.grid, [[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    assert_eq!(right.moves, 0);
+
+    // Nothing relays back or twice
+    relay.update(&mut left, &mut right);
+    run_frames(&mut left, 20);
+    assert_eq!(left.grid, SOLVED);
+    assert!(right.auto_moves.is_empty());
+}
+
+#[test]
+fn power_ups_swap_neighbours_and_teleport_the_gap() {
+    let (mut world, desktop) = new_world([[1, 0, 2], [3, 4, 5], [6, -1, 7]]);
+    world.exit_on_win = false;
+    world.show_power_ups();
+
+    let click = |world: &mut World<FakeRenderer>, label: &str| {
+        let window = desktop.find_window(label);
+        desktop.press_mouse(window, 50, 50);
+        desktop.release_mouse(window);
+        run_frames(world, 1);
+    };
+
+    // Shift+click 1 then 2 swaps them
+    desktop.press_key(desktop.find_window("1"), Key::LShift);
+    click(&mut world, "1");
+    click(&mut world, "2");
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    // That finished both rows, earning one of each per row
+    assert_eq!(world.power_ups.as_ref().unwrap().swaps, 2);
+    assert_eq!(world.power_ups.as_ref().unwrap().teleports, 3);
+
+    // Pieces that aren't neighbours can't be swapped
+    click(&mut world, "1");
+    click(&mut world, "5");
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    // Ctrl+click 8 swaps it with the gap and solves the board
+    desktop.push_event(
+        desktop.find_window("1"),
+        Event::KeyReleased {
+            code: Key::LShift,
+            scan: sfml::window::Scancode::Unknown,
+            alt: false,
+            ctrl: false,
+            shift: false,
+            system: false,
+        },
+    );
+    desktop.press_key(desktop.find_window("1"), Key::LControl);
+    click(&mut world, "8");
+    run_frames(&mut world, 20);
+
+    assert_eq!(world.grid, SOLVED);
+    assert!(world.solved);
+    assert_eq!(world.moves, 2);
+    assert_eq!(world.history[1].power_up, Some(PowerUp::Teleport));
+    assert_eq!(world.power_ups.as_ref().unwrap().teleports, 2);
+}
+
+#[test]
+fn descent_shrinks_the_budget_until_time_runs_out() {
+    let (mut world, _desktop) = new_world(SOLVED);
+    world.exit_on_win = false;
+
+    let mut descent = Descent::start(&mut world);
+    assert_ne!(world.canonical_grid(), SOLVED);
+    let first_budget = descent.budget;
+
+    // Solve the first board
+    world.set_grid(Grid::solved(3, 3));
+    world.moves = 1;
+    run_frames(&mut world, 1);
+    descent.update(&mut world);
+
+    assert_eq!(descent.cleared, 1);
+    assert!(descent.budget < first_budget);
+    assert_ne!(world.canonical_grid(), SOLVED);
+
+    descent.deadline = std::time::Instant::now();
+    descent.update(&mut world);
+    assert!(descent.over);
+    assert!(!world.playing);
+}
+
+#[test]
+fn the_palette_cant_change_the_board_size_during_a_descent() {
+    let (mut world, desktop) = new_world(SOLVED);
+    world.exit_on_win = false;
+    world.three_by_three_only = Some("--descent");
+    let mut descent = Descent::start(&mut world);
+    let dealt = world.canonical_grid();
+
+    desktop.press_key_with(desktop.find_window("1"), Key::K, true);
+    run_frames(&mut world, 1);
+    let palette = desktop.find_window("Commands");
+    for unicode in "grid 4x4".chars() {
+        desktop.push_event(palette, Event::TextEntered { unicode });
+    }
+    desktop.press_key(palette, Key::Enter);
+    run_frames(&mut world, 1);
+    descent.update(&mut world);
+
+    assert_eq!((world.width, world.height), (3, 3));
+    assert_eq!(world.pieces.len(), 8);
+    assert_eq!(world.canonical_grid(), dealt);
+    assert!(!descent.over);
+}
+
+#[test]
+fn parity_trainer_asks_before_the_board_can_be_played() {
+    // 8 and 7 swapped: a single inversion
+    let swapped = Grid::from([[0, 1, 2], [3, 4, 5], [7, 6, -1]]);
+    assert_eq!(parity::inversions(&swapped), 1);
+    assert!(parity::explain(&swapped).contains("never"));
+    let mut rng = random::rng();
+    for _ in 0..20 {
+        let (grid, solvable) = parity::deal(&mut rng);
+        assert_eq!(Board::new(grid).is_solvable(), solvable);
+    }
+
+    let (mut world, desktop) = new_world(SOLVED);
+    world.exit_on_win = false;
+    let mut quiz = Quiz::start(&mut world);
+    assert!(world.locked);
+
+    let window = desktop.find_window("Parity trainer");
+    let key = if quiz.solvable { Key::Y } else { Key::N };
+    desktop.press_key(window, key);
+    quiz.update(&mut world);
+    assert_eq!((quiz.correct, quiz.asked), (1, 1));
+    assert_eq!(world.locked, !quiz.solvable);
+
+    // Moving on locks the next board until it's answered
+    desktop.press_key(window, Key::Space);
+    quiz.update(&mut world);
+    assert_eq!(quiz.answer, None);
+    assert!(world.locked);
+
+    let key = if quiz.solvable { Key::N } else { Key::Y };
+    desktop.press_key(window, key);
+    quiz.update(&mut world);
+    assert_eq!((quiz.correct, quiz.asked), (1, 2));
+}
+
+#[test]
+fn piece_size_is_clamped_to_fit_the_screen() {
+    let screen = Vector2::new(1920, 1080);
+    assert_eq!(fit_piece_size(100, 10, 3, 3, screen), Ok(100));
+    // 1080 high, less two gaps, split three ways
+    assert_eq!(fit_piece_size(500, 10, 3, 3, screen), Ok(353));
+    // or five ways for a 5x5 board
+    assert_eq!(fit_piece_size(500, 10, 5, 5, screen), Ok(212));
+
+    assert!(fit_piece_size(MIN_PIECE_SIZE - 1, 10, 3, 3, screen).is_err());
+    assert!(fit_piece_size(100, 10, 3, 3, Vector2::new(100, 100)).is_err());
+    assert!(fit_piece_size(100, 600, 3, 3, screen).is_err());
+
+    // Smaller boards play the same
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop.clone(), 3, 3, 60, 5, 0);
+    world.set_grid([[0, 1, 2], [3, 4, 5], [6, -1, 7]].into());
+    drag(&mut world, &desktop, "8", Vector2::new(-50, 0));
+    assert_eq!(world.grid, SOLVED);
+}
+
+#[test]
+fn layout_presets_place_the_board_and_side_windows() {
+    let ultrawide = Vector2::new(3440, 1440);
+    let left = Layout {
+        preset: LayoutPreset::Left,
+        margin: 0,
+    };
+    assert_eq!(
+        Layout::default().board_center(ultrawide, 100, 10, 3, 3),
+        Vector2::new(1670, 670)
+    );
+    // The board's middle a quarter of the way across
+    assert_eq!(
+        left.board_center(ultrawide, 100, 10, 3, 3).x,
+        860 - 160 + 110
+    );
+    let margin = Layout {
+        margin: 1000,
+        ..left
+    };
+    assert_eq!(margin.board_center(ultrawide, 100, 10, 3, 3).x, 1000 + 110);
+    assert_eq!(
+        LayoutPreset::for_screen(Vector2::new(1080, 1920)),
+        LayoutPreset::Stacked
+    );
+
+    // Stacked, the power-ups go under the right half of the board
+    let (mut world, desktop) = new_world(SOLVED);
+    world.set_layout(Layout {
+        preset: LayoutPreset::Stacked,
+        margin: 0,
+    });
+    world.show_power_ups();
+    let top_left = desktop.window_position(desktop.find_window("1"));
+    assert_eq!(
+        desktop.window_position(desktop.find_window("Power-ups")),
+        Vector2::new(top_left.x + 160 + 5, top_left.y + 320 + 10)
+    );
+
+    let mut preferences = LayoutPreferences::default();
+    preferences.set(ultrawide, margin);
+    preferences.set(Vector2::new(1080, 1920), Layout::default());
+    preferences.set(ultrawide, left);
+    assert_eq!(preferences.get(ultrawide), Some(left));
+    assert_eq!(preferences.get(Vector2::new(1920, 1080)), None);
+    assert_eq!(
+        LayoutPreferences::parse(&preferences.to_text()),
+        Ok(preferences)
+    );
+    assert!(LayoutPreferences::parse("3440x1440 = diagonal").is_err());
+    assert!(LayoutPreferences::parse("wide = left").is_err());
+}
+
+#[test]
+fn dragging_the_resize_handle_scales_the_board() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    let top_left = desktop.window_position(desktop.find_window("1"));
+    let mut handle = ResizeHandle::open(&mut world);
+    let grip = desktop.find_window("Resize");
+    // 3 pieces and 2 gaps of 100 and 10
+    assert_eq!(
+        desktop.window_position(grip),
+        top_left + Vector2::new(320, 320)
+    );
+
+    // Drag the corner out to make the board half as big again
+    desktop.press_mouse(grip, 5, 5);
+    handle.update(&mut world);
+    desktop.move_mouse(top_left + Vector2::new(480, 480));
+    handle.update(&mut world);
+    desktop.release_mouse(grip);
+    handle.update(&mut world);
+    run_frames(&mut world, 120);
+
+    assert_eq!((world.piece_size, world.padding), (150, 15));
+    assert_eq!(desktop.window_size(desktop.find_window("5")), 150);
+    assert_eq!(desktop.window_position(desktop.find_window("1")), top_left);
+    assert_eq!(
+        desktop.window_position(desktop.find_window("5")),
+        top_left + Vector2::new(165, 165)
+    );
+    assert_eq!(
+        desktop.window_position(grip),
+        top_left + Vector2::new(480, 480)
+    );
+
+    // The board still plays at its new size
+    drag(&mut world, &desktop, "8", Vector2::new(-120, 0));
+    assert_eq!(world.grid, SOLVED);
+
+    // Pieces can't be shrunk past the smallest usable size
+    desktop.press_mouse(grip, 5, 5);
+    handle.update(&mut world);
+    desktop.move_mouse(top_left);
+    handle.update(&mut world);
+    assert_eq!(world.piece_size, MIN_PIECE_SIZE);
+}
+
+#[test]
+fn numbers_and_times_follow_the_locale() {
+    let german = Locale::from_name("de_DE.UTF-8");
+    assert_eq!(
+        german.format_duration(Duration::from_millis(83_400)),
+        "1:23,4"
+    );
+    assert_eq!(german.format_count(12345), "12.345");
+    assert_eq!(german.format_decimal(1234.5, 2), "1.234,50");
+
+    let french = Locale::from_name("fr_FR");
+    assert_eq!(french.format_count(1234), "1\u{a0}234");
+
+    let english = Locale::from_name("en_US.UTF-8");
+    assert_eq!(english, Locale::from_name("C"));
+    assert_eq!(
+        english.format_duration(Duration::from_millis(83_400)),
+        "1:23.4"
+    );
+    assert_eq!(
+        english.format_duration_millis(Duration::from_millis(83_456)),
+        "1:23.456"
+    );
+    assert_eq!(english.format_count(999), "999");
+    assert_eq!(english.format_count(1000), "1,000");
+    assert_eq!(english.format_decimal(-0.25, 1), "-0.2");
+}
+
+#[test]
+fn update_notice_shows_newer_releases() {
+    let json = r###"{"tag_name": "v0.2.0", "html_url": "https://example.com/v0.2.0",
+        "body": "## What's new\r\n\r\n- Parity trainer mode for learning which boards can be solved"}"###;
+    let release = updates::parse_release(json).unwrap();
+    assert_eq!(release.version, "0.2.0");
+    assert!(updates::parse_release("{}").is_err());
+
+    assert!(updates::is_newer("0.2.0", "0.1.0"));
+    assert!(updates::is_newer("0.1.1", "0.1"));
+    assert!(!updates::is_newer("0.1.0", "0.1.0"));
+    assert!(!updates::is_newer("0.0.9", "0.1.0"));
+
+    let lines = updates::wrap(&release.notes, 32);
+    assert_eq!(lines[0], "What's new");
+    assert!(lines.iter().all(|line| line.chars().count() <= 32));
+    assert!(lines.len() > 2);
+
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut notice = UpdateNotice::open(&mut desktop.clone(), &release);
+    notice.render();
+    desktop.press_key(desktop.find_window("Update available"), Key::Escape);
+    notice.update();
+    assert!(notice.closed);
+}
+
+const PACK: &str = "title: 2026-W42\nWarm up | 123/456/7_8\nMix | 153/_86/472\n";
+const PACK_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+const PACK_SIGNATURE: &str = "untrusted comment: test pack
+RUQBAgMEBQYHCCYlziuXyDTihOz5n+E1x1ebHUxg7/xgm/SgiIqCESjKUeQuul9qk3DTUQ5xzXBuIS1RXLCROoPMZE7C6jui0QI=
+trusted comment: timestamp:1760000000
+p2eqPFipEJ9OOa+ZHwU0LXLrrzSorvVCooIjDShr0lbY3UUCzWR1G6Fxpvz1jEN03S0wavGb/8qDHXf/UID3DQ==
+";
+
+#[test]
+fn packs_parse_and_check_their_signature() {
+    let pack = packs::parse(PACK).unwrap();
+    assert_eq!(pack.title, "2026-W42");
+    assert_eq!(pack.levels.len(), 2);
+    assert_eq!(pack.levels[0].0, "Warm up");
+    assert_eq!(pack.levels[0].1, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+
+    assert!(packs::verify(PACK, PACK_SIGNATURE, PACK_KEY).is_ok());
+    let tampered = PACK.replace("7_8", "78_");
+    assert!(packs::verify(&tampered, PACK_SIGNATURE, PACK_KEY).is_err());
+
+    assert!(packs::parse("Warm up | 123/456/7_8").is_err());
+    assert!(packs::parse("title: Bad\nOops | 213/456/78_").is_err());
+}
+
+#[test]
+fn resources_prefer_installed_files_over_built_in_ones() {
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-assets-{}", std::process::id()));
+    let missing = dir.join("missing");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(resources::PATTERNS), "Mine | 012345678 | \n").unwrap();
+
+    let dirs = [missing.clone(), dir.clone()];
+    assert_eq!(
+        resources::load_from(&dirs, resources::PATTERNS),
+        b"Mine | 012345678 | \n"
+    );
+    assert_eq!(
+        resources::load_from(&[missing], resources::PATTERNS),
+        resources::embedded(resources::PATTERNS)
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn theme_file_recolours_the_board() {
+    let path = std::env::temp_dir().join(format!("slide-puzzle-theme-{}.txt", std::process::id()));
+    std::fs::write(&path, "# Blue for done\nin_place = #0000ff\n").unwrap();
+
+    let (mut world, _desktop) = new_world(SOLVED);
+    world.theme_watcher = Some(ThemeWatcher::new(path.clone()));
+    run_frames(&mut world, 1);
+
+    assert_eq!(world.theme.in_place, Color::rgb(0, 0, 255));
+    assert_eq!(world.theme.out_of_place, Theme::default().out_of_place);
+    assert_eq!(world.pieces[0].target_color, Color::rgb(0, 0, 255));
+
+    assert!(Theme::parse("in_place = green").is_err());
+    assert!(Theme::parse("background = #000000").is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn theme_glyphs_animate_numbers_from_a_sprite_sheet() {
+    let theme = Theme::parse("glyph_fps = 4\nglyphs = digits.png\nglyph_frames = 3\n").unwrap();
+    let style = theme.glyphs.unwrap();
+    assert_eq!(style.sheet, Path::new("digits.png"));
+    assert_eq!((style.frames, style.fps), (3, 4.0));
+    // Sheets are found next to the theme that names them
+    let moved = theme.relative_to(Path::new("themes"));
+    assert_eq!(moved.glyphs.unwrap().sheet, Path::new("themes/digits.png"));
+    assert!(Theme::parse("glyphs =").is_err());
+    assert!(Theme::parse("glyphs = digits.png\nglyph_frames = 0").is_err());
+    assert!(Theme::parse("glyph_fps = fast").is_err());
+
+    // Digits are rows and frames are columns
+    let glyph = Vector2::new(8, 12);
+    assert_eq!(glyphs::frame_rect(3, 2, glyph), IntRect::new(16, 36, 8, 12));
+    // Four frames a second, with each digit a frame ahead of the last
+    assert_eq!(style.frame_at(Duration::from_millis(100), 0), 0);
+    assert_eq!(style.frame_at(Duration::from_millis(600), 0), 2);
+    assert_eq!(style.frame_at(Duration::from_millis(600), 1), 0);
+
+    // A sheet that can't be used leaves the font's numbers in place
+    let (mut world, _desktop) = new_world(SOLVED);
+    world.theme = Theme::parse("glyphs = /nowhere/digits.png").unwrap();
+    run_frames(&mut world, 1);
+    let labels = world.pieces[0].labels.as_ref().unwrap();
+    assert_eq!(labels.key.glyphs, world.theme.glyphs);
+    assert!(labels.glyphs.is_none());
+}
+
+#[test]
+fn config_file_sets_defaults_and_is_written_on_first_run() {
+    let config = Config::parse(
+        "# Mine\npiece_size = 120 # bigger\nshuffle = 50\n\n[colors]\nin_place = \"#0000ff\" # blue\n",
+    )
+    .unwrap();
+    assert_eq!(config.piece_size, 120);
+    assert_eq!(config.shuffle, 50);
+    assert_eq!(config.padding, Config::default().padding);
+    assert_eq!(config.theme.in_place, Color::rgb(0, 0, 255));
+
+    assert!(Config::parse("piece_size = big").is_err());
+    assert!(Config::parse("shuffle = 0").is_err());
+    assert!(Config::parse("speed = 3").is_err());
+    assert!(Config::parse("[colors]\nin_place = #0000ff").is_err());
+    assert!(Config::parse("[keys]").is_err());
+
+    // Any way TOML has of writing the same thing
+    let config = Config::parse("shuffle = 1_000\ncolors.in_place = '#0000ff'\n").unwrap();
+    assert_eq!(config.shuffle, 1000);
+    assert_eq!(config.theme.in_place, Color::rgb(0, 0, 255));
+    let config = Config::parse("colors = { goal = \"#000000\" }").unwrap();
+    assert_eq!(config.theme.goal, Color::BLACK);
+    assert_eq!(config.theme.in_place, Config::default().theme.in_place);
+    assert!(Config::parse("[colors]\nin_place = \"blue\"").is_err());
+    assert!(Config::parse("[colors]\nsky = \"#0000ff\"").is_err());
+
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-config-{}", std::process::id()));
+    let path = dir.join(config::FILE_NAME);
+    assert_eq!(Config::load(&path), Config::default());
+    // Everything in the file written is commented out, so it changes nothing
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(Config::parse(&written), Ok(Config::default()));
+    assert!(written.contains("# shuffle = 7"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn goal_labels_follow_the_board_and_show_in_captures() {
+    let (mut world, desktop) = new_world(SOLVED);
+    assert_eq!(world.goal_name(0), "r1c1");
+    assert_eq!(world.goal_name(5), "r2c3");
+
+    desktop.press_key(desktop.find_window("1"), Key::G);
+    desktop.press_key(desktop.find_window("1"), Key::R);
+    run_frames(&mut world, 1);
+    assert!(world.goal_labels);
+    // A quarter turn clockwise takes the top left corner to the top right
+    assert_eq!(world.goal_name(0), "r1c3");
+    // and the label drawn is rebuilt to match
+    assert_eq!(world.pieces[0].labels.as_ref().unwrap().key.goal_cell, 2);
+
+    let dir = std::env::temp_dir().join(format!("slide-puzzle-goals-{}", std::process::id()));
+    world.capture_dir = dir.clone();
+    desktop.press_key(desktop.find_window("1"), Key::P);
+    run_frames(&mut world, 1);
+    io_worker::flush();
+    let _ = std::fs::remove_dir_all(&dir);
+    // A background, a label and a goal for each of the 8 pieces
+    assert_eq!(desktop.captures(), [24]);
+
+    let theme = Theme::parse("goal = #ffff00").unwrap();
+    assert_eq!(theme.goal, Color::rgb(255, 255, 0));
+}
+
+#[test]
+fn theme_shape_draws_pieces_inside_their_windows() {
+    let theme = Theme::parse("shape = hexagon\nin_place = #0000ff\n").unwrap();
+    assert_eq!(theme.shape, PieceShape::Hexagon);
+    assert!(Theme::parse("shape = triangle").is_err());
+
+    let (mut world, desktop) = new_world(SOLVED);
+    world.theme = theme;
+    run_frames(&mut world, 1);
+
+    // The piece is drawn as a shape, leaving the rest of the window black
+    assert_eq!(desktop.clear_color(desktop.find_window("1")), Color::BLACK);
+
+    // The whole window still grabs the piece, corners included
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.theme = theme;
+    let window = desktop.find_window("8");
+    let start = desktop.window_position(window);
+    desktop.press_mouse(window, 2, 2);
+    run_frames(&mut world, 1);
+    desktop.move_mouse(Vector2::new(start.x + 2 - 80, start.y + 2));
+    run_frames(&mut world, 1);
+    desktop.release_mouse(window);
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, SOLVED);
+}
+
+#[test]
+fn keyboard_cursor_picks_a_piece_and_enter_slides_it() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    let window = desktop.find_window("1");
+    let press = |world: &mut World<FakeRenderer>, key| {
+        desktop.press_key(window, key);
+        run_frames(world, 1);
+    };
+
+    // The cursor starts next to the gap
+    press(&mut world, Key::Tab);
+    assert_eq!(world.cursor, Some(3));
+    press(&mut world, Key::Right);
+    assert_eq!(world.cursor, Some(4));
+    // Not next to the gap, so nothing moves
+    press(&mut world, Key::Enter);
+    assert_eq!(world.moves, 0);
+
+    press(&mut world, Key::Down);
+    press(&mut world, Key::Enter);
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    // The cursor stays on the piece it slid, and hops over the gap
+    assert_eq!(world.cursor, Some(6));
+    press(&mut world, Key::Right);
+    assert_eq!(world.cursor, Some(7));
+    press(&mut world, Key::Right);
+    assert_eq!(world.cursor, Some(7));
+
+    // Tab again and the arrow keys slide pieces
+    press(&mut world, Key::Tab);
+    assert_eq!(world.cursor, None);
+    press(&mut world, Key::Right);
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+}
+
+#[test]
+fn arrow_keys_are_buffered_and_played_in_order() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, -1, 5], [6, 4, 7]]);
+    let window = desktop.find_window("1");
+
+    // All three arrive in one frame and play over the next three
+    for key in [Key::Down, Key::Right, Key::Up] {
+        desktop.press_key(window, key);
+    }
+    run_frames(&mut world, 1);
+    assert_eq!(world.moves, 1);
+    run_frames(&mut world, 2);
+    assert_eq!(world.grid, [[3, 0, 2], [-1, 1, 5], [6, 4, 7]]);
+    assert_eq!(world.moves, 3);
+
+    // Keys past the buffer's size are dropped
+    for _ in 0..5 {
+        desktop.press_key(window, Key::Down);
+        desktop.press_key(window, Key::Up);
+    }
+    run_frames(&mut world, 20);
+    assert_eq!(world.moves, 3 + 8);
+}
+
+#[test]
+fn speed_mode_snaps_pieces_and_plays_every_buffered_move_at_once() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, -1, 5], [6, 4, 7]]);
+    world.speed = true;
+    let window = desktop.find_window("1");
+
+    for key in [Key::Down, Key::Right, Key::Up] {
+        desktop.press_key(window, key);
+    }
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[3, 0, 2], [-1, 1, 5], [6, 4, 7]]);
+    assert_eq!(world.moves, 3);
+    // No glide, piece 1 is already where it belongs
+    let target = world.grid_pos_to_px(1, 0);
+    assert_eq!(desktop.window_position(window), target);
+}
+
+#[test]
+fn metrics_count_dropped_frames_and_input_lag() {
+    let budget = Duration::from_millis(16);
+    let mut metrics = FrameMetrics::new(budget);
+    metrics.record_frame(Duration::from_millis(4), budget);
+    // Three frames' worth of waiting is two frames missed
+    metrics.record_frame(Duration::from_millis(40), budget * 3);
+    assert_eq!(metrics.frames, 2);
+    assert_eq!(metrics.dropped, 2);
+    assert_eq!(metrics.worst_frame, Duration::from_millis(40));
+
+    // A buffered key moves its piece a frame later, which is what gets measured
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, -1, 5], [6, 4, 7]]);
+    desktop.press_key(desktop.find_window("1"), Key::Down);
+    run_frames(&mut world, 1);
+    assert_eq!(world.moves, 1);
+    assert_eq!(world.input_latency, None);
+    run_frames(&mut world, 1);
+    let latency = world.input_latency.take().unwrap();
+    metrics.record_latency(latency);
+    assert_eq!(metrics.worst_latency, Some(latency));
+}
+
+#[test]
+fn gamepad_buttons_slide_pieces_and_can_be_remapped() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.exit_on_win = false;
+    let press = |button: u32| Event::JoystickButtonPressed {
+        joystickid: 0,
+        button,
+    };
+
+    // Down, then up again, with the default buttons
+    desktop.push_event(desktop.find_window("1"), press(0));
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[0, 1, 2], [3, -1, 5], [6, 4, 7]]);
+    desktop.push_event(desktop.find_window("1"), press(3));
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    assert_eq!(world.moves, 2);
+
+    let mut renderer = desktop.clone();
+    let mut remapper = Remapper::open(&mut renderer);
+    let window = desktop.find_window("Gamepad setup");
+    for button in [10, 11, 11, 12] {
+        desktop.push_event(window, press(button));
+    }
+    assert_eq!(remapper.update(), None);
+    desktop.push_event(window, press(13));
+    let mapping = remapper.update().unwrap();
+
+    assert_eq!(mapping.buttons, [10, 11, 12, 13]);
+    assert_eq!(Mapping::parse(&mapping.to_text()), Ok(mapping));
+    assert!(Mapping::parse("sideways = 4").is_err());
+}
+
+#[test]
+fn shared_input_hears_each_gamepad_press_once() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.exit_on_win = false;
+    assert!(world.share_input());
+    let down = Event::JoystickButtonPressed {
+        joystickid: 0,
+        button: 0,
+    };
+
+    // Every window hears the same press, only the input window's counts
+    for title in ["Input", "1", "2", "5"] {
+        desktop.push_event(desktop.find_window(title), down);
+    }
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, [[0, 1, 2], [3, -1, 5], [6, 4, 7]]);
+    assert_eq!(world.moves, 1);
+
+    // The keyboard still comes through the pieces' windows
+    desktop.press_key(desktop.find_window("4"), Key::Down);
+    run_frames(&mut world, 2);
+    assert_eq!(world.moves, 2);
+}
+
+#[test]
+fn profiles_keep_their_files_apart() {
+    let alice = Profile::named("alice").unwrap();
+    let bob = Profile::named("bob_2").unwrap();
+    for name in ["", "../alice", "a b", "alice/bob", &"x".repeat(33)] {
+        assert!(Profile::named(name).is_err(), "{:?}", name);
+    }
+
+    assert_ne!(alice.config_dir(), bob.config_dir());
+    assert_ne!(alice.data_dir(), Profile::default().data_dir());
+    assert!(alice
+        .data_path("splits.txt".into())
+        .ends_with("profiles/alice/splits.txt"));
+    assert_eq!(
+        Profile::default().data_path("splits.txt".into()),
+        Path::new("splits.txt")
+    );
+    assert_eq!(
+        alice.data_path("/tmp/splits.txt".into()),
+        Path::new("/tmp/splits.txt")
+    );
+
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut picker = ProfilePicker::open(&mut desktop.clone(), vec![alice, bob.clone()]);
+    let window = desktop.find_window("Who's playing?");
+    // Nobody is listed third
+    desktop.press_key(window, Key::Num3);
+    assert_eq!(picker.update(), None);
+    desktop.press_key(window, Key::Num2);
+    assert_eq!(picker.update(), Some(bob));
+
+    desktop.press_key(window, Key::Escape);
+    assert_eq!(picker.update(), Some(Profile::default()));
+}
+
+#[test]
+fn bookmarked_seeds_are_named_and_listed_as_favourites() {
+    let path =
+        std::env::temp_dir().join(format!("slide-puzzle-bookmarks-{}.txt", std::process::id()));
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [6, -1, 7]]);
+    world.bookmarks_path = Some(path.clone());
+
+    desktop.press_key(desktop.find_window("1"), Key::B);
+    run_frames(&mut world, 1);
+    let prompt = desktop.find_window("Bookmark seed");
+    for unicode in "Nice onee".chars() {
+        desktop.push_event(prompt, Event::TextEntered { unicode });
+    }
+    desktop.press_key(prompt, Key::Backspace);
+    desktop.press_key(prompt, Key::Enter);
+    run_frames(&mut world, 1);
+    io_worker::flush();
+
+    let nice = Bookmark {
+        name: "Nice one".into(),
+        seed: random::seed(),
+    };
+    assert!(world.bookmark_prompt.is_none());
+    assert_eq!(Bookmarks::load(&path).entries, vec![nice.clone()]);
+
+    // Bookmarking the same seed again renames it, and a blank name is
+    // made up
+    let mut bookmarks = Bookmarks::parse("7 = = sign\n").unwrap();
+    assert_eq!(bookmarks.entries[0].name, "= sign");
+    bookmarks.add("Renamed", 7);
+    bookmarks.add(" ", 8);
+    assert_eq!(bookmarks.to_text(), "7 = Renamed\n8 = Seed 8\n");
+    assert!(Bookmarks::parse("seven = nope").is_err());
+
+    let mut picker = FavouritesPicker::open(
+        &mut desktop.clone(),
+        vec![nice.clone(), bookmarks.entries[0].clone()],
+    );
+    let window = desktop.find_window("Favourites");
+    // The newest is listed first
+    desktop.press_key(window, Key::Num2);
+    assert_eq!(picker.update(), Some(nice));
+    desktop.press_key(window, Key::Escape);
+    assert_eq!(picker.update(), None);
+    assert!(picker.cancelled);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "ranked")]
+#[test]
+fn ranked_server_replies_are_understood() {
+    use crate::ranked::{self, Match, Outcome};
+
+    assert_eq!(ranked::parse_match(r#"{"status": "waiting"}"#), Ok(None));
+    assert_eq!(
+        ranked::parse_match(
+            r#"{"status": "matched", "match": "m1", "seed": 42, "opponent": "bob",
+                "rating": 1500, "opponent_rating": 1530}"#
+        ),
+        Ok(Some(Match {
+            id: "m1".into(),
+            seed: 42,
+            opponent: "bob".into(),
+            rating: 1500,
+            opponent_rating: 1530,
+        }))
+    );
+    assert!(ranked::parse_match(r#"{"status": "matched", "match": "m1"}"#).is_err());
+    assert!(ranked::parse_match("<html>").is_err());
+
+    assert_eq!(ranked::parse_outcome(r#"{"status": "pending"}"#), Ok(None));
+    assert_eq!(
+        ranked::parse_outcome(
+            r#"{"status": "finished", "won": false, "rating": 1488, "change": -12}"#
+        ),
+        Ok(Some(Outcome {
+            won: false,
+            rating: 1488,
+            change: -12,
+        }))
+    );
+    assert!(ranked::parse_outcome(r#"{"status": "abandoned"}"#).is_err());
+}
+
+#[cfg(feature = "ranked")]
+#[test]
+fn chat_messages_are_understood() {
+    use crate::chat::{self, ChatMessage};
+
+    assert_eq!(
+        chat::parse_messages(r#"{"messages": [{"player": "bob", "text": "gl hf"}]}"#),
+        Ok(vec![ChatMessage {
+            player: "bob".into(),
+            text: "gl hf".into(),
+        }])
+    );
+    assert_eq!(chat::parse_messages(r#"{"messages": []}"#), Ok(Vec::new()));
+    assert!(chat::parse_messages(r#"{"messages": [{"player": "bob"}]}"#).is_err());
+    assert!(chat::parse_messages("{}").is_err());
+}
+
+#[cfg(feature = "ranked")]
+#[test]
+fn opponent_progress_glides_between_updates_and_resyncs() {
+    use crate::race::{self, OpponentTrack, Progress, Sample};
+
+    let ms = Duration::from_millis;
+    let sample = |seq, at, moves, in_place| Sample {
+        seq,
+        at: ms(at),
+        moves,
+        in_place,
+        board: None,
+    };
+    assert_eq!(race::parse_sample(r#"{"opponent": null}"#), Ok(None));
+    assert_eq!(
+        race::parse_sample(r#"{"opponent": {"seq": 2, "at_ms": 900, "moves": 4, "in_place": 3}}"#),
+        Ok(Some(sample(2, 900, 4, 3)))
+    );
+
+    let mut track = OpponentTrack::default();
+    assert_eq!(track.progress(ms(0)), Progress::default());
+    // Both took 100ms to arrive, and a repeat of the first changes nothing
+    track.push(sample(1, 0, 0, 0), ms(100));
+    track.push(sample(2, 1000, 10, 4), ms(1100));
+    track.push(sample(1, 0, 0, 0), ms(1200));
+
+    // Shown half a second late, so halfway between the two
+    assert_eq!(
+        track.progress(ms(1100)),
+        Progress {
+            moves: 5.0,
+            in_place: 2.0,
+        }
+    );
+    assert_eq!(track.progress(ms(5000)).in_place, 4.0);
+    assert!(!track.desynced(ms(5000)));
+
+    // One arriving seconds later than the rest means the estimate is off
+    track.push(sample(3, 2000, 20, 8), ms(5000));
+    assert!(track.desynced(ms(5000)));
+    assert_eq!(track.progress(ms(5000)).in_place, 8.0);
+    assert!(!track.desynced(ms(8000)));
+}
+
+#[cfg(feature = "ranked")]
+#[test]
+fn opponent_board_follows_the_progress_being_shown() {
+    use crate::race::{self, OpponentTrack, Sample};
+
+    let ms = Duration::from_millis;
+    let scrambled = Grid::from([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    let solved = Grid::solved(3, 3);
+    let sample = |seq, at, board| Sample {
+        seq,
+        at: ms(at),
+        moves: seq as u32,
+        in_place: 0,
+        board,
+    };
+    assert_eq!(
+        race::parse_sample(
+            r#"{"opponent": {"seq": 1, "at_ms": 0, "moves": 0, "in_place": 6, "board": "123/456/_78"}}"#
+        )
+        .unwrap()
+        .unwrap()
+        .board,
+        Some(scrambled.clone())
+    );
+    assert!(race::parse_sample(
+        r#"{"opponent": {"seq": 1, "at_ms": 0, "moves": 0, "in_place": 6, "board": "123/456"}}"#
+    )
+    .is_err());
+
+    let mut track = OpponentTrack::default();
+    assert_eq!(track.board(ms(0)), None);
+    track.push(sample(1, 0, Some(scrambled.clone())), ms(100));
+    track.push(sample(2, 1000, Some(solved.clone())), ms(1100));
+
+    // Still on the first board until the second one is due
+    assert_eq!(track.board(ms(100)), Some(&scrambled));
+    assert_eq!(track.board(ms(1500)), Some(&scrambled));
+    assert_eq!(track.board(ms(1600)), Some(&solved));
+}
+
+#[test]
+fn idle_nudge_points_at_the_solvers_next_piece() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    assert_eq!(world.nudged_piece(), None);
+
+    world.nudge_after = Some(Duration::from_millis(50));
+    assert_eq!(world.nudged_piece(), None);
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(world.nudged_piece(), Some(6));
+
+    // Moving resets the wait
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    assert_eq!(world.nudged_piece(), None);
+}
+
+#[test]
+fn hint_key_pulses_the_next_piece_a_few_times_a_game() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    world.exit_on_win = false;
+    let window = desktop.find_window("1");
+    assert_eq!(world.hinted_piece(), None);
+
+    desktop.press_key(window, Key::H);
+    run_frames(&mut world, 1);
+    assert_eq!(world.hinted_piece(), Some(6));
+    assert!(matches!(world.pieces[6].state, PieceState::Hinted { .. }));
+    assert_eq!(world.hints_used, 1);
+
+    // Gone once the piece is moved
+    drag(&mut world, &desktop, "7", Vector2::new(-80, 0));
+    assert_eq!(world.hinted_piece(), None);
+
+    // Asking past the limit shows nothing new
+    for _ in 0..HINTS_PER_GAME {
+        desktop.press_key(window, Key::H);
+    }
+    run_frames(&mut world, 1);
+    assert_eq!(world.hints_used, HINTS_PER_GAME);
+    assert_eq!(world.hinted_piece(), Some(7));
+
+    let won = Rc::new(RefCell::new(None));
+    let heard = won.clone();
+    world.hooks.on_win = Some(Box::new(move |win| *heard.borrow_mut() = Some(win.hints)));
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert_eq!(*won.borrow(), Some(HINTS_PER_GAME));
+}
+
+#[test]
+fn hints_on_bigger_boards_come_from_the_solver_thread() {
+    let desktop = FakeRenderer::new(1920, 1080);
+    let mut world = World::new(desktop.clone(), 4, 4, 100, 10, 0);
+    world.set_grid(Grid::from([
+        [0, 1, 2, 3],
+        [4, 5, 6, 7],
+        [8, 9, 10, 11],
+        [12, -1, 13, 14],
+    ]));
+
+    desktop.press_key(desktop.find_window("1"), Key::H);
+    run_frames(&mut world, 1);
+    // Searched for rather than looked up, without holding up the frame
+    assert!(world.solve_job.is_some());
+
+    let started = Instant::now();
+    while world.hinted_piece().is_none() && started.elapsed() < Duration::from_secs(10) {
+        run_frames(&mut world, 1);
+    }
+    assert_eq!(world.hinted_piece(), Some(13));
+    assert_eq!(world.hints_used, 1);
+    // The hint only pulses the piece, nothing is played
+    assert!(world.auto_moves.is_empty());
+}
+
+#[test]
+fn board_moves_to_the_main_screen_when_its_screen_is_unplugged() {
+    let (mut world, desktop) = new_world(SOLVED);
+    world.exit_on_win = false;
+    let main = IntRect::new(0, 0, 1920, 1080);
+    let side = IntRect::new(1920, 0, 1280, 1024);
+    let check_screens = |world: &mut World<FakeRenderer>| {
+        world.monitors_checked_at -= MONITOR_CHECK_INTERVAL;
+        run_frames(world, 1);
+    };
+
+    // Plugging a screen in leaves the board be
+    desktop.set_monitors(vec![main, side]);
+    world.set_center(Vector2::new(2560, 512));
+    let window = desktop.find_window("5");
+    let on_side = desktop.window_position(window);
+    check_screens(&mut world);
+    assert_eq!(desktop.window_position(window), on_side);
+
+    desktop.set_monitors(vec![main]);
+    check_screens(&mut world);
+    let moved = desktop.window_position(window);
+    assert!((0..1920).contains(&moved.x) && (0..1080).contains(&moved.y));
+    desktop.find_window("Notice");
+}
+
+#[test]
+fn screensaver_exits_on_input_without_moving_pieces() {
+    let grid = [[0, 1, 2], [3, 4, 5], [6, -1, 7]];
+    let (mut world, desktop) = new_world(grid);
+    world.exit_on_input = true;
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+
+    assert_eq!(world.grid, grid);
+    assert!(!world.playing);
+}
+
+#[test]
+fn simple_mode_only_slides_pieces_and_deals_new_boards() {
+    let grid = [[0, 1, 2], [3, 4, 5], [6, -1, 7]];
+    let (mut world, desktop) = new_world(grid);
+    world.simple = true;
+    world.exit_on_win = false;
+
+    let window = desktop.find_window("1");
+    for key in [Key::Escape, Key::G, Key::R, Key::M, Key::H] {
+        desktop.press_key(window, key);
+    }
+    desktop.press_key_with(window, Key::Num3, true);
+    desktop.push_event(
+        window,
+        Event::MouseButtonPressed {
+            button: mouse::Button::Right,
+            x: 50,
+            y: 50,
+        },
+    );
+    run_frames(&mut world, 1);
+    assert!(world.playing);
+    assert!(!world.goal_labels);
+    assert!(world.solve_job.is_none());
+    assert_eq!(world.grid, grid);
+
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    assert!(world.solved);
+    // The solved board stays up for a moment
+    run_frames(&mut world, 1);
+    assert_eq!(world.grid, SOLVED);
+
+    world.last_move_at -= simple::NEXT_BOARD_DELAY;
+    run_frames(&mut world, 1);
+    assert_ne!(world.grid, SOLVED);
+    assert_eq!(world.moves, 0);
+    assert!(Board::new(world.canonical_grid()).is_solvable());
+}
+
+#[test]
+fn exhibition_alternates_showcases_and_visitors() {
+    let (mut world, desktop) = new_world(SOLVED);
+    world.exit_on_win = false;
+    let mut exhibition = Exhibition::start(&mut world);
+    assert_eq!(exhibition.lines()[0], "Watch this!");
+    assert_ne!(world.grid, SOLVED);
+
+    // A visitor touching the board takes over from the showcase
+    desktop.press_key(desktop.find_window("1"), Key::Space);
+    run_frames(&mut world, 1);
+    exhibition.update(&mut world);
+    assert_eq!(exhibition.lines()[0], "Your turn!");
+    assert_eq!(world.moves, 0);
+
+    world.set_grid([[0, 1, 2], [3, 4, 5], [6, -1, 7]].into());
+    drag(&mut world, &desktop, "8", Vector2::new(-80, 0));
+    exhibition.update(&mut world);
+    assert_eq!(exhibition.lines(), ["Solved!", "In 1 move"]);
+
+    // Moving on from the solved board starts another showcase, which goes
+    // back to waiting for someone once it's been left alone long enough
+    world.set_grid([[0, 1, 2], [3, 4, 5], [6, -1, 7]].into());
+    run_frames(&mut world, 1);
+    exhibition.update(&mut world);
+    assert_eq!(exhibition.lines()[0], "Watch this!");
+
+    desktop.press_key(desktop.find_window("1"), Key::Space);
+    run_frames(&mut world, 1);
+    exhibition.update(&mut world);
+    world.touched_at = Some(std::time::Instant::now() - exhibition::INACTIVITY_RESET);
+    world.last_move_at -= exhibition::INACTIVITY_RESET;
+    exhibition.update(&mut world);
+    assert_eq!(exhibition.lines()[0], "Watch this!");
+}
+
+#[test]
+fn demo_script_solves_the_board_through_input_events() {
+    let (mut world, _desktop) = new_world(SOLVED);
+    world.exit_on_win = false;
+
+    let mut demo = Demo::from_script("0 6\n0 5\n0 8").unwrap();
+    demo.start(&mut world);
+    assert_ne!(world.grid, SOLVED);
+
+    for _ in 0..100 {
+        demo.update(&mut world);
+        run_frames(&mut world, 1);
+    }
+
+    assert_eq!(world.grid, SOLVED);
+    assert!(world.solved);
+    assert!(world.playing);
+}
+
+#[test]
+fn replays_round_trip_and_play_back() {
+    let (mut world, desktop) = new_world([[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    world.exit_on_win = false;
+    drag(&mut world, &desktop, "7", Vector2::new(-110, 0));
+    drag(&mut world, &desktop, "8", Vector2::new(-110, 0));
+    assert!(world.solved);
+
+    let replay = Replay::from_game(42, world.start_grid, &world.history);
+    let bytes = replay.encode().unwrap();
+    assert!(bytes.starts_with(b"SPRP\x01"));
+    let decoded = Replay::decode(&bytes).unwrap();
+    assert_eq!(decoded.seed, 42);
+    assert_eq!(decoded.start_grid, [[0, 1, 2], [3, 4, 5], [-1, 6, 7]]);
+    assert_eq!(decoded.moves.len(), 2);
+    // Only whole milliseconds are kept
+    assert_eq!(
+        decoded.moves[1].at.as_millis(),
+        world.history[1].at.as_millis()
+    );
+
+    let (mut viewer, _) = new_world(SOLVED);
+    viewer.exit_on_win = false;
+    let mut demo = Demo::from_replay(&decoded).unwrap();
+    demo.start(&mut viewer);
+    assert_eq!(viewer.grid, decoded.start_grid);
+    // Replays keep real time, starting with a short pause
+    let deadline = std::time::Instant::now() + Duration::from_secs(3);
+    while !viewer.solved && std::time::Instant::now() < deadline {
+        demo.update(&mut viewer);
+        run_frames(&mut viewer, 1);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(viewer.grid, SOLVED);
+
+    let mut newer = bytes.clone();
+    newer[4] = 2;
+    assert!(Replay::decode(&newer).unwrap_err().contains("format 2"));
+    assert!(Replay::decode(b"PNG").is_err());
+}
+
+#[test]
+fn fuzz_replay_reader() {
+    let replay = Replay {
+        seed: u64::MAX,
+        start_grid: Grid::solved(3, 3),
+        moves: (0..50)
+            .map(|i| ReplayMove {
+                piece: i % 8,
+                at: Duration::from_millis(i as u64 * 300),
+                power_up: None,
+            })
+            .collect(),
+    };
+    let bytes = replay.encode().unwrap();
+    assert_eq!(Replay::decode(&bytes), Ok(replay));
+
+    // Whatever the bytes, the answer is an error or a replay, never a panic.
+    // Most damage to the compressed file is caught by zstd, so the payload
+    // inside is damaged on its own too.
+    fuzz(&[&bytes], |bytes| {
+        let _ = Replay::decode(bytes);
+    });
+    let payload = zstd::bulk::decompress(&bytes[5..], 1 << 20).unwrap();
+    fuzz(&[&payload], |payload| {
+        let _ = Replay::parse_payload(payload);
+    });
+}
+
+#[test]
+fn fuzz_scramble_parser() {
+    fuzz(
+        &[
+            b"123/456/78_",
+            b"slidingpuzzle://8_1/234/567/",
+            b" 4\xc3\xa91/_23/5678 ",
+        ],
+        |bytes| {
+            if let Ok(grid) = scramble::parse(&String::from_utf8_lossy(bytes)) {
+                assert_eq!(scramble::parse(&scramble::to_notation(&grid)), Ok(grid));
+            }
+        },
+    );
+}
+
+#[test]
+fn fuzz_theme_loader() {
+    fuzz(
+        &[b"# Dark\nin_place = #00c800\nout_of_place=#c80000\nlabel = #ffffff\nshape = circle\n"],
+        |bytes| {
+            let _ = Theme::parse(&String::from_utf8_lossy(bytes));
+        },
+    );
+}
+
+#[test]
+fn fuzz_save_loader() {
+    let path = std::env::temp_dir().join(format!("slide-puzzle-fuzz-{}.txt", std::process::id()));
+    saves::write_versioned(&path, &Mapping::default().to_text(), &[]).unwrap();
+    let gamepad = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    fuzz(&[&gamepad, b"# version 0\n1500\n2500\n2500\n"], |bytes| {
+        let text = saves::verify(&String::from_utf8_lossy(bytes)).and_then(saves::split_version);
+        if let Ok((_, text)) = text {
+            let _ = Mapping::parse(&text);
+        }
+    });
+}
+
+#[test]
+fn demo_script_rejects_impossible_moves() {
+    assert!(Demo::from_script("0 1").is_err());
+    assert!(Demo::from_script("0 9").is_err());
+    assert!(Demo::from_script("soon 8").is_err());
+}
+
+#[test]
+fn neighbor_table_matches_the_grid() {
+    let table = neighbor_table(3, 3);
+
+    assert_eq!(table.neighbors(0), &[1, 3]);
+    assert_eq!(table.neighbors(4), &[3, 5, 1, 7]);
+    assert_eq!(table.neighbors(8), &[7, 5]);
+    assert!(!table.are_neighbors(2, 3));
+    assert!(std::ptr::eq(table, neighbor_table(3, 3)));
+    // The right edge of a 4x3 board doesn't wrap onto the next row
+    assert_eq!(neighbor_table(4, 3).neighbors(3), &[2, 7]);
+}
+
+#[test]
+fn io_worker_runs_jobs_in_order_before_flush_returns() {
+    let written = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    for i in 0..10 {
+        let written = std::sync::Arc::clone(&written);
+        io_worker::spawn(move || written.lock().unwrap().push(i));
+    }
+    io_worker::flush();
+
+    assert_eq!(*written.lock().unwrap(), (0..10).collect::<Vec<_>>());
+}
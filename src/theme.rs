@@ -0,0 +1,291 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use log::{debug, info, warn};
+use sfml::graphics::{CircleShape, Color, Shape};
+
+use crate::glyphs::{self, GlyphStyle};
+
+// How often the theme file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// What a piece looks like inside its window. Only the look changes: the
+// whole square window still grabs the mouse, so round pieces are as easy to
+// drag as square ones.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PieceShape {
+    #[default]
+    Square,
+    Hexagon,
+    Circle,
+}
+
+impl PieceShape {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "square" => Some(PieceShape::Square),
+            "hexagon" => Some(PieceShape::Hexagon),
+            "circle" => Some(PieceShape::Circle),
+            _ => None,
+        }
+    }
+
+    // The shape to draw over a black window `size` pixels across, or None
+    // when the piece fills its window
+    pub fn outline(&self, size: f32, color: Color) -> Option<CircleShape<'static>> {
+        let points = match self {
+            PieceShape::Square => return None,
+            PieceShape::Hexagon => 6,
+            PieceShape::Circle => 60,
+        };
+
+        let mut outline = CircleShape::new(size / 2.0, points);
+        outline.set_fill_color(color);
+        Some(outline)
+    }
+}
+
+// The colours and shape the board is drawn with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub in_place: Color,
+    pub out_of_place: Color,
+    // Pieces hidden by fog
+    pub hidden: Color,
+    pub label: Color,
+    // The goal position under the label, when shown
+    pub goal: Color,
+    // The frame around the piece the keyboard cursor is on
+    pub cursor: Color,
+    pub shape: PieceShape,
+    // Numbers from a sprite sheet instead of the font, see glyphs.rs
+    pub glyphs: Option<GlyphStyle>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            in_place: Color::rgb(0, 200, 0),
+            out_of_place: Color::rgb(200, 0, 0),
+            hidden: Color::rgb(60, 60, 60),
+            label: Color::WHITE,
+            goal: Color::rgb(230, 230, 230),
+            cursor: Color::rgb(255, 220, 0),
+            shape: PieceShape::Square,
+            glyphs: None,
+        }
+    }
+}
+
+impl Theme {
+    // Ready-made themes to switch between from the command palette
+    pub fn presets() -> [(&'static str, Theme); 4] {
+        [
+            ("default", Theme::default()),
+            (
+                "dark",
+                Theme {
+                    in_place: Color::rgb(20, 90, 50),
+                    out_of_place: Color::rgb(90, 30, 40),
+                    hidden: Color::rgb(25, 25, 25),
+                    label: Color::rgb(200, 200, 200),
+                    goal: Color::rgb(140, 140, 140),
+                    cursor: Color::rgb(120, 160, 255),
+                    ..Theme::default()
+                },
+            ),
+            (
+                "pastel",
+                Theme {
+                    in_place: Color::rgb(170, 220, 180),
+                    out_of_place: Color::rgb(240, 180, 190),
+                    hidden: Color::rgb(200, 200, 210),
+                    label: Color::rgb(60, 60, 80),
+                    goal: Color::rgb(100, 100, 120),
+                    cursor: Color::rgb(150, 120, 220),
+                    ..Theme::default()
+                },
+            ),
+            // Black and white apart from the pieces, for telling them apart
+            // without relying on red and green
+            (
+                "high contrast",
+                Theme {
+                    in_place: Color::rgb(0, 90, 255),
+                    out_of_place: Color::rgb(255, 150, 0),
+                    hidden: Color::BLACK,
+                    label: Color::WHITE,
+                    goal: Color::WHITE,
+                    cursor: Color::rgb(255, 0, 255),
+                    ..Theme::default()
+                },
+            ),
+        ]
+    }
+
+    // One `name = #rrggbb` line per colour, plus an optional `shape = ...`
+    // line and optional `glyphs = <sheet>`, `glyph_frames = N` and
+    // `glyph_fps = N` lines. Anything left out keeps its default, and lines
+    // starting with # are comments.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut theme = Self::default();
+        let mut glyph_frames = None;
+        let mut glyph_fps = None;
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("Line {}: {}", line_number + 1, message);
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected \"name = #rrggbb\""))?;
+            if name.trim() == "shape" {
+                theme.shape = PieceShape::parse(value.trim())
+                    .ok_or_else(|| error("shape must be square, hexagon or circle"))?;
+                continue;
+            }
+            match name.trim() {
+                "glyphs" => {
+                    let sheet = value.trim();
+                    if sheet.is_empty() {
+                        return Err(error("glyphs expects a sprite sheet"));
+                    }
+                    theme.glyphs = Some(GlyphStyle::new(Path::new(sheet)));
+                    continue;
+                }
+                "glyph_frames" => {
+                    glyph_frames = Some(
+                        value
+                            .trim()
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|&frames| frames > 0)
+                            .ok_or_else(|| error("glyph_frames expects a number of frames"))?,
+                    );
+                    continue;
+                }
+                "glyph_fps" => {
+                    glyph_fps = Some(
+                        value
+                            .trim()
+                            .parse::<f32>()
+                            .ok()
+                            .filter(|fps| fps.is_finite() && *fps >= 0.0)
+                            .ok_or_else(|| error("glyph_fps expects frames a second"))?,
+                    );
+                    continue;
+                }
+                _ => {}
+            }
+            let color = parse_color(value.trim()).ok_or_else(|| error("bad colour"))?;
+            theme
+                .set_color(name.trim(), color)
+                .map_err(|message| error(&message))?;
+        }
+
+        if let Some(glyphs) = &mut theme.glyphs {
+            glyphs.frames = glyph_frames.unwrap_or(glyphs.frames);
+            glyphs.fps = glyph_fps.unwrap_or(glyphs.fps);
+        }
+        Ok(theme)
+    }
+
+    // The theme with its sprite sheet, if it names one by a relative path,
+    // found next to the theme file in `dir`
+    pub fn relative_to(mut self, dir: &Path) -> Self {
+        if let Some(glyphs) = &mut self.glyphs {
+            if glyphs.sheet.is_relative() {
+                glyphs.sheet = glyphs::intern(&dir.join(glyphs.sheet));
+            }
+        }
+        self
+    }
+
+    pub fn set_color(&mut self, name: &str, color: Color) -> Result<(), String> {
+        match name {
+            "in_place" => self.in_place = color,
+            "out_of_place" => self.out_of_place = color,
+            "hidden" => self.hidden = color,
+            "label" => self.label = color,
+            "goal" => self.goal = color,
+            "cursor" => self.cursor = color,
+            other => return Err(format!("unknown colour \"{}\"", other)),
+        }
+
+        Ok(())
+    }
+}
+
+// #rrggbb
+pub fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+    Some(Color::rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+// Reloads the theme whenever its file is saved, so themes can be designed
+// with the game running
+pub struct ThemeWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    checked_at: Option<Instant>,
+}
+
+impl ThemeWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+            checked_at: None,
+        }
+    }
+
+    // The new theme if the file changed since the last call
+    pub fn poll(&mut self) -> Option<Theme> {
+        if self
+            .checked_at
+            .is_some_and(|checked_at| checked_at.elapsed() < POLL_INTERVAL)
+        {
+            return None;
+        }
+        self.checked_at = Some(Instant::now());
+
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(error) => {
+                debug!("Couldn't read {}: {}", self.path.display(), error);
+                return None;
+            }
+        };
+        // Keep the last good theme while the file is mid-edit
+        match Theme::parse(&text) {
+            Ok(theme) => {
+                info!("Loaded theme from {}", self.path.display());
+                let dir = self.path.parent().unwrap_or(Path::new(""));
+                Some(theme.relative_to(dir))
+            }
+            Err(error) => {
+                warn!("Theme {} is invalid: {}", self.path.display(), error);
+                None
+            }
+        }
+    }
+}
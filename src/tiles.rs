@@ -0,0 +1,226 @@
+// Tile packs, for --tiles: a folder with a picture for each piece instead
+// of one picture cut up across the board. The folder's tiles.txt says which
+// picture goes on which piece, and optionally how big to make them, e.g.
+//
+//   size = 200
+//   1 = cat.png
+//   2 = dog.jpg
+//
+// The tiles are scaled to squares and put together into one picture with
+// each tile in its piece's goal cell, so the board shows them just as it
+// shows an --image. That picture is kept in the cache directory, so the
+// originals, which can be big photos, only have to be read and scaled once.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+use log::{debug, info, warn};
+use sfml::{graphics::Image, system::Vector2};
+
+pub const MANIFEST: &str = "tiles.txt";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilePack {
+    // How many pixels across each tile is scaled to, if the pack says
+    pub size: Option<u32>,
+    // The picture for each piece, piece 1's first
+    pub tiles: Vec<PathBuf>,
+}
+
+// Blank lines and lines starting with # are skipped. Tiles are named
+// relative to `dir`.
+pub fn parse_manifest(text: &str, dir: &Path) -> Result<TilePack, String> {
+    let mut size = None;
+    let mut tiles: Vec<Option<PathBuf>> = Vec::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: &str| format!("Line {}: {}", line_number + 1, message);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected \"piece = file\""))?;
+        let (name, value) = (name.trim(), value.trim());
+
+        if name == "size" {
+            size = Some(
+                value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| error("size expects a number of pixels"))?,
+            );
+            continue;
+        }
+
+        let piece = name
+            .parse::<usize>()
+            .ok()
+            .filter(|&piece| piece > 0)
+            .ok_or_else(|| error(&format!("\"{}\" isn't a piece number", name)))?;
+        if value.is_empty() {
+            return Err(error(&format!("no file given for piece {}", piece)));
+        }
+        if tiles.len() < piece {
+            tiles.resize(piece, None);
+        }
+        if tiles[piece - 1].is_some() {
+            return Err(error(&format!("piece {} already has a tile", piece)));
+        }
+        tiles[piece - 1] = Some(dir.join(value));
+    }
+
+    let tiles = tiles
+        .into_iter()
+        .enumerate()
+        .map(|(index, tile)| tile.ok_or(format!("No tile for piece {}", index + 1)))
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(TilePack { size, tiles })
+}
+
+pub fn load(dir: &Path) -> Result<TilePack, String> {
+    let path = dir.join(MANIFEST);
+    let text = fs::read_to_string(&path)
+        .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+    parse_manifest(&text, dir).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+// An RGBA picture `from` pixels big as a `size` pixel square, each pixel
+// the average of the ones it covers so shrinking a photo doesn't sparkle
+pub fn scale(pixels: &[u8], from: Vector2<u32>, size: u32) -> Vec<u8> {
+    let (from_width, from_height) = (from.x as usize, from.y as usize);
+    let size = size as usize;
+    let mut scaled = vec![0; size * size * 4];
+    if from_width == 0 || from_height == 0 {
+        return scaled;
+    }
+
+    // The source pixels from `start` up to `end` along one side, never none
+    let span = |to: usize, from: usize| {
+        let start = to * from / size;
+        let end = ((to + 1) * from / size).max(start + 1).min(from);
+        start..end
+    };
+    for y in 0..size {
+        for x in 0..size {
+            let mut total = [0u32; 4];
+            let mut count = 0;
+            for source_y in span(y, from_height) {
+                for source_x in span(x, from_width) {
+                    let index = (source_y * from_width + source_x) * 4;
+                    for (channel, total) in total.iter_mut().enumerate() {
+                        *total += pixels[index + channel] as u32;
+                    }
+                    count += 1;
+                }
+            }
+
+            let index = (y * size + x) * 4;
+            for (channel, total) in total.into_iter().enumerate() {
+                scaled[index + channel] = (total / count) as u8;
+            }
+        }
+    }
+    scaled
+}
+
+// The whole board as one RGBA picture, `size` pixels to a cell, with each
+// of `tiles` (already scaled) in its piece's goal cell and the gap's cell
+// left clear
+pub fn compose(tiles: &[Vec<u8>], width: usize, height: usize, size: u32) -> Vec<u8> {
+    let size = size as usize;
+    let row_bytes = width * size * 4;
+    let mut board = vec![0; row_bytes * height * size];
+
+    for (piece, tile) in tiles.iter().enumerate().take(width * height - 1) {
+        let (cell_x, cell_y) = (piece % width, piece / width);
+        for y in 0..size {
+            let start = (cell_y * size + y) * row_bytes + cell_x * size * 4;
+            board[start..start + size * 4].copy_from_slice(&tile[y * size * 4..(y + 1) * size * 4]);
+        }
+    }
+    board
+}
+
+// Where the board picture for this pack, board and size is kept. Any change
+// to the manifest or a tile gives a different name, so nothing stale is
+// ever shown.
+fn cache_path(dir: &Path, pack: &TilePack, width: usize, height: usize, size: u32) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    dir.hash(&mut hasher);
+    (width, height, size).hash(&mut hasher);
+    for tile in &pack.tiles {
+        tile.hash(&mut hasher);
+        if let Ok(metadata) = fs::metadata(tile) {
+            (metadata.len(), metadata.modified().ok()).hash(&mut hasher);
+        }
+    }
+
+    let name = format!("{:016x}.png", hasher.finish());
+    ProjectDirs::from("", "", "slide-puzzle")
+        .map_or_else(std::env::temp_dir, |dirs| dirs.cache_dir().join("tiles"))
+        .join(name)
+}
+
+// The pack in `dir` put together for a `width` by `height` board, from the
+// cache if it's been put together before. `size` is used unless the pack
+// gives its own.
+pub fn board_image(dir: &Path, width: usize, height: usize, size: u32) -> Result<Image, String> {
+    let pack = load(dir)?;
+    let pieces = width * height - 1;
+    if pack.tiles.len() < pieces {
+        return Err(format!(
+            "A {}x{} board needs {} tiles, {} only has {}",
+            width,
+            height,
+            pieces,
+            dir.display(),
+            pack.tiles.len()
+        ));
+    }
+    let size = pack.size.unwrap_or(size);
+
+    let cached = cache_path(dir, &pack, width, height, size);
+    if let Some(image) = Image::from_file(&cached.to_string_lossy()) {
+        debug!("Loaded tiles from {}", cached.display());
+        return Ok(image);
+    }
+
+    let tiles = pack.tiles[..pieces]
+        .iter()
+        .map(|path| {
+            let image = Image::from_file(&path.to_string_lossy())
+                .ok_or(format!("Couldn't load a tile from {}", path.display()))?;
+            Ok(scale(image.pixel_data(), image.size(), size))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let pixels = compose(&tiles, width, height, size);
+    let (board_width, board_height) = (width as u32 * size, height as u32 * size);
+    // SAFETY: compose() gives exactly four bytes for every pixel
+    let image = unsafe { Image::create_from_pixels(board_width, board_height, &pixels) }
+        .ok_or("Couldn't make a picture from the tiles")?;
+
+    let saved = cached
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(|error| error.to_string())
+        .and_then(|()| {
+            image
+                .save_to_file(&cached.to_string_lossy())
+                .then_some(())
+                .ok_or("the picture couldn't be written".to_string())
+        });
+    match saved {
+        Ok(()) => info!("Cached tiles in {}", cached.display()),
+        Err(error) => warn!("Couldn't cache tiles in {}: {}", cached.display(), error),
+    }
+    Ok(image)
+}
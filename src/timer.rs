@@ -0,0 +1,167 @@
+// The best solve time for each board size, kept between games, and a window
+// showing the clock running against it. The file has a line per size, e.g.
+//
+//   3x3 = 41250
+//   4x4 = 187900
+//
+// with the time in milliseconds.
+
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+use log::{debug, info, warn};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+};
+
+use crate::{
+    font, io_worker,
+    renderer::{Renderer, WindowHandle},
+    saves::{self, Migration},
+    share::format_duration,
+};
+
+pub const FILE_NAME: &str = "best-times.txt";
+pub const WINDOW_SIZE: u32 = 200;
+const LINE_HEIGHT: f32 = 28.0;
+
+// Changes to the best times file's format, oldest first
+const MIGRATIONS: &[Migration] = &[];
+
+#[derive(Debug, Default)]
+pub struct BestTimes {
+    // By board width and height
+    pub times: BTreeMap<(usize, usize), Duration>,
+    // Where they're kept, if they're kept at all
+    pub path: Option<PathBuf>,
+}
+
+impl BestTimes {
+    pub fn load(path: PathBuf) -> Self {
+        let times = match saves::read_versioned(&path, MIGRATIONS).and_then(|text| parse(&text)) {
+            Ok(times) => times,
+            Err(error) => {
+                debug!("No best times at {}: {}", path.display(), error);
+                BTreeMap::new()
+            }
+        };
+
+        Self {
+            times,
+            path: Some(path),
+        }
+    }
+
+    pub fn get(&self, width: usize, height: usize) -> Option<Duration> {
+        self.times.get(&(width, height)).copied()
+    }
+
+    // Keep `time` if it beats the best for a `width` by `height` board, and
+    // say whether it did
+    pub fn record(&mut self, width: usize, height: usize, time: Duration) -> bool {
+        if self.get(width, height).is_some_and(|best| best <= time) {
+            return false;
+        }
+        info!(
+            "New best time for {}x{}: {}",
+            width,
+            height,
+            format_duration(time)
+        );
+        self.times.insert((width, height), time);
+
+        if let Some(path) = self.path.clone() {
+            let text = format(&self.times);
+            io_worker::spawn(move || {
+                if let Err(error) = saves::write_versioned(&path, &text, MIGRATIONS) {
+                    warn!("Couldn't save best times to {}: {}", path.display(), error);
+                }
+            });
+        }
+        true
+    }
+}
+
+// Blank lines and lines starting with # are skipped
+pub fn parse(text: &str) -> Result<BTreeMap<(usize, usize), Duration>, String> {
+    let mut times = BTreeMap::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: &str| format!("Line {}: {}", line_number + 1, message);
+        let (size, millis) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected \"WxH = milliseconds\""))?;
+        let size = size
+            .trim()
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .filter(|&(width, height)| width >= 2 && height >= 2)
+            .ok_or_else(|| error(&format!("\"{}\" isn't a board size", size.trim())))?;
+        let millis = millis
+            .trim()
+            .parse()
+            .map_err(|_| error("the time should be in milliseconds"))?;
+        times.insert(size, Duration::from_millis(millis));
+    }
+
+    Ok(times)
+}
+
+pub fn format(times: &BTreeMap<(usize, usize), Duration>) -> String {
+    times
+        .iter()
+        .map(|((width, height), time)| format!("{}x{} = {}\n", width, height, time.as_millis()))
+        .collect()
+}
+
+// Shows the clock while playing, with the time to beat under it
+pub struct TimerWindow<W: WindowHandle> {
+    window: W,
+}
+
+impl<W: WindowHandle> TimerWindow<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, position: Vector2<i32>) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Timer");
+        window.set_position(position);
+
+        Self { window }
+    }
+
+    pub fn update(&mut self) {
+        // Nothing to interact with, but events still need draining
+        while self.window.poll_event().is_some() {}
+    }
+
+    // `timer` is the clock as the game shows it. It turns green once the
+    // board is solved, if that beat `best`.
+    pub fn render(&mut self, timer: &str, elapsed: Duration, best: Option<Duration>, solved: bool) {
+        self.window.clear(Color::BLACK);
+
+        let color = if solved && best.is_none_or(|best| elapsed <= best) {
+            Color::rgb(0, 200, 0)
+        } else {
+            Color::WHITE
+        };
+        self.draw_text(timer, Vector2::new(5.0, 5.0), color);
+        let best = best.map_or("-".to_string(), format_duration);
+        self.draw_text(
+            &format!("Best {}", best),
+            Vector2::new(5.0, LINE_HEIGHT + 5.0),
+            Color::WHITE,
+        );
+
+        self.window.display();
+    }
+
+    fn draw_text(&mut self, string: &str, position: Vector2<f32>, color: Color) {
+        let mut text = Text::new(string, font(), 24);
+        text.set_position(position);
+        text.set_fill_color(color);
+        self.window.draw(&text);
+    }
+}
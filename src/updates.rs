@@ -0,0 +1,206 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use log::{debug, info};
+use sfml::{
+    graphics::{Color, Text, Transformable},
+    system::Vector2,
+    window::{Event, Key},
+};
+
+use crate::{
+    font,
+    renderer::{Renderer, WindowHandle},
+};
+
+const RELEASES_URL: &str =
+    "https://api.github.com/repos/cmoyates/rust-sliding-puzzle/releases/latest";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+const WINDOW_SIZE: u32 = 400;
+const LINE_HEIGHT: f32 = 28.0;
+// Roughly what fits across the window at 24px
+const LINE_CHARS: usize = 32;
+const CHANGELOG_LINES: usize = 9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub notes: String,
+    pub url: String,
+}
+
+// The parts of a GitHub release we show
+pub fn parse_release(json: &str) -> Result<Release, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|error| format!("Bad release JSON: {}", error))?;
+    let field = |name: &str| value.get(name).and_then(|field| field.as_str());
+
+    Ok(Release {
+        version: field("tag_name")
+            .ok_or("Release has no tag")?
+            .trim_start_matches('v')
+            .to_string(),
+        notes: field("body").unwrap_or_default().to_string(),
+        url: field("html_url").unwrap_or_default().to_string(),
+    })
+}
+
+// Compares dotted version numbers, treating missing parts as 0
+pub fn is_newer(version: &str, current: &str) -> bool {
+    let parts = |version: &str| -> Vec<u32> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (version, current) = (parts(version), parts(current));
+
+    for i in 0..version.len().max(current.len()) {
+        let (a, b) = (
+            version.get(i).copied().unwrap_or(0),
+            current.get(i).copied().unwrap_or(0),
+        );
+        if a != b {
+            return a > b;
+        }
+    }
+    false
+}
+
+// Asks GitHub for the latest release on a background thread, so a slow or
+// missing connection never holds up the game
+pub struct UpdateCheck {
+    receiver: Receiver<Option<Release>>,
+}
+
+impl UpdateCheck {
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let result = ureq::get(RELEASES_URL)
+                .timeout(FETCH_TIMEOUT)
+                .call()
+                .map_err(|error| error.to_string())
+                .and_then(|response| response.into_string().map_err(|error| error.to_string()))
+                .and_then(|json| parse_release(&json));
+
+            let newer = match result {
+                Ok(release) if is_newer(&release.version, env!("CARGO_PKG_VERSION")) => {
+                    info!("Version {} is available", release.version);
+                    Some(release)
+                }
+                Ok(release) => {
+                    debug!("Up to date, the latest release is {}", release.version);
+                    None
+                }
+                Err(error) => {
+                    debug!("Couldn't check for updates: {}", error);
+                    None
+                }
+            };
+            let _ = sender.send(newer);
+        });
+
+        Self { receiver }
+    }
+
+    // Some(release) once, when a newer version turns out to exist
+    pub fn poll(&mut self) -> Option<Release> {
+        self.receiver.try_recv().ok().flatten()
+    }
+}
+
+// Tells the player about a new release, with the start of its changelog
+pub struct UpdateNotice<W: WindowHandle> {
+    window: W,
+    lines: Vec<String>,
+    pub closed: bool,
+}
+
+impl<W: WindowHandle> UpdateNotice<W> {
+    pub fn open<R: Renderer<Window = W>>(renderer: &mut R, release: &Release) -> Self {
+        let mut window = renderer.create_window(WINDOW_SIZE, "Update available");
+        let desktop = renderer.desktop_size();
+        window.set_position(Vector2::new((desktop.x - WINDOW_SIZE) as i32 - 20, 20));
+
+        let mut lines = vec![
+            format!("Version {} is out!", release.version),
+            String::new(),
+        ];
+        lines.extend(
+            wrap(&release.notes, LINE_CHARS)
+                .into_iter()
+                .take(CHANGELOG_LINES),
+        );
+        if !release.url.is_empty() {
+            println!("Slide Puzzle {} is out: {}", release.version, release.url);
+        }
+
+        Self {
+            window,
+            lines,
+            closed: false,
+        }
+    }
+
+    pub fn update(&mut self) {
+        while let Some(event) = self.window.poll_event() {
+            if let Event::Closed
+            | Event::KeyPressed {
+                code: Key::Escape, ..
+            } = event
+            {
+                self.closed = true;
+            }
+        }
+    }
+
+    pub fn render(&mut self) {
+        self.window.clear(Color::BLACK);
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let color = if index == 0 {
+                Color::rgb(0, 200, 0)
+            } else {
+                Color::WHITE
+            };
+            let mut text = Text::new(line, font(), 24);
+            text.set_fill_color(color);
+            text.set_position(Vector2::new(5.0, index as f32 * LINE_HEIGHT + 5.0));
+            self.window.draw(&text);
+        }
+
+        let mut text = Text::new("Escape to close", font(), 24);
+        text.set_fill_color(Color::WHITE);
+        text.set_position(Vector2::new(5.0, WINDOW_SIZE as f32 - LINE_HEIGHT - 5.0));
+        self.window.draw(&text);
+
+        self.window.display();
+    }
+}
+
+// Break Markdown-ish release notes into lines of at most `width` characters
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let mut line = String::new();
+        for word in paragraph.trim_start_matches('#').split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}